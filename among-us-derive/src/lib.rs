@@ -0,0 +1,257 @@
+//! Derive macros for `among_us::net::binary`.
+//!
+//! These are re-exported by `among-us` when the `derive` feature is enabled,
+//! and shouldn't be depended on directly.
+//!
+//! Fields are encoded in declaration order. Each field can be annotated with a
+//! `#[binary(...)]` attribute:
+//!
+//! * `#[binary(skip)]`: the field isn't encoded, and decodes as
+//!   `Default::default()`.
+//! * `#[binary(packed)]`: a `u32` or `i32` field is encoded as a packed
+//!   integer.
+//! * `#[binary(big_endian)]`: a numeric field is encoded big-endian.
+//! * `#[binary(len = "u8")]`: a `Vec` or `String` field is encoded with the
+//!   given length prefix instead of its default. This can be `u8`, `u16`,
+//!   `u32` or `packed`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Lit, Meta,
+    NestedMeta, Type,
+};
+
+/// Derives `Encode` for a struct.
+#[proc_macro_derive(Encode, attributes(binary))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_encode(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Decode` for a struct.
+#[proc_macro_derive(Decode, attributes(binary))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_decode(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a single field is encoded.
+enum Mode {
+    Default,
+    Skip,
+    Packed(TokenStream2),
+    BigEndian,
+    Len(TokenStream2),
+}
+
+/// A field of the struct being derived.
+struct Field {
+    /// The name of the field, or its index for tuple structs.
+    member: syn::Member,
+    /// A local binding for the field when decoding.
+    binding: syn::Ident,
+    mode: Mode,
+}
+
+fn expand_encode(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = fields(&input)?;
+
+    add_bounds(&mut input, quote!(::among_us::net::binary::encode::Encode));
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let encodes = fields.iter().map(|field| {
+        let member = &field.member;
+
+        match &field.mode {
+            Mode::Default => quote! {
+                cursor.encode(&self.#member)?;
+            },
+            Mode::Skip => quote!(),
+            Mode::Packed(ty) => quote! {
+                cursor.encode(&#ty(self.#member))?;
+            },
+            Mode::BigEndian => quote! {
+                cursor.encode(&::among_us::net::binary::endian::BigEndian(self.#member))?;
+            },
+            Mode::Len(ty) => quote! {
+                ::among_us::net::binary::prefix::encode_prefixed::<#ty, _>(cursor, &self.#member)?;
+            },
+        }
+    });
+
+    let encode = quote!(::among_us::net::binary::encode);
+
+    Ok(quote! {
+        impl #impl_generics #encode::Encode for #name #ty_generics #where_clause {
+            fn encode(
+                &self,
+                cursor: &mut #encode::CursorMut,
+            ) -> ::core::result::Result<(), #encode::Error> {
+                #(#encodes)*
+                Ok(())
+            }
+        }
+    })
+}
+
+fn expand_decode(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = fields(&input)?;
+
+    add_bounds(&mut input, quote!(::among_us::net::binary::decode::Decode));
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let decodes = fields.iter().map(|field| {
+        let binding = &field.binding;
+
+        let value = match &field.mode {
+            Mode::Default => quote!(cursor.decode()?),
+            Mode::Skip => quote!(::core::default::Default::default()),
+            Mode::Packed(ty) => quote!(cursor.decode::<#ty>()?.0),
+            Mode::BigEndian => quote! {
+                cursor.decode::<::among_us::net::binary::endian::BigEndian<_>>()?.0
+            },
+            Mode::Len(ty) => quote! {
+                ::among_us::net::binary::prefix::decode_prefixed::<#ty, _, _>(cursor)?
+            },
+        };
+
+        quote!(let #binding = #value;)
+    });
+
+    let members = fields.iter().map(|field| &field.member);
+    let bindings = fields.iter().map(|field| &field.binding);
+
+    let decode = quote!(::among_us::net::binary::decode);
+
+    Ok(quote! {
+        impl #impl_generics #decode::Decode for #name #ty_generics #where_clause {
+            fn decode<__T>(
+                cursor: &mut #decode::Cursor<__T>,
+            ) -> ::core::result::Result<Self, #decode::Error>
+            where __T: ::core::convert::AsRef<[u8]> {
+                #(#decodes)*
+                Ok(#name { #(#members: #bindings),* })
+            }
+        }
+    })
+}
+
+/// Adds a trait bound to every type parameter.
+fn add_bounds(input: &mut DeriveInput, bound: TokenStream2) {
+    for param in input.generics.params.iter_mut() {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(parse_quote!(#bound));
+        }
+    }
+}
+
+/// Collects the fields of a struct and their attributes.
+fn fields(input: &DeriveInput) -> Result<Vec<Field>, Error> {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => return Err(Error::new(
+            Span::call_site(),
+            "binary codecs can only be derived for structs",
+        )),
+    };
+
+    let fields = match &data.fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    fields
+        .into_iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let member = match &field.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(i.into()),
+            };
+
+            Ok(Field {
+                member,
+                binding: format_ident!("__field{}", i),
+                mode: mode(field)?,
+            })
+        })
+        .collect()
+}
+
+/// Parses the `#[binary(...)]` attributes of a field.
+fn mode(field: &syn::Field) -> Result<Mode, Error> {
+    let mut mode = Mode::Default;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("binary")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected `binary(...)`")),
+        };
+
+        for nested in list.nested {
+            if !matches!(mode, Mode::Default) {
+                return Err(Error::new_spanned(nested, "conflicting binary attributes"));
+            }
+
+            mode = match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => Mode::Skip,
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("packed") => {
+                    Mode::Packed(packed_type(&field.ty)?)
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("big_endian") => {
+                    Mode::BigEndian
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("len") => {
+                    match &nv.lit {
+                        Lit::Str(lit) => Mode::Len(prefix_type(lit)?),
+                        lit => return Err(Error::new_spanned(lit, "expected a string")),
+                    }
+                }
+                _ => return Err(Error::new_spanned(nested, "unknown binary attribute")),
+            };
+        }
+    }
+
+    Ok(mode)
+}
+
+/// Picks the packed wrapper for a field type.
+fn packed_type(ty: &Type) -> Result<TokenStream2, Error> {
+    if let Type::Path(path) = ty {
+        if path.path.is_ident("u32") {
+            return Ok(quote!(::among_us::net::binary::packed::PackedU32));
+        } else if path.path.is_ident("i32") {
+            return Ok(quote!(::among_us::net::binary::packed::PackedI32));
+        }
+    }
+
+    Err(Error::new_spanned(ty, "only `u32` and `i32` fields can be packed"))
+}
+
+/// Parses a length prefix type.
+fn prefix_type(lit: &syn::LitStr) -> Result<TokenStream2, Error> {
+    match lit.value().as_str() {
+        "packed" => Ok(quote!(::among_us::net::binary::packed::PackedU32)),
+        "u8" | "u16" | "u32" => {
+            let ty = syn::Ident::new(&lit.value(), lit.span());
+
+            Ok(quote!(#ty))
+        }
+        _ => Err(Error::new_spanned(lit, "expected `u8`, `u16`, `u32` or `packed`")),
+    }
+}
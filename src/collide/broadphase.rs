@@ -0,0 +1,181 @@
+//! Broad-phase collision.
+//!
+//! These narrow down which shapes could possibly collide by their bounding
+//! boxes, so only those pairs have to be tested properly.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::math::grid::{Grid, TileCoord};
+use crate::math::{Rect, Vector2};
+
+/// A uniform grid of cells, each listing the boxes that overlap it.
+///
+/// Works best when the cells are a bit bigger than the typical box. Every
+/// cell a query covers is looked at, even empty ones, so huge queries on a
+/// fine grid are slow.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<K> {
+    grid: Grid,
+    cells: HashMap<TileCoord, Vec<K>>,
+    entries: HashMap<K, Rect>,
+}
+
+impl<K> SpatialHash<K>
+where K: Copy + Eq + Hash {
+    /// Create a new, empty `SpatialHash`.
+    ///
+    /// # Panics
+    /// Panics if `cell_size` is not positive.
+    pub fn new(cell_size: f32) -> SpatialHash<K> {
+        SpatialHash {
+            grid: Grid::new(Vector2::zeros(), cell_size),
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The number of boxes in the hash.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the hash is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The box of a key, if it is in the hash.
+    pub fn get(&self, key: K) -> Option<Rect> {
+        self.entries.get(&key).copied()
+    }
+
+    /// Inserts a box, or moves it if the key is already in the hash.
+    pub fn insert(&mut self, key: K, aabb: Rect) {
+        self.remove(key);
+
+        let (min, max) = self.tiles(&aabb);
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(TileCoord::new(x, y)).or_default().push(key);
+            }
+        }
+
+        self.entries.insert(key, aabb);
+    }
+
+    /// Removes a box, returning it.
+    pub fn remove(&mut self, key: K) -> Option<Rect> {
+        let aabb = self.entries.remove(&key)?;
+        let (min, max) = self.tiles(&aabb);
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let tile = TileCoord::new(x, y);
+
+                if let Some(cell) = self.cells.get_mut(&tile) {
+                    cell.retain(|other| *other != key);
+
+                    if cell.is_empty() {
+                        self.cells.remove(&tile);
+                    }
+                }
+            }
+        }
+
+        Some(aabb)
+    }
+
+    /// Removes every box.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    /// The keys of every box overlapping a region.
+    pub fn query(&self, region: Rect) -> impl Iterator<Item = K> + '_ {
+        let (min, max) = self.tiles(&region);
+
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| TileCoord::new(x, y)))
+            .filter_map(move |tile| self.cells.get(&tile).map(|cell| (tile, cell)))
+            .flat_map(move |(tile, cell)| {
+                cell.iter().copied().filter(move |key| {
+                    let aabb = &self.entries[key];
+
+                    // a box spanning several cells is only reported from the
+                    // first one the query shares with it
+                    self.first_tile(aabb, &region) == tile && aabb.intersects(&region)
+                })
+            })
+    }
+
+    /// Every pair of overlapping boxes, each reported once.
+    pub fn pairs(&self) -> impl Iterator<Item = (K, K)> + '_ {
+        self.cells.iter().flat_map(move |(&tile, cell)| {
+            cell.iter().enumerate().flat_map(move |(i, &a)| {
+                cell[i + 1..].iter().copied().filter_map(move |b| {
+                    let (a_aabb, b_aabb) = (&self.entries[&a], &self.entries[&b]);
+
+                    if self.first_tile(a_aabb, b_aabb) == tile && a_aabb.intersects(b_aabb) {
+                        Some((a, b))
+                    } else {
+                        None
+                    }
+                })
+            })
+        })
+    }
+
+    fn tiles(&self, aabb: &Rect) -> (TileCoord, TileCoord) {
+        (self.grid.to_tile(aabb.min()), self.grid.to_tile(aabb.max()))
+    }
+
+    /// The lowest tile two boxes both cover.
+    fn first_tile(&self, a: &Rect, b: &Rect) -> TileCoord {
+        let (a, b) = (self.grid.to_tile(a.min()), self.grid.to_tile(b.min()));
+
+        TileCoord::new(a.x.max(b.x), a.y.max(b.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect::from_origin_size(Vector2::new(x, y), Vector2::new(w, h))
+    }
+
+    #[test]
+    fn query_reports_once() {
+        let mut hash = SpatialHash::new(1.);
+        hash.insert(0, rect(-0.5, -0.5, 3., 3.));
+        hash.insert(1, rect(5., 5., 0.5, 0.5));
+        hash.insert(2, rect(1.2, 1.2, 0.2, 0.2));
+
+        let mut found: Vec<_> = hash.query(rect(0., 0., 2., 2.)).collect();
+        found.sort();
+        assert_eq!(found, vec![0, 2]);
+
+        hash.remove(2);
+        assert_eq!(hash.query(rect(0., 0., 2., 2.)).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn pairs_report_once() {
+        let mut hash = SpatialHash::new(1.);
+        hash.insert(0, rect(0., 0., 3., 3.));
+        hash.insert(1, rect(2., 2., 3., 3.));
+        hash.insert(2, rect(10., 10., 1., 1.));
+        hash.insert(3, rect(-2., -2., 1.5, 1.5));
+
+        let pairs: Vec<_> = hash.pairs().map(|(a, b)| (a.min(b), a.max(b))).collect();
+        assert_eq!(pairs, vec![(0, 1)]);
+
+        // moving a box drops it from its old cells
+        hash.insert(1, rect(20., 20., 1., 1.));
+        assert_eq!(hash.pairs().count(), 0);
+    }
+}
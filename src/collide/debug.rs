@@ -0,0 +1,199 @@
+//! Drawing colliders, for debugging.
+//!
+//! When a collision test disagrees with what the map looks like, it helps to
+//! see the shapes. [`lines`] turns them into line segments any renderer can
+//! draw, and [`svg`] writes a picture that opens in a browser.
+
+use std::f32::consts::TAU;
+use std::fmt::Write as _;
+
+use crate::math::{Rect, Vector2};
+
+use super::shape::edges;
+use super::{Capsule, Collider, ColliderId, ColliderKind, Geometry, ShapeRef, World};
+
+/// How many lines a circle is drawn with.
+pub const CIRCLE_SEGMENTS: usize = 24;
+
+/// A line segment to draw.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line {
+    pub a: Vector2,
+    pub b: Vector2,
+}
+
+/// Adds the outline of a shape to a list of lines.
+///
+/// Circles are drawn as a polygon of [`CIRCLE_SEGMENTS`] sides, and the ends
+/// of capsules as half of one.
+pub fn shape_lines<'a>(shape: impl Into<ShapeRef<'a>>, lines: &mut Vec<Line>) {
+    match shape.into() {
+        ShapeRef::Circle(circle) => {
+            let point = |i: usize| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+                circle.center + Vector2::new(angle.cos(), angle.sin()) * circle.radius
+            };
+
+            lines.extend((0..CIRCLE_SEGMENTS).map(|i| Line { a: point(i), b: point(i + 1) }));
+        }
+        ShapeRef::Polygon(polygon) => {
+            lines.extend(edges(polygon.points()).map(|(a, b)| Line { a, b }));
+        }
+        ShapeRef::Edge(edge) => lines.push(Line { a: edge.a, b: edge.b }),
+        ShapeRef::Capsule(capsule) => {
+            lines.extend(edges(&capsule_outline(capsule)).map(|(a, b)| Line { a, b }));
+        }
+        ShapeRef::Obb(obb) => lines.extend(edges(&obb.corners()).map(|(a, b)| Line { a, b })),
+    }
+}
+
+/// The outline of a capsule, going counter-clockwise.
+fn capsule_outline(capsule: &Capsule) -> Vec<Vector2> {
+    let along = capsule.b - capsule.a;
+    let start = along.y.atan2(along.x) - TAU / 4.;
+    let half = CIRCLE_SEGMENTS / 2;
+
+    // half a circle around each end, starting from the side to the right of
+    // the way it points
+    let arc = |center: Vector2, start: f32| {
+        (0..=half).map(move |i| {
+            let angle = start + i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + Vector2::new(angle.cos(), angle.sin()) * capsule.radius
+        })
+    };
+
+    arc(capsule.b, start).chain(arc(capsule.a, start + TAU / 2.)).collect()
+}
+
+/// The outlines of every collider in a world, in the order they were added.
+pub fn lines(world: &World) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    for (_, collider) in sorted(world) {
+        shape_lines(&collider.shape, &mut lines);
+    }
+
+    lines
+}
+
+/// Draws every collider in a world as an SVG image.
+///
+/// Solid colliders are black, triggers are dashed green, and one-way edges
+/// are blue. The image is flipped so positive y points up, like in the game.
+pub fn svg(world: &World) -> String {
+    let colliders = sorted(world);
+    let bounds = colliders
+        .iter()
+        .map(|(_, collider)| collider.shape.aabb())
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or_else(|| Rect::from_min_max(Vector2::zeros(), Vector2::zeros()));
+
+    let (min, size) = (bounds.min(), bounds.size());
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min.x - 1.,
+        -bounds.max().y - 1.,
+        size.x + 2.,
+        size.y + 2.,
+    );
+    let _ = writeln!(svg, r#"<g transform="scale(1 -1)" fill="none" stroke-width="0.05">"#);
+
+    for (_, collider) in colliders {
+        let style = match collider.kind {
+            ColliderKind::Solid => r#"stroke="black""#,
+            ColliderKind::Trigger => r#"stroke="green" stroke-dasharray="0.2 0.1""#,
+            ColliderKind::OneWay => r#"stroke="blue""#,
+        };
+
+        let corners = |points: &[Vector2]| {
+            let points: Vec<_> = points.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+            points.join(" ")
+        };
+
+        let _ = match collider.shape.to_ref() {
+            ShapeRef::Circle(circle) => writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}" {}/>"#,
+                circle.center.x, circle.center.y, circle.radius, style,
+            ),
+            ShapeRef::Polygon(polygon) => {
+                writeln!(svg, r#"<polygon points="{}" {}/>"#, corners(polygon.points()), style)
+            }
+            ShapeRef::Edge(edge) => writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {}/>"#,
+                edge.a.x, edge.a.y, edge.b.x, edge.b.y, style,
+            ),
+            ShapeRef::Capsule(capsule) => {
+                let outline = capsule_outline(capsule);
+                writeln!(svg, r#"<polygon points="{}" {}/>"#, corners(&outline), style)
+            }
+            ShapeRef::Obb(obb) => {
+                writeln!(svg, r#"<polygon points="{}" {}/>"#, corners(&obb.corners()), style)
+            }
+        };
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// The colliders of a world, by id.
+fn sorted(world: &World) -> Vec<(ColliderId, &Collider)> {
+    let mut colliders: Vec<_> = world.colliders().collect();
+    colliders.sort_by_key(|(id, _)| *id);
+    colliders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::map::MapColliders;
+    use crate::collide::Circle;
+
+    #[test]
+    fn circle_is_closed() {
+        let mut lines = Vec::new();
+        shape_lines(&Circle::new(Vector2::new(1., 1.), 2.), &mut lines);
+
+        assert_eq!(lines.len(), CIRCLE_SEGMENTS);
+        assert!((lines[0].a - lines[CIRCLE_SEGMENTS - 1].b).norm() < 1e-5);
+        assert!((lines[0].a - Vector2::new(3., 1.)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn capsule_is_closed() {
+        let mut lines = Vec::new();
+        let capsule = Capsule::new(Vector2::new(0., 0.), Vector2::new(4., 0.), 1.);
+        shape_lines(&capsule, &mut lines);
+
+        // two half circles, and a straight side between each of their ends
+        assert_eq!(lines.len(), CIRCLE_SEGMENTS + 2);
+        assert!((lines[0].a - Vector2::new(4., -1.)).norm() < 1e-5);
+        assert!((lines[CIRCLE_SEGMENTS / 2].b - Vector2::new(0., 1.)).norm() < 1e-5);
+        assert_eq!(lines[0].a, lines[CIRCLE_SEGMENTS + 1].b);
+    }
+
+    #[test]
+    fn world() {
+        let map: MapColliders = "
+            polygon 0,0 4,0 4,1
+            oneway 4,4 0,4
+            vent 3 2,2 0.75
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(lines(&map.world).len(), 3 + 1 + CIRCLE_SEGMENTS);
+
+        let svg = svg(&map.world);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"<polygon points="0,0 4,0 4,1" stroke="black"/>"#));
+        assert!(svg.contains(r#"<line x1="4" y1="4" x2="0" y2="4" stroke="blue"/>"#));
+        assert!(svg.contains(r#"<circle cx="2" cy="2" r="0.75" stroke="green""#));
+    }
+}
@@ -0,0 +1,328 @@
+//! GJK and EPA.
+//!
+//! The separating axis test tries the normal of every edge of both shapes, so
+//! it slows down for shapes with lots of corners. GJK only asks each shape for
+//! its furthest point along a direction, through [`Geometry::support`], and
+//! usually finishes in a few steps however many corners there are. When the
+//! shapes overlap, EPA works out how deep they go from where GJK stopped.
+//!
+//! Both work on the Minkowski difference of the shapes, every point of the
+//! first minus every point of the second, which holds the origin exactly
+//! when the shapes overlap. Circles and capsules take part as their center
+//! or middle segment, with their radius added back afterward.
+
+use crate::math::Vector2;
+
+use super::shape::closest_on_segment;
+use super::{Contact, Edge, Geometry, ShapeRef};
+
+/// How many steps GJK and EPA take before settling on what they have.
+///
+/// The cores of shapes are all corners, so they finish well before this. It
+/// only stops rounding errors from going around in circles.
+const MAX_ITERATIONS: usize = 32;
+
+/// How close to the outline of the difference an answer has to be.
+const TOLERANCE: f32 = 1e-5;
+
+/// Tests two shapes for a collision, like [`collide`](super::collide).
+///
+/// The normal is the shortest way out, which isn't always the way away from
+/// the center of the other shape. A shape pushed deep enough into an edge can
+/// come out the other side.
+pub fn collide<'a, 'b>(a: impl Into<ShapeRef<'a>>, b: impl Into<ShapeRef<'b>>) -> Option<Contact> {
+    collide_within(a, b, 0.)
+}
+
+/// Like [`collide`], but shapes have to overlap by more than `tolerance` to
+/// collide, like [`collide_within`](super::collide_within).
+pub fn collide_within<'a, 'b>(
+    a: impl Into<ShapeRef<'a>>,
+    b: impl Into<ShapeRef<'b>>,
+    tolerance: f32,
+) -> Option<Contact> {
+    let (a, b) = (a.into(), b.into());
+    let radius = radius(&a) + radius(&b);
+
+    let (normal, depth) = match enclose(&a, &b) {
+        Some(simplex) => {
+            let (normal, depth) = expand(&a, &b, simplex);
+            (-normal, depth + radius)
+        }
+        // the cores are apart, but the radius around them might still reach
+        None => {
+            let closest = closest(&a, &b);
+            let normal = closest
+                .try_normalize(f32::EPSILON)
+                .or_else(|| (a.center() - b.center()).try_normalize(f32::EPSILON))
+                .unwrap_or_else(Vector2::x);
+
+            (normal, radius - closest.norm())
+        }
+    };
+
+    // shapes that only touch don't collide
+    if depth <= tolerance {
+        return None;
+    }
+
+    Some(Contact { normal, depth })
+}
+
+/// How far apart two shapes are, or `0` if they overlap, like
+/// [`distance`](super::distance).
+pub fn distance<'a, 'b>(a: impl Into<ShapeRef<'a>>, b: impl Into<ShapeRef<'b>>) -> f32 {
+    let (a, b) = (a.into(), b.into());
+
+    (closest(&a, &b).norm() - radius(&a) - radius(&b)).max(0.)
+}
+
+/// How far out a round shape goes from its core.
+fn radius(shape: &ShapeRef) -> f32 {
+    match *shape {
+        ShapeRef::Circle(circle) => circle.radius,
+        ShapeRef::Capsule(capsule) => capsule.radius,
+        _ => 0.,
+    }
+}
+
+/// The point of the core of a shape furthest along a direction.
+///
+/// Round shapes would need endless corners to get right, so their radius is
+/// left out and added back at the end. What's left of them is a point or a
+/// segment.
+fn support(shape: &ShapeRef, direction: Vector2) -> Vector2 {
+    match *shape {
+        ShapeRef::Circle(circle) => circle.center,
+        ShapeRef::Capsule(capsule) => Edge::new(capsule.a, capsule.b).support(direction),
+        _ => shape.support(direction),
+    }
+}
+
+/// The point of the difference of the cores of two shapes furthest along a
+/// direction.
+fn difference(a: &ShapeRef, b: &ShapeRef, direction: Vector2) -> Vector2 {
+    support(a, direction) - support(b, -direction)
+}
+
+/// Runs GJK for the point of the difference of the cores of two shapes
+/// closest to the origin, which is zero if they overlap.
+fn closest(a: &ShapeRef, b: &ShapeRef) -> Vector2 {
+    let mut simplex = vec![difference(a, b, a.center() - b.center())];
+
+    for _ in 0..MAX_ITERATIONS {
+        let closest = match *simplex.as_slice() {
+            [point] => point,
+            [first, second] => closest_on_segment(first, second, Vector2::zeros()),
+            _ => unreachable!("the simplex is cut back to a line every step"),
+        };
+
+        if closest.norm_squared() <= TOLERANCE * TOLERANCE {
+            return Vector2::zeros();
+        }
+
+        let point = difference(a, b, -closest);
+
+        // nothing further toward the origin, so this is as close as it gets
+        if closest.dot(&closest) - point.dot(&closest) <= TOLERANCE * closest.norm() {
+            return closest;
+        }
+
+        // keep whichever line through the new point is closer, unless the
+        // triangle of all three holds the origin
+        simplex = match *simplex.as_slice() {
+            [first] => vec![first, point],
+            [first, second] => {
+                if triangle_contains(first, second, point) {
+                    return Vector2::zeros();
+                }
+
+                let to_first = closest_on_segment(first, point, Vector2::zeros());
+                let to_second = closest_on_segment(second, point, Vector2::zeros());
+
+                if to_first.norm_squared() <= to_second.norm_squared() {
+                    vec![first, point]
+                } else {
+                    vec![second, point]
+                }
+            }
+            _ => unreachable!("the simplex is cut back to a line every step"),
+        };
+    }
+
+    match *simplex.as_slice() {
+        [first, second] => closest_on_segment(first, second, Vector2::zeros()),
+        _ => simplex[0],
+    }
+}
+
+/// Runs GJK, returning a triangle of the difference around the origin if the
+/// cores of the shapes overlap.
+fn enclose(a: &ShapeRef, b: &ShapeRef) -> Option<Vec<Vector2>> {
+    let start = a.center() - b.center();
+    let mut direction = if start == Vector2::zeros() { Vector2::x() } else { start };
+
+    let mut simplex = vec![difference(a, b, direction)];
+    direction = -simplex[0];
+
+    for _ in 0..MAX_ITERATIONS {
+        // the origin is on the simplex
+        if direction == Vector2::zeros() {
+            return None;
+        }
+
+        let point = difference(a, b, direction);
+
+        // the furthest the difference goes doesn't reach the origin
+        if point.dot(&direction) <= 0. {
+            return None;
+        }
+
+        simplex.push(point);
+
+        match *simplex.as_slice() {
+            [second, first] => {
+                // toward the origin, square to the line
+                let edge = second - first;
+                let normal = Vector2::new(-edge.y, edge.x);
+                direction = if normal.dot(&-first) >= 0. { normal } else { -normal };
+            }
+            [third, second, first] => {
+                let (to_second, to_third) = (second - first, third - first);
+
+                // the normals of the two new edges, facing away from the
+                // corner left out of each
+                let mut second_out = Vector2::new(-to_second.y, to_second.x);
+                if second_out.dot(&to_third) > 0. {
+                    second_out = -second_out;
+                }
+                let mut third_out = Vector2::new(-to_third.y, to_third.x);
+                if third_out.dot(&to_second) > 0. {
+                    third_out = -third_out;
+                }
+
+                if second_out.dot(&-first) > 0. {
+                    simplex = vec![second, first];
+                    direction = second_out;
+                } else if third_out.dot(&-first) > 0. {
+                    simplex = vec![third, first];
+                    direction = third_out;
+                } else {
+                    return Some(simplex);
+                }
+            }
+            _ => unreachable!("the simplex never grows past a triangle"),
+        }
+    }
+
+    None
+}
+
+/// Runs EPA, growing the triangle from GJK out to the edge of the difference
+/// closest to the origin, and returning its normal and distance.
+fn expand(a: &ShapeRef, b: &ShapeRef, mut polytope: Vec<Vector2>) -> (Vector2, f32) {
+    // counter-clockwise, so the normals of the edges face out
+    if (polytope[1] - polytope[0]).perp(&(polytope[2] - polytope[0])) < 0. {
+        polytope.reverse();
+    }
+
+    let mut closest = (Vector2::x(), 0.);
+
+    for _ in 0..MAX_ITERATIONS {
+        let nearest = (0..polytope.len())
+            .filter_map(|index| {
+                let (start, end) = (polytope[index], polytope[(index + 1) % polytope.len()]);
+                let edge = end - start;
+                let normal = Vector2::new(edge.y, -edge.x).try_normalize(f32::EPSILON)?;
+
+                Some((index, normal, normal.dot(&start)))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+
+        let (index, normal, distance) = match nearest {
+            Some(nearest) => nearest,
+            None => return closest,
+        };
+        closest = (normal, distance);
+
+        let point = difference(a, b, normal);
+
+        // the edge is already on the outline
+        if point.dot(&normal) - distance <= TOLERANCE {
+            break;
+        }
+
+        polytope.insert(index + 1, point);
+    }
+
+    closest
+}
+
+/// Whether a triangle holds the origin, going either way around.
+fn triangle_contains(a: Vector2, b: Vector2, c: Vector2) -> bool {
+    let sides = [(b - a).perp(&-a), (c - b).perp(&-b), (a - c).perp(&-c)];
+
+    sides.iter().all(|side| *side >= 0.) || sides.iter().all(|side| *side <= 0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::{Capsule, Circle, Edge, Obb, Polygon, Shape};
+    use crate::math::angle::Angle;
+
+    fn shapes() -> Vec<Shape> {
+        vec![
+            Polygon::rect(Vector2::zeros(), Vector2::new(1., 1.)).into(),
+            Polygon::rect(Vector2::new(1.5, 0.2), Vector2::new(1., 1.)).into(),
+            Polygon::rect(Vector2::new(4., 0.), Vector2::new(1., 1.)).into(),
+            Polygon::regular(Vector2::new(0.5, 2.), 1., 12).unwrap().into(),
+            Polygon::triangle(
+                Vector2::new(-3., -1.),
+                Vector2::new(-1.2, -1.),
+                Vector2::new(-2., 2.),
+            )
+            .unwrap()
+            .into(),
+            Circle::new(Vector2::new(0., 1.5), 1.).into(),
+            Circle::new(Vector2::new(-2., 3.), 0.5).into(),
+            Capsule::new(Vector2::new(-2., -2.), Vector2::new(2., -2.), 0.5).into(),
+            Obb::new(Vector2::new(2., 2.), Vector2::new(1., 0.5), Angle::from_degrees(30.)).into(),
+        ]
+    }
+
+    #[test]
+    fn agrees_with_sat() {
+        let shapes = shapes();
+
+        for a in &shapes {
+            for b in &shapes {
+                let (gjk, sat) = (collide(a, b), super::super::collide(a, b));
+                assert_eq!(gjk.is_some(), sat.is_some(), "{:?} {:?}", a, b);
+
+                // a shape against itself could be pushed out any side
+                if let (Some(gjk), Some(sat)) = (gjk, sat) {
+                    assert!((gjk.depth - sat.depth).abs() < 1e-3, "{:?} {:?}", a, b);
+                    assert!(a == b || (gjk.normal - sat.normal).norm() < 1e-2, "{:?} {:?}", a, b);
+                }
+
+                let (gjk, exact) = (distance(a, b), super::super::distance(a, b));
+                assert!((gjk - exact).abs() < 1e-3, "{} != {} {:?} {:?}", gjk, exact, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn edges() {
+        let floor = Edge::new(Vector2::new(-5., 0.), Vector2::new(5., 0.));
+        let player = Circle::new(Vector2::new(1., 0.3), 0.5);
+
+        let contact = collide(&player, &floor).unwrap();
+        assert!((contact.normal - Vector2::y()).norm() < 1e-3);
+        assert!((contact.depth - 0.2).abs() < 1e-3);
+
+        assert!(collide(&Circle::new(Vector2::new(1., 0.5), 0.5), &floor).is_none());
+        assert!((distance(&Circle::new(Vector2::new(1., 2.), 0.5), &floor) - 1.5).abs() < 1e-3);
+    }
+}
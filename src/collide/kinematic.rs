@@ -0,0 +1,138 @@
+//! Kinematic movement.
+
+use crate::math::Vector2;
+
+use super::{ColliderId, Contact, Geometry, World};
+
+/// How many times a step tries to push a collider out of what it hit.
+const MAX_ITERATIONS: usize = 4;
+
+/// The most steps a single move is split into.
+const MAX_STEPS: u32 = 64;
+
+/// Moves a collider, sliding along whatever it runs into, and returns how
+/// far it actually moved.
+///
+/// A collider is stopped by everything that [blocks](super::Collider::blocks)
+/// it, so triggers are passed through and one-way edges only stop it from
+/// the front. Running into a wall at an angle keeps the part of the movement along
+/// the wall, like players do. Long moves are split into steps no longer than
+/// half the collider, so it can't skip through thin walls.
+pub fn move_and_slide(world: &mut World, id: ColliderId, motion: Vector2) -> Vector2 {
+    let size = match world.get(id) {
+        Some(collider) => collider.shape.aabb().size(),
+        None => return Vector2::zeros(),
+    };
+
+    let max_step = (size.x.min(size.y) / 2.).max(f32::EPSILON);
+    let steps = ((motion.norm() / max_step).ceil() as u32).clamp(1, MAX_STEPS);
+    let step = motion / steps as f32;
+    let mut moved = Vector2::zeros();
+
+    for _ in 0..steps {
+        world.translate(id, step);
+        moved += step;
+
+        for _ in 0..MAX_ITERATIONS {
+            let contact = match deepest_contact(world, id, step) {
+                Some(contact) => contact,
+                None => break,
+            };
+
+            let push = contact.normal * contact.depth;
+            world.translate(id, push);
+            moved += push;
+        }
+    }
+
+    moved
+}
+
+/// The deepest contact of a collider with anything that blocks it.
+fn deepest_contact(world: &World, id: ColliderId, motion: Vector2) -> Option<Contact> {
+    let collider = world.get(id)?;
+
+    world
+        .query_shape(collider.shape.to_ref(), collider.mask)
+        .filter_map(|(other, contact)| {
+            let other = world.get(other)?;
+            other.blocks(collider, &contact, motion).then_some(contact)
+        })
+        .max_by(|a, b| a.depth.total_cmp(&b.depth))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::{Circle, Collider, ColliderKind, Edge, Polygon};
+
+    fn near(a: Vector2, b: Vector2) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    #[test]
+    fn slides_along_walls() {
+        let mut world = World::default();
+        world.insert(Collider::new(0, Edge::new(Vector2::new(-10., 0.), Vector2::new(10., 0.))));
+        let player = world.insert(Collider::new(1, Circle::new(Vector2::new(0., 1.), 0.5)));
+
+        let moved = move_and_slide(&mut world, player, Vector2::new(2., -2.));
+        assert!(near(moved, Vector2::new(2., -0.5)), "moved {}", moved);
+    }
+
+    #[test]
+    fn stops_in_corners() {
+        let mut world = World::default();
+        let corner = Polygon::new(vec![
+            Vector2::new(1., -5.),
+            Vector2::new(5., -5.),
+            Vector2::new(5., 5.),
+            Vector2::new(1., 5.),
+        ])
+        .unwrap();
+        world.insert(Collider::new(0, corner));
+        world.insert(Collider::new(0, Edge::new(Vector2::new(-10., 0.), Vector2::new(10., 0.))));
+        let player = world.insert(Collider::new(1, Circle::new(Vector2::new(0., 1.), 0.5)));
+
+        let moved = move_and_slide(&mut world, player, Vector2::new(3., -3.));
+        assert!(near(moved, Vector2::new(0.5, -0.5)), "moved {}", moved);
+    }
+
+    #[test]
+    fn one_way_edges() {
+        let mut world = World::default();
+        let edge = Collider {
+            kind: ColliderKind::OneWay,
+            // facing up
+            ..Collider::new(0, Edge::new(Vector2::new(-10., 0.), Vector2::new(10., 0.)))
+        };
+        world.insert(edge);
+        world.insert(Collider::trigger(0, Circle::new(Vector2::new(0., -2.), 1.)));
+
+        let player = world.insert(Collider::new(1, Circle::new(Vector2::new(0., -3.), 0.5)));
+
+        // from behind, and through the trigger
+        let moved = move_and_slide(&mut world, player, Vector2::new(0., 4.));
+        assert!(near(moved, Vector2::new(0., 4.)), "moved {}", moved);
+
+        // and back from the front
+        let moved = move_and_slide(&mut world, player, Vector2::new(0., -4.));
+        assert!(near(moved, Vector2::new(0., -0.5)), "moved {}", moved);
+    }
+
+    #[test]
+    fn ghosts_pass_through() {
+        let mut world = World::default();
+        world.insert(Collider::new(0, Edge::new(Vector2::new(-10., 0.), Vector2::new(10., 0.))));
+
+        let ghost = Collider {
+            mask: 0,
+            ..Collider::new(1, Circle::new(Vector2::new(0., 1.), 0.5))
+        };
+        let ghost = world.insert(ghost);
+
+        let moved = move_and_slide(&mut world, ghost, Vector2::new(0., -3.));
+        assert!(near(moved, Vector2::new(0., -3.)));
+    }
+}
@@ -0,0 +1,242 @@
+//! Contact manifolds.
+//!
+//! A [`Contact`] is enough to push shapes apart. Friction, and drawing where
+//! things hit, also need to know where the shapes touch.
+
+use crate::math::Vector2;
+
+use super::{middle, points, separate, Contact, ShapeRef};
+
+/// How much less deep than the deepest corner another corner can be and still
+/// count as touching, so edges lying flat against each other touch along
+/// their length.
+const FLAT_TOLERANCE: f32 = 1e-4;
+
+/// Which of the two shapes in a test something belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    First,
+    Second,
+}
+
+/// The part of a shape a separating axis came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// The normal of an edge, by the index of the corner it starts at.
+    ///
+    /// An [`Edge`](super::Edge) only has the one, at index 0.
+    Edge(usize),
+    /// The axis from the center of a circle, or either end of a capsule, to
+    /// the closest corner of the other shape.
+    Circle,
+}
+
+/// Everything about how two shapes overlap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Manifold {
+    pub contact: Contact,
+    /// The shape the axis of the contact came from.
+    pub side: Side,
+    pub feature: Feature,
+    points: [Vector2; 2],
+    len: usize,
+}
+
+impl Manifold {
+    /// Where the shapes touch.
+    ///
+    /// This is a single point, unless two edges lie against each other, in
+    /// which case it is both ends of where they overlap.
+    pub fn points(&self) -> &[Vector2] {
+        &self.points[..self.len]
+    }
+}
+
+/// Tests two shapes for a collision like [`collide`](super::collide), and
+/// works out where they touch.
+pub fn manifold<'a, 'b>(
+    a: impl Into<ShapeRef<'a>>,
+    b: impl Into<ShapeRef<'b>>,
+) -> Option<Manifold> {
+    let (a, b) = (a.into(), b.into());
+    let (contact, side, feature) = separate(&a, &b, 0.)?;
+
+    // the normal pushes `a` out of `b`, so the deepest part of `a` is the
+    // part facing away from it
+    let a_support = support(&a, -contact.normal);
+    let b_support = support(&b, contact.normal);
+
+    let (points, len) = match (a_support, b_support) {
+        (Support::Flat(a), Support::Flat(b)) => clip(contact.normal, a, b),
+        (Support::Corner(point), Support::Corner(other))
+        | (Support::Round(point), Support::Round(other)) => ([(point + other) / 2., point], 1),
+        (Support::Corner(point), _) | (_, Support::Corner(point)) => ([point, point], 1),
+        (Support::Round(point), _) | (_, Support::Round(point)) => ([point, point], 1),
+    };
+
+    Some(Manifold { contact, side, feature, points, len })
+}
+
+/// The deepest part of a shape in a direction.
+enum Support {
+    /// A corner.
+    Corner(Vector2),
+    /// The edge of a circle.
+    Round(Vector2),
+    /// An edge facing the direction, by its ends.
+    Flat([Vector2; 2]),
+}
+
+fn support(shape: &ShapeRef, direction: Vector2) -> Support {
+    // round shapes are their middle, pushed out by their radius
+    if let Some((ends, radius)) = middle(shape) {
+        let offset = direction * radius;
+
+        return match deepest(&ends, direction, ends[0]) {
+            Support::Flat([a, b]) => Support::Flat([a + offset, b + offset]),
+            Support::Corner(point) | Support::Round(point) => Support::Round(point + offset),
+        };
+    }
+
+    let points = points(shape);
+
+    deepest(points.as_slice(), direction, shape.center())
+}
+
+/// The deepest corner of some points in a direction, or the ends of the edge
+/// if more than one are as deep. With no points at all, it is `fallback`.
+fn deepest(points: &[Vector2], direction: Vector2, fallback: Vector2) -> Support {
+    let tangent = Vector2::new(-direction.y, direction.x);
+
+    let depth = points.iter().map(|point| point.dot(&direction)).fold(f32::NEG_INFINITY, f32::max);
+    let mut deepest =
+        points.iter().copied().filter(|point| point.dot(&direction) >= depth - FLAT_TOLERANCE);
+
+    let first = match deepest.next() {
+        Some(first) => first,
+        None => return Support::Corner(fallback),
+    };
+
+    // straight corners in the middle of an edge don't matter, only its ends
+    let (low, high) = deepest.fold((first, first), |(low, high), point| {
+        let along = point.dot(&tangent);
+        (
+            if along < low.dot(&tangent) { point } else { low },
+            if along > high.dot(&tangent) { point } else { high },
+        )
+    });
+
+    if low == high {
+        Support::Corner(low)
+    } else {
+        Support::Flat([low, high])
+    }
+}
+
+/// Where two edges lying against each other overlap, along the edge of `b`.
+fn clip(normal: Vector2, a: [Vector2; 2], b: [Vector2; 2]) -> ([Vector2; 2], usize) {
+    let tangent = Vector2::new(-normal.y, normal.x);
+    let range = |[start, end]: [Vector2; 2]| {
+        let (start, end) = (start.dot(&tangent), end.dot(&tangent));
+        (start.min(end), start.max(end))
+    };
+
+    let ((a_min, a_max), (b_min, b_max)) = (range(a), range(b));
+    let (low, high) = (a_min.max(b_min), a_max.min(b_max));
+
+    // the edge of `b` isn't parallel to the tangent, or it wouldn't be flat
+    let edge = b[1] - b[0];
+    let along = edge.dot(&tangent);
+    let at = |value: f32| b[0] + edge * ((value - b[0].dot(&tangent)) / along);
+
+    if high - low <= FLAT_TOLERANCE || along.abs() <= f32::EPSILON {
+        let middle = (a[0] + a[1] + b[0] + b[1]) / 4.;
+        ([middle, middle], 1)
+    } else {
+        ([at(low), at(high)], 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::{Capsule, Circle, Polygon};
+    use crate::math::Rect;
+
+    fn square(center: Vector2, half: f32) -> Polygon {
+        Polygon::rect(center, Vector2::new(half, half))
+    }
+
+    fn assert_near(a: Vector2, b: Vector2) {
+        assert!((a - b).norm() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn edges_against_edges() {
+        let a = square(Vector2::new(0., 0.), 1.);
+        let b = square(Vector2::new(1.5, 0.2), 1.);
+
+        let manifold = manifold(&a, &b).unwrap();
+        assert_eq!(manifold.contact.normal, Vector2::new(-1., 0.));
+        assert_eq!((manifold.side, manifold.feature), (Side::First, Feature::Edge(1)));
+
+        let mut points = manifold.points().to_vec();
+        points.sort_by(|a, b| a.y.total_cmp(&b.y));
+        assert_near(points[0], Vector2::new(0.5, -0.8));
+        assert_near(points[1], Vector2::new(0.5, 1.));
+    }
+
+    #[test]
+    fn corners_against_edges() {
+        let wedge = Polygon::new(vec![
+            Vector2::new(0., -0.2),
+            Vector2::new(1., 1.),
+            Vector2::new(-1., 1.5),
+        ])
+        .unwrap();
+        let floor = square(Vector2::new(0., -1.), 1.);
+
+        let manifold = manifold(&wedge, &floor).unwrap();
+        assert_eq!(manifold.contact.normal, Vector2::new(0., 1.));
+        assert_eq!((manifold.side, manifold.feature), (Side::Second, Feature::Edge(2)));
+        assert_eq!(manifold.points(), [Vector2::new(0., -0.2)]);
+
+        // the other way around, the corner is still where they touch
+        let manifold = super::manifold(&floor, &wedge).unwrap();
+        assert_eq!((manifold.side, manifold.feature), (Side::First, Feature::Edge(2)));
+        assert_eq!(manifold.points(), [Vector2::new(0., -0.2)]);
+    }
+
+    #[test]
+    fn circles() {
+        let a = Circle::new(Vector2::new(0., 0.), 1.);
+        let b = Circle::new(Vector2::new(0., 1.5), 1.);
+
+        let manifold = manifold(&a, &b).unwrap();
+        assert_eq!(manifold.feature, Feature::Circle);
+        assert_eq!(manifold.points(), [Vector2::new(0., 0.75)]);
+
+        assert!(super::manifold(&a, &Circle::new(Vector2::new(0., 2.), 1.)).is_none());
+    }
+
+    #[test]
+    fn capsules() {
+        let capsule = Capsule::new(Vector2::new(0., 0.), Vector2::new(4., 0.), 1.);
+        let floor = Polygon::from(Rect::new(Vector2::new(-2., -3.), Vector2::new(6., -0.8)));
+
+        // lying flat, it touches along its whole length
+        let manifold = manifold(&capsule, &floor).unwrap();
+        assert_near(manifold.contact.normal, Vector2::new(0., 1.));
+
+        let mut points = manifold.points().to_vec();
+        points.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert_eq!(points.len(), 2);
+        assert!((points[0].x, points[1].x) == (0., 4.));
+
+        // standing up, only its end does
+        let standing = Capsule::new(Vector2::new(0., 0.), Vector2::new(0., 4.), 1.);
+        let manifold = super::manifold(&standing, &floor).unwrap();
+        assert_eq!(manifold.points(), [Vector2::new(0., -1.)]);
+    }
+}
@@ -0,0 +1,279 @@
+//! Map collider files.
+//!
+//! Community servers can describe the walls of a custom map in a text file
+//! instead of hardcoding vertices. Each line is one shape, with points
+//! written as `x,y`:
+//!
+//! ```text
+//! # the outer wall of the cafeteria
+//! polygon -5.5,-2.5 5,-2.5 5,6.5 -5.5,6.5
+//! circle 0,1.5 0.8
+//! edge -5.5,1 -5.5,3
+//! # a ledge that can only be jumped down
+//! oneway 2,0 4,0
+//! # vent 3, with a radius players have to be within to use it
+//! vent 3 9.38,-6.44 0.75
+//! ```
+//!
+//! Blank lines and everything after a `#` are ignored.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+use crate::math::Vector2;
+
+use super::{
+    Circle, Collider, ColliderId, ColliderKind, Edge, Polygon, PolygonError, Shape, World,
+};
+
+/// The layer walls are put on.
+pub const WALLS: u32 = 1 << 0;
+
+/// The layer vent zones are put on.
+pub const VENTS: u32 = 1 << 1;
+
+/// The body every collider of the map belongs to.
+pub const MAP_BODY: u32 = u32::MAX;
+
+/// The colliders of a map.
+#[derive(Clone, Debug)]
+pub struct MapColliders {
+    /// A world with the walls on [`WALLS`] and the vent zones, as triggers,
+    /// on [`VENTS`].
+    pub world: World,
+    /// The vent zones, by vent id.
+    pub vents: BTreeMap<u8, ColliderId>,
+}
+
+impl MapColliders {
+    /// Parses a collider file.
+    pub fn parse(source: &str) -> Result<MapColliders, ParseError> {
+        let mut map = MapColliders {
+            world: World::default(),
+            vents: BTreeMap::new(),
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+
+            map.parse_line(line).map_err(|kind| ParseError { line: index + 1, kind })?;
+        }
+
+        Ok(map)
+    }
+
+    /// Reads and parses a collider file.
+    ///
+    /// Parse errors are returned as [`io::ErrorKind::InvalidData`].
+    pub fn read<R>(mut reader: R) -> io::Result<MapColliders>
+    where R: Read {
+        let mut source = String::new();
+        reader.read_to_string(&mut source)?;
+
+        MapColliders::parse(&source).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ParseErrorKind> {
+        let mut words = line.split_whitespace();
+
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => return Ok(()),
+        };
+
+        let collider = match keyword {
+            "polygon" => {
+                let points = words.map(point).collect::<Result<Vec<_>, _>>()?;
+                let polygon = Polygon::new(points).map_err(ParseErrorKind::InvalidPolygon)?;
+
+                wall(polygon, ColliderKind::Solid)
+            }
+            "circle" => {
+                let center = point(next(&mut words)?)?;
+                let radius = number(next(&mut words)?)?;
+                end(&mut words)?;
+
+                wall(Circle::new(center, radius), ColliderKind::Solid)
+            }
+            "edge" | "oneway" => {
+                let a = point(next(&mut words)?)?;
+                let b = point(next(&mut words)?)?;
+                end(&mut words)?;
+
+                let kind = match keyword {
+                    "edge" => ColliderKind::Solid,
+                    _ => ColliderKind::OneWay,
+                };
+                wall(Edge::new(a, b), kind)
+            }
+            "vent" => {
+                let word = next(&mut words)?;
+                let id = word.parse().map_err(|_| ParseErrorKind::InvalidNumber(word.into()))?;
+                let center = point(next(&mut words)?)?;
+                let radius = number(next(&mut words)?)?;
+                end(&mut words)?;
+
+                if self.vents.contains_key(&id) {
+                    return Err(ParseErrorKind::DuplicateVent(id));
+                }
+
+                let zone = Collider {
+                    layer: VENTS,
+                    ..Collider::trigger(MAP_BODY, Circle::new(center, radius))
+                };
+
+                self.vents.insert(id, self.world.insert(zone));
+                return Ok(());
+            }
+            keyword => return Err(ParseErrorKind::UnknownShape(keyword.into())),
+        };
+
+        self.world.insert(collider);
+        Ok(())
+    }
+}
+
+impl FromStr for MapColliders {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<MapColliders, ParseError> {
+        MapColliders::parse(s)
+    }
+}
+
+fn wall(shape: impl Into<Shape>, kind: ColliderKind) -> Collider {
+    Collider {
+        layer: WALLS,
+        kind,
+        ..Collider::new(MAP_BODY, shape)
+    }
+}
+
+fn next<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<&'a str, ParseErrorKind> {
+    words.next().ok_or(ParseErrorKind::MissingValue)
+}
+
+fn end<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<(), ParseErrorKind> {
+    match words.next() {
+        Some(word) => Err(ParseErrorKind::UnexpectedValue(word.into())),
+        None => Ok(()),
+    }
+}
+
+fn number(word: &str) -> Result<f32, ParseErrorKind> {
+    match word.parse::<f32>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(ParseErrorKind::InvalidNumber(word.into())),
+    }
+}
+
+fn point(word: &str) -> Result<Vector2, ParseErrorKind> {
+    let (x, y) = word.split_once(',').ok_or_else(|| ParseErrorKind::InvalidPoint(word.into()))?;
+
+    Ok(Vector2::new(number(x)?, number(y)?))
+}
+
+/// A collider file couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line the error is on, starting at 1.
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// What was wrong with a line of a collider file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The line doesn't start with a known shape.
+    UnknownShape(String),
+    /// A number couldn't be parsed, or isn't finite.
+    InvalidNumber(String),
+    /// A point isn't written as `x,y`.
+    InvalidPoint(String),
+    /// The line ended too early.
+    MissingValue,
+    /// The line has more values than the shape takes.
+    UnexpectedValue(String),
+    /// A polygon's points don't make a convex polygon.
+    InvalidPolygon(PolygonError),
+    /// Two vents have the same id.
+    DuplicateVent(u8),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ParseErrorKind::UnknownShape(shape) => write!(f, "unknown shape {:?}", shape),
+            ParseErrorKind::InvalidNumber(number) => write!(f, "invalid number {:?}", number),
+            ParseErrorKind::InvalidPoint(point) => write!(f, "invalid point {:?}", point),
+            ParseErrorKind::MissingValue => f.write_str("missing value"),
+            ParseErrorKind::UnexpectedValue(value) => write!(f, "unexpected value {:?}", value),
+            ParseErrorKind::InvalidPolygon(err) => err.fmt(f),
+            ParseErrorKind::DuplicateVent(id) => write!(f, "vent {} is defined twice", id),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::math::Rect;
+
+    const SOURCE: &str = "
+        # a room
+        polygon 0,0 4,0 4,1 0,1
+        circle 2,5 0.5  # a pillar
+        edge 0,0 0,4
+        oneway 4,4 0,4
+
+        vent 3 2,2 0.75
+    ";
+
+    #[test]
+    fn parse() {
+        let map: MapColliders = SOURCE.parse().unwrap();
+
+        assert_eq!(map.world.len(), 5);
+        assert_eq!(map.vents.len(), 1);
+
+        let region = Rect::new(Vector2::new(1.5, 1.5), Vector2::new(2.5, 2.5));
+        assert_eq!(map.world.query_region(region, VENTS), vec![map.vents[&3]]);
+        assert!(map.world.query_region(region, WALLS).is_empty());
+
+        let oneway = map.world.colliders().find(|(_, c)| c.kind == ColliderKind::OneWay);
+        assert!(oneway.is_some());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| MapColliders::parse(source).unwrap_err();
+
+        assert_eq!(
+            error("\ncube 0,0 1"),
+            ParseError {
+                line: 2,
+                kind: ParseErrorKind::UnknownShape("cube".into()),
+            },
+        );
+        assert_eq!(
+            error("polygon 0,0 1,1").kind,
+            ParseErrorKind::InvalidPolygon(PolygonError::TooFewPoints),
+        );
+        assert_eq!(
+            error("polygon 0,0 2,0 1,0.5 2,2 0,2").kind,
+            ParseErrorKind::InvalidPolygon(PolygonError::Concave),
+        );
+        assert_eq!(error("circle 0,0").kind, ParseErrorKind::MissingValue);
+        assert_eq!(error("circle 0,0 1 2").kind, ParseErrorKind::UnexpectedValue("2".into()));
+        assert_eq!(error("edge 0;0 1,1").kind, ParseErrorKind::InvalidPoint("0;0".into()));
+        assert_eq!(error("circle 0,0 nan").kind, ParseErrorKind::InvalidNumber("nan".into()));
+        assert_eq!(error("vent 1 0,0 1\nvent 1 2,2 1").kind, ParseErrorKind::DuplicateVent(1));
+    }
+}
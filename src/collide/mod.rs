@@ -0,0 +1,727 @@
+//! Collision detection.
+//!
+//! Shapes are tested against each other with the separating axis theorem:
+//! two convex shapes overlap unless there is an axis their projections don't
+//! overlap on, and for polygons it is enough to try the normals of their
+//! edges. [`collide`] finds how to push two shapes apart, and [`manifold`]
+//! also finds where they touch.
+//!
+//! Testing every pair of shapes gets slow quickly, so a
+//! [`SpatialHash`](broadphase::SpatialHash) or a [`Quadtree`](quadtree::Quadtree)
+//! narrows down which pairs are worth testing first. A [`World`] puts the two
+//! together, and [`move_and_slide`] moves things around in one.
+
+pub mod broadphase;
+pub mod debug;
+pub mod gjk;
+mod kinematic;
+mod manifold;
+pub mod map;
+pub mod quadtree;
+mod ray;
+mod shape;
+mod sweep;
+pub mod vision;
+mod world;
+
+pub use kinematic::move_and_slide;
+pub use manifold::{manifold, Feature, Manifold, Side};
+pub use ray::{Ray, RayHit};
+pub use sweep::{sweep, TimeOfImpact};
+pub use shape::{
+    Capsule, Circle, Edge, Geometry, Obb, Polygon, PolygonError, Projection, Shape, ShapeRef,
+};
+pub use world::{Collider, ColliderId, ColliderKind, Event, Pair, World, DEFAULT_CELL_SIZE};
+
+use crate::math::Vector2;
+
+use shape::{closest_between_segments, edges};
+
+/// How two shapes overlap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    /// The direction to push the first shape to get it out of the second.
+    ///
+    /// Always normalized.
+    pub normal: Vector2,
+    /// How far the first shape has to be pushed.
+    pub depth: f32,
+}
+
+/// How to test two shapes for a collision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The separating axis test, with [`collide`].
+    #[default]
+    Sat,
+    /// GJK and EPA, with [`gjk::collide`].
+    ///
+    /// Faster for shapes with lots of corners.
+    Gjk,
+}
+
+impl Algorithm {
+    /// Tests two shapes for a collision.
+    pub fn collide<'a, 'b>(
+        self,
+        a: impl Into<ShapeRef<'a>>,
+        b: impl Into<ShapeRef<'b>>,
+    ) -> Option<Contact> {
+        self.collide_within(a, b, 0.)
+    }
+
+    /// Tests two shapes for a collision, ignoring overlaps up to `tolerance`
+    /// deep.
+    pub fn collide_within<'a, 'b>(
+        self,
+        a: impl Into<ShapeRef<'a>>,
+        b: impl Into<ShapeRef<'b>>,
+        tolerance: f32,
+    ) -> Option<Contact> {
+        match self {
+            Algorithm::Sat => collide_within(a, b, tolerance),
+            Algorithm::Gjk => gjk::collide_within(a, b, tolerance),
+        }
+    }
+
+    /// How far apart two shapes are, or `0` if they overlap.
+    pub fn distance<'a, 'b>(self, a: impl Into<ShapeRef<'a>>, b: impl Into<ShapeRef<'b>>) -> f32 {
+        match self {
+            Algorithm::Sat => distance(a, b),
+            Algorithm::Gjk => gjk::distance(a, b),
+        }
+    }
+}
+
+/// Tests two shapes for a collision.
+///
+/// Shapes that only touch don't collide.
+pub fn collide<'a, 'b>(
+    a: impl Into<ShapeRef<'a>>,
+    b: impl Into<ShapeRef<'b>>,
+) -> Option<Contact> {
+    collide_within(a, b, 0.)
+}
+
+/// Like [`collide`], but shapes have to overlap by more than `tolerance` to
+/// collide.
+///
+/// Shapes resting against each other come out a tiny bit apart or a tiny bit
+/// overlapping depending on rounding. A small tolerance makes them agree
+/// that they only touch.
+pub fn collide_within<'a, 'b>(
+    a: impl Into<ShapeRef<'a>>,
+    b: impl Into<ShapeRef<'b>>,
+    tolerance: f32,
+) -> Option<Contact> {
+    separate(&a.into(), &b.into(), tolerance).map(|(contact, _, _)| contact)
+}
+
+/// Runs the separating axis test, returning the contact and where its axis
+/// came from.
+///
+/// Any axis the shapes overlap on by `tolerance` or less separates them.
+fn separate(a: &ShapeRef, b: &ShapeRef, tolerance: f32) -> Option<(Contact, Side, Feature)> {
+    if let (Some(a), Some(b)) = (middle(a), middle(b)) {
+        return separate_round(a, b, tolerance);
+    }
+
+    let (a_points, b_points) = (points(a), points(b));
+    let offset = a.center() - b.center();
+    let mut best: Option<(Contact, Side, Feature)> = None;
+    let mut best_facing = false;
+
+    let mut separated = |axis: Vector2, side: Side, feature: Feature| {
+        let (a_extent, b_extent) = (a.project(axis), b.project(axis));
+
+        // always push away from the other center, so a thin wall doesn't pull
+        // a shape through it
+        let (normal, depth) = if offset.dot(&axis) >= 0. {
+            (axis, b_extent.max - a_extent.min)
+        } else {
+            (-axis, a_extent.max - b_extent.min)
+        };
+
+        if depth <= tolerance {
+            return true;
+        }
+
+        // opposite edges of a polygon give the same axis, so on a tie the
+        // edge facing the other shape wins
+        let facing = (normal == axis) == (side == Side::Second);
+        let better = match best {
+            Some((best, _, _)) => {
+                depth < best.depth || (depth == best.depth && facing && !best_facing)
+            }
+            None => true,
+        };
+
+        if better {
+            best = Some((Contact { normal, depth }, side, feature));
+            best_facing = facing;
+        }
+
+        false
+    };
+
+    let mut first = |axis, feature| separated(axis, Side::First, feature);
+    if any_axis(a, b_points.as_slice(), b.center(), &mut first) {
+        return None;
+    }
+
+    let mut second = |axis, feature| separated(axis, Side::Second, feature);
+    if any_axis(b, a_points.as_slice(), a.center(), &mut second) {
+        return None;
+    }
+
+    best
+}
+
+/// The segment down the middle of a round shape, and its radius.
+///
+/// A circle is a segment with no length.
+fn middle(shape: &ShapeRef) -> Option<([Vector2; 2], f32)> {
+    match *shape {
+        ShapeRef::Circle(circle) => Some(([circle.center, circle.center], circle.radius)),
+        ShapeRef::Capsule(capsule) => Some(([capsule.a, capsule.b], capsule.radius)),
+        _ => None,
+    }
+}
+
+/// Two round shapes only need the distance between the closest points of
+/// their middles, which for two circles is between their centers.
+fn separate_round(
+    (a, a_radius): ([Vector2; 2], f32),
+    (b, b_radius): ([Vector2; 2], f32),
+    tolerance: f32,
+) -> Option<(Contact, Side, Feature)> {
+    let (a_closest, b_closest) = closest_between_segments(a, b);
+    let offset = a_closest - b_closest;
+    let depth = a_radius + b_radius - offset.norm();
+
+    if depth <= tolerance {
+        return None;
+    }
+
+    // middles that touch or cross have nowhere to point, so they get pushed
+    // apart by their centers, and along x if those are on top of each other
+    // too
+    let centers = (a[0] + a[1]) / 2. - (b[0] + b[1]) / 2.;
+    let normal = offset
+        .try_normalize(f32::EPSILON)
+        .or_else(|| centers.try_normalize(f32::EPSILON))
+        .unwrap_or_else(Vector2::x);
+
+    Some((Contact { normal, depth }, Side::First, Feature::Circle))
+}
+
+/// Projects many shapes onto the same axis.
+///
+/// The axis should be normalized.
+pub fn project_many<'a, I>(shapes: I, axis: Vector2) -> impl Iterator<Item = Projection> + 'a
+where I: IntoIterator<Item = ShapeRef<'a>>, I::IntoIter: 'a {
+    shapes.into_iter().map(move |shape| shape.project(axis))
+}
+
+/// Checks if two shapes overlap.
+pub fn intersects<'a, 'b>(a: impl Into<ShapeRef<'a>>, b: impl Into<ShapeRef<'b>>) -> bool {
+    collide(a, b).is_some()
+}
+
+/// How far apart two shapes are, or `0` if they overlap.
+pub fn distance<'a, 'b>(a: impl Into<ShapeRef<'a>>, b: impl Into<ShapeRef<'b>>) -> f32 {
+    let (a, b) = (a.into(), b.into());
+
+    if separate(&a, &b, 0.).is_some() {
+        return 0.;
+    }
+
+    // shapes that don't overlap are closest somewhere along their outlines,
+    // which is their segments pushed out by their radius
+    let ((a, a_radius), (b, b_radius)) = (segments(&a), segments(&b));
+
+    let closest = a
+        .iter()
+        .flat_map(|a| b.iter().map(move |b| closest_between_segments(*a, *b)))
+        .map(|(a, b)| (a - b).norm())
+        .fold(f32::INFINITY, f32::min);
+
+    (closest - a_radius - b_radius).max(0.)
+}
+
+/// The segments making up the outline of a shape, and how far out from them
+/// the shape goes.
+fn segments(shape: &ShapeRef) -> (Vec<[Vector2; 2]>, f32) {
+    match *shape {
+        ShapeRef::Polygon(polygon) => (edges(polygon.points()).map(|(a, b)| [a, b]).collect(), 0.),
+        ShapeRef::Edge(edge) => (vec![[edge.a, edge.b]], 0.),
+        ShapeRef::Circle(circle) => (vec![[circle.center, circle.center]], circle.radius),
+        ShapeRef::Capsule(capsule) => (vec![[capsule.a, capsule.b]], capsule.radius),
+        ShapeRef::Obb(obb) => (edges(&obb.corners()).map(|(a, b)| [a, b]).collect(), 0.),
+    }
+}
+
+impl<'a> From<&'a Circle> for ShapeRef<'a> {
+    fn from(circle: &'a Circle) -> ShapeRef<'a> {
+        ShapeRef::Circle(circle)
+    }
+}
+
+impl<'a> From<&'a Polygon> for ShapeRef<'a> {
+    fn from(polygon: &'a Polygon) -> ShapeRef<'a> {
+        ShapeRef::Polygon(polygon)
+    }
+}
+
+impl<'a> From<&'a Edge> for ShapeRef<'a> {
+    fn from(edge: &'a Edge) -> ShapeRef<'a> {
+        ShapeRef::Edge(edge)
+    }
+}
+
+impl<'a> From<&'a Capsule> for ShapeRef<'a> {
+    fn from(capsule: &'a Capsule) -> ShapeRef<'a> {
+        ShapeRef::Capsule(capsule)
+    }
+}
+
+impl<'a> From<&'a Obb> for ShapeRef<'a> {
+    fn from(obb: &'a Obb) -> ShapeRef<'a> {
+        ShapeRef::Obb(obb)
+    }
+}
+
+/// The corners of a shape, without allocating for edges.
+enum Points<'a> {
+    Slice(&'a [Vector2]),
+    Pair([Vector2; 2]),
+    Quad([Vector2; 4]),
+}
+
+impl<'a> Points<'a> {
+    fn as_slice(&self) -> &[Vector2] {
+        match self {
+            Points::Slice(points) => points,
+            Points::Pair(points) => points,
+            Points::Quad(points) => points,
+        }
+    }
+}
+
+fn points<'a>(shape: &ShapeRef<'a>) -> Points<'a> {
+    match *shape {
+        // round shapes have no corners
+        ShapeRef::Circle(_) | ShapeRef::Capsule(_) => Points::Slice(&[]),
+        ShapeRef::Polygon(polygon) => Points::Slice(polygon.points()),
+        ShapeRef::Edge(edge) => Points::Pair([edge.a, edge.b]),
+        ShapeRef::Obb(obb) => Points::Quad(obb.corners()),
+    }
+}
+
+/// Calls `f` with each axis a shape adds to the test, stopping once it
+/// returns `true`.
+///
+/// Circles have no edges, so their axis points at the closest corner of the
+/// other shape, or its center if it has none. Capsules have one of those for
+/// each end, besides the normal of their sides. Opposite sides of a box share
+/// an axis, so it only needs the two sides facing the other shape.
+fn any_axis<F>(shape: &ShapeRef, other: &[Vector2], other_center: Vector2, f: &mut F) -> bool
+where F: FnMut(Vector2, Feature) -> bool {
+    let round_axis = |center: Vector2| {
+        let closest = other
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                let a = (a - center).norm_squared();
+                let b = (b - center).norm_squared();
+                a.total_cmp(&b)
+            })
+            .unwrap_or(other_center);
+
+        (closest - center).try_normalize(f32::EPSILON).unwrap_or_else(Vector2::x)
+    };
+
+    match shape {
+        ShapeRef::Circle(circle) => f(round_axis(circle.center), Feature::Circle),
+        ShapeRef::Polygon(polygon) => {
+            let mut normals = polygon.normals().iter().enumerate();
+            normals.any(|(index, normal)| f(*normal, Feature::Edge(index)))
+        }
+        ShapeRef::Edge(edge) => f(edge.normal(), Feature::Edge(0)),
+        ShapeRef::Capsule(capsule) => {
+            f(Edge::new(capsule.a, capsule.b).normal(), Feature::Edge(0))
+                || f(round_axis(capsule.a), Feature::Circle)
+                || f(round_axis(capsule.b), Feature::Circle)
+        }
+        ShapeRef::Obb(obb) => {
+            let normals = obb.normals();
+            let facing = |index: usize| {
+                let toward = (other_center - obb.center).dot(&normals[index]) >= 0.;
+                if toward { index } else { index + 2 }
+            };
+
+            let (x, y) = (facing(1), facing(2) % 4);
+            f(normals[x], Feature::Edge(x)) || f(normals[y], Feature::Edge(y))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::math::angle::Angle;
+    use crate::math::transform::Transform;
+    use crate::math::Rect;
+
+    fn square(center: Vector2, half: f32) -> Polygon {
+        Polygon::rect(center, Vector2::new(half, half))
+    }
+
+    #[test]
+    fn polygons() {
+        let a = square(Vector2::new(0., 0.), 1.);
+        let b = square(Vector2::new(1.5, 0.2), 1.);
+
+        let contact = collide(&a, &b).unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-5);
+        assert_eq!(contact.normal, Vector2::new(-1., 0.));
+
+        assert!(collide(&a, &square(Vector2::new(2., 0.), 1.)).is_none());
+        assert!(collide(&a, &square(Vector2::new(3., 0.), 1.)).is_none());
+    }
+
+    #[test]
+    fn tolerance() {
+        let a = square(Vector2::new(0., 0.), 1.);
+        // only overlapping because of rounding
+        let resting = square(Vector2::new(2. - 1e-5, 0.), 1.);
+
+        assert!(collide(&a, &resting).is_some());
+        assert!(collide_within(&a, &resting, 1e-4).is_none());
+        assert!(Algorithm::Gjk.collide_within(&a, &resting, 1e-4).is_none());
+
+        let contact = collide_within(&a, &square(Vector2::new(1.5, 0.), 1.), 1e-4).unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-5);
+
+        let circle = Circle::new(Vector2::new(0., 2. - 1e-5), 1.);
+        assert!(collide_within(&Circle::new(Vector2::zeros(), 1.), &circle, 1e-4).is_none());
+    }
+
+    #[test]
+    fn circles() {
+        let a = Circle::new(Vector2::new(0., 0.), 1.);
+        let b = Circle::new(Vector2::new(0., 1.5), 1.);
+
+        let contact = collide(&a, &b).unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-5);
+        assert_eq!(contact.normal, Vector2::new(0., -1.));
+
+        let contact = collide(&a, &Circle::new(Vector2::new(1., 1.), 1.)).unwrap();
+        assert!((contact.depth - (2. - 2f32.sqrt())).abs() < 1e-5);
+        assert!((contact.normal - Vector2::new(-1., -1.).normalize()).norm() < 1e-5);
+
+        let contact = collide(&a, &a).unwrap();
+        assert_eq!((contact.normal, contact.depth), (Vector2::x(), 2.));
+        assert!(!intersects(&a, &Circle::new(Vector2::new(0., 2.), 1.)));
+
+        // the corner of the square is more than a radius away
+        let corner = square(Vector2::new(1.6, 1.6), 0.5);
+        assert!(!intersects(&a, &corner));
+        assert!(intersects(&a, &square(Vector2::new(1.2, 0.), 0.5)));
+    }
+
+    #[test]
+    fn capsules() {
+        let capsule = Capsule::new(Vector2::new(0., 0.), Vector2::new(4., 0.), 1.);
+
+        let contact = collide(&capsule, &Circle::new(Vector2::new(2., 1.5), 1.)).unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-5);
+        assert_eq!(contact.normal, Vector2::new(0., -1.));
+
+        // lying side by side, and crossing right through the middle
+        let above = Capsule::new(Vector2::new(1., 1.5), Vector2::new(5., 1.5), 1.);
+        let contact = collide(&capsule, &above).unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-5);
+        assert_eq!(contact.normal, Vector2::new(0., -1.));
+        let across = Capsule::new(Vector2::new(2., -3.), Vector2::new(2., 3.), 0.5);
+        assert!(intersects(&capsule, &across));
+        let apart = Capsule::new(Vector2::new(6.5, -3.), Vector2::new(6.5, 3.), 1.);
+        assert!(!intersects(&capsule, &apart));
+
+        let contact = collide(&capsule, &square(Vector2::new(5.5, 0.), 1.)).unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-5);
+        assert_eq!(contact.normal, Vector2::new(-1., 0.));
+        // only the round end keeps this corner out
+        assert!(intersects(&capsule, &square(Vector2::new(5.2, 1.2), 0.5)));
+        assert!(!intersects(&capsule, &square(Vector2::new(5.3, 1.3), 0.5)));
+        assert!(!intersects(&square(Vector2::new(5.3, 1.3), 0.5), &capsule));
+
+        assert!(capsule.contains(Vector2::new(2., 0.9)));
+        assert!(capsule.contains(Vector2::new(4.7, 0.7)));
+        assert!(!capsule.contains(Vector2::new(4.8, 0.8)));
+        assert!(!capsule.contains(Vector2::new(-0.5, -1.)));
+    }
+
+    #[test]
+    fn obbs() {
+        let door = Obb::new(Vector2::zeros(), Vector2::new(2., 0.5), Angle::from_degrees(45.));
+        let polygon = Polygon::new(door.corners().to_vec()).unwrap();
+
+        // two axes find the same contacts as the four of the same polygon
+        let others = [
+            Shape::from(square(Vector2::new(2., -1.), 1.)),
+            Shape::from(square(Vector2::new(1.5, 2.5), 0.5)),
+            Shape::from(square(Vector2::new(3., -3.), 1.)),
+            Shape::from(Circle::new(Vector2::new(-1., -0.5), 0.5)),
+            Shape::from(Edge::new(Vector2::new(-3., 1.), Vector2::new(3., 1.))),
+            Shape::from(Obb::new(Vector2::new(1.5, 0.5), Vector2::new(1., 0.25), Angle::default())),
+        ];
+
+        for other in &others {
+            let (a, b) = (collide(&door, other), collide(&polygon, other));
+            assert_eq!(a.is_some(), b.is_some(), "{:?}", other);
+
+            if let (Some(a), Some(b)) = (a, b) {
+                assert!((a.depth - b.depth).abs() < 1e-5, "{:?}", other);
+                assert!((a.normal - b.normal).norm() < 1e-5, "{:?}", other);
+            }
+        }
+
+        let half = 0.5f32.sqrt();
+        assert!(door.contains(Vector2::new(1., 1.)));
+        assert!(!door.contains(Vector2::new(1., -1.)));
+        assert!((door.closest_point(Vector2::new(0., 3.)) - door.corners()[2]).norm() < 1e-5);
+        assert!((door.aabb().max() - Vector2::new(2.5 * half, 2.5 * half)).norm() < 1e-5);
+
+        let turned = door.transformed(&Transform::new(Vector2::x(), Angle::from_degrees(45.), 1.));
+        assert!((turned.corners()[0] - Vector2::new(1.5, -2.)).norm() < 1e-5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        use crate::net::binary::serde::{from_slice, to_vec};
+
+        let door = Obb::new(Vector2::y(), Vector2::new(2., 1.), Angle::from_degrees(30.));
+        let ledge = Edge::new(Vector2::x(), Vector2::y());
+        let colliders = vec![
+            Collider::new(0, square(Vector2::new(1., 2.), 1.)),
+            Collider::trigger(1, Circle::new(Vector2::new(-3., 0.), 0.75)),
+            Collider::new(2, Capsule::new(Vector2::zeros(), Vector2::x(), 0.5)),
+            Collider::new(3, door),
+            Collider { kind: ColliderKind::OneWay, ..Collider::new(4, ledge) },
+        ];
+
+        let bytes = to_vec(&colliders).unwrap();
+        assert_eq!(from_slice::<Vec<Collider>>(&bytes).unwrap(), colliders);
+
+        // polygons are checked when they are loaded
+        let line = to_vec(&vec![Vector2::zeros(), Vector2::x(), Vector2::new(2., 0.)]).unwrap();
+        assert!(from_slice::<Polygon>(&line).is_err());
+    }
+
+    #[test]
+    fn closest_points() {
+        let polygon = square(Vector2::new(0., 0.), 1.);
+        assert_eq!(polygon.closest_point(Vector2::new(3., 0.5)), Vector2::new(1., 0.5));
+        assert_eq!(polygon.closest_point(Vector2::new(-3., 2.)), Vector2::new(-1., 1.));
+        assert_eq!(polygon.closest_point(Vector2::new(0.5, 0.)), Vector2::new(0.5, 0.));
+
+        let circle = Circle::new(Vector2::new(0., 0.), 2.);
+        assert_eq!(circle.closest_point(Vector2::new(0., 5.)), Vector2::new(0., 2.));
+        assert_eq!(circle.closest_point(Vector2::new(1., 1.)), Vector2::new(1., 1.));
+
+        let capsule = Capsule::new(Vector2::new(0., 0.), Vector2::new(4., 0.), 1.);
+        assert_eq!(capsule.closest_point(Vector2::new(2., -3.)), Vector2::new(2., -1.));
+        assert_eq!(capsule.closest_point(Vector2::new(7., 0.)), Vector2::new(5., 0.));
+
+        // edges have no inside, so even points on them are on the edge
+        let edge = Edge::new(Vector2::new(-1., 0.), Vector2::new(1., 0.));
+        assert_eq!(edge.closest_point(Vector2::new(3., 2.)), Vector2::new(1., 0.));
+        assert_eq!(edge.closest_point(Vector2::new(0.5, 2.)), Vector2::new(0.5, 0.));
+    }
+
+    #[test]
+    fn distances() {
+        let a = square(Vector2::new(0., 0.), 1.);
+
+        assert_eq!(distance(&a, &square(Vector2::new(4., 0.), 1.)), 2.);
+        assert_eq!(distance(&a, &square(Vector2::new(1.5, 0.), 1.)), 0.);
+        // corner to corner
+        let diagonal = distance(&a, &square(Vector2::new(3., 3.), 1.));
+        assert!((diagonal - 2f32.sqrt()).abs() < 1e-5);
+
+        let player = Circle::new(Vector2::new(5., 0.5), 0.5);
+        assert_eq!(distance(&a, &player), 3.5);
+        assert_eq!(distance(&player, &a), 3.5);
+        assert_eq!(distance(&player, &Circle::new(Vector2::new(5., 3.), 1.)), 1.);
+
+        let capsule = Capsule::new(Vector2::new(3., -2.), Vector2::new(3., 2.), 0.5);
+        assert_eq!(distance(&a, &capsule), 1.5);
+        let edge = Edge::new(Vector2::new(0., -3.), Vector2::new(6., -3.));
+        assert_eq!(distance(&capsule, &edge), 0.5);
+    }
+
+    #[test]
+    fn invalid_polygons() {
+        let polygon = |points: &[(f32, f32)]| {
+            Polygon::new(points.iter().map(|&(x, y)| Vector2::new(x, y)).collect())
+        };
+
+        assert_eq!(polygon(&[(0., 0.), (1., 0.)]), Err(PolygonError::TooFewPoints));
+        assert_eq!(polygon(&[(0., 0.), (1., 1.), (2., 2.)]), Err(PolygonError::Degenerate));
+        assert_eq!(
+            polygon(&[(0., 0.), (1., 0.), (1., 0.), (0., 1.)]),
+            Err(PolygonError::Degenerate),
+        );
+        assert_eq!(
+            polygon(&[(0., 0.), (2., 0.), (1., 0.5), (2., 2.), (0., 2.)]),
+            Err(PolygonError::Concave),
+        );
+        // a star turns the same way at every corner, but crosses over itself
+        let star = [(0., 1.), (-0.59, -0.81), (0.95, 0.31), (-0.95, 0.31), (0.59, -0.81)];
+        assert_eq!(polygon(&star), Err(PolygonError::Concave));
+
+        // clockwise corners are turned around
+        let clockwise = polygon(&[(0., 0.), (0., 1.), (1., 1.), (1., 0.)]).unwrap();
+        assert_eq!(clockwise.points()[1], Vector2::new(1., 1.));
+        assert_eq!(clockwise.normals()[0], Vector2::new(1., 0.));
+    }
+
+    #[test]
+    fn constructors() {
+        let triangle = Polygon::triangle(Vector2::zeros(), Vector2::new(0., 1.), Vector2::x());
+        // turned around to go counter-clockwise
+        assert_eq!(triangle.unwrap().points(), [Vector2::x(), Vector2::y(), Vector2::zeros()]);
+        let flat = Polygon::triangle(Vector2::zeros(), Vector2::x(), Vector2::new(2., 0.));
+        assert_eq!(flat, Err(PolygonError::Degenerate));
+
+        let rect = Polygon::rect(Vector2::new(1., 1.), Vector2::new(2., 0.5));
+        assert_eq!(rect.aabb(), Rect::new(Vector2::new(-1., 0.5), Vector2::new(3., 1.5)));
+
+        let hexagon = Polygon::regular(Vector2::new(1., 0.), 2., 6).unwrap();
+        assert_eq!(hexagon.points().len(), 6);
+        assert_eq!(hexagon.points()[0], Vector2::new(3., 0.));
+        assert!(hexagon.points().iter().all(|p| ((p - hexagon.center()).norm() - 2.).abs() < 1e-5));
+        assert_eq!(Polygon::regular(Vector2::zeros(), 1., 2), Err(PolygonError::TooFewPoints));
+        assert_eq!(Polygon::regular(Vector2::zeros(), 0., 5), Err(PolygonError::Degenerate));
+    }
+
+    #[test]
+    fn closing_edge() {
+        let a = Polygon::new(vec![
+            Vector2::new(0., 0.),
+            Vector2::new(2., 0.),
+            Vector2::new(0., 2.),
+        ])
+        .unwrap();
+        // only the normal of the edge from the last corner of `a` back to its
+        // first separates these
+        let b = Polygon::new(vec![
+            Vector2::new(-0.5, 1.),
+            Vector2::new(-1.2, 1.7),
+            Vector2::new(-1.9, 1.),
+            Vector2::new(-1.2, 0.3),
+        ])
+        .unwrap();
+
+        assert!(!intersects(&a, &b));
+        assert!(!intersects(&b, &a));
+    }
+
+    #[test]
+    fn contains() {
+        let polygon = square(Vector2::new(0., 0.), 1.);
+
+        assert!(polygon.contains(Vector2::new(0.5, -0.5)));
+        assert!(!polygon.contains(Vector2::new(1.5, 0.)));
+        assert!(!polygon.contains(Vector2::new(-0.5, -1.1)));
+        // on an edge and on a corner
+        assert!(polygon.contains(Vector2::new(1., 0.3)));
+        assert!(polygon.contains(Vector2::new(-1., -1.)));
+
+        let circle = Circle::new(Vector2::new(2., 1.), 0.5);
+
+        assert!(circle.contains(Vector2::new(2.2, 1.1)));
+        assert!(!circle.contains(Vector2::new(2.4, 1.4)));
+        assert!(circle.contains(Vector2::new(2., 1.5)));
+    }
+
+    #[test]
+    fn circle_near_a_corner() {
+        let circle = Circle::new(Vector2::new(0., 0.), 1.);
+        // the closest corner is just over a radius away, but the corner still
+        // overlaps along the axis to the center of the rectangle
+        let rect = Polygon::new(vec![
+            Vector2::new(0.75, 0.7),
+            Vector2::new(4.75, 0.7),
+            Vector2::new(4.75, 1.7),
+            Vector2::new(0.75, 1.7),
+        ])
+        .unwrap();
+
+        assert!(!intersects(&circle, &rect));
+        assert!(!intersects(&rect, &circle));
+    }
+
+    #[test]
+    fn transforms() {
+        let local = Polygon::from(Rect::from_center(Vector2::zeros(), Vector2::new(1., 1.)));
+        let transform = Transform::new(Vector2::new(5., 0.), Angle::from_degrees(45.), 2.);
+        let placed = local.transformed(&transform);
+
+        // the corners now point along the axes, 2√2 from the center
+        assert!(placed.contains(Vector2::new(7.7, 0.)));
+        assert!(!placed.contains(Vector2::new(7.9, 0.)));
+        assert!(!placed.contains(Vector2::new(6.5, 1.5)));
+        assert!((placed.normals()[0] - Vector2::new(1., -1.).normalize()).norm() < 1e-5);
+        assert_eq!(Shape::from(local.clone()).transformed(&transform), Shape::Polygon(placed));
+
+        // a negative scale is half a turn, and the normals still face out
+        let flipped = local.transformed(&Transform { scale: -1., ..Transform::default() });
+        assert_eq!(flipped.normals()[0], Vector2::y());
+        assert!(intersects(&flipped, &Circle::new(Vector2::new(0., 1.2), 0.5)));
+
+        let circle = Circle::new(Vector2::new(1., 0.), 0.5).transformed(&transform);
+        assert!((circle.center - Vector2::new(5. + 2f32.sqrt(), 2f32.sqrt())).norm() < 1e-5);
+        assert_eq!(circle.radius, 1.);
+    }
+
+    #[test]
+    fn edges() {
+        let wall = Edge::new(Vector2::new(-2., 0.), Vector2::new(2., 0.));
+        let player = Circle::new(Vector2::new(0.5, 0.2), 0.3);
+
+        let contact = collide(&player, &wall).unwrap();
+        assert!((contact.depth - 0.1).abs() < 1e-5);
+        assert_eq!(contact.normal, Vector2::new(0., 1.));
+
+        // past the end of the edge
+        assert!(!intersects(&Circle::new(Vector2::new(2.5, 0.), 0.3), &wall));
+    }
+
+    #[test]
+    fn project_batch() {
+        let a = square(Vector2::new(0., 0.), 1.);
+        let b = Circle::new(Vector2::new(5., 0.), 2.);
+        let shapes = [ShapeRef::Polygon(&a), ShapeRef::Circle(&b)];
+
+        let projections: Vec<_> = project_many(shapes.iter().copied(), Vector2::x()).collect();
+        assert_eq!(
+            projections,
+            vec![Projection { min: -1., max: 1. }, Projection { min: 3., max: 7. }],
+        );
+    }
+
+    #[test]
+    fn rects() {
+        let rect = Rect::new(Vector2::new(2., 1.), Vector2::new(0., 0.));
+        let polygon = Polygon::from(rect);
+
+        assert_eq!(polygon.points(), &rect.corners()[..]);
+        assert_eq!(polygon.aabb(), rect);
+        assert!(intersects(&polygon, &Circle::new(Vector2::new(2.2, 0.5), 0.5)));
+        assert_eq!(Shape::from(rect), Shape::Polygon(polygon));
+    }
+}
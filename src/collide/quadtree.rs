@@ -0,0 +1,246 @@
+//! Quadtree broad-phase.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::math::{Rect, Vector2};
+
+/// How many boxes a node holds before it splits.
+const NODE_CAPACITY: usize = 8;
+
+/// How many times the tree can split.
+const MAX_DEPTH: u32 = 8;
+
+/// A tree of regions, each split into four once it holds too many boxes.
+///
+/// Unlike a [`SpatialHash`](super::broadphase::SpatialHash), empty space costs
+/// nothing, which suits big maps with a lot of it like the Airship. Boxes
+/// outside of the tree's bounds still work, they are just kept in the root.
+#[derive(Clone, Debug)]
+pub struct Quadtree<K> {
+    root: Node<K>,
+    entries: HashMap<K, Rect>,
+}
+
+#[derive(Clone, Debug)]
+struct Node<K> {
+    bounds: Rect,
+    depth: u32,
+    /// Boxes that don't fit entirely in one child.
+    items: Vec<(K, Rect)>,
+    children: Option<Box<[Node<K>; 4]>>,
+}
+
+impl<K> Quadtree<K>
+where K: Copy + Eq + Hash {
+    /// Create a new, empty `Quadtree` covering a region.
+    pub fn new(bounds: Rect) -> Quadtree<K> {
+        Quadtree {
+            root: Node::new(bounds, 0),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The region the tree covers.
+    pub fn bounds(&self) -> Rect {
+        self.root.bounds
+    }
+
+    /// The number of boxes in the tree.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The box of a key, if it is in the tree.
+    pub fn get(&self, key: K) -> Option<Rect> {
+        self.entries.get(&key).copied()
+    }
+
+    /// Inserts a box, or moves it if the key is already in the tree.
+    pub fn insert(&mut self, key: K, aabb: Rect) {
+        self.remove(key);
+
+        self.root.insert(key, aabb);
+        self.entries.insert(key, aabb);
+    }
+
+    /// Moves a box, returning `false` if the key isn't in the tree.
+    pub fn update(&mut self, key: K, aabb: Rect) -> bool {
+        if self.entries.contains_key(&key) {
+            self.insert(key, aabb);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes a box, returning it.
+    pub fn remove(&mut self, key: K) -> Option<Rect> {
+        let aabb = self.entries.remove(&key)?;
+        self.root.remove(key, &aabb);
+
+        Some(aabb)
+    }
+
+    /// Removes every box.
+    pub fn clear(&mut self) {
+        self.root = Node::new(self.root.bounds, 0);
+        self.entries.clear();
+    }
+
+    /// The keys of every box overlapping a region.
+    pub fn query(&self, region: Rect) -> Query<'_, K> {
+        Query {
+            region,
+            stack: vec![&self.root],
+            items: [].iter(),
+        }
+    }
+}
+
+impl<K> Node<K>
+where K: Copy + Eq {
+    fn new(bounds: Rect, depth: u32) -> Node<K> {
+        Node {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    fn insert(&mut self, key: K, aabb: Rect) {
+        if let Some(child) = self.child_for(&aabb) {
+            return child.insert(key, aabb);
+        }
+
+        self.items.push((key, aabb));
+
+        if self.children.is_none() && self.items.len() > NODE_CAPACITY && self.depth < MAX_DEPTH
+        {
+            self.split();
+        }
+    }
+
+    fn remove(&mut self, key: K, aabb: &Rect) {
+        if let Some(child) = self.child_for(aabb) {
+            return child.remove(key, aabb);
+        }
+
+        self.items.retain(|(other, _)| *other != key);
+    }
+
+    /// The child a box fits entirely inside of, if any.
+    fn child_for(&mut self, aabb: &Rect) -> Option<&mut Node<K>> {
+        self.children
+            .as_mut()?
+            .iter_mut()
+            .find(|child| child.bounds.contains_rect(aabb))
+    }
+
+    fn split(&mut self) {
+        let (min, center, max) = (self.bounds.min(), self.bounds.center(), self.bounds.max());
+        let depth = self.depth + 1;
+        let quadrant = |a: Vector2, b: Vector2| Node::new(Rect::new(a, b), depth);
+
+        self.children = Some(Box::new([
+            quadrant(min, center),
+            quadrant(Vector2::new(center.x, min.y), Vector2::new(max.x, center.y)),
+            quadrant(center, max),
+            quadrant(Vector2::new(min.x, center.y), Vector2::new(center.x, max.y)),
+        ]));
+
+        for (key, aabb) in std::mem::take(&mut self.items) {
+            self.insert(key, aabb);
+        }
+    }
+}
+
+/// An iterator over the keys of boxes in a region.
+///
+/// Created by [`Quadtree::query`].
+pub struct Query<'a, K> {
+    region: Rect,
+    stack: Vec<&'a Node<K>>,
+    items: std::slice::Iter<'a, (K, Rect)>,
+}
+
+impl<'a, K> Iterator for Query<'a, K>
+where K: Copy {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        loop {
+            for (key, aabb) in &mut self.items {
+                if aabb.intersects(&self.region) {
+                    return Some(*key);
+                }
+            }
+
+            let node = self.stack.pop()?;
+
+            if let Some(children) = &node.children {
+                let region = self.region;
+                self.stack.extend(children.iter().filter(|child| child.bounds.intersects(&region)));
+            }
+
+            self.items = node.items.iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect::from_origin_size(Vector2::new(x, y), Vector2::new(w, h))
+    }
+
+    #[test]
+    fn query_after_split() {
+        let mut tree = Quadtree::new(rect(0., 0., 64., 64.));
+
+        for i in 0..64 {
+            tree.insert(i, rect((i % 8) as f32 * 8. + 1., (i / 8) as f32 * 8. + 1., 2., 2.));
+        }
+
+        // straddles the middle, so it stays high up
+        tree.insert(100, rect(30., 30., 4., 4.));
+        // outside of the bounds entirely
+        tree.insert(101, rect(-10., -10., 1., 1.));
+
+        let mut found: Vec<_> = tree.query(rect(0., 0., 10., 10.)).collect();
+        found.sort();
+        assert_eq!(found, vec![0, 1, 8, 9]);
+
+        let mut found: Vec<_> = tree.query(rect(31., 31., 1.5, 1.5)).collect();
+        found.sort();
+        assert_eq!(found, vec![100]);
+
+        assert_eq!(tree.query(rect(-11., -11., 2., 2.)).collect::<Vec<_>>(), vec![101]);
+    }
+
+    #[test]
+    fn update_and_remove() {
+        let mut tree = Quadtree::new(rect(0., 0., 64., 64.));
+
+        for i in 0..32 {
+            tree.insert(i, rect(i as f32, i as f32, 0.5, 0.5));
+        }
+
+        assert!(tree.update(3, rect(60., 2., 0.5, 0.5)));
+        assert!(!tree.update(99, rect(0., 0., 1., 1.)));
+        assert_eq!(tree.query(rect(59., 1., 2., 2.)).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(tree.query(rect(3., 3., 0.2, 0.2)).count(), 0);
+
+        assert_eq!(tree.remove(3), Some(rect(60., 2., 0.5, 0.5)));
+        assert_eq!(tree.query(rect(59., 1., 2., 2.)).count(), 0);
+        assert_eq!(tree.len(), 31);
+    }
+}
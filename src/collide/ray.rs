@@ -0,0 +1,234 @@
+//! Raycasting.
+
+use crate::math::Vector2;
+
+use super::shape::edges;
+use super::{Geometry, ShapeRef};
+
+/// A ray, going in a straight line from a point.
+///
+/// A ray with a [`max`](Ray::max) distance is a line segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vector2,
+    /// The direction of the ray.
+    ///
+    /// Always normalized.
+    pub direction: Vector2,
+    /// How far the ray goes.
+    pub max: f32,
+}
+
+/// Where a ray hit a shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// How far along the ray the shape was hit.
+    pub distance: f32,
+    /// The normal of the surface that was hit, facing the ray.
+    ///
+    /// Always normalized.
+    pub normal: Vector2,
+}
+
+impl Ray {
+    /// Create a new `Ray` that goes on forever.
+    ///
+    /// The direction is normalized, and a direction of zero points along x.
+    pub fn new(origin: Vector2, direction: Vector2) -> Ray {
+        Ray {
+            origin,
+            direction: direction.try_normalize(f32::EPSILON).unwrap_or_else(Vector2::x),
+            max: f32::INFINITY,
+        }
+    }
+
+    /// Create a new `Ray` going from `a` to `b`, and no further.
+    pub fn segment(a: Vector2, b: Vector2) -> Ray {
+        Ray {
+            max: (b - a).norm(),
+            ..Ray::new(a, b - a)
+        }
+    }
+
+    /// The point a distance along the ray.
+    pub fn at(&self, distance: f32) -> Vector2 {
+        self.origin + self.direction * distance
+    }
+
+    /// Where the ray first hits a shape.
+    ///
+    /// A ray starting inside of anything but an edge hits it right away, with
+    /// a normal facing straight back at it.
+    pub fn cast<'a>(&self, shape: impl Into<ShapeRef<'a>>) -> Option<RayHit> {
+        let hit = match shape.into() {
+            ShapeRef::Circle(circle) => {
+                let offset = self.origin - circle.center;
+                let b = offset.dot(&self.direction);
+                let c = offset.norm_squared() - circle.radius * circle.radius;
+
+                if c <= 0. {
+                    Some(self.inside())
+                } else {
+                    let discriminant = b * b - c;
+
+                    if discriminant < 0. || b > 0. {
+                        None
+                    } else {
+                        let distance = -b - discriminant.sqrt();
+                        let normal = (self.at(distance) - circle.center) / circle.radius;
+                        Some(RayHit { distance, normal })
+                    }
+                }
+            }
+            ShapeRef::Polygon(polygon) => {
+                if polygon.contains(self.origin) {
+                    Some(self.inside())
+                } else {
+                    self.cast_outline(polygon.points(), polygon.normals())
+                }
+            }
+            ShapeRef::Obb(obb) => {
+                if obb.contains(self.origin) {
+                    Some(self.inside())
+                } else {
+                    self.cast_outline(&obb.corners(), &obb.normals())
+                }
+            }
+            ShapeRef::Edge(edge) => self.cast_edge(edge.a, edge.b).map(|distance| {
+                let normal = edge.normal();
+
+                RayHit {
+                    distance,
+                    normal: if normal.dot(&self.direction) > 0. { -normal } else { normal },
+                }
+            }),
+            ShapeRef::Capsule(capsule) => {
+                if capsule.contains(self.origin) {
+                    Some(self.inside())
+                } else {
+                    capsule
+                        .pieces()
+                        .iter()
+                        .filter_map(|piece| self.cast(piece))
+                        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+                }
+            }
+        };
+
+        hit.filter(|hit| hit.distance <= self.max)
+    }
+
+    fn inside(&self) -> RayHit {
+        RayHit {
+            distance: 0.,
+            normal: -self.direction,
+        }
+    }
+
+    /// The first edge of an outline the ray hits, with the normal of that
+    /// edge.
+    fn cast_outline(&self, points: &[Vector2], normals: &[Vector2]) -> Option<RayHit> {
+        edges(points)
+            .zip(normals)
+            .filter_map(|((a, b), normal)| {
+                let distance = self.cast_edge(a, b)?;
+                Some(RayHit {
+                    distance,
+                    normal: *normal,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    fn cast_edge(&self, a: Vector2, b: Vector2) -> Option<f32> {
+        let edge = b - a;
+        let denominator = self.direction.perp(&edge);
+
+        // parallel rays never hit, even running along the edge
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let offset = a - self.origin;
+        let distance = offset.perp(&edge) / denominator;
+        let along = offset.perp(&self.direction) / denominator;
+
+        if distance >= 0. && (0. ..=1.).contains(&along) {
+            Some(distance)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::{Capsule, Circle, Edge, Obb, Polygon};
+    use crate::math::angle::Angle;
+    use crate::math::Rect;
+
+    #[test]
+    fn normals() {
+        let ray = Ray::new(Vector2::zeros(), Vector2::new(2., 0.));
+
+        let pillar = Circle::new(Vector2::new(5., 0.5), 1.);
+        let hit = ray.cast(&pillar).unwrap();
+        assert!(((ray.at(hit.distance) - pillar.center).norm() - 1.).abs() < 1e-5);
+        assert!(hit.normal.x < 0. && hit.normal.y < 0.);
+
+        let wall = Polygon::from(Rect::new(Vector2::new(1., -1.), Vector2::new(2., 1.)));
+        let hit = ray.cast(&wall).unwrap();
+        assert_eq!((hit.distance, hit.normal), (1., -Vector2::x()));
+
+        // edges face whichever side the ray comes from
+        let edge = Edge::new(Vector2::new(3., -1.), Vector2::new(3., 1.));
+        assert_eq!(ray.cast(&edge).unwrap().normal, -Vector2::x());
+        let back = Ray::new(Vector2::new(6., 0.), -Vector2::x());
+        assert_eq!(back.cast(&edge).unwrap().normal, Vector2::x());
+    }
+
+    #[test]
+    fn segments() {
+        let wall = Polygon::from(Rect::new(Vector2::new(1., -1.), Vector2::new(2., 1.)));
+
+        let short = Ray::segment(Vector2::zeros(), Vector2::new(0.5, 0.));
+        assert_eq!(short.cast(&wall), None);
+
+        let long = Ray::segment(Vector2::zeros(), Vector2::new(1.5, 0.));
+        assert_eq!(long.cast(&wall).map(|hit| hit.distance), Some(1.));
+
+        let inside = Ray::segment(Vector2::new(1.5, 0.), Vector2::new(3., 0.));
+        let hit = inside.cast(&wall).unwrap();
+        assert_eq!((hit.distance, hit.normal), (0., -Vector2::x()));
+    }
+
+    #[test]
+    fn capsules() {
+        let capsule = Capsule::new(Vector2::new(3., -2.), Vector2::new(3., 2.), 0.5);
+
+        // the side, and the round end past it
+        let hit = Ray::new(Vector2::zeros(), Vector2::x()).cast(&capsule).unwrap();
+        assert_eq!((hit.distance, hit.normal), (2.5, -Vector2::x()));
+        let hit = Ray::new(Vector2::new(3., 5.), -Vector2::y()).cast(&capsule).unwrap();
+        assert_eq!((hit.distance, hit.normal), (2.5, Vector2::y()));
+
+        assert_eq!(Ray::new(Vector2::new(0., 3.), Vector2::x()).cast(&capsule), None);
+        let inside = Ray::new(Vector2::new(3., 2.2), Vector2::x()).cast(&capsule).unwrap();
+        assert_eq!(inside.distance, 0.);
+    }
+
+    #[test]
+    fn boxes() {
+        let door = Obb::new(Vector2::new(3., 0.), Vector2::new(1., 1.), Angle::from_degrees(45.));
+
+        // straight at a corner, and at a side past it
+        let hit = Ray::new(Vector2::zeros(), Vector2::x()).cast(&door).unwrap();
+        assert!((hit.distance - (3. - 2f32.sqrt())).abs() < 1e-5);
+        let hit = Ray::new(Vector2::new(0., 0.5), Vector2::x()).cast(&door).unwrap();
+        assert!((hit.normal - Vector2::new(-1., 1.).normalize()).norm() < 1e-5);
+
+        assert_eq!(Ray::new(Vector2::new(0., 2.), Vector2::x()).cast(&door), None);
+    }
+}
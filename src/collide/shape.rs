@@ -0,0 +1,848 @@
+//! Collision shapes.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::math::angle::Angle;
+use crate::math::transform::Transform;
+use crate::math::{Rect, Vector2};
+
+/// How far a corner can be outside of an edge before a polygon counts as
+/// concave, so rounding in nearly straight corners doesn't reject it.
+const CONVEX_TOLERANCE: f32 = 1e-4;
+
+/// The extent of a shape along an axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Projection {
+    /// How far two projections overlap.
+    ///
+    /// This is negative if there is a gap between them.
+    pub fn overlap(&self, other: &Projection) -> f32 {
+        self.max.min(other.max) - self.min.max(other.min)
+    }
+}
+
+/// Something collisions can be tested against.
+pub trait Geometry {
+    /// The smallest rectangle containing the shape.
+    fn aabb(&self) -> Rect;
+
+    /// Projects the shape onto an axis.
+    ///
+    /// The axis should be normalized.
+    fn project(&self, axis: Vector2) -> Projection;
+
+    /// Checks if a point is inside of the shape.
+    ///
+    /// Points on the boundary count as inside. Edges have no inside, so they
+    /// never contain anything.
+    fn contains(&self, point: Vector2) -> bool;
+
+    /// The point of the shape closest to another point.
+    ///
+    /// Points inside the shape are closest to themselves.
+    fn closest_point(&self, point: Vector2) -> Vector2;
+
+    /// The point of the shape furthest along a direction.
+    ///
+    /// The direction doesn't have to be normalized.
+    fn support(&self, direction: Vector2) -> Vector2;
+}
+
+/// A circle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Circle {
+    pub center: Vector2,
+    pub radius: f32,
+}
+
+impl Circle {
+    /// Create a new `Circle`.
+    pub fn new(center: Vector2, radius: f32) -> Circle {
+        Circle { center, radius }
+    }
+
+    /// The circle moved, rotated and scaled by a transform.
+    pub fn transformed(&self, transform: &Transform) -> Circle {
+        Circle::new(transform.apply_to_point(self.center), self.radius * transform.scale.abs())
+    }
+}
+
+impl Geometry for Circle {
+    fn aabb(&self) -> Rect {
+        Rect::from_center(self.center, Vector2::new(self.radius, self.radius))
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        let center = self.center.dot(&axis);
+
+        Projection {
+            min: center - self.radius,
+            max: center + self.radius,
+        }
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        (point - self.center).norm_squared() <= self.radius * self.radius
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        round_closest(self.center, self.radius, point)
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        round_support(self.center, self.radius, direction)
+    }
+}
+
+/// A convex polygon.
+///
+/// Concave walls have to be split up into convex pieces first.
+///
+/// With the `serde` feature, a polygon is stored as its list of corners, and
+/// checked like [`Polygon::new`] when it is loaded.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<Vector2>", into = "Vec<Vector2>")
+)]
+pub struct Polygon {
+    /// The corners, in counter-clockwise order.
+    points: Vec<Vector2>,
+    /// The outward normal of the edge starting at each corner, worked out
+    /// once up front since every test needs them.
+    normals: Vec<Vector2>,
+}
+
+impl Polygon {
+    /// Create a new `Polygon` from its corners.
+    ///
+    /// The corners can go around in either direction; clockwise corners are
+    /// reversed so the normals always point outward.
+    pub fn new(mut points: Vec<Vector2>) -> Result<Polygon, PolygonError> {
+        if points.len() < 3 {
+            return Err(PolygonError::TooFewPoints);
+        }
+
+        let area: f32 = edges(&points).map(|(a, b)| a.perp(&b)).sum();
+
+        if area.abs() <= f32::EPSILON {
+            return Err(PolygonError::Degenerate);
+        }
+
+        if area < 0. {
+            points.reverse();
+        }
+
+        let normals = edges(&points)
+            .map(|(a, b)| {
+                let edge = b - a;
+                Vector2::new(edge.y, -edge.x).try_normalize(f32::EPSILON)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(PolygonError::Degenerate)?;
+
+        // every corner has to be on the inside of every edge, which also
+        // catches polygons that cross over themselves
+        let concave = edges(&points).any(|(a, b)| {
+            points.iter().any(|point| (b - a).perp(&(point - a)) < -CONVEX_TOLERANCE)
+        });
+
+        if concave {
+            return Err(PolygonError::Concave);
+        }
+
+        Ok(Polygon { points, normals })
+    }
+
+    /// Create a new triangle.
+    pub fn triangle(a: Vector2, b: Vector2, c: Vector2) -> Result<Polygon, PolygonError> {
+        Polygon::new(vec![a, b, c])
+    }
+
+    /// Create a new rectangle, lined up with the axes.
+    pub fn rect(center: Vector2, half_extents: Vector2) -> Polygon {
+        Rect::from_center(center, half_extents).into()
+    }
+
+    /// Create a new polygon with all its sides the same length, and a corner
+    /// `radius` to the right of the center.
+    pub fn regular(center: Vector2, radius: f32, sides: usize) -> Result<Polygon, PolygonError> {
+        let points = (0..sides)
+            .map(|i| {
+                let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+                center + Vector2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+
+        Polygon::new(points)
+    }
+
+    /// The corners, in counter-clockwise order.
+    pub fn points(&self) -> &[Vector2] {
+        &self.points
+    }
+
+    /// The average of the corners.
+    pub fn center(&self) -> Vector2 {
+        self.points.iter().sum::<Vector2>() / self.points.len() as f32
+    }
+
+    /// The outward normals of each edge, in order.
+    pub fn normals(&self) -> &[Vector2] {
+        &self.normals
+    }
+
+    /// Moves the polygon by an offset.
+    pub fn translate(&mut self, offset: Vector2) {
+        for point in &mut self.points {
+            *point += offset;
+        }
+    }
+
+    /// The polygon moved, rotated and scaled by a transform.
+    ///
+    /// A uniform scale never turns the corners around, so the polygon stays
+    /// valid. Even a negative scale is only half a turn.
+    pub fn transformed(&self, transform: &Transform) -> Polygon {
+        let direction = Transform { scale: transform.scale.signum(), ..*transform };
+
+        Polygon {
+            points: self.points.iter().map(|point| transform.apply_to_point(*point)).collect(),
+            normals: self.normals.iter().map(|normal| direction.apply_to_vector(*normal)).collect(),
+        }
+    }
+}
+
+impl Geometry for Polygon {
+    fn aabb(&self) -> Rect {
+        points_aabb(&self.points)
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        project_points(&self.points, axis)
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        edges(&self.points).all(|(a, b)| (b - a).perp(&(point - a)) >= 0.)
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        if self.contains(point) {
+            return point;
+        }
+
+        edges(&self.points)
+            .map(|(a, b)| closest_on_segment(a, b, point))
+            .min_by(|a, b| (a - point).norm_squared().total_cmp(&(b - point).norm_squared()))
+            .unwrap_or(point)
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        support_points(&self.points, direction)
+    }
+}
+
+impl From<Rect> for Polygon {
+    fn from(rect: Rect) -> Polygon {
+        // the normals of a rectangle don't depend on its size, so even an
+        // empty one gets all four
+        Polygon {
+            points: rect.corners().to_vec(),
+            normals: vec![-Vector2::y(), Vector2::x(), Vector2::y(), -Vector2::x()],
+        }
+    }
+}
+
+impl TryFrom<Vec<Vector2>> for Polygon {
+    type Error = PolygonError;
+
+    fn try_from(points: Vec<Vector2>) -> Result<Polygon, PolygonError> {
+        Polygon::new(points)
+    }
+}
+
+impl From<Polygon> for Vec<Vector2> {
+    fn from(polygon: Polygon) -> Vec<Vector2> {
+        polygon.points
+    }
+}
+
+/// Why a list of corners isn't a valid [`Polygon`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolygonError {
+    /// There are fewer than three corners.
+    TooFewPoints,
+    /// An edge has no length, or the polygon has no area.
+    Degenerate,
+    /// The polygon isn't convex.
+    Concave,
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolygonError::TooFewPoints => f.write_str("a polygon needs at least three points"),
+            PolygonError::Degenerate => f.write_str("polygon has no area"),
+            PolygonError::Concave => f.write_str("polygon is not convex"),
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// A line segment.
+///
+/// Edges block from both sides. The side [`normal`](Edge::normal) points to
+/// is its front, which matters for one-way edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edge {
+    pub a: Vector2,
+    pub b: Vector2,
+}
+
+impl Edge {
+    /// Create a new `Edge` between two points.
+    pub fn new(a: Vector2, b: Vector2) -> Edge {
+        Edge { a, b }
+    }
+
+    /// The normal on the left of the edge, going from `a` to `b`.
+    pub fn normal(&self) -> Vector2 {
+        let edge = self.b - self.a;
+
+        Vector2::new(-edge.y, edge.x).try_normalize(f32::EPSILON).unwrap_or_else(Vector2::y)
+    }
+
+    /// The middle of the edge.
+    pub fn center(&self) -> Vector2 {
+        (self.a + self.b) / 2.
+    }
+
+    /// The edge moved, rotated and scaled by a transform.
+    pub fn transformed(&self, transform: &Transform) -> Edge {
+        Edge::new(transform.apply_to_point(self.a), transform.apply_to_point(self.b))
+    }
+}
+
+impl Geometry for Edge {
+    fn aabb(&self) -> Rect {
+        Rect::new(self.a, self.b)
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        project_points(&[self.a, self.b], axis)
+    }
+
+    fn contains(&self, _point: Vector2) -> bool {
+        false
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        closest_on_segment(self.a, self.b, point)
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        support_points(&[self.a, self.b], direction)
+    }
+}
+
+/// A line segment with round ends, like a circle swept from one end to the
+/// other.
+///
+/// A player moving between two ticks fits one better than a circle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capsule {
+    pub a: Vector2,
+    pub b: Vector2,
+    pub radius: f32,
+}
+
+impl Capsule {
+    /// Create a new `Capsule` around the segment between two points.
+    pub fn new(a: Vector2, b: Vector2, radius: f32) -> Capsule {
+        Capsule { a, b, radius }
+    }
+
+    /// The middle of the capsule.
+    pub fn center(&self) -> Vector2 {
+        (self.a + self.b) / 2.
+    }
+
+    /// The capsule moved, rotated and scaled by a transform.
+    pub fn transformed(&self, transform: &Transform) -> Capsule {
+        Capsule::new(
+            transform.apply_to_point(self.a),
+            transform.apply_to_point(self.b),
+            self.radius * transform.scale.abs(),
+        )
+    }
+
+    /// The capsule as a circle at each end and the rectangle between them,
+    /// which together cover the same area.
+    ///
+    /// The rectangle is an edge if the capsule has no radius, and left out
+    /// if it has no length.
+    pub(crate) fn pieces(&self) -> Vec<Shape> {
+        let mut pieces = vec![
+            Shape::Circle(Circle::new(self.a, self.radius)),
+            Shape::Circle(Circle::new(self.b, self.radius)),
+        ];
+
+        let side = Edge::new(self.a, self.b).normal() * self.radius;
+        let rect = Polygon::new(vec![self.a - side, self.b - side, self.b + side, self.a + side]);
+
+        match rect {
+            Ok(rect) => pieces.push(Shape::Polygon(rect)),
+            Err(_) if self.a != self.b => pieces.push(Shape::Edge(Edge::new(self.a, self.b))),
+            Err(_) => (),
+        }
+
+        pieces
+    }
+}
+
+impl Geometry for Capsule {
+    fn aabb(&self) -> Rect {
+        let radius = Vector2::repeat(self.radius);
+
+        Rect::from_min_max(self.a.inf(&self.b) - radius, self.a.sup(&self.b) + radius)
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        let ends = project_points(&[self.a, self.b], axis);
+
+        Projection {
+            min: ends.min - self.radius,
+            max: ends.max + self.radius,
+        }
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        let closest = closest_on_segment(self.a, self.b, point);
+
+        (point - closest).norm_squared() <= self.radius * self.radius
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        round_closest(closest_on_segment(self.a, self.b, point), self.radius, point)
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        round_support(support_points(&[self.a, self.b], direction), self.radius, direction)
+    }
+}
+
+/// A rectangle turned by some angle, an oriented bounding box.
+///
+/// Unlike a [`Polygon`], it needs no allocation, and only tests two axes
+/// instead of four.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Obb {
+    pub center: Vector2,
+    /// Half of the width and height, before it is turned.
+    pub half_extents: Vector2,
+    pub rotation: Angle,
+}
+
+impl Obb {
+    /// Create a new `Obb`.
+    pub fn new(center: Vector2, half_extents: Vector2, rotation: Angle) -> Obb {
+        Obb { center, half_extents, rotation }
+    }
+
+    /// The directions the width and height of the box point in.
+    pub fn axes(&self) -> [Vector2; 2] {
+        let x = self.rotation.to_vector();
+        [x, Vector2::new(-x.y, x.x)]
+    }
+
+    /// The corners, in counter-clockwise order.
+    pub fn corners(&self) -> [Vector2; 4] {
+        let [x, y] = self.axes();
+        let (x, y) = (x * self.half_extents.x, y * self.half_extents.y);
+
+        [self.center - x - y, self.center + x - y, self.center + x + y, self.center - x + y]
+    }
+
+    /// The outward normals of each edge, in the same order as the corners
+    /// they start at.
+    pub fn normals(&self) -> [Vector2; 4] {
+        let [x, y] = self.axes();
+        [-y, x, y, -x]
+    }
+
+    /// The box moved, rotated and scaled by a transform.
+    pub fn transformed(&self, transform: &Transform) -> Obb {
+        // a negative scale is half a turn, which leaves a box the same
+        Obb::new(
+            transform.apply_to_point(self.center),
+            self.half_extents * transform.scale.abs(),
+            self.rotation + transform.rotation,
+        )
+    }
+
+    /// Where a point is along each of the axes, from the center.
+    fn local(&self, point: Vector2) -> Vector2 {
+        let [x, y] = self.axes();
+        let offset = point - self.center;
+
+        Vector2::new(offset.dot(&x), offset.dot(&y))
+    }
+}
+
+impl Geometry for Obb {
+    fn aabb(&self) -> Rect {
+        let [x, y] = self.axes();
+        let extents = x.abs() * self.half_extents.x + y.abs() * self.half_extents.y;
+
+        Rect::from_center(self.center, extents)
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        let [x, y] = self.axes();
+        let center = self.center.dot(&axis);
+        let extent =
+            x.dot(&axis).abs() * self.half_extents.x + y.dot(&axis).abs() * self.half_extents.y;
+
+        Projection {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        let local = self.local(point);
+
+        local.x.abs() <= self.half_extents.x && local.y.abs() <= self.half_extents.y
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        let [x, y] = self.axes();
+        let local = self.local(point).sup(&-self.half_extents).inf(&self.half_extents);
+
+        self.center + x * local.x + y * local.y
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        let [x, y] = self.axes();
+        let x = x * self.half_extents.x * x.dot(&direction).signum();
+        let y = y * self.half_extents.y * y.dot(&direction).signum();
+
+        self.center + x + y
+    }
+}
+
+/// Any shape.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    Circle(Circle),
+    Polygon(Polygon),
+    Edge(Edge),
+    Capsule(Capsule),
+    Obb(Obb),
+}
+
+impl Shape {
+    /// Borrows the shape.
+    pub fn to_ref(&self) -> ShapeRef<'_> {
+        match self {
+            Shape::Circle(circle) => ShapeRef::Circle(circle),
+            Shape::Polygon(polygon) => ShapeRef::Polygon(polygon),
+            Shape::Edge(edge) => ShapeRef::Edge(edge),
+            Shape::Capsule(capsule) => ShapeRef::Capsule(capsule),
+            Shape::Obb(obb) => ShapeRef::Obb(obb),
+        }
+    }
+
+    /// Moves the shape by an offset.
+    pub fn translate(&mut self, offset: Vector2) {
+        match self {
+            Shape::Circle(circle) => circle.center += offset,
+            Shape::Polygon(polygon) => polygon.translate(offset),
+            Shape::Edge(edge) => {
+                edge.a += offset;
+                edge.b += offset;
+            }
+            Shape::Capsule(capsule) => {
+                capsule.a += offset;
+                capsule.b += offset;
+            }
+            Shape::Obb(obb) => obb.center += offset,
+        }
+    }
+
+    /// The shape moved, rotated and scaled by a transform.
+    pub fn transformed(&self, transform: &Transform) -> Shape {
+        match self {
+            Shape::Circle(circle) => Shape::Circle(circle.transformed(transform)),
+            Shape::Polygon(polygon) => Shape::Polygon(polygon.transformed(transform)),
+            Shape::Edge(edge) => Shape::Edge(edge.transformed(transform)),
+            Shape::Capsule(capsule) => Shape::Capsule(capsule.transformed(transform)),
+            Shape::Obb(obb) => Shape::Obb(obb.transformed(transform)),
+        }
+    }
+}
+
+impl Geometry for Shape {
+    fn aabb(&self) -> Rect {
+        self.to_ref().aabb()
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        self.to_ref().project(axis)
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        self.to_ref().contains(point)
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        self.to_ref().closest_point(point)
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        self.to_ref().support(direction)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(circle: Circle) -> Shape {
+        Shape::Circle(circle)
+    }
+}
+
+impl From<Polygon> for Shape {
+    fn from(polygon: Polygon) -> Shape {
+        Shape::Polygon(polygon)
+    }
+}
+
+impl From<Rect> for Shape {
+    fn from(rect: Rect) -> Shape {
+        Shape::Polygon(rect.into())
+    }
+}
+
+impl From<Edge> for Shape {
+    fn from(edge: Edge) -> Shape {
+        Shape::Edge(edge)
+    }
+}
+
+impl From<Capsule> for Shape {
+    fn from(capsule: Capsule) -> Shape {
+        Shape::Capsule(capsule)
+    }
+}
+
+impl From<Obb> for Shape {
+    fn from(obb: Obb) -> Shape {
+        Shape::Obb(obb)
+    }
+}
+
+/// A borrowed shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeRef<'a> {
+    Circle(&'a Circle),
+    Polygon(&'a Polygon),
+    Edge(&'a Edge),
+    Capsule(&'a Capsule),
+    Obb(&'a Obb),
+}
+
+impl<'a> ShapeRef<'a> {
+    /// The center of the shape.
+    ///
+    /// For polygons this is the average of the corners, which is all
+    /// collision needs.
+    pub fn center(&self) -> Vector2 {
+        match self {
+            ShapeRef::Circle(circle) => circle.center,
+            ShapeRef::Polygon(polygon) => polygon.center(),
+            ShapeRef::Edge(edge) => edge.center(),
+            ShapeRef::Capsule(capsule) => capsule.center(),
+            ShapeRef::Obb(obb) => obb.center,
+        }
+    }
+}
+
+impl<'a> Geometry for ShapeRef<'a> {
+    fn aabb(&self) -> Rect {
+        match self {
+            ShapeRef::Circle(circle) => circle.aabb(),
+            ShapeRef::Polygon(polygon) => polygon.aabb(),
+            ShapeRef::Edge(edge) => edge.aabb(),
+            ShapeRef::Capsule(capsule) => capsule.aabb(),
+            ShapeRef::Obb(obb) => obb.aabb(),
+        }
+    }
+
+    fn project(&self, axis: Vector2) -> Projection {
+        match self {
+            ShapeRef::Circle(circle) => circle.project(axis),
+            ShapeRef::Polygon(polygon) => polygon.project(axis),
+            ShapeRef::Edge(edge) => edge.project(axis),
+            ShapeRef::Capsule(capsule) => capsule.project(axis),
+            ShapeRef::Obb(obb) => obb.project(axis),
+        }
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        match self {
+            ShapeRef::Circle(circle) => circle.contains(point),
+            ShapeRef::Polygon(polygon) => polygon.contains(point),
+            ShapeRef::Edge(edge) => edge.contains(point),
+            ShapeRef::Capsule(capsule) => capsule.contains(point),
+            ShapeRef::Obb(obb) => obb.contains(point),
+        }
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        match self {
+            ShapeRef::Circle(circle) => circle.closest_point(point),
+            ShapeRef::Polygon(polygon) => polygon.closest_point(point),
+            ShapeRef::Edge(edge) => edge.closest_point(point),
+            ShapeRef::Capsule(capsule) => capsule.closest_point(point),
+            ShapeRef::Obb(obb) => obb.closest_point(point),
+        }
+    }
+
+    fn support(&self, direction: Vector2) -> Vector2 {
+        match self {
+            ShapeRef::Circle(circle) => circle.support(direction),
+            ShapeRef::Polygon(polygon) => polygon.support(direction),
+            ShapeRef::Edge(edge) => edge.support(direction),
+            ShapeRef::Capsule(capsule) => capsule.support(direction),
+            ShapeRef::Obb(obb) => obb.support(direction),
+        }
+    }
+}
+
+impl<'a> From<&'a Shape> for ShapeRef<'a> {
+    fn from(shape: &'a Shape) -> ShapeRef<'a> {
+        shape.to_ref()
+    }
+}
+
+/// Each edge of a closed loop of points.
+pub(crate) fn edges(points: &[Vector2]) -> impl Iterator<Item = (Vector2, Vector2)> + '_ {
+    points.iter().copied().zip(points.iter().copied().cycle().skip(1))
+}
+
+/// The closest point to `point` on the segment from `a` to `b`.
+pub(crate) fn closest_on_segment(a: Vector2, b: Vector2, point: Vector2) -> Vector2 {
+    let edge = b - a;
+    let length = edge.norm_squared();
+
+    if length <= f32::EPSILON {
+        return a;
+    }
+
+    a + edge * ((point - a).dot(&edge) / length).clamp(0., 1.)
+}
+
+/// The closest point of a circle to another point, or the point itself if the
+/// circle contains it.
+fn round_closest(center: Vector2, radius: f32, point: Vector2) -> Vector2 {
+    let offset = point - center;
+
+    if offset.norm_squared() <= radius * radius {
+        point
+    } else {
+        center + offset.normalize() * radius
+    }
+}
+
+/// The point of a circle furthest along a direction.
+fn round_support(center: Vector2, radius: f32, direction: Vector2) -> Vector2 {
+    center + direction.try_normalize(f32::EPSILON).unwrap_or_else(Vector2::zeros) * radius
+}
+
+/// The point furthest along a direction, out of some points.
+fn support_points(points: &[Vector2], direction: Vector2) -> Vector2 {
+    points
+        .iter()
+        .copied()
+        .max_by(|a, b| a.dot(&direction).total_cmp(&b.dot(&direction)))
+        .unwrap_or_else(Vector2::zeros)
+}
+
+/// The closest points between two segments, the first on `a` and the second
+/// on `b`.
+///
+/// Either segment can have no length.
+pub(crate) fn closest_between_segments(a: [Vector2; 2], b: [Vector2; 2]) -> (Vector2, Vector2) {
+    let (a_edge, b_edge) = (a[1] - a[0], b[1] - b[0]);
+    let offset = a[0] - b[0];
+    let (a_length, b_length) = (a_edge.norm_squared(), b_edge.norm_squared());
+    let (c, f) = (a_edge.dot(&offset), b_edge.dot(&offset));
+
+    // how far along each segment the closest points are, from 0 to 1
+    let (s, t) = if a_length <= f32::EPSILON && b_length <= f32::EPSILON {
+        (0., 0.)
+    } else if a_length <= f32::EPSILON {
+        (0., (f / b_length).clamp(0., 1.))
+    } else if b_length <= f32::EPSILON {
+        ((-c / a_length).clamp(0., 1.), 0.)
+    } else {
+        let along = a_edge.dot(&b_edge);
+        let denominator = a_length * b_length - along * along;
+
+        // parallel segments are closest anywhere they overlap, so start at
+        // the start of `a`
+        let s = if denominator > f32::EPSILON {
+            ((along * f - c * b_length) / denominator).clamp(0., 1.)
+        } else {
+            0.
+        };
+        let t = (along * s + f) / b_length;
+
+        if t < 0. {
+            ((-c / a_length).clamp(0., 1.), 0.)
+        } else if t > 1. {
+            (((along - c) / a_length).clamp(0., 1.), 1.)
+        } else {
+            (s, t)
+        }
+    };
+
+    (a[0] + a_edge * s, b[0] + b_edge * t)
+}
+
+pub(crate) fn project_points(points: &[Vector2], axis: Vector2) -> Projection {
+    let mut projection = Projection {
+        min: f32::INFINITY,
+        max: f32::NEG_INFINITY,
+    };
+
+    for point in points {
+        let distance = point.dot(&axis);
+
+        projection.min = projection.min.min(distance);
+        projection.max = projection.max.max(distance);
+    }
+
+    projection
+}
+
+fn points_aabb(points: &[Vector2]) -> Rect {
+    let (min, max) = points.iter().fold(
+        (Vector2::repeat(f32::INFINITY), Vector2::repeat(f32::NEG_INFINITY)),
+        |(min, max), point| (min.inf(point), max.sup(point)),
+    );
+
+    Rect::from_min_max(min, max)
+}
@@ -0,0 +1,258 @@
+//! Swept collision.
+//!
+//! [`collide`] only looks at where shapes are, so a shape moving far enough
+//! in one step skips right over a thin wall. Sweeping finds the first moment
+//! along a move that it touches anything.
+
+use crate::math::Vector2;
+
+use super::shape::edges;
+use super::{any_axis, collide, Capsule, Circle, Edge, Geometry, Ray, RayHit, ShapeRef};
+
+/// When a moving shape first touches another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeOfImpact {
+    /// How far along the move the shapes touch, from `0` to `1`.
+    pub time: f32,
+    /// The direction to push the moving shape back out, like
+    /// [`Contact::normal`](super::Contact::normal).
+    pub normal: Vector2,
+}
+
+/// When a shape moving by `motion` first touches another that stays still.
+///
+/// Shapes that already overlap touch at time `0`, with the normal
+/// [`collide`] gives.
+pub fn sweep<'a, 'b>(
+    a: impl Into<ShapeRef<'a>>,
+    motion: Vector2,
+    b: impl Into<ShapeRef<'b>>,
+) -> Option<TimeOfImpact> {
+    let (a, b) = (a.into(), b.into());
+
+    if let Some(contact) = collide(a, b) {
+        return Some(TimeOfImpact {
+            time: 0.,
+            normal: contact.normal,
+        });
+    }
+
+    match (a, b) {
+        (ShapeRef::Circle(circle), other) => sweep_circle(circle, motion, &other),
+        // the same as the circle moving the other way
+        (other, ShapeRef::Circle(circle)) => {
+            sweep_circle(circle, -motion, &other).map(|impact| TimeOfImpact {
+                normal: -impact.normal,
+                ..impact
+            })
+        }
+        // otherwise capsules are hit when the first of their pieces is
+        (ShapeRef::Capsule(capsule), other) => {
+            let pieces = capsule.pieces();
+            earliest(pieces.iter().filter_map(|piece| sweep(piece, motion, other)))
+        }
+        (other, ShapeRef::Capsule(capsule)) => {
+            let pieces = capsule.pieces();
+            earliest(pieces.iter().filter_map(|piece| sweep(other, motion, piece)))
+        }
+        _ => sweep_axes(&a, motion, &b),
+    }
+}
+
+/// Sweeps a circle by casting its center against the other shape grown by
+/// its radius: every edge pushed out, with a circle around every corner.
+fn sweep_circle(circle: &Circle, motion: Vector2, other: &ShapeRef) -> Option<TimeOfImpact> {
+    let ray = Ray::segment(circle.center, circle.center + motion);
+    let radius = circle.radius;
+
+    let grown_edge = |a: Vector2, b: Vector2, normal: Vector2| {
+        ray.cast(&Edge::new(a + normal * radius, b + normal * radius))
+    };
+    let grown_corner = |corner: Vector2| ray.cast(&Circle::new(corner, radius));
+    let grown_outline = |points: &[Vector2], normals: &[Vector2]| {
+        edges(points)
+            .zip(normals)
+            .filter_map(|((a, b), normal)| grown_edge(a, b, *normal))
+            .chain(points.iter().filter_map(|corner| grown_corner(*corner)))
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    };
+
+    let hit = match *other {
+        ShapeRef::Circle(other) => ray.cast(&Circle::new(other.center, other.radius + radius)),
+        ShapeRef::Polygon(polygon) => grown_outline(polygon.points(), polygon.normals()),
+        ShapeRef::Obb(obb) => grown_outline(&obb.corners(), &obb.normals()),
+        ShapeRef::Edge(edge) => {
+            // edges block from both sides
+            let normal = edge.normal();
+
+            [
+                grown_edge(edge.a, edge.b, normal),
+                grown_edge(edge.a, edge.b, -normal),
+                grown_corner(edge.a),
+                grown_corner(edge.b),
+            ]
+            .iter()
+            .flatten()
+            .copied()
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        }
+        ShapeRef::Capsule(other) => {
+            ray.cast(&Capsule::new(other.a, other.b, other.radius + radius))
+        }
+    }?;
+
+    let RayHit { distance, normal } = hit;
+    Some(TimeOfImpact {
+        time: distance / ray.max.max(f32::EPSILON),
+        normal,
+    })
+}
+
+/// The first of some impacts.
+fn earliest(impacts: impl Iterator<Item = TimeOfImpact>) -> Option<TimeOfImpact> {
+    impacts.min_by(|a, b| a.time.total_cmp(&b.time))
+}
+
+/// Sweeps shapes without circles with the separating axis test, finding when
+/// they start overlapping on every axis.
+fn sweep_axes(a: &ShapeRef, motion: Vector2, b: &ShapeRef) -> Option<TimeOfImpact> {
+    let mut enter = f32::NEG_INFINITY;
+    let mut normal = Vector2::zeros();
+    let mut exit = f32::INFINITY;
+
+    let mut separated = |axis: Vector2| {
+        let (a_extent, b_extent) = (a.project(axis), b.project(axis));
+        let speed = motion.dot(&axis);
+
+        // not moving along the axis, so the shapes stay apart on it or never
+        // are
+        if speed.abs() <= f32::EPSILON {
+            return a_extent.max <= b_extent.min || b_extent.max <= a_extent.min;
+        }
+
+        let touch = (b_extent.min - a_extent.max) / speed;
+        let leave = (b_extent.max - a_extent.min) / speed;
+        let (start, end) = if touch < leave { (touch, leave) } else { (leave, touch) };
+
+        if start > enter {
+            enter = start;
+            normal = if speed > 0. { -axis } else { axis };
+        }
+
+        exit = exit.min(end);
+        false
+    };
+
+    if any_axis(a, &[], b.center(), &mut |axis, _| separated(axis))
+        || any_axis(b, &[], a.center(), &mut |axis, _| separated(axis))
+    {
+        return None;
+    }
+
+    if enter >= exit || enter > 1. || exit <= 0. {
+        return None;
+    }
+
+    Some(TimeOfImpact {
+        time: enter.max(0.),
+        normal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::{Capsule, Obb, Polygon};
+    use crate::math::angle::Angle;
+    use crate::math::Rect;
+
+    fn square(center: Vector2, half: f32) -> Polygon {
+        Polygon::rect(center, Vector2::new(half, half))
+    }
+
+    fn assert_impact(impact: Option<TimeOfImpact>, time: f32, normal: Vector2) {
+        let impact = impact.unwrap();
+
+        assert!((impact.time - time).abs() < 1e-4, "hit at {}", impact.time);
+        assert!((impact.normal - normal).norm() < 1e-4, "normal {}", impact.normal);
+    }
+
+    #[test]
+    fn thin_walls() {
+        let bullet = Circle::new(Vector2::zeros(), 0.25);
+        let wall = Edge::new(Vector2::new(5., -1.), Vector2::new(5., 1.));
+
+        // a single step would jump right over the wall
+        assert!(collide(&Circle::new(Vector2::new(10., 0.), 0.25), &wall).is_none());
+        assert_impact(sweep(&bullet, Vector2::new(10., 0.), &wall), 0.475, -Vector2::x());
+        assert_impact(sweep(&wall, Vector2::new(-10., 0.), &bullet), 0.475, Vector2::x());
+
+        assert!(sweep(&bullet, Vector2::new(4., 0.), &wall).is_none());
+        assert!(sweep(&bullet, Vector2::new(10., 3.), &wall).is_none());
+    }
+
+    #[test]
+    fn corners() {
+        let circle = Circle::new(Vector2::new(0., 1.2), 0.25);
+        let block = Polygon::from(Rect::new(Vector2::new(5., -1.), Vector2::new(7., 1.)));
+
+        // clips the corner, which is a little under the center of the circle
+        assert_impact(
+            sweep(&circle, Vector2::new(10., 0.), &block),
+            0.485,
+            Vector2::new(-0.6, 0.8),
+        );
+        assert!(sweep(&circle, Vector2::new(10., 0.2), &block).is_none());
+    }
+
+    #[test]
+    fn polygons() {
+        let a = square(Vector2::zeros(), 0.5);
+        let b = square(Vector2::new(3., 0.), 0.5);
+
+        assert_impact(sweep(&a, Vector2::new(4., 0.), &b), 0.5, -Vector2::x());
+        assert_impact(sweep(&a, Vector2::new(4., 0.4), &b), 0.5, -Vector2::x());
+        assert!(sweep(&a, Vector2::new(4., 2.), &b).is_none());
+        assert!(sweep(&a, Vector2::new(1., 0.), &b).is_none());
+
+        let pillar = Circle::new(Vector2::new(3., 0.), 0.5);
+        assert_impact(sweep(&a, Vector2::new(4., 0.), &pillar), 0.5, -Vector2::x());
+    }
+
+    #[test]
+    fn already_touching() {
+        let a = square(Vector2::zeros(), 0.5);
+        let b = square(Vector2::new(0.8, 0.), 0.5);
+
+        assert_impact(sweep(&a, Vector2::new(-1., 0.), &b), 0., -Vector2::x());
+    }
+
+    #[test]
+    fn capsules() {
+        let player = Capsule::new(Vector2::new(0., -0.5), Vector2::new(0., 0.5), 0.25);
+        let wall = Edge::new(Vector2::new(5., -1.), Vector2::new(5., 1.));
+
+        assert_impact(sweep(&player, Vector2::new(10., 0.), &wall), 0.475, -Vector2::x());
+        assert_impact(sweep(&wall, Vector2::new(-10., 0.), &player), 0.475, Vector2::x());
+
+        // a circle clips the round top of the capsule
+        let ball = Circle::new(Vector2::new(-5., 0.8), 0.25);
+        assert_impact(sweep(&ball, Vector2::new(10., 0.), &player), 0.46, Vector2::new(-0.8, 0.6));
+
+        let block = square(Vector2::new(3., 0.), 0.5);
+        assert_impact(sweep(&player, Vector2::new(5., 0.), &block), 0.45, -Vector2::x());
+        assert!(sweep(&player, Vector2::new(1., 0.), &block).is_none());
+    }
+
+    #[test]
+    fn boxes() {
+        let door = Obb::new(Vector2::new(5., 0.), Vector2::new(1., 1.), Angle::from_degrees(45.));
+        let bullet = Circle::new(Vector2::zeros(), 0.25);
+
+        // hits the corner pointing back at it
+        let time = (5. - 2f32.sqrt() - 0.25) / 10.;
+        assert_impact(sweep(&bullet, Vector2::new(10., 0.), &door), time, -Vector2::x());
+        assert!(sweep(&bullet, Vector2::new(10., 5.), &door).is_none());
+    }
+}
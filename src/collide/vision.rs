@@ -0,0 +1,228 @@
+//! Vision.
+//!
+//! Players see everything within their vision radius that isn't behind a
+//! wall. Checking the same thing on the server means it doesn't have to trust
+//! clients about who saw a kill, and lets it shrink crewmate vision when the
+//! lights are sabotaged by just passing a smaller radius.
+
+use crate::math::Vector2;
+
+use super::{intersects, Circle, ColliderKind, Edge, Ray, ShapeRef, World};
+
+/// How many rays [`sight_polygon`] casts around the viewer, besides the ones
+/// aimed at corners.
+pub const SIGHT_RAYS: usize = 64;
+
+/// How far to either side of a corner [`sight_polygon`] casts its extra rays,
+/// in radians.
+const CORNER_OFFSET: f32 = 1e-4;
+
+/// How far along a ray a shape is hit, if it is hit within `max` of the
+/// origin.
+///
+/// The direction has to be normalized. A ray starting inside of a circle or
+/// polygon hits it right away.
+pub fn raycast<'a>(
+    origin: Vector2,
+    direction: Vector2,
+    max: f32,
+    shape: impl Into<ShapeRef<'a>>,
+) -> Option<f32> {
+    Ray { origin, direction, max }.cast(shape).map(|hit| hit.distance)
+}
+
+/// Whether a viewer can see a point past some walls.
+///
+/// The point has to be within `radius` of the viewer, with no wall crossing
+/// the line between them.
+pub fn can_see<'a, I>(viewer: Vector2, radius: f32, target: Vector2, walls: I) -> bool
+where I: IntoIterator<Item = ShapeRef<'a>> {
+    if !within(viewer, radius, target) {
+        return false;
+    }
+
+    let sight = Edge::new(viewer, target);
+
+    walls.into_iter().all(|wall| !intersects(&sight, wall))
+}
+
+/// Like [`can_see`], with the walls of a world.
+///
+/// Colliders on one of the `mask` layers block sight, unless they are
+/// triggers.
+pub fn can_see_in(world: &World, viewer: Vector2, radius: f32, target: Vector2, mask: u32) -> bool {
+    if !within(viewer, radius, target) {
+        return false;
+    }
+
+    let sight = Edge::new(viewer, target);
+
+    let blocked = world.query_shape(ShapeRef::Edge(&sight), mask).any(|(id, _)| {
+        world.get(id).is_some_and(|collider| collider.kind != ColliderKind::Trigger)
+    });
+
+    !blocked
+}
+
+/// The outline of everything a viewer can see, going counter-clockwise.
+///
+/// Rays are cast at [`SIGHT_RAYS`] even angles, and on either side of every
+/// corner within the radius so the shadows of walls come out sharp. Each
+/// point is where a ray hit a wall, or the edge of the radius.
+pub fn sight_polygon<'a, I>(viewer: Vector2, radius: f32, walls: I) -> Vec<Vector2>
+where I: IntoIterator<Item = ShapeRef<'a>> {
+    let walls: Vec<_> = walls.into_iter().collect();
+
+    let mut angles: Vec<f32> = (0..SIGHT_RAYS)
+        .map(|i| i as f32 / SIGHT_RAYS as f32 * std::f32::consts::TAU)
+        .collect();
+
+    for wall in &walls {
+        let (ends, box_corners);
+        let corners: &[Vector2] = match wall {
+            ShapeRef::Polygon(polygon) => polygon.points(),
+            ShapeRef::Obb(obb) => {
+                box_corners = obb.corners();
+                &box_corners
+            }
+            ShapeRef::Edge(edge) => {
+                ends = [edge.a, edge.b];
+                &ends
+            }
+            ShapeRef::Circle(circle) => {
+                aim_past(viewer, radius, circle, &mut angles);
+                &[]
+            }
+            ShapeRef::Capsule(capsule) => {
+                // the sides of a capsule are the sides of its ends
+                aim_past(viewer, radius, &Circle::new(capsule.a, capsule.radius), &mut angles);
+                aim_past(viewer, radius, &Circle::new(capsule.b, capsule.radius), &mut angles);
+                &[]
+            }
+        };
+
+        for corner in corners {
+            let offset = corner - viewer;
+
+            if offset != Vector2::zeros() && offset.norm() <= radius {
+                let angle = offset.y.atan2(offset.x);
+                angles.extend([angle - CORNER_OFFSET, angle, angle + CORNER_OFFSET]);
+            }
+        }
+    }
+
+    for angle in &mut angles {
+        *angle = angle.rem_euclid(std::f32::consts::TAU);
+    }
+
+    angles.sort_by(f32::total_cmp);
+    angles.dedup();
+
+    angles
+        .into_iter()
+        .map(|angle| {
+            let direction = Vector2::new(angle.cos(), angle.sin());
+            let distance = walls
+                .iter()
+                .filter_map(|wall| raycast(viewer, direction, radius, *wall))
+                .fold(radius, f32::min);
+
+            viewer + direction * distance
+        })
+        .collect()
+}
+
+/// Aims rays just past both sides of a circle.
+fn aim_past(viewer: Vector2, radius: f32, circle: &Circle, angles: &mut Vec<f32>) {
+    let offset = circle.center - viewer;
+    let distance = offset.norm();
+
+    if distance > circle.radius && distance - circle.radius <= radius {
+        let angle = offset.y.atan2(offset.x);
+        let spread = (circle.radius / distance).asin();
+        angles.extend([angle - spread - CORNER_OFFSET, angle + spread + CORNER_OFFSET]);
+    }
+}
+
+fn within(viewer: Vector2, radius: f32, target: Vector2) -> bool {
+    (target - viewer).norm_squared() <= radius * radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::map::{MapColliders, WALLS};
+    use crate::collide::{Capsule, Polygon};
+
+    fn wall() -> Polygon {
+        Polygon::new(vec![
+            Vector2::new(1., -1.),
+            Vector2::new(2., -1.),
+            Vector2::new(2., 1.),
+            Vector2::new(1., 1.),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn raycasts() {
+        let right = Vector2::x();
+
+        assert_eq!(raycast(Vector2::zeros(), right, 10., &wall()), Some(1.));
+        assert_eq!(raycast(Vector2::zeros(), -right, 10., &wall()), None);
+        assert_eq!(raycast(Vector2::zeros(), right, 0.5, &wall()), None);
+        assert_eq!(raycast(Vector2::new(1.5, 0.), right, 10., &wall()), Some(0.));
+
+        let pillar = Circle::new(Vector2::new(5., 0.), 1.);
+        assert_eq!(raycast(Vector2::zeros(), right, 10., &pillar), Some(4.));
+        assert_eq!(raycast(Vector2::zeros(), Vector2::y(), 10., &pillar), None);
+
+        let crate_ = Capsule::new(Vector2::new(3., -1.), Vector2::new(3., 1.), 0.5);
+        assert_eq!(raycast(Vector2::zeros(), right, 10., &crate_), Some(2.5));
+    }
+
+    #[test]
+    fn walls_block_sight() {
+        let wall = wall();
+        let walls = || std::iter::once(ShapeRef::Polygon(&wall));
+
+        assert!(!can_see(Vector2::zeros(), 5., Vector2::new(3., 0.), walls()));
+        assert!(can_see(Vector2::zeros(), 5., Vector2::new(2., 4.), walls()));
+        // out of range
+        assert!(!can_see(Vector2::zeros(), 5., Vector2::new(0., 6.), walls()));
+        // lights are out
+        assert!(!can_see(Vector2::zeros(), 1., Vector2::new(0., 2.), walls()));
+    }
+
+    #[test]
+    fn world() {
+        let map: MapColliders = "
+            polygon 1,-1 2,-1 2,1 1,1
+            vent 3 0,2 0.5
+        "
+        .parse()
+        .unwrap();
+
+        assert!(!can_see_in(&map.world, Vector2::zeros(), 5., Vector2::new(3., 0.), WALLS));
+        // triggers don't block
+        assert!(can_see_in(&map.world, Vector2::zeros(), 5., Vector2::new(0., 3.), u32::MAX));
+    }
+
+    #[test]
+    fn shadows() {
+        let wall = wall();
+        let walls = std::iter::once(ShapeRef::Polygon(&wall));
+        let outline = sight_polygon(Vector2::zeros(), 5., walls);
+
+        // straight ahead is blocked by the wall, straight back isn't
+        let ahead = outline.iter().find(|p| p.y.abs() < 1e-3 && p.x > 0.).unwrap();
+        assert!((ahead.x - 1.).abs() < 1e-4);
+
+        let behind = outline.iter().find(|p| p.y.abs() < 1e-3 && p.x < 0.).unwrap();
+        assert!((behind.x + 5.).abs() < 1e-4);
+
+        // just past the corner, the ray reaches the radius
+        assert!(outline.iter().any(|p| (p.norm() - 5.).abs() < 1e-3 && p.x > 3.5 && p.y > 3.5));
+    }
+}
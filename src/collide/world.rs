@@ -0,0 +1,469 @@
+//! A world of colliders.
+//!
+//! A [`World`] keeps its colliders in a broad-phase so it can find the pairs
+//! that touch cheaply. Each step it reports which pairs started and stopped
+//! touching, which is what a server needs to notice players walking into
+//! things.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::math::{Rect, Vector2};
+
+use super::broadphase::SpatialHash;
+use super::{Algorithm, Contact, Geometry, Polygon, Shape, ShapeRef};
+
+/// How a collider affects things moving into it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColliderKind {
+    /// Blocks movement, like walls and players.
+    #[default]
+    Solid,
+    /// Only reports overlaps, like task consoles, vent entrances and the
+    /// report radius.
+    Trigger,
+    /// Blocks movement from in front of it, and lets things through from
+    /// behind.
+    ///
+    /// Only edges have a front, so any other shape blocks like a solid one.
+    OneWay,
+}
+
+/// The cell size of a world made with [`World::default`].
+///
+/// A bit bigger than a player.
+pub const DEFAULT_CELL_SIZE: f32 = 2.;
+
+/// A collider in a [`World`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ColliderId(u32);
+
+/// A shape in a [`World`].
+///
+/// Two colliders collide if each is on a layer in the other's mask, and they
+/// don't belong to the same body. For example, ghosts pass through walls if
+/// their mask leaves out the layer the walls are on.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collider {
+    pub shape: Shape,
+    /// What the collider belongs to, like a player id.
+    pub body: u32,
+    /// The layers the collider is on, one bit each.
+    pub layer: u32,
+    /// The layers the collider collides with.
+    pub mask: u32,
+    pub kind: ColliderKind,
+}
+
+impl Collider {
+    /// Create a new solid `Collider` on the first layer, colliding with
+    /// everything.
+    pub fn new(body: u32, shape: impl Into<Shape>) -> Collider {
+        Collider {
+            shape: shape.into(),
+            body,
+            layer: 1,
+            mask: u32::MAX,
+            kind: ColliderKind::Solid,
+        }
+    }
+
+    /// Create a new trigger `Collider` on the first layer, colliding with
+    /// everything.
+    pub fn trigger(body: u32, shape: impl Into<Shape>) -> Collider {
+        Collider {
+            kind: ColliderKind::Trigger,
+            ..Collider::new(body, shape)
+        }
+    }
+
+    /// Whether two colliders can collide at all.
+    pub fn interacts(&self, other: &Collider) -> bool {
+        self.body != other.body && self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+
+    /// Whether the collider stops another moving by `motion` from moving into
+    /// it.
+    ///
+    /// The contact pushes the mover out of this collider, as returned by
+    /// [`collide`] with the mover first. Triggers never block, and never get
+    /// blocked either.
+    pub fn blocks(&self, mover: &Collider, contact: &Contact, motion: Vector2) -> bool {
+        if !self.interacts(mover) || mover.kind == ColliderKind::Trigger {
+            return false;
+        }
+
+        match (self.kind, &self.shape) {
+            (ColliderKind::Trigger, _) => false,
+            // only when coming at the front, from the front
+            (ColliderKind::OneWay, Shape::Edge(edge)) => {
+                let normal = edge.normal();
+                contact.normal.dot(&normal) > 0. && motion.dot(&normal) < 0.
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Something that happened between two colliders in a step.
+///
+/// Triggers report these just like solid colliders. The lower id is always
+/// first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// The colliders started touching.
+    Started(ColliderId, ColliderId, Contact),
+    /// The colliders stopped touching, or one of them was removed.
+    Stopped(ColliderId, ColliderId),
+}
+
+/// A shape from a batch query overlapping a collider.
+///
+/// Returned by [`World::query_many`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pair {
+    /// The index of the shape in the batch.
+    pub index: usize,
+    pub collider: ColliderId,
+    /// Pushes the shape out of the collider.
+    pub contact: Contact,
+}
+
+/// A set of colliders.
+#[derive(Clone, Debug)]
+pub struct World {
+    colliders: HashMap<ColliderId, Collider>,
+    next_id: u32,
+    broadphase: SpatialHash<ColliderId>,
+    touching: BTreeSet<(ColliderId, ColliderId)>,
+    algorithm: Algorithm,
+    tolerance: f32,
+}
+
+impl World {
+    /// Create a new, empty `World`.
+    ///
+    /// # Panics
+    /// Panics if `cell_size` is not positive.
+    pub fn new(cell_size: f32) -> World {
+        World {
+            colliders: HashMap::new(),
+            next_id: 0,
+            broadphase: SpatialHash::new(cell_size),
+            touching: BTreeSet::new(),
+            algorithm: Algorithm::default(),
+            tolerance: 0.,
+        }
+    }
+
+    /// How the world tests colliders for collisions.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Changes how the world tests colliders for collisions. Worlds use
+    /// [`Algorithm::Sat`] to begin with.
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+    }
+
+    /// How deep colliders can overlap and still only count as touching.
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    /// Changes how deep colliders can overlap and still only count as
+    /// touching, like in [`collide_within`](super::collide_within). Worlds
+    /// have no tolerance to begin with.
+    pub fn set_tolerance(&mut self, tolerance: f32) {
+        self.tolerance = tolerance;
+    }
+
+    /// Adds a collider.
+    pub fn insert(&mut self, collider: Collider) -> ColliderId {
+        let id = ColliderId(self.next_id);
+        self.next_id += 1;
+
+        self.broadphase.insert(id, collider.shape.aabb());
+        self.colliders.insert(id, collider);
+
+        id
+    }
+
+    /// Removes a collider, returning it.
+    pub fn remove(&mut self, id: ColliderId) -> Option<Collider> {
+        self.broadphase.remove(id);
+        self.colliders.remove(&id)
+    }
+
+    /// A collider by id.
+    pub fn get(&self, id: ColliderId) -> Option<&Collider> {
+        self.colliders.get(&id)
+    }
+
+    /// Every collider, in no particular order.
+    pub fn colliders(&self) -> impl Iterator<Item = (ColliderId, &Collider)> {
+        self.colliders.iter().map(|(id, collider)| (*id, collider))
+    }
+
+    /// The number of colliders.
+    pub fn len(&self) -> usize {
+        self.colliders.len()
+    }
+
+    /// Whether there are no colliders.
+    pub fn is_empty(&self) -> bool {
+        self.colliders.is_empty()
+    }
+
+    /// Moves a collider, returning `false` if it doesn't exist.
+    pub fn translate(&mut self, id: ColliderId, offset: Vector2) -> bool {
+        match self.colliders.get_mut(&id) {
+            Some(collider) => {
+                collider.shape.translate(offset);
+                self.broadphase.insert(id, collider.shape.aabb());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The colliders on one of the `mask` layers that a shape overlaps.
+    pub fn query_shape<'a>(
+        &'a self,
+        shape: ShapeRef<'a>,
+        mask: u32,
+    ) -> impl Iterator<Item = (ColliderId, Contact)> + 'a {
+        self.broadphase.query(shape.aabb()).filter_map(move |id| {
+            let collider = &self.colliders[&id];
+
+            if collider.layer & mask == 0 {
+                return None;
+            }
+
+            self.algorithm
+                .collide_within(shape, collider.shape.to_ref(), self.tolerance)
+                .map(|contact| (id, contact))
+        })
+    }
+
+    /// Queries many shapes at once, like every player against the walls.
+    ///
+    /// The pairs are sorted by the index of the shape, then by collider.
+    pub fn query_many(&self, shapes: &[ShapeRef], mask: u32) -> Vec<Pair> {
+        let mut pairs = Vec::new();
+        self.query_many_into(shapes, mask, &mut pairs);
+        pairs
+    }
+
+    /// Like [`query_many`](World::query_many), but reuses a buffer.
+    ///
+    /// The buffer is cleared first.
+    pub fn query_many_into(&self, shapes: &[ShapeRef], mask: u32, pairs: &mut Vec<Pair>) {
+        pairs.clear();
+
+        for (index, shape) in shapes.iter().enumerate() {
+            let start = pairs.len();
+
+            pairs.extend(
+                self.query_shape(*shape, mask)
+                    .map(|(collider, contact)| Pair { index, collider, contact }),
+            );
+            pairs[start..].sort_by_key(|pair| pair.collider);
+        }
+    }
+
+    /// The colliders on one of the `mask` layers that overlap a region.
+    pub fn query_region(&self, region: Rect, mask: u32) -> Vec<ColliderId> {
+        let region = Polygon::from(region);
+
+        self.query_shape(ShapeRef::Polygon(&region), mask).map(|(id, _)| id).collect()
+    }
+
+    /// The pairs of colliders touching as of the last step.
+    pub fn touching(&self) -> impl Iterator<Item = (ColliderId, ColliderId)> + '_ {
+        self.touching.iter().copied()
+    }
+
+    /// Finds every pair of touching colliders, and reports the changes since
+    /// the last step.
+    ///
+    /// The events are sorted by the ids of the colliders.
+    pub fn step(&mut self) -> Vec<Event> {
+        let mut touching = BTreeSet::new();
+        let mut events = Vec::new();
+
+        for (a, b) in self.broadphase.pairs() {
+            let (a, b) = (a.min(b), a.max(b));
+            let (a_collider, b_collider) = (&self.colliders[&a], &self.colliders[&b]);
+
+            if !a_collider.interacts(b_collider) {
+                continue;
+            }
+
+            let (a_shape, b_shape) = (&a_collider.shape, &b_collider.shape);
+
+            if let Some(contact) = self.algorithm.collide_within(a_shape, b_shape, self.tolerance) {
+                touching.insert((a, b));
+
+                if !self.touching.contains(&(a, b)) {
+                    events.push(Event::Started(a, b, contact));
+                }
+            }
+        }
+
+        for &(a, b) in self.touching.difference(&touching) {
+            events.push(Event::Stopped(a, b));
+        }
+
+        events.sort_by_key(|event| match *event {
+            Event::Started(a, b, _) | Event::Stopped(a, b) => (a, b),
+        });
+
+        self.touching = touching;
+        events
+    }
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::{Circle, Edge};
+
+    const WALLS: u32 = 1;
+    const PLAYERS: u32 = 2;
+    const GHOSTS: u32 = 4;
+
+    fn wall() -> Collider {
+        let edge = Edge::new(Vector2::new(-5., 0.), Vector2::new(5., 0.));
+
+        Collider {
+            layer: WALLS,
+            mask: PLAYERS,
+            ..Collider::new(0, edge)
+        }
+    }
+
+    fn player(body: u32, layer: u32, mask: u32, position: Vector2) -> Collider {
+        Collider {
+            layer,
+            mask,
+            ..Collider::new(body, Circle::new(position, 0.5))
+        }
+    }
+
+    #[test]
+    fn events() {
+        let mut world = World::default();
+        let wall = world.insert(wall());
+        let crewmate = world.insert(player(1, PLAYERS, WALLS, Vector2::new(0., 2.)));
+        let ghost = world.insert(player(2, GHOSTS, 0, Vector2::new(1., 2.)));
+
+        assert!(world.step().is_empty());
+
+        world.translate(crewmate, Vector2::new(0., -1.8));
+        world.translate(ghost, Vector2::new(0., -1.8));
+
+        match world.step().as_slice() {
+            [Event::Started(a, b, contact)] => {
+                assert_eq!((*a, *b), (wall, crewmate));
+                assert_eq!(contact.normal, Vector2::new(0., -1.));
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+
+        // nothing changed
+        assert!(world.step().is_empty());
+        assert_eq!(world.touching().collect::<Vec<_>>(), vec![(wall, crewmate)]);
+
+        world.remove(crewmate);
+        assert_eq!(world.step(), vec![Event::Stopped(wall, crewmate)]);
+    }
+
+    #[test]
+    fn query_region() {
+        let mut world = World::default();
+        let wall = world.insert(wall());
+        let crewmate = world.insert(player(1, PLAYERS, WALLS, Vector2::new(3., 3.)));
+
+        let region = Rect::new(Vector2::new(2., -1.), Vector2::new(4., 4.));
+        let mut found = world.query_region(region, u32::MAX);
+        found.sort();
+        assert_eq!(found, vec![wall, crewmate]);
+
+        assert_eq!(world.query_region(region, PLAYERS), vec![crewmate]);
+    }
+
+    #[test]
+    fn triggers_report_overlaps() {
+        let mut world = World::default();
+        let vent = world.insert(Collider::trigger(0, Circle::new(Vector2::new(0., 0.), 1.)));
+        let crewmate = world.insert(player(1, PLAYERS, u32::MAX, Vector2::new(1.2, 0.)));
+
+        match world.step().as_slice() {
+            [Event::Started(a, b, _)] => assert_eq!((*a, *b), (vent, crewmate)),
+            events => panic!("unexpected events {:?}", events),
+        }
+    }
+
+    #[test]
+    fn query_many() {
+        let mut world = World::default();
+        let wall = world.insert(wall());
+        let pillar = world.insert(Collider::new(0, Circle::new(Vector2::new(3., 1.), 0.5)));
+
+        let players = [
+            Circle::new(Vector2::new(0., 0.2), 0.5),
+            Circle::new(Vector2::new(0., 3.), 0.5),
+            Circle::new(Vector2::new(3., 0.3), 0.5),
+        ];
+        let shapes: Vec<_> = players.iter().map(ShapeRef::Circle).collect();
+
+        let pairs: Vec<_> = world
+            .query_many(&shapes, u32::MAX)
+            .iter()
+            .map(|pair| (pair.index, pair.collider))
+            .collect();
+        assert_eq!(pairs, vec![(0, wall), (2, wall), (2, pillar)]);
+    }
+
+    #[test]
+    fn tolerance() {
+        let mut world = World::default();
+        world.insert(wall());
+        world.insert(player(1, PLAYERS, WALLS, Vector2::new(0., 0.5 - 1e-5)));
+
+        assert_eq!(world.step().len(), 1);
+
+        world.set_tolerance(1e-4);
+        assert_eq!(world.tolerance(), 1e-4);
+        assert_eq!(world.step().len(), 1);
+        assert_eq!(world.touching().count(), 0);
+    }
+
+    #[test]
+    fn algorithms() {
+        let mut world = World::default();
+        let wall = world.insert(wall());
+        let crewmate = Circle::new(Vector2::new(1., 0.3), 0.5);
+
+        for algorithm in [Algorithm::Sat, Algorithm::Gjk] {
+            world.set_algorithm(algorithm);
+            assert_eq!(world.algorithm(), algorithm);
+
+            let hits: Vec<_> = world.query_shape(ShapeRef::Circle(&crewmate), WALLS).collect();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].0, wall);
+            assert!((hits[0].1.normal - Vector2::y()).norm() < 1e-4);
+            assert!((hits[0].1.depth - 0.2).abs() < 1e-4);
+        }
+    }
+}
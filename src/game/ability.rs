@@ -0,0 +1,389 @@
+//! Role abilities.
+//!
+//! Every role past crewmate and impostor has something it can do, on a
+//! cooldown. The state of it lives in an [`Ability`] on the player, and is
+//! driven by [`tick`](Ability::tick) with the time since the last tick, in
+//! seconds like the rest of the game.
+//!
+//! Abilities only keep time. Whether a player is close enough to a vent, or
+//! who they are allowed to shift into, is up to the caller.
+
+use crate::game::Role;
+use crate::net::protocol::options::RoleOptions;
+
+/// What a player can do because of their role.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ability {
+    /// The role has nothing to keep track of.
+    None,
+    Scientist(Vitals),
+    Engineer(Venting),
+    Shapeshifter(Effect),
+    GuardianAngel(Effect),
+}
+
+impl Ability {
+    /// The ability of a role, fresh at the start of a game.
+    pub fn new(role: Role, options: &RoleOptions) -> Ability {
+        match role {
+            Role::Scientist => Ability::Scientist(Vitals::new(
+                options.scientist_battery as f32,
+                options.scientist_cooldown as f32,
+            )),
+            Role::Engineer => Ability::Engineer(Venting::new(
+                options.engineer_vent_time as f32,
+                options.engineer_cooldown as f32,
+            )),
+            Role::Shapeshifter => Ability::Shapeshifter(Effect::new(
+                options.shapeshifter_duration as f32,
+                options.shapeshifter_cooldown as f32,
+            )),
+            Role::GuardianAngel => Ability::GuardianAngel(Effect::new(
+                options.protect_duration as f32,
+                options.guardian_angel_cooldown as f32,
+            )),
+            _ => Ability::None,
+        }
+    }
+
+    /// Advances the ability by `delta` seconds.
+    ///
+    /// Returns `true` if something ran out on this tick: the scientist's
+    /// battery, the engineer's time in a vent, a shapeshift or a protection.
+    /// The caller should then tell everyone, since the player didn't stop
+    /// it themselves.
+    pub fn tick(&mut self, delta: f32) -> bool {
+        match self {
+            Ability::None => false,
+            Ability::Scientist(vitals) => vitals.tick(delta),
+            Ability::Engineer(venting) => venting.tick(delta),
+            Ability::Shapeshifter(effect) | Ability::GuardianAngel(effect) => effect.tick(delta),
+        }
+    }
+}
+
+/// Counts a timer down, returning whether it reached zero on this tick.
+fn count_down(timer: &mut f32, delta: f32) -> bool {
+    if *timer <= 0. {
+        return false;
+    }
+
+    *timer = (*timer - delta).max(0.);
+    *timer <= 0.
+}
+
+/// A scientist's portable vitals.
+///
+/// Looking at vitals drains the battery. Once it is empty, it recharges fully
+/// after a cooldown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vitals {
+    /// How many seconds of battery are left.
+    pub battery: f32,
+    /// How long the battery lasts when full.
+    pub capacity: f32,
+    /// How long until the battery is recharged.
+    pub cooldown: f32,
+    /// How long recharging takes.
+    pub recharge: f32,
+    open: bool,
+}
+
+impl Vitals {
+    /// Create a new `Vitals`, fully charged.
+    pub fn new(capacity: f32, recharge: f32) -> Vitals {
+        Vitals {
+            battery: capacity,
+            capacity,
+            cooldown: 0.,
+            recharge,
+            open: false,
+        }
+    }
+
+    /// Whether vitals are being looked at.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens vitals, if there is any battery left.
+    pub fn open(&mut self) -> bool {
+        self.open = self.battery > 0.;
+        self.open
+    }
+
+    /// Closes vitals.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    fn tick(&mut self, delta: f32) -> bool {
+        if self.open {
+            if count_down(&mut self.battery, delta) {
+                self.open = false;
+                self.cooldown = self.recharge;
+                return true;
+            }
+        } else if count_down(&mut self.cooldown, delta) {
+            self.battery = self.capacity;
+        }
+
+        false
+    }
+}
+
+/// An engineer's use of vents.
+///
+/// An engineer can stay in a vent for a while, then has to wait before going
+/// back in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Venting {
+    /// How many seconds are left in the vent, if in one.
+    pub remaining: Option<f32>,
+    /// How long the engineer can stay in a vent.
+    pub max_time: f32,
+    /// How long until the engineer can vent again.
+    pub cooldown: f32,
+    /// How long the engineer waits between vents.
+    pub recharge: f32,
+}
+
+impl Venting {
+    /// Create a new `Venting`, ready to vent.
+    pub fn new(max_time: f32, recharge: f32) -> Venting {
+        Venting {
+            remaining: None,
+            max_time,
+            cooldown: 0.,
+            recharge,
+        }
+    }
+
+    /// Whether the engineer is in a vent.
+    pub fn in_vent(&self) -> bool {
+        self.remaining.is_some()
+    }
+
+    /// Whether the engineer can go into a vent.
+    pub fn can_enter(&self) -> bool {
+        !self.in_vent() && self.cooldown <= 0.
+    }
+
+    /// Goes into a vent, if the cooldown is over.
+    pub fn enter(&mut self) -> bool {
+        if !self.can_enter() {
+            return false;
+        }
+
+        self.remaining = Some(self.max_time);
+        true
+    }
+
+    /// Comes out of a vent, starting the cooldown.
+    pub fn exit(&mut self) {
+        if self.remaining.take().is_some() {
+            self.cooldown = self.recharge;
+        }
+    }
+
+    fn tick(&mut self, delta: f32) -> bool {
+        match &mut self.remaining {
+            Some(remaining) => {
+                if count_down(remaining, delta) {
+                    self.exit();
+                    return true;
+                }
+            }
+            None => {
+                count_down(&mut self.cooldown, delta);
+            }
+        }
+
+        false
+    }
+}
+
+/// An ability used on another player for a while, like a shapeshift or a
+/// guardian angel's protection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Effect {
+    /// The player the ability is used on, if it is in effect.
+    pub target: Option<u8>,
+    /// How many seconds the effect has left.
+    pub remaining: f32,
+    /// How long the effect lasts.
+    pub duration: f32,
+    /// How long until the ability can be used again.
+    pub cooldown: f32,
+    /// How long the ability takes to be ready again once the effect ends.
+    pub recharge: f32,
+}
+
+impl Effect {
+    /// Create a new `Effect`.
+    ///
+    /// Like in the official client, the ability starts on cooldown.
+    pub fn new(duration: f32, recharge: f32) -> Effect {
+        Effect {
+            target: None,
+            remaining: 0.,
+            duration,
+            cooldown: recharge,
+            recharge,
+        }
+    }
+
+    /// Whether the ability is in effect.
+    pub fn is_active(&self) -> bool {
+        self.target.is_some()
+    }
+
+    /// Whether the ability is in effect on a player.
+    pub fn is_active_on(&self, player: u8) -> bool {
+        self.target == Some(player)
+    }
+
+    /// Whether the ability can be used.
+    pub fn is_ready(&self) -> bool {
+        !self.is_active() && self.cooldown <= 0.
+    }
+
+    /// Uses the ability on a player, if it is ready.
+    pub fn start(&mut self, target: u8) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+
+        self.target = Some(target);
+        self.remaining = self.duration;
+        true
+    }
+
+    /// Ends the effect early, starting the cooldown.
+    pub fn end(&mut self) {
+        if self.target.take().is_some() {
+            self.remaining = 0.;
+            self.cooldown = self.recharge;
+        }
+    }
+
+    fn tick(&mut self, delta: f32) -> bool {
+        if self.is_active() {
+            if count_down(&mut self.remaining, delta) {
+                self.end();
+                return true;
+            }
+        } else {
+            count_down(&mut self.cooldown, delta);
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abilities_of_roles() {
+        let options = RoleOptions::default();
+
+        assert_eq!(Ability::new(Role::Crewmate, &options), Ability::None);
+        assert_eq!(Ability::new(Role::Impostor, &options), Ability::None);
+        assert_eq!(
+            Ability::new(Role::Engineer, &options),
+            Ability::Engineer(Venting::new(15., 30.)),
+        );
+        assert_eq!(
+            Ability::new(Role::GuardianAngel, &options),
+            Ability::GuardianAngel(Effect::new(10., 60.)),
+        );
+    }
+
+    #[test]
+    fn vitals() {
+        let mut vitals = Vitals::new(5., 15.);
+
+        assert!(vitals.open());
+        assert!(!vitals.tick(4.));
+        assert_eq!(vitals.battery, 1.);
+
+        // closing keeps what is left of the battery
+        vitals.close();
+        assert!(!vitals.tick(10.));
+        assert_eq!(vitals.battery, 1.);
+
+        assert!(vitals.open());
+        assert!(vitals.tick(2.), "battery ran out");
+        assert!(!vitals.is_open());
+        assert!(!vitals.open());
+
+        assert!(!vitals.tick(14.));
+        assert_eq!(vitals.battery, 0.);
+        vitals.tick(1.);
+        assert_eq!(vitals.battery, 5.);
+        assert!(vitals.open());
+    }
+
+    #[test]
+    fn venting() {
+        let mut venting = Venting::new(15., 30.);
+
+        assert!(venting.enter());
+        assert!(!venting.enter());
+        assert!(!venting.tick(10.));
+
+        venting.exit();
+        assert!(!venting.in_vent());
+        assert!(!venting.enter(), "on cooldown");
+
+        venting.tick(30.);
+        assert!(venting.enter());
+        assert!(venting.tick(15.), "kicked out of the vent");
+        assert!(!venting.in_vent());
+        assert_eq!(venting.cooldown, 30.);
+    }
+
+    #[test]
+    fn effect() {
+        let mut ability = Ability::Shapeshifter(Effect::new(30., 10.));
+
+        let effect = |ability: &mut Ability| match ability {
+            Ability::Shapeshifter(effect) => *effect,
+            _ => unreachable!(),
+        };
+
+        // starts on cooldown
+        assert!(!effect(&mut ability).is_ready());
+        ability.tick(10.);
+        assert!(effect(&mut ability).is_ready());
+
+        if let Ability::Shapeshifter(effect) = &mut ability {
+            assert!(effect.start(3));
+            assert!(!effect.start(4));
+        }
+
+        assert!(effect(&mut ability).is_active_on(3));
+        assert!(!ability.tick(29.));
+        assert!(ability.tick(1.), "the shift ran out");
+        assert!(!effect(&mut ability).is_active());
+        assert_eq!(effect(&mut ability).cooldown, 10.);
+    }
+
+    #[test]
+    fn ending_early() {
+        let mut effect = Effect::new(10., 60.);
+        effect.cooldown = 0.;
+
+        assert!(effect.start(1));
+        effect.end();
+        assert!(!effect.is_active());
+        assert_eq!(effect.cooldown, 60.);
+
+        // ending twice doesn't restart the cooldown
+        effect.tick(30.);
+        effect.end();
+        assert_eq!(effect.cooldown, 30.);
+    }
+}
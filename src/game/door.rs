@@ -0,0 +1,372 @@
+//! Doors.
+//!
+//! Impostors can shut the doors of a room with the CloseDoorsOfType RPC. On
+//! the Skeld they open again by themselves, and on Polus and the Airship
+//! crewmates can also open them by hand with a RepairSystem RPC to
+//! [`DOORS`]. Either way, a room can't be shut again until its cooldown runs
+//! out.
+//!
+//! The decontamination rooms of Mira HQ and Polus are here too: a player
+//! walking up to one starts a sequence of the doors opening, closing and
+//! opening on the other side.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::game::map::MapData;
+use crate::game::Player;
+use crate::net::objects::ship::{Map, ShipStatus};
+use crate::net::objects::systems::{
+    System, DECONTAMINATION, DECONTAMINATION_2, DECON_CLOSED, DECON_ENTER, DECON_EXIT,
+    DECON_HEADING_UP, DOORS,
+};
+use crate::net::protocol::Rpc;
+
+/// How long doors stay shut before they open by themselves, in seconds.
+pub const CLOSE_TIME: f32 = 10.;
+
+/// How long until the doors of a room can be shut again, in seconds.
+pub const COOLDOWN: f32 = 30.;
+
+/// How long each step of decontamination takes, in seconds.
+pub const DECON_TIME: f32 = 3.;
+
+/// Why doors can't be shut or opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorError {
+    /// The room has no doors.
+    NoDoors(u8),
+    /// The map has no door with the id.
+    NoSuchDoor(u8),
+    /// The map has no decontamination room of the system type.
+    NoSuchDecon(u8),
+    /// The doors of the map only open by themselves.
+    NotManual,
+    /// The room's doors were shut too recently.
+    Cooldown,
+    /// The decontamination room is already in use.
+    Busy,
+    /// The player isn't an impostor.
+    NotAllowed,
+}
+
+impl fmt::Display for DoorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DoorError::NoDoors(room) => write!(f, "room {} has no doors", room),
+            DoorError::NoSuchDoor(id) => write!(f, "no door with id {}", id),
+            DoorError::NoSuchDecon(system) => {
+                write!(f, "no decontamination room for system {}", system)
+            }
+            DoorError::NotManual => write!(f, "doors can't be opened by hand"),
+            DoorError::Cooldown => write!(f, "on cooldown"),
+            DoorError::Busy => write!(f, "decontamination already in use"),
+            DoorError::NotAllowed => write!(f, "not allowed to shut doors"),
+        }
+    }
+}
+
+impl std::error::Error for DoorError {}
+
+/// A decontamination room.
+#[derive(Clone, Copy, Debug)]
+struct Decon {
+    system: u8,
+    state: u8,
+    timer: f32,
+}
+
+impl Decon {
+    fn new(system: u8) -> Decon {
+        Decon {
+            system,
+            state: 0,
+            timer: 0.,
+        }
+    }
+
+    /// Moves on to the next step once the timer runs out.
+    fn tick(&mut self, delta: f32) {
+        if self.state == 0 {
+            return;
+        }
+
+        self.timer -= delta;
+
+        if self.timer > 0. {
+            return;
+        }
+
+        let heading = self.state & DECON_HEADING_UP;
+
+        if self.state & DECON_ENTER != 0 {
+            self.state = DECON_CLOSED | heading;
+            self.timer = DECON_TIME;
+        } else if self.state & DECON_CLOSED != 0 {
+            self.state = DECON_EXIT | heading;
+            self.timer = DECON_TIME;
+        } else {
+            self.state = 0;
+            self.timer = 0.;
+        }
+    }
+}
+
+/// The doors of a map, and its decontamination rooms.
+#[derive(Clone, Debug)]
+pub struct Doors {
+    data: &'static MapData,
+    manual: bool,
+    /// Seconds until each door opens by itself, by id, or `None` if it's
+    /// open.
+    doors: Vec<Option<f32>>,
+    /// Seconds until the doors of each room can be shut again.
+    cooldowns: BTreeMap<u8, f32>,
+    decons: Vec<Decon>,
+}
+
+impl Doors {
+    /// Create a new `Doors` for a map, with every door open.
+    pub fn new(data: &'static MapData) -> Doors {
+        let count = data.doors.iter().map(|door| door.id as usize + 1).max().unwrap_or(0);
+
+        let decons = match data.map {
+            Map::MiraHq => vec![Decon::new(DECONTAMINATION)],
+            Map::Polus => vec![Decon::new(DECONTAMINATION), Decon::new(DECONTAMINATION_2)],
+            _ => Vec::new(),
+        };
+
+        Doors {
+            data,
+            manual: matches!(data.map, Map::Polus | Map::Airship),
+            doors: vec![None; count],
+            cooldowns: BTreeMap::new(),
+            decons,
+        }
+    }
+
+    /// Whether a door is open.
+    ///
+    /// Doors that don't exist are always open.
+    pub fn is_open(&self, id: u8) -> bool {
+        self.doors.get(id as usize).is_none_or(Option::is_none)
+    }
+
+    /// Seconds until the doors of a room can be shut again.
+    pub fn cooldown(&self, room: u8) -> f32 {
+        self.cooldowns.get(&room).copied().unwrap_or(0.)
+    }
+
+    /// Checks whether the doors of a room could be shut right now.
+    pub fn can_close(&self, room: u8) -> Result<(), DoorError> {
+        if self.data.doors_of(room).next().is_none() {
+            return Err(DoorError::NoDoors(room));
+        }
+
+        if self.cooldown(room) > 0. {
+            return Err(DoorError::Cooldown);
+        }
+
+        Ok(())
+    }
+
+    /// Shuts every door of a room.
+    pub fn close_room(&mut self, room: u8) -> Result<(), DoorError> {
+        self.can_close(room)?;
+
+        for door in self.data.doors_of(room) {
+            self.doors[door.id as usize] = Some(CLOSE_TIME);
+        }
+
+        self.cooldowns.insert(room, COOLDOWN);
+
+        Ok(())
+    }
+
+    /// Opens a door by hand.
+    pub fn open(&mut self, id: u8) -> Result<(), DoorError> {
+        if !self.manual {
+            return Err(DoorError::NotManual);
+        }
+
+        let door = self.doors.get_mut(id as usize).ok_or(DoorError::NoSuchDoor(id))?;
+        *door = None;
+
+        Ok(())
+    }
+
+    /// Opens every door, like when a meeting is called.
+    ///
+    /// Cooldowns keep running.
+    pub fn open_all(&mut self) {
+        for door in &mut self.doors {
+            *door = None;
+        }
+    }
+
+    /// The state of a decontamination room, as `DECON_*` flags.
+    pub fn decon_state(&self, system: u8) -> Option<u8> {
+        self.decons.iter().find(|decon| decon.system == system).map(|decon| decon.state)
+    }
+
+    /// Starts decontamination, when a player walks up to one of its doors.
+    ///
+    /// `heading_up` is whether the player came in from the bottom door.
+    pub fn decontaminate(&mut self, system: u8, heading_up: bool) -> Result<(), DoorError> {
+        let decon = self
+            .decons
+            .iter_mut()
+            .find(|decon| decon.system == system)
+            .ok_or(DoorError::NoSuchDecon(system))?;
+
+        if decon.state != 0 {
+            return Err(DoorError::Busy);
+        }
+
+        decon.state = if heading_up { DECON_ENTER | DECON_HEADING_UP } else { DECON_ENTER };
+        decon.timer = DECON_TIME;
+
+        Ok(())
+    }
+
+    /// Handles a CloseDoorsOfType or RepairSystem RPC sent by a player.
+    ///
+    /// Only impostors can shut doors, but anyone can open them. A repair of
+    /// [`DOORS`] opens the door in the low 5 bits of the amount. A repair of a
+    /// decontamination room starts it, heading up with an amount of 1 and
+    /// down with 2. Other RPCs are ignored.
+    pub fn handle_rpc(&mut self, player: &Player, rpc: &Rpc) -> Result<(), DoorError> {
+        match *rpc {
+            Rpc::CloseDoorsOfType { system } => {
+                if !player.is_impostor() {
+                    return Err(DoorError::NotAllowed);
+                }
+
+                self.close_room(system)
+            }
+            Rpc::RepairSystem { system: DOORS, amount, .. } => self.open(amount & 0x1f),
+            Rpc::RepairSystem { system, amount, .. }
+                if system == DECONTAMINATION || system == DECONTAMINATION_2 =>
+            {
+                match amount {
+                    1 => self.decontaminate(system, true),
+                    2 => self.decontaminate(system, false),
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Advances the door timers, cooldowns and decontamination by `delta`
+    /// seconds.
+    pub fn tick(&mut self, delta: f32) {
+        for door in &mut self.doors {
+            if let Some(timer) = door {
+                *timer -= delta;
+
+                if *timer <= 0. {
+                    *door = None;
+                }
+            }
+        }
+
+        for cooldown in self.cooldowns.values_mut() {
+            *cooldown -= delta;
+        }
+
+        self.cooldowns.retain(|_, cooldown| *cooldown > 0.);
+
+        for decon in &mut self.decons {
+            decon.tick(delta);
+        }
+    }
+
+    /// Writes the doors into a ship, marking the systems that changed dirty.
+    ///
+    /// Room cooldowns on Polus and the Airship are only written when a room
+    /// is shut or its cooldown runs out, since clients count them down
+    /// themselves.
+    pub fn sync(&self, ship: &mut ShipStatus) {
+        let changed = match ship.system(DOORS) {
+            Some(System::AutoDoors(system)) => self.differs(&system.doors),
+            Some(System::Doors(system)) => {
+                self.differs(&system.doors)
+                    || !system.timers.iter().map(|(room, _)| room).eq(self.cooldowns.keys())
+            }
+            _ => false,
+        };
+
+        if changed {
+            match ship.system_mut(DOORS) {
+                Some(System::AutoDoors(system)) => {
+                    for (index, door) in self.doors.iter().enumerate() {
+                        if system.doors.get(index) != Some(&door.is_none()) {
+                            system.set_door(index, door.is_none());
+                        }
+                    }
+                }
+                Some(System::Doors(system)) => {
+                    system.doors = self.doors.iter().map(Option::is_none).collect();
+                    system.timers =
+                        self.cooldowns.iter().map(|(room, cooldown)| (*room, *cooldown)).collect();
+                }
+                _ => (),
+            }
+        }
+
+        for decon in &self.decons {
+            let timer = decon.timer.max(0.).ceil() as u8;
+
+            let changed = match ship.system(decon.system) {
+                Some(System::Decon(system)) => system.timer != timer || system.state != decon.state,
+                _ => false,
+            };
+
+            if changed {
+                if let Some(System::Decon(system)) = ship.system_mut(decon.system) {
+                    system.timer = timer;
+                    system.state = decon.state;
+                }
+            }
+        }
+    }
+
+    fn differs(&self, doors: &[bool]) -> bool {
+        !doors.iter().copied().eq(self.doors.iter().map(Option::is_none))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::game::map;
+    use crate::game::role::Role;
+
+    fn player(role: Role) -> Player {
+        let mut player = Player::new(0, 1, "Red".into());
+        player.role = role;
+        player
+    }
+
+    #[test]
+    fn only_impostors_close_doors() {
+        let mut doors = Doors::new(map::data(Map::Polus));
+        let room = map::data(Map::Polus).doors[0].room;
+        let rpc = Rpc::CloseDoorsOfType { system: room };
+
+        let crewmate = player(Role::Crewmate);
+        assert_eq!(doors.handle_rpc(&crewmate, &rpc), Err(DoorError::NotAllowed));
+        assert!(doors.is_open(0));
+
+        let impostor = player(Role::Impostor);
+        assert_eq!(doors.handle_rpc(&impostor, &rpc), Ok(()));
+        assert!(!doors.is_open(0));
+
+        // but anyone can open them again
+        let open = Rpc::RepairSystem { system: DOORS, player: 0, amount: 0x40 };
+        assert_eq!(doors.handle_rpc(&crewmate, &open), Ok(()));
+        assert!(doors.is_open(0));
+    }
+}
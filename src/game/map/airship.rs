@@ -0,0 +1,219 @@
+//! The Airship.
+//!
+//! Players choose where to spawn on the Airship, at the start of the game and
+//! after every meeting, so the spawn circles are only a fallback.
+
+use crate::game::task::TaskLength::{Common, Long, Short};
+use crate::net::objects::ship::Map;
+use crate::net::objects::systems::{
+    ARMORY, BRIG, CARGO_BAY, COCKPIT, COMMS, ELECTRICAL, ENGINE, GAP_ROOM, HALL_OF_PORTRAITS,
+    KITCHEN, LOUNGE, MAIN_HALL, MEDICAL, MEETING_ROOM, RECORDS, SHOWERS, VAULT_ROOM,
+    VENTILATION, VIEWING_DECK,
+};
+
+use super::{rect, v, Camera, Door, MapData, Room, SpawnPoint, Task, Vent};
+
+pub static DATA: MapData = MapData {
+    map: Map::Airship,
+    name: "Airship",
+    spawn_center: v(8.5, 15.5),
+    meeting_center: v(8.5, 15.5),
+    spawn_radius: 1.55,
+    spawn_points: SPAWN_POINTS,
+    rooms: ROOMS,
+    vents: VENTS,
+    doors: DOORS,
+    tasks: TASKS,
+    cameras: CAMERAS,
+};
+
+const SPAWN_POINTS: &[SpawnPoint] = &[
+    SpawnPoint { room: BRIG, position: v(-0.7, 8.5) },
+    SpawnPoint { room: ENGINE, position: v(-0.7, -1.0) },
+    SpawnPoint { room: KITCHEN, position: v(-7.0, -11.5) },
+    SpawnPoint { room: MAIN_HALL, position: v(10.0, 0.0) },
+    SpawnPoint { room: RECORDS, position: v(20.0, 8.0) },
+    SpawnPoint { room: CARGO_BAY, position: v(33.5, -1.5) },
+];
+
+const ROOMS: &[Room] = &[
+    Room { system: COCKPIT, name: "Cockpit", bounds: rect(-25.0, -3.0, -19.0, 3.0) },
+    Room { system: COMMS, name: "Communications", bounds: rect(-19.0, 0.0, -14.0, 4.0) },
+    Room { system: VAULT_ROOM, name: "Vault", bounds: rect(-14.5, 6.0, -6.5, 11.0) },
+    Room { system: ARMORY, name: "Armory", bounds: rect(-14.0, -4.0, -8.0, 2.0) },
+    Room { system: VIEWING_DECK, name: "Viewing Deck", bounds: rect(-17.0, -14.0, -12.0, -10.0) },
+    Room { system: ENGINE, name: "Engine Room", bounds: rect(-7.0, -5.0, 3.0, 3.0) },
+    Room { system: KITCHEN, name: "Kitchen", bounds: rect(-8.0, -13.0, 2.0, -7.0) },
+    Room { system: BRIG, name: "Brig", bounds: rect(-2.0, 5.0, 4.0, 12.0) },
+    Room { system: MEETING_ROOM, name: "Meeting Room", bounds: rect(5.0, 13.0, 12.0, 18.0) },
+    Room { system: MAIN_HALL, name: "Main Hall", bounds: rect(4.0, -5.0, 14.0, 2.0) },
+    Room { system: GAP_ROOM, name: "Gap Room", bounds: rect(10.0, 4.0, 16.0, 10.0) },
+    Room {
+        system: HALL_OF_PORTRAITS,
+        name: "Hall of Portraits",
+        bounds: rect(3.0, -14.0, 11.0, -8.0),
+    },
+    Room { system: ELECTRICAL, name: "Electrical", bounds: rect(12.0, -12.0, 19.0, -7.0) },
+    Room { system: RECORDS, name: "Records", bounds: rect(16.0, 4.0, 23.0, 12.0) },
+    Room { system: SHOWERS, name: "Showers", bounds: rect(18.0, -2.0, 25.0, 3.0) },
+    Room { system: LOUNGE, name: "Lounge", bounds: rect(25.0, 4.0, 33.0, 10.0) },
+    Room { system: MEDICAL, name: "Medical", bounds: rect(23.0, -10.0, 30.0, -3.0) },
+    Room { system: CARGO_BAY, name: "Cargo Bay", bounds: rect(30.0, -5.0, 39.0, 4.0) },
+    Room { system: VENTILATION, name: "Ventilation", bounds: rect(19.0, -16.0, 25.0, -11.0) },
+];
+
+const VENTS: &[Vent] = &[
+    Vent { id: 0, name: "Vault", position: v(-12.63, 8.47), connections: &[1] },
+    Vent { id: 1, name: "Cockpit", position: v(-22.10, -1.51), connections: &[0, 2] },
+    Vent { id: 2, name: "Viewing Deck", position: v(-15.66, -11.60), connections: &[1] },
+    Vent { id: 3, name: "Engine Room", position: v(0.20, -2.52), connections: &[4, 5] },
+    Vent { id: 4, name: "Kitchen", position: v(-2.60, -9.34), connections: &[3] },
+    Vent { id: 5, name: "Main Hall Bottom", position: v(7.02, -3.73), connections: &[3] },
+    Vent { id: 6, name: "Gap Room Right", position: v(15.22, 8.60), connections: &[11] },
+    Vent { id: 7, name: "Gap Room Left", position: v(10.60, 6.90), connections: &[8, 9] },
+    Vent { id: 8, name: "Meeting Room", position: v(6.60, 14.20), connections: &[7] },
+    Vent { id: 9, name: "Main Hall Top", position: v(10.00, 0.50), connections: &[7] },
+    Vent { id: 10, name: "Showers", position: v(21.90, -0.20), connections: &[11, 12] },
+    Vent { id: 11, name: "Records", position: v(20.30, 10.10), connections: &[6, 10] },
+    Vent { id: 12, name: "Cargo Bay", position: v(33.50, -1.40), connections: &[10] },
+];
+
+const DOORS: &[Door] = &[
+    Door { id: 0, room: COMMS, position: v(-16.5, 0.0) },
+    Door { id: 1, room: COMMS, position: v(-14.0, 2.0) },
+    Door { id: 2, room: BRIG, position: v(-2.0, 8.0) },
+    Door { id: 3, room: BRIG, position: v(4.0, 8.0) },
+    Door { id: 4, room: BRIG, position: v(1.0, 5.0) },
+    Door { id: 5, room: KITCHEN, position: v(-8.0, -10.0) },
+    Door { id: 6, room: KITCHEN, position: v(2.0, -10.0) },
+    Door { id: 7, room: KITCHEN, position: v(-3.0, -7.0) },
+    Door { id: 8, room: MAIN_HALL, position: v(4.0, -1.5) },
+    Door { id: 9, room: MAIN_HALL, position: v(14.0, -1.5) },
+    Door { id: 10, room: MAIN_HALL, position: v(9.0, 2.0) },
+    Door { id: 11, room: MAIN_HALL, position: v(9.0, -5.0) },
+    Door { id: 12, room: RECORDS, position: v(16.0, 8.0) },
+    Door { id: 13, room: RECORDS, position: v(23.0, 8.0) },
+    Door { id: 14, room: RECORDS, position: v(19.5, 4.0) },
+    Door { id: 15, room: LOUNGE, position: v(25.0, 7.0) },
+    Door { id: 16, room: LOUNGE, position: v(33.0, 7.0) },
+    Door { id: 17, room: LOUNGE, position: v(29.0, 4.0) },
+    Door { id: 18, room: LOUNGE, position: v(29.0, 10.0) },
+    Door { id: 19, room: MEDICAL, position: v(23.0, -6.5) },
+    Door { id: 20, room: MEDICAL, position: v(26.5, -3.0) },
+];
+
+const TASKS: &[Task] = &[
+    Task {
+        id: 0,
+        name: "Fix Wiring",
+        length: Common,
+        room: MAIN_HALL,
+        consoles: &[v(5.4, 1.6), v(-11.9, 1.5), v(17.7, -7.2)],
+    },
+    Task { id: 1, name: "Enter ID Code", length: Common, room: BRIG, consoles: &[v(1.5, 11.2)] },
+    Task {
+        id: 2,
+        name: "Develop Photos",
+        length: Long,
+        room: MAIN_HALL,
+        consoles: &[v(12.6, 1.3)],
+    },
+    Task {
+        id: 3,
+        name: "Empty Garbage",
+        length: Long,
+        room: MAIN_HALL,
+        consoles: &[v(11.8, -4.6), v(-6.3, -12.5), v(35.5, 2.0)],
+    },
+    Task { id: 4, name: "Fix Shower", length: Long, room: SHOWERS, consoles: &[v(21.0, 2.2)] },
+    Task {
+        id: 5,
+        name: "Reset Breakers",
+        length: Long,
+        room: ELECTRICAL,
+        consoles: &[v(15.0, -9.0), v(17.5, -10.5)],
+    },
+    Task { id: 6, name: "Unlock Safe", length: Long, room: VAULT_ROOM, consoles: &[v(-8.7, 9.7)] },
+    Task {
+        id: 7,
+        name: "Calibrate Distributor",
+        length: Short,
+        room: ELECTRICAL,
+        consoles: &[v(13.6, -8.2)],
+    },
+    Task { id: 8, name: "Clean Toilet", length: Short, room: LOUNGE, consoles: &[v(32.2, 9.2)] },
+    Task {
+        id: 9,
+        name: "Divert Power",
+        length: Short,
+        room: ELECTRICAL,
+        consoles: &[v(18.2, -8.4), v(-19.8, 3.2)],
+    },
+    Task {
+        id: 10,
+        name: "Dress Mannequin",
+        length: Short,
+        room: VAULT_ROOM,
+        consoles: &[v(-13.2, 10.0)],
+    },
+    Task { id: 11, name: "Make Burger", length: Short, room: KITCHEN, consoles: &[v(-5.2, -11.7)] },
+    Task {
+        id: 12,
+        name: "Pick Up Towels",
+        length: Short,
+        room: SHOWERS,
+        consoles: &[v(23.6, 1.4)],
+    },
+    Task {
+        id: 13,
+        name: "Polish Ruby",
+        length: Short,
+        room: VAULT_ROOM,
+        consoles: &[v(-10.1, 7.2)],
+    },
+    Task {
+        id: 14,
+        name: "Put Away Pistols",
+        length: Short,
+        room: ARMORY,
+        consoles: &[v(-12.8, 1.3)],
+    },
+    Task {
+        id: 15,
+        name: "Put Away Rifles",
+        length: Short,
+        room: ARMORY,
+        consoles: &[v(-9.0, 1.3)],
+    },
+    Task { id: 16, name: "Sort Records", length: Short, room: RECORDS, consoles: &[v(20.6, 11.2)] },
+    Task {
+        id: 17,
+        name: "Stabilize Steering",
+        length: Short,
+        room: COCKPIT,
+        consoles: &[v(-23.6, -0.8)],
+    },
+    Task {
+        id: 18,
+        name: "Start Fans",
+        length: Short,
+        room: VENTILATION,
+        consoles: &[v(22.0, -14.5)],
+    },
+    Task {
+        id: 19,
+        name: "Upload Data",
+        length: Short,
+        room: COCKPIT,
+        consoles: &[v(-22.5, 1.7), v(-17.4, 2.5)],
+    },
+];
+
+const CAMERAS: &[Camera] = &[
+    Camera { name: "Engine Room", position: v(-2.4, 0.2) },
+    Camera { name: "Vault", position: v(-8.3, 9.7) },
+    Camera { name: "Records", position: v(22.1, 9.0) },
+    Camera { name: "Cargo Bay", position: v(29.5, 1.2) },
+    Camera { name: "Meeting Room", position: v(11.0, 16.3) },
+    Camera { name: "Kitchen", position: v(-7.5, -8.0) },
+];
@@ -0,0 +1,147 @@
+//! Mira HQ.
+//!
+//! Mira HQ has no doors to sabotage and no cameras, only a door log.
+
+use crate::game::task::TaskLength::{Common, Long, Short};
+use crate::net::objects::ship::Map;
+use crate::net::objects::systems::{
+    ADMIN, BALCONY, CAFETERIA, COMMS, DECONTAMINATION, GREENHOUSE, HALLWAY, LABORATORY,
+    LAUNCHPAD, LOCKER_ROOM, MED_BAY, OFFICE, REACTOR, STORAGE,
+};
+
+use super::{rect, v, MapData, Room, Task, Vent};
+
+pub static DATA: MapData = MapData {
+    map: Map::MiraHq,
+    name: "Mira HQ",
+    spawn_center: v(-4.4, 2.2),
+    meeting_center: v(24.04, 1.72),
+    spawn_radius: 1.55,
+    spawn_points: &[],
+    rooms: ROOMS,
+    vents: VENTS,
+    doors: &[],
+    tasks: TASKS,
+    cameras: &[],
+};
+
+const ROOMS: &[Room] = &[
+    Room { system: LAUNCHPAD, name: "Launchpad", bounds: rect(-8.0, -1.0, -1.0, 6.0) },
+    Room { system: LOCKER_ROOM, name: "Locker Room", bounds: rect(2.0, -2.0, 6.0, 2.0) },
+    Room {
+        system: DECONTAMINATION,
+        name: "Decontamination",
+        bounds: rect(5.5, 2.0, 8.0, 5.0),
+    },
+    Room { system: REACTOR, name: "Reactor", bounds: rect(-1.5, 8.0, 4.0, 14.0) },
+    Room { system: LABORATORY, name: "Laboratory", bounds: rect(7.0, 9.0, 14.0, 15.0) },
+    Room { system: HALLWAY, name: "Hallway", bounds: rect(9.0, 15.0, 19.0, 17.5) },
+    Room { system: OFFICE, name: "Office", bounds: rect(11.5, 17.5, 16.0, 22.0) },
+    Room { system: ADMIN, name: "Admin", bounds: rect(19.5, 16.0, 24.0, 21.0) },
+    Room { system: GREENHOUSE, name: "Greenhouse", bounds: rect(14.0, 22.5, 22.0, 27.0) },
+    Room { system: MED_BAY, name: "MedBay", bounds: rect(13.5, -3.0, 17.0, 1.0) },
+    Room { system: COMMS, name: "Communications", bounds: rect(13.5, 1.5, 17.0, 6.0) },
+    Room { system: STORAGE, name: "Storage", bounds: rect(18.0, 1.0, 21.5, 6.0) },
+    Room { system: CAFETERIA, name: "Cafeteria", bounds: rect(21.5, 1.0, 28.5, 8.0) },
+    Room { system: BALCONY, name: "Balcony", bounds: rect(18.0, -3.5, 30.0, 0.5) },
+];
+
+const VENTS: &[Vent] = &[
+    Vent { id: 1, name: "Balcony", position: v(23.77, -1.58), connections: &[2, 8] },
+    Vent { id: 2, name: "Cafeteria", position: v(23.90, 7.18), connections: &[1, 6] },
+    Vent { id: 3, name: "Reactor", position: v(0.48, 10.70), connections: &[4, 9, 11] },
+    Vent { id: 4, name: "Laboratory", position: v(11.61, 13.82), connections: &[3, 5] },
+    Vent { id: 5, name: "Office", position: v(13.28, 20.13), connections: &[4, 6, 7] },
+    Vent { id: 6, name: "Admin", position: v(22.39, 17.23), connections: &[2, 5, 7] },
+    Vent { id: 7, name: "Greenhouse", position: v(17.85, 25.23), connections: &[5, 6] },
+    Vent { id: 8, name: "MedBay", position: v(15.41, -1.82), connections: &[1, 10] },
+    Vent { id: 9, name: "Decontamination", position: v(6.83, 3.15), connections: &[3, 10] },
+    Vent { id: 10, name: "Locker Room", position: v(4.29, 0.53), connections: &[8, 9, 11] },
+    Vent { id: 11, name: "Launchpad", position: v(-6.18, 3.56), connections: &[3, 10] },
+];
+
+const TASKS: &[Task] = &[
+    Task { id: 0, name: "Enter ID Code", length: Common, room: ADMIN, consoles: &[v(20.7, 19.3)] },
+    Task {
+        id: 1,
+        name: "Fix Wiring",
+        length: Common,
+        room: LAUNCHPAD,
+        consoles: &[v(-5.0, 2.9), v(2.6, 1.3), v(10.2, 13.3)],
+    },
+    Task {
+        id: 2,
+        name: "Water Plants",
+        length: Long,
+        room: STORAGE,
+        consoles: &[v(19.8, 4.7), v(16.0, 23.6)],
+    },
+    Task {
+        id: 3,
+        name: "Run Diagnostics",
+        length: Long,
+        room: LAUNCHPAD,
+        consoles: &[v(-4.2, 0.9)],
+    },
+    Task { id: 4, name: "Sort Samples", length: Long, room: LABORATORY, consoles: &[v(8.3, 12.6)] },
+    Task { id: 5, name: "Start Reactor", length: Long, room: REACTOR, consoles: &[v(2.6, 10.9)] },
+    Task { id: 6, name: "Submit Scan", length: Long, room: MED_BAY, consoles: &[v(15.5, 0.2)] },
+    Task {
+        id: 7,
+        name: "Assemble Artifact",
+        length: Short,
+        room: LABORATORY,
+        consoles: &[v(11.8, 13.3)],
+    },
+    Task {
+        id: 8,
+        name: "Buy Beverage",
+        length: Short,
+        room: CAFETERIA,
+        consoles: &[v(27.6, 5.5)],
+    },
+    Task { id: 9, name: "Chart Course", length: Short, room: ADMIN, consoles: &[v(19.8, 20.3)] },
+    Task {
+        id: 10,
+        name: "Clean O2 Filter",
+        length: Short,
+        room: GREENHOUSE,
+        consoles: &[v(14.8, 23.6)],
+    },
+    Task {
+        id: 11,
+        name: "Clear Asteroids",
+        length: Short,
+        room: BALCONY,
+        consoles: &[v(19.8, -2.1)],
+    },
+    Task {
+        id: 12,
+        name: "Divert Power",
+        length: Short,
+        room: REACTOR,
+        consoles: &[v(2.5, 13.5), v(23.8, 6.2)],
+    },
+    Task {
+        id: 13,
+        name: "Empty Garbage",
+        length: Short,
+        room: CAFETERIA,
+        consoles: &[v(22.1, 2.3)],
+    },
+    Task {
+        id: 14,
+        name: "Fuel Engines",
+        length: Short,
+        room: LAUNCHPAD,
+        consoles: &[v(-6.1, 1.6)],
+    },
+    Task {
+        id: 15,
+        name: "Measure Weather",
+        length: Short,
+        room: BALCONY,
+        consoles: &[v(28.6, -1.6)],
+    },
+    Task { id: 16, name: "Process Data", length: Short, room: OFFICE, consoles: &[v(13.4, 20.2)] },
+];
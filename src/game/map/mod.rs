@@ -0,0 +1,339 @@
+//! Static map data.
+//!
+//! Everything a server or a bot needs to know about the world of a map, like
+//! where the rooms, vents and task consoles are, without loading the game's
+//! assets. Positions are in world units, the same ones movement is sent in,
+//! and were taken by hand from the game, so they are close but not exact.
+//!
+//! Rooms, doors and task consoles are identified by the system type of their
+//! room, as in [`systems`](crate::net::objects::systems).
+
+use crate::game::task::{TaskDef, TaskLength, TaskPool};
+use crate::math::{Rect, Vector2};
+use crate::net::objects::ship::Map;
+
+mod airship;
+mod mira;
+mod polus;
+mod skeld;
+
+/// A room of a map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Room {
+    /// The system type of the room.
+    pub system: u8,
+    pub name: &'static str,
+    pub bounds: Rect,
+}
+
+/// A vent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vent {
+    pub id: u8,
+    pub name: &'static str,
+    pub position: Vector2,
+    /// The ids of the vents this one leads to.
+    pub connections: &'static [u8],
+}
+
+/// A door that can be closed by sabotage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Door {
+    pub id: u8,
+    /// The system type of the room the door closes off.
+    pub room: u8,
+    pub position: Vector2,
+}
+
+/// A task of a map, and where it is done.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Task {
+    /// The id of the task on this map.
+    pub id: u8,
+    pub name: &'static str,
+    pub length: TaskLength,
+    /// The system type of the room the task starts in.
+    pub room: u8,
+    /// Where the consoles of the task are, in the order they are done. Tasks
+    /// with more than one step, like wiring, have more than one.
+    pub consoles: &'static [Vector2],
+}
+
+impl Task {
+    /// The task as handed out by a [`TaskPool`].
+    pub fn def(&self) -> TaskDef {
+        TaskDef {
+            id: self.id,
+            length: self.length,
+        }
+    }
+}
+
+/// A security camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub name: &'static str,
+    pub position: Vector2,
+}
+
+/// Where players can spawn at the start of a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpawnPoint {
+    /// The system type of the room of the spawn point.
+    pub room: u8,
+    pub position: Vector2,
+}
+
+/// The data of a map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapData {
+    pub map: Map,
+    pub name: &'static str,
+    /// Where players are put in a circle around at the start of a game.
+    pub spawn_center: Vector2,
+    /// Where players are put in a circle around after a meeting.
+    pub meeting_center: Vector2,
+    /// The radius of the spawn circles.
+    pub spawn_radius: f32,
+    /// The spawn points players choose between, on maps that let them.
+    pub spawn_points: &'static [SpawnPoint],
+    pub rooms: &'static [Room],
+    pub vents: &'static [Vent],
+    pub doors: &'static [Door],
+    pub tasks: &'static [Task],
+    pub cameras: &'static [Camera],
+}
+
+impl MapData {
+    /// A room by its system type.
+    pub fn room(&self, system: u8) -> Option<&'static Room> {
+        self.rooms.iter().find(|room| room.system == system)
+    }
+
+    /// The room a point is in, if any.
+    pub fn room_at(&self, point: Vector2) -> Option<&'static Room> {
+        self.rooms.iter().find(|room| room.bounds.contains(point))
+    }
+
+    /// A vent by id.
+    pub fn vent(&self, id: u8) -> Option<&'static Vent> {
+        self.vents.iter().find(|vent| vent.id == id)
+    }
+
+    /// A door by id.
+    pub fn door(&self, id: u8) -> Option<&'static Door> {
+        self.doors.iter().find(|door| door.id == id)
+    }
+
+    /// The doors of a room.
+    pub fn doors_of(&self, room: u8) -> impl Iterator<Item = &'static Door> {
+        self.doors.iter().filter(move |door| door.room == room)
+    }
+
+    /// A task by id.
+    pub fn task(&self, id: u8) -> Option<&'static Task> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+
+    /// A pool of every task of the map.
+    pub fn task_pool(&self) -> TaskPool {
+        let mut pool = TaskPool::new();
+
+        for task in self.tasks {
+            pool.add(task.def());
+        }
+
+        pool
+    }
+
+    /// Where the `index`th of `count` players spawns at the start of a game.
+    ///
+    /// Players are spread around a circle, starting from straight up.
+    pub fn spawn_position(&self, index: usize, count: usize) -> Vector2 {
+        circle(self.spawn_center, self.spawn_radius, index, count)
+    }
+
+    /// Where the `index`th of `count` players is put after a meeting.
+    pub fn meeting_position(&self, index: usize, count: usize) -> Vector2 {
+        circle(self.meeting_center, self.spawn_radius, index, count)
+    }
+}
+
+/// A point on a circle, for the `index`th of `count` players.
+fn circle(center: Vector2, radius: f32, index: usize, count: usize) -> Vector2 {
+    let angle = index as f32 / count.max(1) as f32 * std::f32::consts::TAU;
+    let (sin, cos) = angle.sin_cos();
+
+    // rotating straight up counter-clockwise
+    center + Vector2::new(-sin, cos) * radius
+}
+
+/// The data of a map.
+///
+/// The April Fools Skeld is the Skeld mirrored left to right, and has the
+/// same data; negate the x of its positions.
+pub fn data(map: Map) -> &'static MapData {
+    match map {
+        Map::Skeld | Map::AprilSkeld => &skeld::DATA,
+        Map::MiraHq => &mira::DATA,
+        Map::Polus => &polus::DATA,
+        Map::Airship => &airship::DATA,
+    }
+}
+
+/// Shorthand for a position in the data.
+const fn v(x: f32, y: f32) -> Vector2 {
+    Vector2::new(x, y)
+}
+
+/// Shorthand for the bounds of a room in the data.
+const fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Rect {
+    Rect::from_min_max(v(x0, y0), v(x1, y1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::objects::ship;
+    use crate::net::objects::systems::OUTSIDE;
+
+    const MAPS: [Map; 4] = [Map::Skeld, Map::MiraHq, Map::Polus, Map::Airship];
+
+    fn unique<I>(ids: I) -> bool
+    where I: IntoIterator<Item = u8> {
+        let mut ids: Vec<_> = ids.into_iter().collect();
+        let len = ids.len();
+
+        ids.sort_unstable();
+        ids.dedup();
+        ids.len() == len
+    }
+
+    #[test]
+    fn maps() {
+        for &map in &MAPS {
+            assert_eq!(data(map).map, map);
+        }
+
+        assert_eq!(data(Map::AprilSkeld), data(Map::Skeld));
+    }
+
+    #[test]
+    fn ids_are_unique() {
+        for &map in &MAPS {
+            let data = data(map);
+
+            assert!(unique(data.rooms.iter().map(|room| room.system)), "{} rooms", data.name);
+            assert!(unique(data.vents.iter().map(|vent| vent.id)), "{} vents", data.name);
+            assert!(unique(data.doors.iter().map(|door| door.id)), "{} doors", data.name);
+            assert!(unique(data.tasks.iter().map(|task| task.id)), "{} tasks", data.name);
+        }
+    }
+
+    #[test]
+    fn vents_connect_both_ways() {
+        for &map in &MAPS {
+            let data = data(map);
+
+            for vent in data.vents {
+                for &id in vent.connections {
+                    let other = data.vent(id).expect("connected vent exists");
+
+                    assert_ne!(other.id, vent.id, "{} vent {} leads to itself", data.name, id);
+                    assert!(
+                        other.connections.contains(&vent.id),
+                        "{}: {} leads to {} but not back",
+                        data.name,
+                        vent.name,
+                        other.name,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rooms_exist() {
+        for &map in &MAPS {
+            let data = data(map);
+
+            for door in data.doors {
+                assert!(data.room(door.room).is_some(), "{} door {}", data.name, door.id);
+            }
+
+            // outside isn't a room with bounds, but tasks can start there
+            for task in data.tasks.iter().filter(|task| task.room != OUTSIDE) {
+                assert!(data.room(task.room).is_some(), "{} {}", data.name, task.name);
+            }
+
+            for task in data.tasks {
+                assert!(!task.consoles.is_empty(), "{} {}", data.name, task.name);
+            }
+
+            for spawn in data.spawn_points {
+                assert!(data.room(spawn.room).is_some(), "{} spawn", data.name);
+            }
+
+            for room in data.rooms {
+                assert!(room.bounds.min().x < room.bounds.max().x, "{} {}", data.name, room.name);
+                assert!(room.bounds.min().y < room.bounds.max().y, "{} {}", data.name, room.name);
+            }
+        }
+    }
+
+    #[test]
+    fn door_counts_match_the_ship() {
+        assert_eq!(data(Map::Skeld).doors.len(), ship::SKELD_DOORS);
+        assert_eq!(data(Map::Polus).doors.len(), ship::POLUS_DOORS);
+        assert_eq!(data(Map::Airship).doors.len(), ship::AIRSHIP_DOORS);
+        assert!(data(Map::MiraHq).doors.is_empty());
+    }
+
+    #[test]
+    fn every_task_length() {
+        for &map in &MAPS {
+            let data = data(map);
+
+            for &length in &[TaskLength::Common, TaskLength::Short, TaskLength::Long] {
+                assert!(
+                    data.tasks.iter().any(|task| task.length == length),
+                    "{} has no {:?} tasks",
+                    data.name,
+                    length,
+                );
+            }
+
+            assert!(!data.task_pool().is_empty());
+        }
+    }
+
+    #[test]
+    fn room_at() {
+        let skeld = data(Map::Skeld);
+
+        assert_eq!(skeld.room_at(v(0., 0.)).map(|room| room.name), Some("Cafeteria"));
+        assert_eq!(skeld.room_at(v(100., 100.)), None);
+    }
+
+    #[test]
+    fn spawn_circle() {
+        let skeld = data(Map::Skeld);
+        let center = skeld.spawn_center;
+
+        // the first player is straight up, and the rest go around
+        let first = skeld.spawn_position(0, 4);
+        assert!((first - (center + v(0., skeld.spawn_radius))).norm() < 1e-5);
+
+        let second = skeld.spawn_position(1, 4);
+        assert!((second - (center + v(-skeld.spawn_radius, 0.))).norm() < 1e-5);
+
+        for index in 0..10 {
+            let position = skeld.spawn_position(index, 10);
+            assert!(((position - center).norm() - skeld.spawn_radius).abs() < 1e-5);
+        }
+
+        // a lone player still gets a spot
+        assert!((skeld.spawn_position(0, 0) - first).norm() < 1e-5);
+    }
+}
@@ -0,0 +1,205 @@
+//! Polus.
+
+use crate::game::task::TaskLength::{Common, Long, Short};
+use crate::net::objects::ship::Map;
+use crate::net::objects::systems::{
+    ADMIN, BOILER_ROOM, COMMS, DECONTAMINATION, DECONTAMINATION_2, DROPSHIP, ELECTRICAL,
+    LABORATORY, LIFE_SUPP, OFFICE, OUTSIDE, SECURITY, SPECIMENS, STORAGE, WEAPONS,
+};
+
+use super::{rect, v, Camera, Door, MapData, Room, Task, Vent};
+
+pub static DATA: MapData = MapData {
+    map: Map::Polus,
+    name: "Polus",
+    spawn_center: v(16.64, -2.46),
+    meeting_center: v(17.73, -16.29),
+    spawn_radius: 1.55,
+    spawn_points: &[],
+    rooms: ROOMS,
+    vents: VENTS,
+    doors: DOORS,
+    tasks: TASKS,
+    cameras: CAMERAS,
+};
+
+const ROOMS: &[Room] = &[
+    Room { system: DROPSHIP, name: "Dropship", bounds: rect(13.5, -4.0, 20.0, 0.0) },
+    Room { system: SECURITY, name: "Security", bounds: rect(0.5, -11.0, 4.0, -8.0) },
+    Room { system: ELECTRICAL, name: "Electrical", bounds: rect(5.0, -13.5, 11.0, -8.5) },
+    Room { system: LIFE_SUPP, name: "O2", bounds: rect(0.5, -22.0, 5.5, -15.0) },
+    Room { system: BOILER_ROOM, name: "Boiler Room", bounds: rect(1.0, -26.0, 6.0, -22.0) },
+    Room { system: COMMS, name: "Communications", bounds: rect(10.5, -20.5, 14.0, -15.0) },
+    Room { system: WEAPONS, name: "Weapons", bounds: rect(10.5, -25.0, 14.0, -21.0) },
+    Room { system: OFFICE, name: "Office", bounds: rect(15.0, -21.0, 22.0, -16.0) },
+    Room { system: ADMIN, name: "Admin", bounds: rect(20.0, -27.0, 25.5, -21.5) },
+    Room { system: STORAGE, name: "Storage", bounds: rect(19.0, -14.0, 23.5, -9.0) },
+    Room { system: LABORATORY, name: "Laboratory", bounds: rect(27.0, -11.0, 41.0, -5.0) },
+    Room {
+        system: DECONTAMINATION_2,
+        name: "Laboratory Decontamination",
+        bounds: rect(38.0, -4.5, 41.0, -2.0),
+    },
+    Room {
+        system: DECONTAMINATION,
+        name: "Specimen Decontamination",
+        bounds: rect(33.0, -17.5, 36.0, -15.5),
+    },
+    Room { system: SPECIMENS, name: "Specimen Room", bounds: rect(34.0, -24.0, 39.0, -18.0) },
+];
+
+const VENTS: &[Vent] = &[
+    Vent { id: 1, name: "Security", position: v(1.93, -9.56), connections: &[2, 3] },
+    Vent { id: 2, name: "Electrical", position: v(6.27, -9.67), connections: &[1, 3] },
+    Vent { id: 3, name: "O2", position: v(3.51, -16.22), connections: &[1, 2] },
+    Vent { id: 4, name: "Communications", position: v(12.30, -18.90), connections: &[5] },
+    Vent { id: 5, name: "Office", position: v(16.38, -19.60), connections: &[4, 6] },
+    Vent { id: 6, name: "Admin", position: v(20.09, -25.52), connections: &[5, 8] },
+    Vent { id: 7, name: "Laboratory", position: v(32.96, -9.53), connections: &[8] },
+    Vent { id: 8, name: "Lava Pool", position: v(30.91, -11.86), connections: &[6, 7] },
+    Vent { id: 9, name: "Storage", position: v(22.00, -12.19), connections: &[10] },
+    Vent { id: 10, name: "Right Stabilizer", position: v(24.02, -8.39), connections: &[9, 11] },
+    Vent { id: 11, name: "Left Stabilizer", position: v(9.64, -7.72), connections: &[10] },
+];
+
+const DOORS: &[Door] = &[
+    Door { id: 0, room: ELECTRICAL, position: v(8.1, -8.5) },
+    Door { id: 1, room: ELECTRICAL, position: v(11.0, -11.6) },
+    Door { id: 2, room: LIFE_SUPP, position: v(5.5, -16.7) },
+    Door { id: 3, room: LIFE_SUPP, position: v(3.0, -15.0) },
+    Door { id: 4, room: WEAPONS, position: v(12.2, -21.0) },
+    Door { id: 5, room: COMMS, position: v(14.0, -17.0) },
+    Door { id: 6, room: OFFICE, position: v(18.4, -16.0) },
+    Door { id: 7, room: OFFICE, position: v(15.0, -19.1) },
+    Door { id: 8, room: LABORATORY, position: v(27.0, -7.6) },
+    Door { id: 9, room: LABORATORY, position: v(30.8, -11.0) },
+    Door { id: 10, room: STORAGE, position: v(21.2, -9.0) },
+    Door { id: 11, room: STORAGE, position: v(19.0, -11.9) },
+];
+
+const TASKS: &[Task] = &[
+    Task { id: 0, name: "Swipe Card", length: Common, room: OFFICE, consoles: &[v(19.6, -17.4)] },
+    Task { id: 1, name: "Insert Keys", length: Common, room: DROPSHIP, consoles: &[v(16.7, -1.6)] },
+    Task {
+        id: 2,
+        name: "Scan Boarding Pass",
+        length: Common,
+        room: OFFICE,
+        consoles: &[v(20.1, -17.4)],
+    },
+    Task {
+        id: 3,
+        name: "Fix Wiring",
+        length: Common,
+        room: ELECTRICAL,
+        consoles: &[v(7.1, -8.9), v(4.0, -17.1), v(13.3, -15.7)],
+    },
+    Task {
+        id: 4,
+        name: "Fix Weather Node",
+        length: Long,
+        room: OUTSIDE,
+        consoles: &[v(5.4, -12.4), v(14.4, -8.0)],
+    },
+    Task {
+        id: 5,
+        name: "Inspect Sample",
+        length: Long,
+        room: LABORATORY,
+        consoles: &[v(36.4, -7.1)],
+    },
+    Task {
+        id: 6,
+        name: "Open Waterways",
+        length: Long,
+        room: BOILER_ROOM,
+        consoles: &[v(2.6, -23.8), v(4.4, -25.7), v(20.6, -24.6)],
+    },
+    Task {
+        id: 7,
+        name: "Replace Water Jug",
+        length: Long,
+        room: BOILER_ROOM,
+        consoles: &[v(3.9, -23.4), v(18.8, -16.6)],
+    },
+    Task { id: 8, name: "Reboot Wifi", length: Long, room: COMMS, consoles: &[v(11.3, -16.1)] },
+    Task {
+        id: 9,
+        name: "Start Reactor",
+        length: Long,
+        room: SPECIMENS,
+        consoles: &[v(37.3, -21.5)],
+    },
+    Task {
+        id: 10,
+        name: "Align Telescope",
+        length: Short,
+        room: LABORATORY,
+        consoles: &[v(33.2, -5.5)],
+    },
+    Task {
+        id: 11,
+        name: "Chart Course",
+        length: Short,
+        room: DROPSHIP,
+        consoles: &[v(18.6, -2.2)],
+    },
+    Task {
+        id: 12,
+        name: "Clean Toilet",
+        length: Short,
+        room: LABORATORY,
+        consoles: &[v(33.9, -10.6)],
+    },
+    Task {
+        id: 13,
+        name: "Fill Canisters",
+        length: Short,
+        room: LIFE_SUPP,
+        consoles: &[v(3.1, -18.0)],
+    },
+    Task {
+        id: 14,
+        name: "Monitor Tree",
+        length: Short,
+        room: LIFE_SUPP,
+        consoles: &[v(1.6, -17.4)],
+    },
+    Task {
+        id: 15,
+        name: "Record Temperature",
+        length: Short,
+        room: OUTSIDE,
+        consoles: &[v(31.5, -13.1)],
+    },
+    Task {
+        id: 16,
+        name: "Repair Drill",
+        length: Short,
+        room: LABORATORY,
+        consoles: &[v(27.7, -7.3)],
+    },
+    Task {
+        id: 17,
+        name: "Store Artifacts",
+        length: Short,
+        room: SPECIMENS,
+        consoles: &[v(35.6, -22.3)],
+    },
+    Task {
+        id: 18,
+        name: "Unlock Manifolds",
+        length: Short,
+        room: SPECIMENS,
+        consoles: &[v(37.7, -19.8)],
+    },
+];
+
+const CAMERAS: &[Camera] = &[
+    Camera { name: "East", position: v(29.0, -6.4) },
+    Camera { name: "Central", position: v(15.3, -16.2) },
+    Camera { name: "Northeast", position: v(24.7, -8.6) },
+    Camera { name: "South", position: v(17.8, -25.9) },
+    Camera { name: "Southwest", position: v(4.8, -22.8) },
+    Camera { name: "Northwest", position: v(11.6, -8.2) },
+];
@@ -0,0 +1,175 @@
+//! The Skeld.
+
+use crate::game::task::TaskLength::{Common, Long, Short};
+use crate::net::objects::ship::Map;
+use crate::net::objects::systems::{
+    ADMIN, CAFETERIA, COMMS, ELECTRICAL, LIFE_SUPP, LOWER_ENGINE, MED_BAY, NAV, REACTOR,
+    SECURITY, SHIELDS, STORAGE, UPPER_ENGINE, WEAPONS,
+};
+
+use super::{rect, v, Camera, Door, MapData, Room, Task, Vent};
+
+pub static DATA: MapData = MapData {
+    map: Map::Skeld,
+    name: "The Skeld",
+    spawn_center: v(-0.72, 0.62),
+    meeting_center: v(-0.72, 0.62),
+    spawn_radius: 1.55,
+    spawn_points: &[],
+    rooms: ROOMS,
+    vents: VENTS,
+    doors: DOORS,
+    tasks: TASKS,
+    cameras: CAMERAS,
+};
+
+const ROOMS: &[Room] = &[
+    Room { system: CAFETERIA, name: "Cafeteria", bounds: rect(-5.5, -2.5, 5.0, 6.5) },
+    Room { system: WEAPONS, name: "Weapons", bounds: rect(6.5, -1.5, 12.0, 4.5) },
+    Room { system: LIFE_SUPP, name: "O2", bounds: rect(5.0, -5.5, 8.0, -2.5) },
+    Room { system: NAV, name: "Navigation", bounds: rect(14.0, -7.5, 19.0, -1.5) },
+    Room { system: SHIELDS, name: "Shields", bounds: rect(7.0, -15.0, 11.5, -10.0) },
+    Room { system: COMMS, name: "Communications", bounds: rect(2.5, -17.0, 6.0, -14.0) },
+    Room { system: STORAGE, name: "Storage", bounds: rect(-5.5, -15.5, 1.5, -7.5) },
+    Room { system: ADMIN, name: "Admin", bounds: rect(2.5, -10.0, 7.0, -6.0) },
+    Room { system: ELECTRICAL, name: "Electrical", bounds: rect(-10.5, -12.0, -5.5, -7.0) },
+    Room { system: LOWER_ENGINE, name: "Lower Engine", bounds: rect(-19.5, -14.0, -14.0, -8.5) },
+    Room { system: SECURITY, name: "Security", bounds: rect(-15.0, -7.5, -11.5, -3.5) },
+    Room { system: REACTOR, name: "Reactor", bounds: rect(-23.5, -9.0, -19.5, -1.0) },
+    Room { system: UPPER_ENGINE, name: "Upper Engine", bounds: rect(-19.5, -3.0, -14.0, 3.0) },
+    Room { system: MED_BAY, name: "MedBay", bounds: rect(-11.0, -6.0, -6.5, -1.0) },
+];
+
+const VENTS: &[Vent] = &[
+    Vent { id: 0, name: "Admin", position: v(2.54, -9.96), connections: &[1, 2] },
+    Vent { id: 1, name: "Right Hallway", position: v(9.38, -6.44), connections: &[0, 2] },
+    Vent { id: 2, name: "Cafeteria", position: v(4.26, -0.28), connections: &[0, 1] },
+    Vent { id: 3, name: "Electrical", position: v(-9.78, -8.03), connections: &[5, 6] },
+    Vent { id: 4, name: "Upper Engine", position: v(-15.29, 2.52), connections: &[11] },
+    Vent { id: 5, name: "Security", position: v(-12.53, -6.59), connections: &[3, 6] },
+    Vent { id: 6, name: "MedBay", position: v(-10.61, -4.18), connections: &[3, 5] },
+    Vent { id: 7, name: "Weapons", position: v(8.62, 3.35), connections: &[12] },
+    Vent { id: 8, name: "Lower Reactor", position: v(-20.80, -6.91), connections: &[9] },
+    Vent { id: 9, name: "Lower Engine", position: v(-15.25, -13.72), connections: &[8] },
+    Vent { id: 10, name: "Shields", position: v(9.52, -14.34), connections: &[13] },
+    Vent { id: 11, name: "Upper Reactor", position: v(-21.88, -2.71), connections: &[4] },
+    Vent { id: 12, name: "Upper Navigation", position: v(16.01, -3.17), connections: &[7] },
+    Vent { id: 13, name: "Lower Navigation", position: v(16.01, -6.38), connections: &[10] },
+];
+
+const DOORS: &[Door] = &[
+    Door { id: 0, room: CAFETERIA, position: v(-5.5, 1.3) },
+    Door { id: 1, room: CAFETERIA, position: v(5.0, 1.3) },
+    Door { id: 2, room: CAFETERIA, position: v(-0.9, -2.5) },
+    Door { id: 3, room: STORAGE, position: v(-1.8, -7.5) },
+    Door { id: 4, room: STORAGE, position: v(1.5, -11.2) },
+    Door { id: 5, room: STORAGE, position: v(-5.5, -11.9) },
+    Door { id: 6, room: ELECTRICAL, position: v(-7.7, -7.0) },
+    Door { id: 7, room: MED_BAY, position: v(-9.0, -6.0) },
+    Door { id: 8, room: SECURITY, position: v(-11.5, -5.0) },
+    Door { id: 9, room: UPPER_ENGINE, position: v(-14.0, 0.6) },
+    Door { id: 10, room: UPPER_ENGINE, position: v(-16.8, -3.0) },
+    Door { id: 11, room: LOWER_ENGINE, position: v(-14.0, -11.8) },
+    Door { id: 12, room: LOWER_ENGINE, position: v(-16.8, -8.5) },
+];
+
+const TASKS: &[Task] = &[
+    Task { id: 0, name: "Swipe Card", length: Common, room: ADMIN, consoles: &[v(5.8, -8.6)] },
+    Task {
+        id: 1,
+        name: "Fix Wiring",
+        length: Common,
+        room: ELECTRICAL,
+        consoles: &[v(-7.6, -7.6), v(-1.6, -7.8), v(2.9, -7.4)],
+    },
+    Task { id: 2, name: "Submit Scan", length: Long, room: MED_BAY, consoles: &[v(-7.6, -5.0)] },
+    Task { id: 3, name: "Clear Asteroids", length: Long, room: WEAPONS, consoles: &[v(9.3, 2.6)] },
+    Task {
+        id: 4,
+        name: "Align Engine Output",
+        length: Long,
+        room: UPPER_ENGINE,
+        consoles: &[v(-19.0, -1.6), v(-19.0, -13.7)],
+    },
+    Task {
+        id: 5,
+        name: "Fuel Engines",
+        length: Long,
+        room: STORAGE,
+        consoles: &[v(-3.2, -13.8), v(-18.9, 0.7), v(-3.2, -13.8), v(-18.9, -10.1)],
+    },
+    Task { id: 6, name: "Start Reactor", length: Long, room: REACTOR, consoles: &[v(-22.2, -6.0)] },
+    Task { id: 7, name: "Inspect Sample", length: Long, room: MED_BAY, consoles: &[v(-7.4, -3.1)] },
+    Task {
+        id: 8,
+        name: "Empty Garbage",
+        length: Long,
+        room: CAFETERIA,
+        consoles: &[v(4.8, 5.1), v(-0.4, -15.5)],
+    },
+    Task { id: 9, name: "Prime Shields", length: Short, room: SHIELDS, consoles: &[v(7.7, -14.0)] },
+    Task { id: 10, name: "Chart Course", length: Short, room: NAV, consoles: &[v(17.9, -3.0)] },
+    Task {
+        id: 11,
+        name: "Upload Data",
+        length: Short,
+        room: CAFETERIA,
+        consoles: &[v(5.2, 4.7), v(4.1, -7.6)],
+    },
+    Task {
+        id: 12,
+        name: "Empty Chute",
+        length: Short,
+        room: LIFE_SUPP,
+        consoles: &[v(4.8, -3.4), v(-0.4, -15.5)],
+    },
+    Task {
+        id: 13,
+        name: "Calibrate Distributor",
+        length: Short,
+        room: ELECTRICAL,
+        consoles: &[v(-5.9, -8.5)],
+    },
+    Task {
+        id: 14,
+        name: "Divert Power",
+        length: Short,
+        room: ELECTRICAL,
+        consoles: &[v(-9.4, -7.2), v(-18.9, -2.8)],
+    },
+    Task {
+        id: 15,
+        name: "Unlock Manifolds",
+        length: Short,
+        room: REACTOR,
+        consoles: &[v(-21.6, -2.3)],
+    },
+    Task {
+        id: 16,
+        name: "Clean O2 Filter",
+        length: Short,
+        room: LIFE_SUPP,
+        consoles: &[v(6.3, -3.3)],
+    },
+    Task {
+        id: 17,
+        name: "Stabilize Steering",
+        length: Short,
+        room: NAV,
+        consoles: &[v(18.4, -5.0)],
+    },
+    Task {
+        id: 18,
+        name: "Download Data",
+        length: Short,
+        room: WEAPONS,
+        consoles: &[v(9.1, 2.9), v(4.1, -7.6)],
+    },
+];
+
+const CAMERAS: &[Camera] = &[
+    Camera { name: "Navigation", position: v(13.3, -4.3) },
+    Camera { name: "Admin", position: v(0.6, -6.6) },
+    Camera { name: "Security", position: v(-17.8, -4.9) },
+    Camera { name: "MedBay", position: v(-7.0, -1.5) },
+];
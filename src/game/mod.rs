@@ -1,4 +1,13 @@
+pub mod ability;
+pub mod door;
+pub mod map;
+pub mod player;
+pub mod role;
+pub mod sabotage;
+pub mod state;
 pub mod task;
+pub mod vent;
 
-/// A placeholder struct for game state.
-pub struct State;
+pub use player::{Player, Players};
+pub use role::{Role, RoleHook};
+pub use state::State;
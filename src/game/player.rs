@@ -0,0 +1,294 @@
+//! Players.
+//!
+//! A [`Player`] is everything the game knows about one person in it, whether
+//! they are still connected or not. Their [`PlayerInfo`] is what gets sent to
+//! everyone else, and can be made from the player at any time.
+//!
+//! Players are kept in a [`Players`] registry, which hands out player ids.
+//! Player ids are separate from client ids: a client id belongs to a
+//! connection, and a player id to a seat in the game, from `0` up.
+
+use std::collections::BTreeMap;
+
+use crate::game::ability::Ability;
+use crate::game::Role;
+use crate::math::Vector2;
+use crate::net::objects::game_data::{PlayerInfo, TaskInfo};
+use crate::net::protocol::rpc::NO_PLAYER;
+
+/// The cosmetics a player is wearing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cosmetics {
+    pub hat: u32,
+    pub pet: u32,
+    pub skin: u32,
+}
+
+/// A player in a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    id: u8,
+    /// The client id of the connection controlling the player, or `None` once
+    /// they have disconnected.
+    pub client_id: Option<i32>,
+    pub name: String,
+    pub color: u8,
+    pub cosmetics: Cosmetics,
+    pub position: Vector2,
+    pub dead: bool,
+    pub role: Role,
+    /// The state of what the player's role lets them do.
+    pub ability: Ability,
+    pub tasks: Vec<TaskInfo>,
+}
+
+impl Player {
+    /// Create a new `Player`, alive and at the origin.
+    pub fn new(id: u8, client_id: i32, name: String) -> Player {
+        Player {
+            id,
+            client_id: Some(client_id),
+            name,
+            color: 0,
+            cosmetics: Cosmetics::default(),
+            position: Vector2::zeros(),
+            dead: false,
+            role: Role::default(),
+            ability: Ability::None,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// The player id of the player.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Whether the player is alive.
+    pub fn is_alive(&self) -> bool {
+        !self.dead
+    }
+
+    /// Whether the player is still connected.
+    pub fn is_connected(&self) -> bool {
+        self.client_id.is_some()
+    }
+
+    /// Whether the player is an impostor.
+    pub fn is_impostor(&self) -> bool {
+        self.role.is_impostor()
+    }
+
+    /// How many of the player's tasks are complete.
+    pub fn tasks_complete(&self) -> usize {
+        self.tasks.iter().filter(|task| task.complete).count()
+    }
+
+    /// The info sent to everyone about the player.
+    pub fn info(&self) -> PlayerInfo {
+        PlayerInfo {
+            player_id: self.id,
+            name: self.name.clone(),
+            color: self.color,
+            hat: self.cosmetics.hat,
+            pet: self.cosmetics.pet,
+            skin: self.cosmetics.skin,
+            disconnected: !self.is_connected(),
+            impostor: self.is_impostor(),
+            dead: self.dead,
+            tasks: self.tasks.clone(),
+        }
+    }
+}
+
+/// Every player in a game, by player id.
+#[derive(Clone, Debug, Default)]
+pub struct Players {
+    players: BTreeMap<u8, Player>,
+}
+
+impl Players {
+    /// Create a new, empty `Players`.
+    pub fn new() -> Players {
+        Players::default()
+    }
+
+    /// Adds a player for a client, giving them the lowest free player id.
+    ///
+    /// Returns `None` if every player id is taken.
+    pub fn add(&mut self, client_id: i32, name: String) -> Option<&mut Player> {
+        let id = (0..NO_PLAYER).find(|id| !self.players.contains_key(id))?;
+
+        Some(self.players.entry(id).or_insert(Player::new(id, client_id, name)))
+    }
+
+    /// Removes a player.
+    pub fn remove(&mut self, id: u8) -> Option<Player> {
+        self.players.remove(&id)
+    }
+
+    /// Gets a player by player id.
+    pub fn get(&self, id: u8) -> Option<&Player> {
+        self.players.get(&id)
+    }
+
+    /// Gets a player by player id, mutably.
+    pub fn get_mut(&mut self, id: u8) -> Option<&mut Player> {
+        self.players.get_mut(&id)
+    }
+
+    /// Finds the player controlled by a client.
+    pub fn by_client(&self, client_id: i32) -> Option<&Player> {
+        self.iter().find(|player| player.client_id == Some(client_id))
+    }
+
+    /// Finds the player controlled by a client, mutably.
+    pub fn by_client_mut(&mut self, client_id: i32) -> Option<&mut Player> {
+        self.iter_mut().find(|player| player.client_id == Some(client_id))
+    }
+
+    /// Marks the player of a client as disconnected, returning their player
+    /// id.
+    ///
+    /// The player stays in the game, so their tasks and role still count.
+    pub fn disconnect(&mut self, client_id: i32) -> Option<u8> {
+        let player = self.by_client_mut(client_id)?;
+        player.client_id = None;
+
+        Some(player.id)
+    }
+
+    /// Whether there is a player with a player id.
+    pub fn contains(&self, id: u8) -> bool {
+        self.players.contains_key(&id)
+    }
+
+    /// How many players there are.
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Whether there are no players.
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Iterates over the players, by player id.
+    pub fn iter(&self) -> impl Iterator<Item = &Player> {
+        self.players.values()
+    }
+
+    /// Iterates over the players, by player id, mutably.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Player> {
+        self.players.values_mut()
+    }
+
+    /// Iterates over the players that are still connected.
+    pub fn connected(&self) -> impl Iterator<Item = &Player> {
+        self.iter().filter(|player| player.is_connected())
+    }
+
+    /// Iterates over the players that are still alive.
+    pub fn alive(&self) -> impl Iterator<Item = &Player> {
+        self.iter().filter(|player| player.is_alive())
+    }
+
+    /// Removes every player.
+    pub fn clear(&mut self) {
+        self.players.clear();
+    }
+}
+
+impl<'a> IntoIterator for &'a Players {
+    type Item = &'a Player;
+    type IntoIter = std::collections::btree_map::Values<'a, u8, Player>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.players.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowest_free_id() {
+        let mut players = Players::new();
+
+        assert_eq!(players.add(10, "Red".into()).map(|p| p.id()), Some(0));
+        assert_eq!(players.add(11, "Blue".into()).map(|p| p.id()), Some(1));
+        assert_eq!(players.add(12, "Green".into()).map(|p| p.id()), Some(2));
+
+        players.remove(1);
+        assert_eq!(players.add(13, "Pink".into()).map(|p| p.id()), Some(1));
+        assert_eq!(players.by_client(13).map(|p| p.name.as_str()), Some("Pink"));
+        assert_eq!(players.len(), 3);
+    }
+
+    #[test]
+    fn full() {
+        let mut players = Players::new();
+
+        for client_id in 0..NO_PLAYER as i32 {
+            assert!(players.add(client_id, String::new()).is_some());
+        }
+
+        // the last id means nobody, so it is never handed out
+        assert!(players.add(1000, String::new()).is_none());
+        assert_eq!(players.len(), NO_PLAYER as usize);
+    }
+
+    #[test]
+    fn disconnect_keeps_the_player() {
+        let mut players = Players::new();
+        players.add(10, "Red".into());
+        players.add(11, "Blue".into());
+
+        assert_eq!(players.disconnect(11), Some(1));
+        assert_eq!(players.disconnect(11), None);
+
+        assert!(players.contains(1));
+        assert!(players.by_client(11).is_none());
+        assert!(!players.get(1).unwrap().is_connected());
+        assert_eq!(players.connected().map(Player::id).collect::<Vec<_>>(), [0]);
+        assert!(players.get(1).unwrap().info().disconnected);
+    }
+
+    #[test]
+    fn alive() {
+        let mut players = Players::new();
+        players.add(10, "Red".into());
+        players.add(11, "Blue".into());
+
+        players.get_mut(0).unwrap().dead = true;
+        assert_eq!(players.alive().map(Player::id).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn info() {
+        let mut player = Player::new(3, 10, "Red".into());
+        player.color = 4;
+        player.cosmetics = Cosmetics { hat: 1, pet: 2, skin: 3 };
+        player.role = Role::Impostor;
+        player.dead = true;
+        player.tasks = vec![
+            TaskInfo { id: 0, complete: true },
+            TaskInfo { id: 1, complete: false },
+        ];
+
+        assert_eq!(player.tasks_complete(), 1);
+        assert_eq!(player.info(), PlayerInfo {
+            player_id: 3,
+            name: "Red".into(),
+            color: 4,
+            hat: 1,
+            pet: 2,
+            skin: 3,
+            disconnected: false,
+            impostor: true,
+            dead: true,
+            tasks: player.tasks.clone(),
+        });
+    }
+}
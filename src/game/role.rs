@@ -0,0 +1,491 @@
+//! Roles.
+//!
+//! Every player is either a crewmate or an impostor. When a game starts, the
+//! host picks the impostors at random from the players still connected, and
+//! tells everyone with a SetInfected RPC.
+//!
+//! Since 2021.11, some of them also get a role with an [`Ability`]: crewmates
+//! can be scientists or engineers, and impostors shapeshifters. How many is
+//! up to the [`RoleRate`]s in the options. Guardian angels are different, and
+//! are only handed out to crewmates as they die, with [`assign_on_death`].
+//!
+//! [`assign`] does all of that from a seeded [`Rng`], so a game can be
+//! replayed from its seed. Modded servers can hand out roles of their own
+//! afterwards with [`assign_with`] and a [`RoleHook`]; roles this crate
+//! doesn't know are kept as [`Role::Unknown`] and count as crewmates.
+
+use crate::game::ability::Ability;
+use crate::game::{Player, Players};
+use crate::math::rng::Rng;
+use crate::net::protocol::options::{GameOptions, RoleRate};
+use crate::net::protocol::Rpc;
+
+/// What a player is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Role {
+    #[default]
+    Crewmate,
+    Impostor,
+    /// A crewmate that can look at vitals from anywhere.
+    Scientist,
+    /// A crewmate that can use vents.
+    Engineer,
+    /// A dead crewmate that can protect the living.
+    GuardianAngel,
+    /// An impostor that can look like someone else.
+    Shapeshifter,
+    Unknown(u16),
+}
+
+impl Role {
+    /// The id of the role.
+    pub fn id(self) -> u16 {
+        match self {
+            Role::Crewmate => 0,
+            Role::Impostor => 1,
+            Role::Scientist => 2,
+            Role::Engineer => 3,
+            Role::GuardianAngel => 4,
+            Role::Shapeshifter => 5,
+            Role::Unknown(id) => id,
+        }
+    }
+
+    /// The role of an id.
+    pub fn from_id(id: u16) -> Role {
+        match id {
+            0 => Role::Crewmate,
+            1 => Role::Impostor,
+            2 => Role::Scientist,
+            3 => Role::Engineer,
+            4 => Role::GuardianAngel,
+            5 => Role::Shapeshifter,
+            id => Role::Unknown(id),
+        }
+    }
+
+    /// Whether the role is on the impostors' side.
+    pub fn is_impostor(self) -> bool {
+        matches!(self, Role::Impostor | Role::Shapeshifter)
+    }
+
+    /// Whether the role can use vents.
+    pub fn can_vent(self) -> bool {
+        matches!(self, Role::Impostor | Role::Shapeshifter | Role::Engineer)
+    }
+}
+
+/// Hands out roles after the impostors are picked.
+pub trait RoleHook {
+    /// Called for every connected crewmate, returning a role to give them
+    /// instead, if any.
+    fn role(&mut self, player: &Player, rng: &mut Rng) -> Option<Role>;
+}
+
+impl<F> RoleHook for F
+where F: FnMut(&Player, &mut Rng) -> Option<Role> {
+    fn role(&mut self, player: &Player, rng: &mut Rng) -> Option<Role> {
+        self(player, rng)
+    }
+}
+
+/// The roles picked for a game.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Assignment {
+    /// The player ids of the impostors.
+    pub impostors: Vec<u8>,
+    /// The players given any role besides crewmate and impostor, by player
+    /// id.
+    pub roles: Vec<(u8, Role)>,
+}
+
+impl Assignment {
+    /// The SetInfected RPC telling everyone who the impostors are.
+    pub fn rpc(&self) -> Rpc {
+        Rpc::SetInfected {
+            players: self.impostors.clone(),
+        }
+    }
+}
+
+/// The most impostors a game of `players` players can have.
+///
+/// This is the limit the official client enforces, and never leaves the
+/// impostors at parity with the crewmates from the start.
+pub fn max_impostors(players: usize) -> usize {
+    match players {
+        0..=3 => players.saturating_sub(1) / 2,
+        4..=6 => 1,
+        7 | 8 => 2,
+        _ => 3,
+    }
+}
+
+/// Picks the roles of a game, and makes everyone else a crewmate.
+///
+/// Only connected players are picked from. The number of impostors is the
+/// one in `options`, as far as [`max_impostors`] allows. Then, each impostor
+/// and crewmate role is given out by its rate.
+pub fn assign(players: &mut Players, options: &GameOptions, rng: &mut Rng) -> Assignment {
+    assign_with(players, options, rng, |_: &Player, _: &mut Rng| None)
+}
+
+/// Picks the roles of a game like [`assign`], then lets `hook` give the
+/// crewmates left other roles.
+pub fn assign_with<H>(
+    players: &mut Players,
+    options: &GameOptions,
+    rng: &mut Rng,
+    mut hook: H,
+) -> Assignment
+where H: RoleHook {
+    let mut candidates = Vec::new();
+
+    for player in players.iter_mut() {
+        player.role = Role::Crewmate;
+        player.ability = Ability::None;
+
+        if player.is_connected() {
+            candidates.push(player.id());
+        }
+    }
+
+    let count = (options.impostors as usize).min(max_impostors(candidates.len()));
+
+    rng.shuffle(&mut candidates);
+
+    let mut impostors = candidates.split_off(candidates.len() - count);
+    let mut roles = Vec::new();
+
+    for &id in &impostors {
+        if let Some(player) = players.get_mut(id) {
+            player.role = Role::Impostor;
+        }
+    }
+
+    roll(&mut impostors, Role::Shapeshifter, options, rng, &mut roles);
+    roll(&mut candidates, Role::Scientist, options, rng, &mut roles);
+    roll(&mut candidates, Role::Engineer, options, rng, &mut roles);
+
+    for &(id, role) in &roles {
+        if let Some(player) = players.get_mut(id) {
+            player.role = role;
+            player.ability = Ability::new(role, &options.roles);
+        }
+    }
+
+    impostors.extend(roles.iter().filter(|(_, role)| role.is_impostor()).map(|(id, _)| id));
+    impostors.sort_unstable();
+    roles.sort_unstable_by_key(|(id, _)| *id);
+
+    // go in player id order, so the hook sees the same thing for a seed
+    candidates.sort_unstable();
+
+    for id in candidates {
+        if let Some(player) = players.get_mut(id) {
+            if let Some(role) = hook.role(player, rng) {
+                player.role = role;
+                player.ability = Ability::new(role, &options.roles);
+            }
+        }
+    }
+
+    Assignment { impostors, roles }
+}
+
+/// Gives out a role to the players left in `pool` by its rate, taking the
+/// ones that get it out of the pool.
+fn roll(
+    pool: &mut Vec<u8>,
+    role: Role,
+    options: &GameOptions,
+    rng: &mut Rng,
+    roles: &mut Vec<(u8, Role)>,
+) {
+    let rate = match options.roles.rate(role.id()) {
+        Some(rate) => rate,
+        None => return,
+    };
+
+    for _ in 0..rate.max_count {
+        if pool.is_empty() {
+            break;
+        }
+
+        if rolls(rate, rng) {
+            let id = pool.swap_remove(rng.index(pool.len()));
+            roles.push((id, role));
+        }
+    }
+}
+
+/// Whether a rate comes up.
+fn rolls(rate: RoleRate, rng: &mut Rng) -> bool {
+    rate.chance >= 100 || rng.below(100) < rate.chance as u32
+}
+
+/// Maybe makes a crewmate that just died a guardian angel, by its rate.
+///
+/// Returns the new role of the player, if it changed. Living players,
+/// impostors and players who are already guardian angels are left alone, and
+/// there are never more guardian angels than the rate allows.
+pub fn assign_on_death(
+    players: &mut Players,
+    id: u8,
+    options: &GameOptions,
+    rng: &mut Rng,
+) -> Option<Role> {
+    let rate = options.roles.rate(Role::GuardianAngel.id())?;
+    let angels = players.iter().filter(|player| player.role == Role::GuardianAngel).count();
+
+    let player = players.get(id)?;
+
+    if player.is_alive() || player.is_impostor() || player.role == Role::GuardianAngel {
+        return None;
+    }
+
+    if angels >= rate.max_count as usize || !rolls(rate, rng) {
+        return None;
+    }
+
+    let player = players.get_mut(id)?;
+    player.role = Role::GuardianAngel;
+    player.ability = Ability::new(Role::GuardianAngel, &options.roles);
+
+    Some(Role::GuardianAngel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players(count: i32) -> Players {
+        let mut players = Players::new();
+
+        for client_id in 0..count {
+            players.add(client_id, format!("player {}", client_id));
+        }
+
+        players
+    }
+
+    fn options(impostors: u8) -> GameOptions {
+        GameOptions {
+            impostors,
+            ..GameOptions::default()
+        }
+    }
+
+    fn impostor_ids(players: &Players) -> Vec<u8> {
+        players.iter().filter(|p| p.is_impostor()).map(Player::id).collect()
+    }
+
+    #[test]
+    fn max_impostors_per_player_count() {
+        let expected = [0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 3, 3, 3, 3, 3];
+
+        for (count, &max) in expected.iter().enumerate() {
+            assert_eq!(max_impostors(count), max, "{} players", count);
+        }
+
+        assert_eq!(max_impostors(100), 3);
+    }
+
+    #[test]
+    fn impostor_counts() {
+        let cases = [
+            // players, impostors asked for, impostors picked
+            (10, 3, 3),
+            (10, 2, 2),
+            (10, 5, 3),
+            (7, 3, 2),
+            (5, 3, 1),
+            (4, 1, 1),
+            (2, 1, 0),
+            (10, 0, 0),
+        ];
+
+        for &(count, asked, picked) in &cases {
+            let mut players = players(count);
+            let assignment = assign(&mut players, &options(asked), &mut Rng::new(7));
+
+            assert_eq!(assignment.impostors.len(), picked, "{} of {}", asked, count);
+            assert_eq!(impostor_ids(&players), assignment.impostors);
+            assert!(assignment.roles.is_empty());
+        }
+    }
+
+    #[test]
+    fn only_connected_players() {
+        let mut players = players(10);
+
+        for client_id in 0..8 {
+            players.disconnect(client_id);
+        }
+
+        // two connected players can't have an impostor
+        let assignment = assign(&mut players, &options(3), &mut Rng::new(7));
+        assert!(assignment.impostors.is_empty());
+
+        players.disconnect(8);
+        players.add(10, "late".into());
+        players.add(11, "later".into());
+        players.add(12, "latest".into());
+        players.add(13, "last".into());
+
+        for seed in 0..20 {
+            let assignment = assign(&mut players, &options(3), &mut Rng::new(seed));
+
+            assert_eq!(assignment.impostors.len(), 1);
+            assert!(players.get(assignment.impostors[0]).unwrap().is_connected());
+        }
+    }
+
+    #[test]
+    fn seeded() {
+        let pick = |seed| assign(&mut players(10), &options(2), &mut Rng::new(seed));
+
+        assert_eq!(pick(42), pick(42));
+        assert!((0..20).any(|seed| pick(seed) != pick(42)));
+    }
+
+    #[test]
+    fn roles_reset() {
+        let mut players = players(10);
+        players.get_mut(0).unwrap().role = Role::GuardianAngel;
+
+        assign(&mut players, &options(0), &mut Rng::new(1));
+        assert!(players.iter().all(|p| p.role == Role::Crewmate));
+        assert!(players.iter().all(|p| p.ability == Ability::None));
+    }
+
+    #[test]
+    fn role_rates() {
+        let mut options = options(2);
+        options.roles.rates = vec![
+            RoleRate { role: Role::Scientist.id(), max_count: 2, chance: 100 },
+            RoleRate { role: Role::Engineer.id(), max_count: 1, chance: 100 },
+            RoleRate { role: Role::Shapeshifter.id(), max_count: 1, chance: 100 },
+        ];
+
+        let mut players = players(10);
+        let assignment = assign(&mut players, &options, &mut Rng::new(3));
+
+        let count = |role| players.iter().filter(|p| p.role == role).count();
+
+        assert_eq!(count(Role::Impostor), 1);
+        assert_eq!(count(Role::Shapeshifter), 1);
+        assert_eq!(count(Role::Scientist), 2);
+        assert_eq!(count(Role::Engineer), 1);
+        assert_eq!(count(Role::Crewmate), 5);
+
+        // shapeshifters are still impostors
+        assert_eq!(impostor_ids(&players), assignment.impostors);
+        assert_eq!(assignment.roles.len(), 4);
+
+        for &(id, role) in &assignment.roles {
+            assert_eq!(players.get(id).unwrap().role, role);
+            assert_ne!(players.get(id).unwrap().ability, Ability::None);
+        }
+
+        assert_eq!(assignment.rpc(), Rpc::SetInfected { players: assignment.impostors });
+    }
+
+    #[test]
+    fn zero_chance() {
+        let mut options = options(1);
+        options.roles.rates = vec![RoleRate {
+            role: Role::Scientist.id(),
+            max_count: 5,
+            chance: 0,
+        }];
+
+        let assignment = assign(&mut players(10), &options, &mut Rng::new(3));
+        assert!(assignment.roles.is_empty());
+    }
+
+    #[test]
+    fn hook() {
+        let mut players = players(10);
+        let mut seen = Vec::new();
+
+        let hook = |player: &Player, _: &mut Rng| {
+            seen.push(player.id());
+            Some(Role::Unknown(100))
+        };
+
+        let assignment = assign_with(&mut players, &options(2), &mut Rng::new(5), hook);
+
+        // the hook only sees crewmates, in player id order
+        let crewmates: Vec<_> = (0..10).filter(|id| !assignment.impostors.contains(id)).collect();
+        assert_eq!(seen, crewmates);
+        assert!(players.iter().all(|p| p.is_impostor() || p.role == Role::Unknown(100)));
+    }
+
+    #[test]
+    fn role_ids() {
+        for id in 0..=6 {
+            assert_eq!(Role::from_id(id).id(), id);
+        }
+
+        assert!(!Role::Unknown(6).is_impostor());
+        assert!(Role::Shapeshifter.can_vent());
+        assert!(Role::Engineer.can_vent());
+        assert!(!Role::Scientist.can_vent());
+    }
+
+    fn angel_options(max_count: u8) -> GameOptions {
+        let mut options = options(1);
+        options.roles.rates = vec![RoleRate {
+            role: Role::GuardianAngel.id(),
+            max_count,
+            chance: 100,
+        }];
+        options
+    }
+
+    #[test]
+    fn guardian_angels_are_dead() {
+        let mut players = players(4);
+        let options = angel_options(2);
+        let mut rng = Rng::new(1);
+
+        // still alive
+        assert_eq!(assign_on_death(&mut players, 0, &options, &mut rng), None);
+        assert_eq!(players.get(0).unwrap().role, Role::Crewmate);
+
+        players.get_mut(0).unwrap().dead = true;
+        assert_eq!(
+            assign_on_death(&mut players, 0, &options, &mut rng),
+            Some(Role::GuardianAngel),
+        );
+        assert!(matches!(players.get(0).unwrap().ability, Ability::GuardianAngel(_)));
+
+        // and only once
+        assert_eq!(assign_on_death(&mut players, 0, &options, &mut rng), None);
+    }
+
+    #[test]
+    fn guardian_angel_limits() {
+        let mut players = players(5);
+        let angels = angel_options(1);
+        let mut rng = Rng::new(1);
+
+        for player in players.iter_mut() {
+            player.dead = true;
+        }
+
+        players.get_mut(0).unwrap().role = Role::Impostor;
+        assert_eq!(assign_on_death(&mut players, 0, &angels, &mut rng), None);
+        assert_eq!(assign_on_death(&mut players, 9, &angels, &mut rng), None);
+
+        assert_eq!(
+            assign_on_death(&mut players, 1, &angels, &mut rng),
+            Some(Role::GuardianAngel),
+        );
+        assert_eq!(assign_on_death(&mut players, 2, &angels, &mut rng), None);
+
+        // without a rate, nobody becomes one
+        assert_eq!(assign_on_death(&mut players, 3, &options(1), &mut rng), None);
+    }
+}
@@ -0,0 +1,709 @@
+//! Sabotages.
+//!
+//! Impostors sabotage a system with a RepairSystem RPC to [`SABOTAGE`], with
+//! the system type as the amount. Everyone else then fixes it with more
+//! RepairSystem RPCs to the system itself, and what the amount means depends
+//! on the system:
+//!
+//! * The reactor and seismic stabilizers need a player holding each of the
+//!   two consoles at the same time. `0x40 | console` starts holding one, and
+//!   `0x20 | console` lets go.
+//! * The oxygen is fixed by entering the code at both consoles, one after
+//!   the other, with `0x40 | console`.
+//! * The lights are fixed by flipping switches, by index, until they all
+//!   match what is expected.
+//! * The comms of most maps are fixed from a single console, with `0x10`.
+//! * The comms of Mira HQ and the crash course of the Airship need the code
+//!   entered at both consoles. `0x40 | console` and `0x20 | console` start
+//!   and stop using one, and `0x10 | console` enters the code. On the
+//!   Airship, the second code has to be entered soon after the first.
+//!
+//! The reactor, oxygen and crash course are critical: if they aren't fixed in
+//! time, the impostors win.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::game::state::GameOverReason;
+use crate::game::Player;
+use crate::math::rng::Rng;
+use crate::net::objects::ship::{Map, ShipStatus};
+use crate::net::objects::systems::{
+    System, COMMS, ELECTRICAL, LABORATORY, LIFE_SUPP, NOT_SABOTAGED, REACTOR, SABOTAGE,
+};
+use crate::net::protocol::Rpc;
+
+/// How long impostors have to wait between sabotages, in seconds.
+pub const COOLDOWN: f32 = 30.;
+
+/// How long the crash course waits for the second code after the first, in
+/// seconds.
+pub const HELI_CODE_TIME: f32 = 10.;
+
+/// How many light switches there are.
+pub const SWITCHES: u8 = 5;
+
+/// How fast the lights dim and come back, in brightness per second.
+pub const LIGHTS_SPEED: f32 = 255.;
+
+/// Why a system can't be sabotaged or repaired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SabotageError {
+    /// The map has no system of the type that can be sabotaged.
+    NoSuchSystem(u8),
+    /// The player isn't an impostor.
+    NotAllowed,
+    /// Something is already sabotaged.
+    Active,
+    /// The impostors have to wait a bit longer.
+    Cooldown,
+}
+
+impl fmt::Display for SabotageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SabotageError::NoSuchSystem(system) => {
+                write!(f, "no system {} to sabotage", system)
+            }
+            SabotageError::NotAllowed => write!(f, "not allowed to sabotage"),
+            SabotageError::Active => write!(f, "already sabotaged"),
+            SabotageError::Cooldown => write!(f, "on cooldown"),
+        }
+    }
+}
+
+impl std::error::Error for SabotageError {}
+
+/// The systems of a map that can be sabotaged.
+#[derive(Clone, Debug)]
+pub struct Sabotage {
+    map: Map,
+    rng: Rng,
+    /// How bright the lights are, before being rounded into the switches.
+    lights: f32,
+    /// The sabotage systems, by system type, with the cooldown under
+    /// [`SABOTAGE`].
+    systems: BTreeMap<u8, System>,
+}
+
+impl Sabotage {
+    /// Create a new `Sabotage` for a map, with nothing sabotaged and no
+    /// cooldown.
+    ///
+    /// The lights are sabotaged with random switches from `rng`.
+    pub fn new(map: Map, rng: Rng) -> Sabotage {
+        let ship = ShipStatus::new(map);
+        let mut systems = BTreeMap::new();
+
+        for system_type in [SABOTAGE, REACTOR, LABORATORY, LIFE_SUPP, ELECTRICAL, COMMS] {
+            if let Some(system) = ship.system(system_type) {
+                systems.insert(system_type, system.clone());
+            }
+        }
+
+        // the comms of mira hq are down until both consoles are fixed
+        if let Some(System::HqHud(hud)) = systems.get_mut(&COMMS) {
+            hud.completed_consoles = vec![0, 1];
+        }
+
+        Sabotage {
+            map,
+            rng,
+            lights: u8::MAX as f32,
+            systems,
+        }
+    }
+
+    /// Seconds until the impostors can sabotage again.
+    pub fn cooldown(&self) -> f32 {
+        match self.systems.get(&SABOTAGE) {
+            Some(System::Sabotage(system)) => system.timer,
+            _ => 0.,
+        }
+    }
+
+    /// Sets the seconds until the impostors can sabotage again, like at the
+    /// start of a game.
+    pub fn set_cooldown(&mut self, seconds: f32) {
+        if let Some(System::Sabotage(system)) = self.systems.get_mut(&SABOTAGE) {
+            system.timer = seconds;
+        }
+    }
+
+    /// Whether a system is sabotaged.
+    pub fn is_sabotaged(&self, system_type: u8) -> bool {
+        self.systems.get(&system_type).is_some_and(is_sabotaged)
+    }
+
+    /// The system that is sabotaged, if any.
+    pub fn active(&self) -> Option<u8> {
+        self.systems
+            .iter()
+            .find(|(_, system)| is_sabotaged(system))
+            .map(|(system_type, _)| *system_type)
+    }
+
+    /// Seconds until the impostors win, if a critical system is sabotaged.
+    pub fn countdown(&self) -> Option<f32> {
+        self.systems
+            .values()
+            .filter_map(|system| match system {
+                System::Reactor(reactor) => Some(reactor.countdown),
+                System::LifeSupp(life_supp) => Some(life_supp.countdown),
+                System::HeliSabotage(heli) => Some(heli.countdown),
+                _ => None,
+            })
+            .find(|countdown| *countdown < NOT_SABOTAGED)
+    }
+
+    /// Whether the comms are down, hiding tasks, cameras and the admin map.
+    pub fn comms_down(&self) -> bool {
+        self.is_sabotaged(COMMS)
+    }
+
+    /// Checks whether a system could be sabotaged right now.
+    pub fn can_sabotage(&self, system_type: u8) -> Result<(), SabotageError> {
+        if system_type == SABOTAGE || !self.systems.contains_key(&system_type) {
+            return Err(SabotageError::NoSuchSystem(system_type));
+        }
+
+        if self.active().is_some() {
+            return Err(SabotageError::Active);
+        }
+
+        if self.cooldown() > 0. {
+            return Err(SabotageError::Cooldown);
+        }
+
+        Ok(())
+    }
+
+    /// Sabotages a system.
+    pub fn sabotage(&mut self, system_type: u8) -> Result<(), SabotageError> {
+        self.can_sabotage(system_type)?;
+
+        let countdown = critical_countdown(self.map);
+
+        match self.systems.get_mut(&system_type) {
+            Some(System::Reactor(reactor)) => {
+                reactor.countdown = countdown;
+                reactor.user_consoles.clear();
+            }
+            Some(System::LifeSupp(life_supp)) => {
+                life_supp.countdown = countdown;
+                life_supp.completed_consoles.clear();
+            }
+            Some(System::HeliSabotage(heli)) => {
+                heli.countdown = countdown;
+                heli.timer = 0.;
+                heli.active_consoles.clear();
+                heli.completed_consoles.clear();
+            }
+            Some(System::Switch(switch)) => {
+                // at least one switch has to be flipped
+                let mut flipped = 1 << self.rng.below(SWITCHES as u32);
+
+                for index in 0..SWITCHES {
+                    if self.rng.chance(0.5) {
+                        flipped |= 1 << index;
+                    }
+                }
+
+                switch.actual ^= flipped;
+            }
+            Some(System::HudOverride(hud)) => hud.active = true,
+            Some(System::HqHud(hud)) => {
+                hud.active_consoles.clear();
+                hud.completed_consoles.clear();
+            }
+            _ => (),
+        }
+
+        self.set_cooldown(COOLDOWN);
+
+        Ok(())
+    }
+
+    /// Repairs a system for a player, with the amount of a RepairSystem RPC.
+    ///
+    /// Repairs to systems that aren't sabotaged are ignored.
+    pub fn repair(&mut self, player: u8, system_type: u8, amount: u8) -> Result<(), SabotageError> {
+        let system = self
+            .systems
+            .get_mut(&system_type)
+            .filter(|system| !matches!(system, System::Sabotage(_)))
+            .ok_or(SabotageError::NoSuchSystem(system_type))?;
+
+        if !is_sabotaged(system) {
+            return Ok(());
+        }
+
+        let console = amount & 0x0f;
+
+        match system {
+            System::Reactor(reactor) => {
+                if amount & 0x40 != 0 {
+                    if !reactor.user_consoles.contains(&(player, console)) {
+                        reactor.user_consoles.push((player, console));
+                    }
+                } else if amount & 0x20 != 0 {
+                    reactor.user_consoles.retain(|user| *user != (player, console));
+                }
+
+                let mut consoles: Vec<u8> =
+                    reactor.user_consoles.iter().map(|(_, console)| *console).collect();
+                consoles.sort_unstable();
+                consoles.dedup();
+
+                if consoles.len() >= 2 {
+                    reactor.countdown = NOT_SABOTAGED;
+                    reactor.user_consoles.clear();
+                }
+            }
+            System::LifeSupp(life_supp) => {
+                let console = console as u32;
+
+                if amount & 0x40 != 0 && !life_supp.completed_consoles.contains(&console) {
+                    life_supp.completed_consoles.push(console);
+                }
+
+                if life_supp.completed_consoles.len() >= 2 {
+                    life_supp.countdown = NOT_SABOTAGED;
+                    life_supp.completed_consoles.clear();
+                }
+            }
+            System::HeliSabotage(heli) => {
+                if amount & 0x40 != 0 {
+                    if !heli.active_consoles.contains(&(player, console)) {
+                        heli.active_consoles.push((player, console));
+                    }
+                } else if amount & 0x20 != 0 {
+                    heli.active_consoles.retain(|user| *user != (player, console));
+                } else if amount & 0x10 != 0 && !heli.completed_consoles.contains(&console) {
+                    heli.completed_consoles.push(console);
+                    heli.timer = HELI_CODE_TIME;
+                }
+
+                if heli.completed_consoles.len() >= 2 {
+                    heli.countdown = NOT_SABOTAGED;
+                    heli.timer = 0.;
+                    heli.active_consoles.clear();
+                    heli.completed_consoles.clear();
+                }
+            }
+            System::Switch(switch) if amount < SWITCHES => switch.actual ^= 1 << amount,
+            System::HudOverride(hud) if amount & 0x10 != 0 => hud.active = false,
+            System::HqHud(hud) => {
+                if amount & 0x40 != 0 {
+                    if !hud.active_consoles.contains(&(player, console)) {
+                        hud.active_consoles.push((player, console));
+                    }
+                } else if amount & 0x20 != 0 {
+                    hud.active_consoles.retain(|user| *user != (player, console));
+                } else if amount & 0x10 != 0 && !hud.completed_consoles.contains(&console) {
+                    hud.completed_consoles.push(console);
+                }
+
+                if hud.completed_consoles.len() >= 2 {
+                    hud.active_consoles.clear();
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Fixes a critical sabotage, like when a body is reported.
+    pub fn fix_critical(&mut self) {
+        for system in self.systems.values_mut() {
+            match system {
+                System::Reactor(reactor) => {
+                    reactor.countdown = NOT_SABOTAGED;
+                    reactor.user_consoles.clear();
+                }
+                System::LifeSupp(life_supp) => {
+                    life_supp.countdown = NOT_SABOTAGED;
+                    life_supp.completed_consoles.clear();
+                }
+                System::HeliSabotage(heli) => {
+                    heli.countdown = NOT_SABOTAGED;
+                    heli.timer = 0.;
+                    heli.active_consoles.clear();
+                    heli.completed_consoles.clear();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Handles a RepairSystem RPC from a player.
+    ///
+    /// Only impostors can sabotage, but anyone can repair. Repairs to systems
+    /// that can't be sabotaged, like doors, and other RPCs are ignored.
+    pub fn handle_rpc(&mut self, player: &Player, rpc: &Rpc) -> Result<(), SabotageError> {
+        match *rpc {
+            Rpc::RepairSystem { system: SABOTAGE, amount, .. } => {
+                if !player.is_impostor() {
+                    return Err(SabotageError::NotAllowed);
+                }
+
+                self.sabotage(amount)
+            }
+            Rpc::RepairSystem { system, amount, .. } if self.systems.contains_key(&system) => {
+                self.repair(player.id(), system, amount)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Advances the countdowns, cooldown and lights by `delta` seconds.
+    ///
+    /// Returns [`GameOverReason::ImpostorBySabotage`] if a critical sabotage
+    /// wasn't fixed in time.
+    pub fn tick(&mut self, delta: f32) -> Option<GameOverReason> {
+        let active = self.active().is_some();
+        let mut over = false;
+
+        for system in self.systems.values_mut() {
+            match system {
+                System::Sabotage(sabotage) if !active => {
+                    sabotage.timer = (sabotage.timer - delta).max(0.);
+                }
+                System::Reactor(reactor) if reactor.countdown < NOT_SABOTAGED => {
+                    reactor.countdown = (reactor.countdown - delta).max(0.);
+                    over |= reactor.countdown <= 0.;
+                }
+                System::LifeSupp(life_supp) if life_supp.countdown < NOT_SABOTAGED => {
+                    life_supp.countdown = (life_supp.countdown - delta).max(0.);
+                    over |= life_supp.countdown <= 0.;
+                }
+                System::HeliSabotage(heli) if heli.countdown < NOT_SABOTAGED => {
+                    heli.countdown = (heli.countdown - delta).max(0.);
+                    over |= heli.countdown <= 0.;
+
+                    if heli.timer > 0. {
+                        heli.timer = (heli.timer - delta).max(0.);
+
+                        // took too long to enter the second code
+                        if heli.timer <= 0. {
+                            heli.completed_consoles.clear();
+                        }
+                    }
+                }
+                System::Switch(switch) => {
+                    self.lights = if switch.actual != switch.expected {
+                        (self.lights - LIGHTS_SPEED * delta).max(0.)
+                    } else {
+                        (self.lights + LIGHTS_SPEED * delta).min(u8::MAX as f32)
+                    };
+
+                    switch.value = self.lights as u8;
+                }
+                _ => (),
+            }
+        }
+
+        if over {
+            Some(GameOverReason::ImpostorBySabotage)
+        } else {
+            None
+        }
+    }
+
+    /// Writes the sabotage systems into a ship, marking the ones that changed
+    /// dirty.
+    ///
+    /// Countdowns and the cooldown are only written when they pass a whole
+    /// second, since clients count them down themselves.
+    pub fn sync(&self, ship: &mut ShipStatus) {
+        for (system_type, system) in &self.systems {
+            let changed = ship.system(*system_type).is_some_and(|old| differs(old, system));
+
+            if changed {
+                if let Some(old) = ship.system_mut(*system_type) {
+                    *old = system.clone();
+                }
+            }
+        }
+    }
+}
+
+/// How long the crewmates have to fix a critical sabotage on a map, in
+/// seconds.
+pub fn critical_countdown(map: Map) -> f32 {
+    match map {
+        Map::Skeld | Map::AprilSkeld => 30.,
+        Map::MiraHq => 45.,
+        Map::Polus => 60.,
+        Map::Airship => 90.,
+    }
+}
+
+fn is_sabotaged(system: &System) -> bool {
+    match system {
+        System::Reactor(reactor) => reactor.countdown < NOT_SABOTAGED,
+        System::LifeSupp(life_supp) => life_supp.countdown < NOT_SABOTAGED,
+        System::HeliSabotage(heli) => heli.countdown < NOT_SABOTAGED,
+        System::Switch(switch) => switch.actual != switch.expected,
+        System::HudOverride(hud) => hud.active,
+        System::HqHud(hud) => hud.completed_consoles.len() < 2,
+        _ => false,
+    }
+}
+
+/// Whether a system has changed enough to be written, counting timers by
+/// whole seconds.
+fn differs(old: &System, new: &System) -> bool {
+    let seconds = |a: f32, b: f32| a.ceil() != b.ceil();
+
+    match (old, new) {
+        (System::Sabotage(old), System::Sabotage(new)) => seconds(old.timer, new.timer),
+        (System::Reactor(old), System::Reactor(new)) => {
+            seconds(old.countdown, new.countdown) || old.user_consoles != new.user_consoles
+        }
+        (System::LifeSupp(old), System::LifeSupp(new)) => {
+            seconds(old.countdown, new.countdown)
+                || old.completed_consoles != new.completed_consoles
+        }
+        (System::HeliSabotage(old), System::HeliSabotage(new)) => {
+            seconds(old.countdown, new.countdown)
+                || seconds(old.timer, new.timer)
+                || old.active_consoles != new.active_consoles
+                || old.completed_consoles != new.completed_consoles
+        }
+        (old, new) => old != new,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::game::Role;
+
+    fn player(id: u8, role: Role) -> Player {
+        let mut player = Player::new(id, id as i32, String::new());
+        player.role = role;
+        player
+    }
+
+    fn repair(system: u8, amount: u8) -> Rpc {
+        Rpc::RepairSystem { system, player: 0, amount }
+    }
+
+    fn sabotaged(map: Map, system_type: u8) -> Sabotage {
+        let mut sabotage = Sabotage::new(map, Rng::new(1));
+        sabotage.sabotage(system_type).unwrap();
+        sabotage
+    }
+
+    #[test]
+    fn only_impostors_sabotage() {
+        let mut sabotage = Sabotage::new(Map::Skeld, Rng::new(1));
+        let crewmate = player(0, Role::Crewmate);
+        let shapeshifter = player(1, Role::Shapeshifter);
+
+        let rpc = repair(SABOTAGE, REACTOR);
+        assert_eq!(sabotage.handle_rpc(&crewmate, &rpc), Err(SabotageError::NotAllowed));
+        assert_eq!(sabotage.active(), None);
+
+        assert_eq!(sabotage.handle_rpc(&shapeshifter, &rpc), Ok(()));
+        assert_eq!(sabotage.active(), Some(REACTOR));
+        assert_eq!(sabotage.countdown(), Some(30.));
+    }
+
+    #[test]
+    fn one_at_a_time() {
+        let mut sabotage = sabotaged(Map::Skeld, COMMS);
+
+        sabotage.set_cooldown(0.);
+        assert_eq!(sabotage.sabotage(REACTOR), Err(SabotageError::Active));
+        assert_eq!(sabotage.sabotage(LABORATORY), Err(SabotageError::NoSuchSystem(LABORATORY)));
+        assert_eq!(sabotage.sabotage(SABOTAGE), Err(SabotageError::NoSuchSystem(SABOTAGE)));
+    }
+
+    #[test]
+    fn cooldown() {
+        let mut sabotage = sabotaged(Map::Skeld, COMMS);
+        assert_eq!(sabotage.cooldown(), COOLDOWN);
+
+        // the cooldown waits for the sabotage to be fixed
+        sabotage.tick(10.);
+        assert_eq!(sabotage.cooldown(), COOLDOWN);
+
+        sabotage.repair(0, COMMS, 0x10).unwrap();
+        assert!(!sabotage.comms_down());
+        assert_eq!(sabotage.can_sabotage(REACTOR), Err(SabotageError::Cooldown));
+
+        sabotage.tick(COOLDOWN);
+        assert_eq!(sabotage.can_sabotage(REACTOR), Ok(()));
+    }
+
+    #[test]
+    fn reactor() {
+        let mut sabotage = sabotaged(Map::Skeld, REACTOR);
+
+        // two players on one console is still one console
+        sabotage.repair(1, REACTOR, 0x40).unwrap();
+        sabotage.repair(2, REACTOR, 0x40).unwrap();
+        assert!(sabotage.is_sabotaged(REACTOR));
+
+        // and a console let go of doesn't count
+        sabotage.repair(1, REACTOR, 0x20).unwrap();
+        sabotage.repair(2, REACTOR, 0x20).unwrap();
+        sabotage.repair(3, REACTOR, 0x41).unwrap();
+        assert!(sabotage.is_sabotaged(REACTOR));
+
+        sabotage.repair(1, REACTOR, 0x40).unwrap();
+        assert!(!sabotage.is_sabotaged(REACTOR));
+        assert_eq!(sabotage.countdown(), None);
+    }
+
+    #[test]
+    fn seismic_stabilizers() {
+        let mut sabotage = sabotaged(Map::Polus, LABORATORY);
+        assert_eq!(sabotage.countdown(), Some(60.));
+
+        sabotage.repair(1, LABORATORY, 0x40).unwrap();
+        sabotage.repair(2, LABORATORY, 0x41).unwrap();
+        assert_eq!(sabotage.active(), None);
+    }
+
+    #[test]
+    fn oxygen() {
+        let mut sabotage = sabotaged(Map::MiraHq, LIFE_SUPP);
+        assert_eq!(sabotage.countdown(), Some(45.));
+
+        sabotage.repair(1, LIFE_SUPP, 0x40).unwrap();
+        sabotage.repair(2, LIFE_SUPP, 0x40).unwrap();
+        assert!(sabotage.is_sabotaged(LIFE_SUPP));
+
+        sabotage.repair(2, LIFE_SUPP, 0x41).unwrap();
+        assert!(!sabotage.is_sabotaged(LIFE_SUPP));
+    }
+
+    #[test]
+    fn running_out_of_time() {
+        let mut sabotage = sabotaged(Map::Skeld, LIFE_SUPP);
+
+        assert_eq!(sabotage.tick(29.), None);
+        assert_eq!(sabotage.countdown(), Some(1.));
+        assert_eq!(sabotage.tick(1.), Some(GameOverReason::ImpostorBySabotage));
+    }
+
+    #[test]
+    fn fix_critical() {
+        let mut sabotage = sabotaged(Map::Skeld, REACTOR);
+        sabotage.fix_critical();
+        assert_eq!(sabotage.active(), None);
+
+        // the lights aren't critical
+        let mut sabotage = sabotaged(Map::Skeld, ELECTRICAL);
+        sabotage.fix_critical();
+        assert_eq!(sabotage.active(), Some(ELECTRICAL));
+    }
+
+    #[test]
+    fn lights() {
+        for seed in 0..20 {
+            let mut sabotage = Sabotage::new(Map::Skeld, Rng::new(seed));
+            sabotage.sabotage(ELECTRICAL).unwrap();
+
+            let (expected, actual) = match sabotage.systems.get(&ELECTRICAL) {
+                Some(System::Switch(switch)) => (switch.expected, switch.actual),
+                _ => unreachable!(),
+            };
+
+            let wrong = expected ^ actual;
+            assert_ne!(wrong, 0, "no switch was flipped");
+            assert!(wrong < 1 << SWITCHES);
+
+            // the lights dim while the switches are wrong
+            sabotage.tick(0.5);
+            assert!(matches!(
+                sabotage.systems.get(&ELECTRICAL),
+                Some(System::Switch(switch)) if switch.value < u8::MAX,
+            ));
+
+            // a switch that doesn't exist does nothing
+            sabotage.repair(0, ELECTRICAL, SWITCHES).unwrap();
+
+            for index in (0..SWITCHES).filter(|index| wrong & 1 << index != 0) {
+                assert!(sabotage.is_sabotaged(ELECTRICAL));
+                sabotage.repair(0, ELECTRICAL, index).unwrap();
+            }
+
+            assert!(!sabotage.is_sabotaged(ELECTRICAL));
+        }
+    }
+
+    #[test]
+    fn mira_comms() {
+        let mut sabotage = Sabotage::new(Map::MiraHq, Rng::new(1));
+        assert!(!sabotage.comms_down());
+
+        sabotage.sabotage(COMMS).unwrap();
+        assert!(sabotage.comms_down());
+
+        sabotage.repair(1, COMMS, 0x40).unwrap();
+        sabotage.repair(1, COMMS, 0x10).unwrap();
+        sabotage.repair(1, COMMS, 0x10).unwrap();
+        assert!(sabotage.comms_down());
+
+        sabotage.repair(2, COMMS, 0x11).unwrap();
+        assert!(!sabotage.comms_down());
+    }
+
+    #[test]
+    fn crash_course() {
+        let mut sabotage = sabotaged(Map::Airship, REACTOR);
+        assert_eq!(sabotage.countdown(), Some(90.));
+
+        // the second code comes too late
+        sabotage.repair(1, REACTOR, 0x10).unwrap();
+        assert_eq!(sabotage.tick(HELI_CODE_TIME), None);
+        sabotage.repair(2, REACTOR, 0x11).unwrap();
+        assert!(sabotage.is_sabotaged(REACTOR));
+
+        sabotage.repair(1, REACTOR, 0x10).unwrap();
+        assert!(!sabotage.is_sabotaged(REACTOR));
+    }
+
+    #[test]
+    fn repairs() {
+        let mut sabotage = Sabotage::new(Map::Skeld, Rng::new(1));
+        let crewmate = player(0, Role::Crewmate);
+
+        // nothing to fix
+        assert_eq!(sabotage.handle_rpc(&crewmate, &repair(REACTOR, 0x40)), Ok(()));
+        assert_eq!(sabotage.repair(0, LABORATORY, 0), Err(SabotageError::NoSuchSystem(21)));
+        assert_eq!(sabotage.repair(0, SABOTAGE, 0), Err(SabotageError::NoSuchSystem(SABOTAGE)));
+
+        // other RPCs are left alone
+        assert_eq!(sabotage.handle_rpc(&crewmate, &Rpc::Close), Ok(()));
+    }
+
+    #[test]
+    fn sync() {
+        let mut ship = ShipStatus::new(Map::Skeld);
+        let mut sabotage = Sabotage::new(Map::Skeld, Rng::new(1));
+
+        sabotage.sync(&mut ship);
+        assert_eq!(ship.dirty, 0);
+
+        sabotage.sabotage(REACTOR).unwrap();
+        sabotage.sync(&mut ship);
+        assert_eq!(ship.dirty, 1 << REACTOR | 1 << SABOTAGE);
+        ship.clear_dirty();
+
+        // only whole seconds are sent
+        sabotage.tick(0.5);
+        sabotage.sync(&mut ship);
+        assert_eq!(ship.dirty & 1 << REACTOR, 0);
+
+        sabotage.tick(0.6);
+        sabotage.sync(&mut ship);
+        assert_ne!(ship.dirty & 1 << REACTOR, 0);
+    }
+}
@@ -0,0 +1,342 @@
+//! The phases of a game.
+//!
+//! A game sits in the lobby until the host starts it, is played until someone
+//! calls a meeting, goes back to being played once the meeting is over, and
+//! ends when one side wins. Then everyone goes back to the lobby to do it all
+//! over again.
+//!
+//! Everything else keys off the phase: tasks can only be done in game, votes
+//! only cast in a meeting, and so on. [`State`] only allows the transitions
+//! that make sense, and queues an [`Event`] for each one so the rest of the
+//! game can react to it.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+/// What phase a game is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Waiting for the host to start the game.
+    Lobby,
+    /// The game is being played.
+    InGame,
+    /// A meeting was called, and everyone is discussing and voting.
+    Meeting {
+        /// The player who called the meeting.
+        caller: u8,
+        /// The player whose body was reported, or `None` for an emergency
+        /// meeting.
+        body: Option<u8>,
+    },
+    /// The game is over.
+    Ended(GameOverReason),
+}
+
+/// Why a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameOverReason {
+    /// The crewmates voted out every impostor.
+    HumansByVote,
+    /// The crewmates finished all of their tasks.
+    HumansByTask,
+    /// The impostors voted out enough crewmates.
+    ImpostorByVote,
+    /// The impostors killed enough crewmates.
+    ImpostorByKill,
+    /// A sabotage wasn't fixed in time.
+    ImpostorBySabotage,
+    /// The last impostor left.
+    ImpostorDisconnect,
+    /// Enough crewmates left that the impostors win.
+    HumansDisconnect,
+    Unknown(u8),
+}
+
+impl GameOverReason {
+    /// The id of the reason on the wire, as in an
+    /// [`EndGame`](crate::net::protocol::root::EndGame).
+    pub fn id(self) -> u8 {
+        match self {
+            GameOverReason::HumansByVote => 0,
+            GameOverReason::HumansByTask => 1,
+            GameOverReason::ImpostorByVote => 2,
+            GameOverReason::ImpostorByKill => 3,
+            GameOverReason::ImpostorBySabotage => 4,
+            GameOverReason::ImpostorDisconnect => 5,
+            GameOverReason::HumansDisconnect => 6,
+            GameOverReason::Unknown(id) => id,
+        }
+    }
+
+    /// The reason of an id.
+    pub fn from_id(id: u8) -> GameOverReason {
+        match id {
+            0 => GameOverReason::HumansByVote,
+            1 => GameOverReason::HumansByTask,
+            2 => GameOverReason::ImpostorByVote,
+            3 => GameOverReason::ImpostorByKill,
+            4 => GameOverReason::ImpostorBySabotage,
+            5 => GameOverReason::ImpostorDisconnect,
+            6 => GameOverReason::HumansDisconnect,
+            id => GameOverReason::Unknown(id),
+        }
+    }
+
+    /// Whether the crewmates won.
+    pub fn crewmates_won(self) -> bool {
+        matches!(
+            self,
+            GameOverReason::HumansByVote
+                | GameOverReason::HumansByTask
+                | GameOverReason::ImpostorDisconnect,
+        )
+    }
+}
+
+/// A change from one [`Phase`] to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    /// From the lobby into the game.
+    StartGame,
+    /// From the game into a meeting.
+    CallMeeting {
+        caller: u8,
+        body: Option<u8>,
+    },
+    /// From a meeting back into the game.
+    EndMeeting {
+        /// The player voted out, if anyone.
+        exiled: Option<u8>,
+    },
+    /// From the game or a meeting to the end.
+    EndGame(GameOverReason),
+    /// From the end back to the lobby.
+    ReturnToLobby,
+}
+
+/// Something that happened to a [`State`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Started,
+    MeetingCalled {
+        caller: u8,
+        body: Option<u8>,
+    },
+    MeetingEnded {
+        exiled: Option<u8>,
+    },
+    Ended(GameOverReason),
+    ReturnedToLobby,
+}
+
+/// A transition that can't happen from the current phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub phase: Phase,
+    pub transition: Transition,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can't {:?} from {:?}", self.transition, self.phase)
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
+/// The state of a game.
+pub struct State {
+    phase: Phase,
+    meetings: u32,
+    events: VecDeque<Event>,
+}
+
+impl State {
+    /// Create a new `State`, in the lobby.
+    pub fn new() -> State {
+        State {
+            phase: Phase::Lobby,
+            meetings: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// The current phase.
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Whether the game is being played, in a meeting or not.
+    pub fn is_playing(&self) -> bool {
+        matches!(self.phase, Phase::InGame | Phase::Meeting { .. })
+    }
+
+    /// How many meetings have been called this game.
+    pub fn meetings(&self) -> u32 {
+        self.meetings
+    }
+
+    /// Starts the game.
+    pub fn start_game(&mut self) -> Result<(), InvalidTransition> {
+        self.apply(Transition::StartGame)
+    }
+
+    /// Calls a meeting, for a reported `body` or an emergency.
+    pub fn call_meeting(&mut self, caller: u8, body: Option<u8>) -> Result<(), InvalidTransition> {
+        self.apply(Transition::CallMeeting { caller, body })
+    }
+
+    /// Ends the meeting, with whoever was voted out.
+    pub fn end_meeting(&mut self, exiled: Option<u8>) -> Result<(), InvalidTransition> {
+        self.apply(Transition::EndMeeting { exiled })
+    }
+
+    /// Ends the game.
+    pub fn end_game(&mut self, reason: GameOverReason) -> Result<(), InvalidTransition> {
+        self.apply(Transition::EndGame(reason))
+    }
+
+    /// Goes back to the lobby after the game ended.
+    pub fn return_to_lobby(&mut self) -> Result<(), InvalidTransition> {
+        self.apply(Transition::ReturnToLobby)
+    }
+
+    /// Applies a transition, if it can happen from the current phase.
+    pub fn apply(&mut self, transition: Transition) -> Result<(), InvalidTransition> {
+        let (phase, event) = match (self.phase, transition) {
+            (Phase::Lobby, Transition::StartGame) => {
+                self.meetings = 0;
+                (Phase::InGame, Event::Started)
+            }
+            (Phase::InGame, Transition::CallMeeting { caller, body }) => {
+                self.meetings += 1;
+                (Phase::Meeting { caller, body }, Event::MeetingCalled { caller, body })
+            }
+            (Phase::Meeting { .. }, Transition::EndMeeting { exiled }) => {
+                (Phase::InGame, Event::MeetingEnded { exiled })
+            }
+            (Phase::InGame, Transition::EndGame(reason))
+            | (Phase::Meeting { .. }, Transition::EndGame(reason)) => {
+                (Phase::Ended(reason), Event::Ended(reason))
+            }
+            (Phase::Ended(_), Transition::ReturnToLobby) => (Phase::Lobby, Event::ReturnedToLobby),
+            (phase, transition) => return Err(InvalidTransition { phase, transition }),
+        };
+
+        self.phase = phase;
+        self.events.push_back(event);
+
+        Ok(())
+    }
+
+    /// Takes the next event.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
+
+impl Default for State {
+    fn default() -> State {
+        State::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(state: &mut State) -> Vec<Event> {
+        std::iter::from_fn(|| state.poll_event()).collect()
+    }
+
+    #[test]
+    fn full_game() {
+        let mut state = State::new();
+        assert_eq!(state.phase(), Phase::Lobby);
+        assert!(!state.is_playing());
+
+        state.start_game().unwrap();
+        assert_eq!(state.phase(), Phase::InGame);
+        assert!(state.is_playing());
+
+        state.call_meeting(1, Some(4)).unwrap();
+        assert_eq!(state.phase(), Phase::Meeting { caller: 1, body: Some(4) });
+        assert!(state.is_playing());
+
+        state.end_meeting(Some(2)).unwrap();
+        state.call_meeting(3, None).unwrap();
+        state.end_game(GameOverReason::HumansByVote).unwrap();
+        assert_eq!(state.phase(), Phase::Ended(GameOverReason::HumansByVote));
+        assert!(!state.is_playing());
+        assert_eq!(state.meetings(), 2);
+
+        state.return_to_lobby().unwrap();
+        assert_eq!(state.phase(), Phase::Lobby);
+
+        assert_eq!(events(&mut state), [
+            Event::Started,
+            Event::MeetingCalled { caller: 1, body: Some(4) },
+            Event::MeetingEnded { exiled: Some(2) },
+            Event::MeetingCalled { caller: 3, body: None },
+            Event::Ended(GameOverReason::HumansByVote),
+            Event::ReturnedToLobby,
+        ]);
+
+        // the meeting count starts over with the next game
+        state.start_game().unwrap();
+        assert_eq!(state.meetings(), 0);
+    }
+
+    #[test]
+    fn invalid_transitions() {
+        let mut state = State::new();
+
+        let invalid = |state: &mut State, transition| {
+            let phase = state.phase();
+            assert_eq!(state.apply(transition), Err(InvalidTransition { phase, transition }));
+            assert_eq!(state.phase(), phase, "{:?} changed the phase", transition);
+        };
+
+        let meeting = Transition::CallMeeting { caller: 0, body: None };
+        let end_meeting = Transition::EndMeeting { exiled: None };
+        let end_game = Transition::EndGame(GameOverReason::ImpostorByKill);
+
+        invalid(&mut state, meeting);
+        invalid(&mut state, end_meeting);
+        invalid(&mut state, end_game);
+        invalid(&mut state, Transition::ReturnToLobby);
+
+        state.start_game().unwrap();
+        invalid(&mut state, Transition::StartGame);
+        invalid(&mut state, end_meeting);
+        invalid(&mut state, Transition::ReturnToLobby);
+
+        state.call_meeting(0, None).unwrap();
+        invalid(&mut state, Transition::StartGame);
+        invalid(&mut state, meeting);
+        invalid(&mut state, Transition::ReturnToLobby);
+
+        state.end_game(GameOverReason::ImpostorByKill).unwrap();
+        invalid(&mut state, Transition::StartGame);
+        invalid(&mut state, meeting);
+        invalid(&mut state, end_meeting);
+        invalid(&mut state, end_game);
+
+        // failed transitions don't queue events
+        assert_eq!(events(&mut state).len(), 3);
+        assert_eq!(state.meetings(), 1);
+    }
+
+    #[test]
+    fn game_over_reasons() {
+        for id in 0..=7 {
+            assert_eq!(GameOverReason::from_id(id).id(), id);
+        }
+
+        assert_eq!(GameOverReason::from_id(7), GameOverReason::Unknown(7));
+        assert!(GameOverReason::HumansByTask.crewmates_won());
+        assert!(GameOverReason::ImpostorDisconnect.crewmates_won());
+        assert!(!GameOverReason::ImpostorBySabotage.crewmates_won());
+        assert!(!GameOverReason::HumansDisconnect.crewmates_won());
+    }
+}
@@ -16,8 +16,17 @@
 //!
 //! The spawned `Minigame`s control their task parents. The tasks implement no
 //! functionality themselves, except for helper functions for networking.
+//!
+//! When a game starts, the host hands out tasks from the map's [`TaskPool`].
+//! Everyone gets the same common tasks, and their own short and long tasks,
+//! as many of each as the options say. Impostors get tasks too, so they have
+//! something to pretend to do.
 
-use crate::game::State;
+use crate::game::{Players, State};
+use crate::math::rng::Rng;
+use crate::net::objects::game_data::TaskInfo;
+use crate::net::protocol::options::GameOptions;
+use crate::net::protocol::Rpc;
 
 /// A task.
 ///
@@ -45,5 +54,315 @@ pub trait Minigame {
     /// before the minigame is displayed to the user on the screen.
     ///
     /// Never called on the server.
-    fn begin(&mut self, state: State, task: &mut Task);
+    fn begin(&mut self, state: &State, task: &mut Task);
+}
+
+/// How long a task takes, which decides how it is handed out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TaskLength {
+    /// Given to everyone, like swiping a card.
+    Common,
+    Short,
+    Long,
+}
+
+/// A task a map has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TaskDef {
+    /// The id of the task on its map, as sent in SetTasks.
+    pub id: u8,
+    pub length: TaskLength,
+}
+
+/// The tasks that can be handed out in a game.
+#[derive(Clone, Debug, Default)]
+pub struct TaskPool {
+    common: Vec<u8>,
+    short: Vec<u8>,
+    long: Vec<u8>,
+}
+
+impl TaskPool {
+    /// Create a new, empty `TaskPool`.
+    pub fn new() -> TaskPool {
+        TaskPool::default()
+    }
+
+    /// Create a new `TaskPool` from the tasks of a map.
+    pub fn from_tasks(tasks: &[TaskDef]) -> TaskPool {
+        let mut pool = TaskPool::new();
+
+        for task in tasks {
+            pool.add(*task);
+        }
+
+        pool
+    }
+
+    /// Adds a task to the pool.
+    pub fn add(&mut self, task: TaskDef) -> &mut TaskPool {
+        let list = match task.length {
+            TaskLength::Common => &mut self.common,
+            TaskLength::Short => &mut self.short,
+            TaskLength::Long => &mut self.long,
+        };
+
+        if !list.contains(&task.id) {
+            list.push(task.id);
+        }
+
+        self
+    }
+
+    /// Whether the pool has no tasks.
+    pub fn is_empty(&self) -> bool {
+        self.common.is_empty() && self.short.is_empty() && self.long.is_empty()
+    }
+
+    /// Hands out tasks to every connected player, replacing the ones they
+    /// had.
+    ///
+    /// Each player gets the common tasks first, then their long tasks, then
+    /// their short tasks. If the options ask for more of a kind than the
+    /// pool has, everyone gets all of them.
+    pub fn assign(
+        &self,
+        players: &mut Players,
+        options: &GameOptions,
+        rng: &mut Rng,
+    ) -> TaskAssignment {
+        let mut common = self.common.clone();
+        rng.shuffle(&mut common);
+        common.truncate(options.common_tasks as usize);
+
+        let mut long = Draw::new(&self.long, rng);
+        let mut short = Draw::new(&self.short, rng);
+
+        let mut tasks = Vec::new();
+
+        for player in players.iter_mut() {
+            if !player.is_connected() {
+                continue;
+            }
+
+            let mut ids = common.clone();
+            long.take(options.long_tasks as usize, &mut ids, rng);
+            short.take(options.short_tasks as usize, &mut ids, rng);
+
+            player.tasks = (0..ids.len() as u32)
+                .map(|id| TaskInfo { id, complete: false })
+                .collect();
+
+            tasks.push((player.id(), ids));
+        }
+
+        TaskAssignment { tasks }
+    }
+}
+
+/// Draws tasks of one length for one player after another.
+///
+/// The tasks are dealt from a shuffled deck, so they are spread out over the
+/// players before any repeat, and the deck is shuffled again once it runs
+/// out.
+struct Draw {
+    deck: Vec<u8>,
+    next: usize,
+}
+
+impl Draw {
+    fn new(tasks: &[u8], rng: &mut Rng) -> Draw {
+        let mut deck = tasks.to_vec();
+        rng.shuffle(&mut deck);
+
+        Draw { deck, next: 0 }
+    }
+
+    /// Draws `count` tasks the player doesn't have yet into `ids`.
+    fn take(&mut self, count: usize, ids: &mut Vec<u8>, rng: &mut Rng) {
+        let count = count.min(self.deck.len());
+        let start = ids.len();
+
+        while ids.len() - start < count {
+            if self.next >= self.deck.len() {
+                rng.shuffle(&mut self.deck);
+                self.next = 0;
+            }
+
+            let id = self.deck[self.next];
+            self.next += 1;
+
+            if !ids[start..].contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+}
+
+/// The tasks handed out in a game.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskAssignment {
+    /// The task ids of every player, by player id.
+    pub tasks: Vec<(u8, Vec<u8>)>,
+}
+
+impl TaskAssignment {
+    /// The task ids of a player.
+    pub fn get(&self, player: u8) -> Option<&[u8]> {
+        self.tasks
+            .iter()
+            .find(|(id, _)| *id == player)
+            .map(|(_, tasks)| tasks.as_slice())
+    }
+
+    /// The SetTasks RPCs telling everyone their tasks, one per player.
+    pub fn rpcs(&self) -> Vec<Rpc> {
+        self.tasks
+            .iter()
+            .map(|(player, tasks)| Rpc::SetTasks {
+                player: *player,
+                tasks: tasks.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMON: [u8; 3] = [0, 1, 2];
+    const LONG: [u8; 4] = [10, 11, 12, 13];
+    const SHORT: [u8; 6] = [20, 21, 22, 23, 24, 25];
+
+    fn pool() -> TaskPool {
+        let mut pool = TaskPool::new();
+
+        for &id in &COMMON {
+            pool.add(TaskDef { id, length: TaskLength::Common });
+        }
+
+        for &id in &LONG {
+            pool.add(TaskDef { id, length: TaskLength::Long });
+        }
+
+        for &id in &SHORT {
+            pool.add(TaskDef { id, length: TaskLength::Short });
+        }
+
+        pool
+    }
+
+    fn players(count: i32) -> Players {
+        let mut players = Players::new();
+
+        for client_id in 0..count {
+            players.add(client_id, String::new());
+        }
+
+        players
+    }
+
+    fn options(common_tasks: u8, long_tasks: u8, short_tasks: u8) -> GameOptions {
+        GameOptions {
+            common_tasks,
+            long_tasks,
+            short_tasks,
+            ..GameOptions::default()
+        }
+    }
+
+    #[test]
+    fn counts_and_order() {
+        let mut players = players(4);
+        let assignment = pool().assign(&mut players, &options(2, 1, 2), &mut Rng::new(9));
+
+        let common = &assignment.get(0).unwrap()[..2];
+
+        for player in players.iter() {
+            let ids = assignment.get(player.id()).unwrap();
+            assert_eq!(ids.len(), 5);
+
+            // everyone has the same common tasks, then their long and short
+            // tasks, without repeats
+            assert_eq!(&ids[..2], common);
+            assert!(ids[..2].iter().all(|id| COMMON.contains(id)));
+            assert!(LONG.contains(&ids[2]));
+            assert!(ids[3..].iter().all(|id| SHORT.contains(id)));
+            assert_ne!(ids[3], ids[4]);
+
+            let indices: Vec<_> = player.tasks.iter().map(|task| task.id).collect();
+            assert_eq!(indices, [0, 1, 2, 3, 4]);
+            assert!(player.tasks.iter().all(|task| !task.complete));
+        }
+    }
+
+    #[test]
+    fn spread_out_before_repeating() {
+        let mut players = players(4);
+        let assignment = pool().assign(&mut players, &options(0, 1, 0), &mut Rng::new(9));
+
+        let mut long: Vec<_> = assignment.tasks.iter().map(|(_, ids)| ids[0]).collect();
+        long.sort_unstable();
+        assert_eq!(long, LONG);
+    }
+
+    #[test]
+    fn more_than_the_pool_has() {
+        let mut players = players(2);
+        let assignment = pool().assign(&mut players, &options(5, 9, 9), &mut Rng::new(9));
+
+        for (_, ids) in &assignment.tasks {
+            let mut ids = ids.clone();
+            ids.sort_unstable();
+
+            let all: Vec<_> = COMMON.iter().chain(&LONG).chain(&SHORT).copied().collect();
+            assert_eq!(ids, all);
+        }
+    }
+
+    #[test]
+    fn only_connected_players() {
+        let mut players = players(3);
+        players.disconnect(1);
+
+        let assignment = pool().assign(&mut players, &options(1, 1, 1), &mut Rng::new(9));
+
+        assert_eq!(assignment.tasks.len(), 2);
+        assert_eq!(assignment.get(1), None);
+        assert!(players.get(1).unwrap().tasks.is_empty());
+    }
+
+    #[test]
+    fn seeded() {
+        let assign = |seed| pool().assign(&mut players(5), &options(1, 1, 2), &mut Rng::new(seed));
+
+        assert_eq!(assign(3), assign(3));
+        assert!((0..20).any(|seed| assign(seed) != assign(3)));
+    }
+
+    #[test]
+    fn rpcs() {
+        let assignment = TaskAssignment {
+            tasks: vec![(0, vec![1, 10]), (2, vec![0, 11])],
+        };
+
+        assert_eq!(assignment.rpcs(), [
+            Rpc::SetTasks { player: 0, tasks: vec![1, 10] },
+            Rpc::SetTasks { player: 2, tasks: vec![0, 11] },
+        ]);
+    }
+
+    #[test]
+    fn pool_ignores_duplicates() {
+        let mut pool = TaskPool::new();
+        assert!(pool.is_empty());
+
+        pool.add(TaskDef { id: 1, length: TaskLength::Short });
+        pool.add(TaskDef { id: 1, length: TaskLength::Short });
+        assert!(!pool.is_empty());
+
+        let assignment = pool.assign(&mut players(1), &options(0, 0, 5), &mut Rng::new(1));
+        assert_eq!(assignment.get(0), Some(&[1][..]));
+    }
 }
@@ -0,0 +1,343 @@
+//! Vents.
+//!
+//! Impostors, and engineers, can jump into a vent, crawl to the vents it
+//! leads to, and jump back out. Clients say when they go in and come out with
+//! the EnterVent and ExitVent RPCs, and move between vents by snapping to
+//! them. [`Vents`] keeps track of who is in which vent, so a server can check
+//! that each of those is allowed before passing it on.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::collide::map::WALLS;
+use crate::collide::{vision, World};
+use crate::game::ability::Ability;
+use crate::game::map::{MapData, Vent};
+use crate::game::{Player, Players};
+use crate::math::Vector2;
+use crate::net::protocol::Rpc;
+
+/// How close a player has to be to a vent to use it.
+pub const VENT_RANGE: f32 = 0.75;
+
+/// How long a player has to wait between moving from vent to vent, in
+/// seconds.
+pub const HOP_COOLDOWN: f32 = 0.4;
+
+/// Why a player can't do something with a vent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VentError {
+    /// The map has no vent with the id.
+    NoSuchVent(u32),
+    /// The player's role can't use vents, or they are dead.
+    NotAllowed,
+    /// The player is too far away from the vent.
+    TooFar,
+    /// There is a wall between the player and the vent.
+    Blocked,
+    /// The player is already in a vent.
+    InVent,
+    /// The player isn't in the vent.
+    NotInVent,
+    /// The vents aren't connected.
+    NotConnected,
+    /// The player has to wait a bit longer.
+    Cooldown,
+}
+
+impl fmt::Display for VentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VentError::NoSuchVent(id) => write!(f, "no vent with id {}", id),
+            VentError::NotAllowed => write!(f, "not allowed to use vents"),
+            VentError::TooFar => write!(f, "too far from the vent"),
+            VentError::Blocked => write!(f, "a wall is in the way"),
+            VentError::InVent => write!(f, "already in a vent"),
+            VentError::NotInVent => write!(f, "not in the vent"),
+            VentError::NotConnected => write!(f, "vents aren't connected"),
+            VentError::Cooldown => write!(f, "on cooldown"),
+        }
+    }
+}
+
+impl std::error::Error for VentError {}
+
+/// A player in a vent.
+#[derive(Clone, Copy, Debug)]
+struct Occupant {
+    vent: u8,
+    /// How long until the player can move to another vent.
+    cooldown: f32,
+}
+
+/// The vents of a map, and who is in them.
+#[derive(Clone, Debug)]
+pub struct Vents {
+    data: &'static MapData,
+    hop_cooldown: f32,
+    occupants: HashMap<u8, Occupant>,
+    walls: Option<World>,
+}
+
+impl Vents {
+    /// Create a new `Vents` for a map, with nobody in them.
+    pub fn new(data: &'static MapData) -> Vents {
+        Vents {
+            data,
+            hop_cooldown: HOP_COOLDOWN,
+            occupants: HashMap::new(),
+            walls: None,
+        }
+    }
+
+    /// Sets how long players wait between moving from vent to vent.
+    pub fn set_hop_cooldown(&mut self, seconds: f32) {
+        self.hop_cooldown = seconds;
+    }
+
+    /// Sets the walls of the map, like the world of a
+    /// [`MapColliders`](crate::collide::map::MapColliders).
+    ///
+    /// Players can't use a vent through colliders on the [`WALLS`] layer.
+    /// Without walls, only the distance is checked.
+    pub fn set_walls(&mut self, walls: Option<World>) {
+        self.walls = walls;
+    }
+
+    /// A vent by id.
+    pub fn get(&self, id: u32) -> Result<&'static Vent, VentError> {
+        u8::try_from(id)
+            .ok()
+            .and_then(|id| self.data.vent(id))
+            .ok_or(VentError::NoSuchVent(id))
+    }
+
+    /// The vents a vent leads to.
+    pub fn connections(&self, id: u8) -> &'static [u8] {
+        self.data.vent(id).map(|vent| vent.connections).unwrap_or(&[])
+    }
+
+    /// The closest vent a player can reach, if any.
+    pub fn nearest(&self, player: &Player) -> Option<&'static Vent> {
+        self.nearest_to(player.position)
+    }
+
+    /// The closest vent within range of a position, if any.
+    fn nearest_to(&self, position: Vector2) -> Option<&'static Vent> {
+        self.data
+            .vents
+            .iter()
+            .map(|vent| (vent, (vent.position - position).norm()))
+            .filter(|(_, distance)| *distance <= VENT_RANGE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(vent, _)| vent)
+    }
+
+    /// The vent a player is in, if any.
+    pub fn vent_of(&self, player: u8) -> Option<u8> {
+        self.occupants.get(&player).map(|occupant| occupant.vent)
+    }
+
+    /// The players in a vent.
+    pub fn players_in(&self, vent: u8) -> impl Iterator<Item = u8> + '_ {
+        self.occupants
+            .iter()
+            .filter(move |(_, occupant)| occupant.vent == vent)
+            .map(|(player, _)| *player)
+    }
+
+    /// Checks whether a player could go into a vent right now.
+    pub fn can_use(&self, player: &Player, vent: u32) -> Result<(), VentError> {
+        let vent = self.get(vent)?;
+
+        if player.dead || !player.role.can_vent() {
+            return Err(VentError::NotAllowed);
+        }
+
+        if self.occupants.contains_key(&player.id()) {
+            return Err(VentError::InVent);
+        }
+
+        if (vent.position - player.position).norm() > VENT_RANGE {
+            return Err(VentError::TooFar);
+        }
+
+        if let Some(walls) = &self.walls {
+            if !vision::can_see_in(walls, player.position, VENT_RANGE, vent.position, WALLS) {
+                return Err(VentError::Blocked);
+            }
+        }
+
+        if let Ability::Engineer(venting) = &player.ability {
+            if !venting.can_enter() {
+                return Err(VentError::Cooldown);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Puts a player in a vent, returning the RPC to send.
+    pub fn enter(&mut self, player: &mut Player, vent: u32) -> Result<Rpc, VentError> {
+        self.can_use(player, vent)?;
+
+        if let Ability::Engineer(venting) = &mut player.ability {
+            venting.enter();
+        }
+
+        let vent = self.get(vent)?;
+        player.position = vent.position;
+
+        self.occupants.insert(
+            player.id(),
+            Occupant {
+                vent: vent.id,
+                cooldown: self.hop_cooldown,
+            },
+        );
+
+        Ok(Rpc::EnterVent { vent: vent.id as u32 })
+    }
+
+    /// Moves a player in a vent to one it leads to.
+    ///
+    /// The player should then be snapped to the new vent.
+    pub fn hop(&mut self, player: &mut Player, to: u32) -> Result<(), VentError> {
+        let to = self.get(to)?;
+        let occupant = self.occupants.get_mut(&player.id()).ok_or(VentError::NotInVent)?;
+
+        if !self.data.vent(occupant.vent).is_some_and(|vent| vent.connections.contains(&to.id)) {
+            return Err(VentError::NotConnected);
+        }
+
+        if occupant.cooldown > 0. {
+            return Err(VentError::Cooldown);
+        }
+
+        occupant.vent = to.id;
+        occupant.cooldown = self.hop_cooldown;
+        player.position = to.position;
+
+        Ok(())
+    }
+
+    /// Takes a player out of the vent they are in, returning the RPC to send.
+    pub fn exit(&mut self, player: &mut Player, vent: u32) -> Result<Rpc, VentError> {
+        let vent = self.get(vent)?;
+
+        if self.vent_of(player.id()) != Some(vent.id) {
+            return Err(VentError::NotInVent);
+        }
+
+        Ok(self.force_exit(player).unwrap_or(Rpc::ExitVent { vent: vent.id as u32 }))
+    }
+
+    /// Takes a player out of whatever vent they are in, like when a meeting
+    /// is called or an engineer runs out of time.
+    ///
+    /// Returns the RPC to send, if they were in one.
+    pub fn force_exit(&mut self, player: &mut Player) -> Option<Rpc> {
+        let occupant = self.occupants.remove(&player.id())?;
+
+        if let Ability::Engineer(venting) = &mut player.ability {
+            venting.exit();
+        }
+
+        Some(Rpc::ExitVent {
+            vent: occupant.vent as u32,
+        })
+    }
+
+    /// Handles an EnterVent, ExitVent or SnapTo RPC from a player.
+    ///
+    /// A SnapTo to a vent while in a vent is a hop there. An ExitVent from a
+    /// different vent than the one the player is in is taken as a hop there
+    /// first. Other RPCs are ignored.
+    pub fn handle_rpc(&mut self, player: &mut Player, rpc: &Rpc) -> Result<(), VentError> {
+        match *rpc {
+            Rpc::EnterVent { vent } => self.enter(player, vent).map(|_| ()),
+            Rpc::SnapTo { position, .. } if self.vent_of(player.id()).is_some() => {
+                let to = self.nearest_to(position).ok_or(VentError::TooFar)?;
+
+                if self.vent_of(player.id()) == Some(to.id) {
+                    return Ok(());
+                }
+
+                self.hop(player, to.id as u32)
+            }
+            Rpc::ExitVent { vent } => {
+                let id = self.get(vent)?.id;
+
+                if self.vent_of(player.id()).is_some_and(|current| current != id) {
+                    self.hop(player, vent)?;
+                }
+
+                self.exit(player, vent).map(|_| ())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Advances the hop cooldowns by `delta` seconds.
+    pub fn tick(&mut self, delta: f32) {
+        for occupant in self.occupants.values_mut() {
+            occupant.cooldown = (occupant.cooldown - delta).max(0.);
+        }
+    }
+
+    /// Takes everyone out of the vents, returning the RPCs to send, by player
+    /// id.
+    pub fn clear(&mut self, players: &mut Players) -> Vec<(u8, Rpc)> {
+        let mut ids: Vec<u8> = self.occupants.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut exits = Vec::new();
+
+        for id in ids {
+            let exit = match players.get_mut(id) {
+                Some(player) => self.force_exit(player),
+                None => self
+                    .occupants
+                    .remove(&id)
+                    .map(|occupant| Rpc::ExitVent { vent: occupant.vent as u32 }),
+            };
+
+            if let Some(exit) = exit {
+                exits.push((id, exit));
+            }
+        }
+
+        exits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::collide::map::MapColliders;
+    use crate::game::map;
+    use crate::game::role::Role;
+    use crate::net::objects::ship::Map;
+
+    #[test]
+    fn walls_block_vents() {
+        let data = map::data(Map::Skeld);
+        let vent = &data.vents[0];
+
+        let mut player = Player::new(0, 1, "Red".into());
+        player.role = Role::Impostor;
+        player.position = vent.position + Vector2::new(0.5, 0.);
+
+        let mut vents = Vents::new(data);
+        assert_eq!(vents.can_use(&player, vent.id as u32), Ok(()));
+
+        let wall = vent.position + Vector2::new(0.25, 0.);
+        let source = format!("edge {},{} {},{}", wall.x, wall.y - 1., wall.x, wall.y + 1.);
+        let map: MapColliders = source.parse().unwrap();
+
+        vents.set_walls(Some(map.world));
+        assert_eq!(vents.can_use(&player, vent.id as u32), Err(VentError::Blocked));
+    }
+}
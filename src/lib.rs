@@ -1,4 +1,28 @@
-#![feature(never_type)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+// lets the derive macros refer to `::among_us` from inside this crate too
+extern crate self as among_us;
+
+/// The bytes of a fixture in `tests/fixtures`.
+///
+/// Fixtures are written as hex, with `#` comments saying what each part is.
+#[cfg(all(test, feature = "std"))]
+macro_rules! fixture {
+    ($name:literal) => {
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/", $name, ".hex"))
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace())
+            .map(|byte| u8::from_str_radix(byte, 16).expect("fixture is hex"))
+            .collect::<alloc::vec::Vec<u8>>()
+    };
+}
+
+#[cfg(feature = "std")]
+pub mod collide;
+#[cfg(feature = "std")]
 pub mod game;
+#[cfg(feature = "std")]
+pub mod math;
 pub mod net;
@@ -0,0 +1,138 @@
+//! Angles.
+//!
+//! Player facing, vent arrows and camera sweeps all rotate around, and raw
+//! floats make it very easy to forget to wrap them.
+
+use std::f32::consts::PI;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use super::Vector2;
+
+/// An angle, in radians.
+///
+/// The angle is always wrapped to the range `(-π, π]`, so arithmetic never
+/// drifts out of range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Angle(f32);
+
+impl Angle {
+    /// The zero angle, pointing towards positive x.
+    pub const ZERO: Angle = Angle(0.);
+
+    /// Create a new `Angle` from radians.
+    pub fn from_radians(radians: f32) -> Angle {
+        Angle(wrap(radians))
+    }
+
+    /// Create a new `Angle` from degrees.
+    pub fn from_degrees(degrees: f32) -> Angle {
+        Angle::from_radians(degrees.to_radians())
+    }
+
+    /// Create a new `Angle` from the direction of a vector.
+    ///
+    /// A zero vector gives [`Angle::ZERO`].
+    pub fn from_vector(vector: Vector2) -> Angle {
+        Angle(vector.y.atan2(vector.x))
+    }
+
+    /// The angle in radians, in the range `(-π, π]`.
+    pub fn radians(self) -> f32 {
+        self.0
+    }
+
+    /// The angle in degrees, in the range `(-180, 180]`.
+    pub fn degrees(self) -> f32 {
+        self.0.to_degrees()
+    }
+
+    /// A unit vector pointing in the direction of the angle.
+    pub fn to_vector(self) -> Vector2 {
+        let (sin, cos) = self.0.sin_cos();
+
+        Vector2::new(cos, sin)
+    }
+
+    /// The signed shortest rotation from `self` to `other`.
+    pub fn delta(self, other: Angle) -> Angle {
+        other - self
+    }
+
+    /// Interpolates between two angles, taking the shortest way around.
+    ///
+    /// `t` is not clamped.
+    pub fn lerp_shortest(self, other: Angle, t: f32) -> Angle {
+        self + self.delta(other) * t
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, rhs: Angle) -> Angle {
+        Angle::from_radians(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Angle {
+    fn add_assign(&mut self, rhs: Angle) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+
+    fn sub(self, rhs: Angle) -> Angle {
+        Angle::from_radians(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Angle {
+    fn sub_assign(&mut self, rhs: Angle) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f32> for Angle {
+    type Output = Angle;
+
+    fn mul(self, rhs: f32) -> Angle {
+        Angle::from_radians(self.0 * rhs)
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+
+    fn neg(self) -> Angle {
+        Angle::from_radians(-self.0)
+    }
+}
+
+/// Stored as radians, and wrapped again when loaded.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Angle {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Angle {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Angle, D::Error> {
+        f32::deserialize(deserializer).map(Angle::from_radians)
+    }
+}
+
+/// Wraps radians to the range `(-π, π]`.
+fn wrap(radians: f32) -> f32 {
+    let wrapped = (radians + PI).rem_euclid(2. * PI) - PI;
+
+    // rem_euclid maps π to -π, but we want to keep π
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
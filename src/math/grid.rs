@@ -0,0 +1,109 @@
+//! Discrete tile coordinates.
+//!
+//! Pathfinding and the admin table don't care about exact positions, only
+//! which cell of a grid laid over the map something is in.
+
+use std::ops::{Add, Sub};
+
+use super::Vector2;
+
+/// The offsets of the four orthogonal neighbors.
+const ORTHOGONAL: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// The offsets of all eight neighbors, orthogonal first.
+const ALL: [(i32, i32); 8] = [
+    (1, 0), (0, 1), (-1, 0), (0, -1),
+    (1, 1), (-1, 1), (-1, -1), (1, -1),
+];
+
+/// An integer tile coordinate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl TileCoord {
+    /// Create a new `TileCoord`.
+    pub fn new(x: i32, y: i32) -> TileCoord {
+        TileCoord { x, y }
+    }
+
+    /// The orthogonal neighbors of this tile.
+    pub fn neighbors(self) -> impl Iterator<Item = TileCoord> {
+        ORTHOGONAL.iter().map(move |&(x, y)| self + TileCoord::new(x, y))
+    }
+
+    /// The orthogonal and diagonal neighbors of this tile.
+    pub fn neighbors_diagonal(self) -> impl Iterator<Item = TileCoord> {
+        ALL.iter().map(move |&(x, y)| self + TileCoord::new(x, y))
+    }
+
+    /// The number of orthogonal steps between two tiles.
+    pub fn manhattan(self, other: TileCoord) -> u32 {
+        (self.x - other.x).unsigned_abs() + (self.y - other.y).unsigned_abs()
+    }
+
+    /// The number of steps between two tiles when diagonal movement is
+    /// allowed.
+    pub fn chebyshev(self, other: TileCoord) -> u32 {
+        (self.x - other.x).unsigned_abs().max((self.y - other.y).unsigned_abs())
+    }
+}
+
+impl Add for TileCoord {
+    type Output = TileCoord;
+
+    fn add(self, rhs: TileCoord) -> TileCoord {
+        TileCoord::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for TileCoord {
+    type Output = TileCoord;
+
+    fn sub(self, rhs: TileCoord) -> TileCoord {
+        TileCoord::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// A uniform grid laid over world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grid {
+    origin: Vector2,
+    cell_size: f32,
+}
+
+impl Grid {
+    /// Create a new `Grid` with the corner of tile `(0, 0)` at `origin`.
+    ///
+    /// # Panics
+    /// Panics if `cell_size` is not positive.
+    pub fn new(origin: Vector2, cell_size: f32) -> Grid {
+        assert!(cell_size > 0., "cell_size must be positive");
+
+        Grid { origin, cell_size }
+    }
+
+    /// The size of each cell.
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// The tile a world position is in.
+    pub fn to_tile(&self, position: Vector2) -> TileCoord {
+        let local = (position - self.origin) / self.cell_size;
+
+        TileCoord::new(local.x.floor() as i32, local.y.floor() as i32)
+    }
+
+    /// The world position of a tile's minimum corner.
+    pub fn to_world(&self, tile: TileCoord) -> Vector2 {
+        self.origin + Vector2::new(tile.x as f32, tile.y as f32) * self.cell_size
+    }
+
+    /// The world position of a tile's center.
+    pub fn to_world_center(&self, tile: TileCoord) -> Vector2 {
+        self.to_world(tile) + Vector2::new(0.5, 0.5) * self.cell_size
+    }
+}
@@ -0,0 +1,97 @@
+//! Interpolation.
+//!
+//! Network positions only arrive 10 to 15 times a second, so clients need to
+//! smooth the gaps between them. These are the building blocks for that.
+
+use super::Vector2;
+
+/// Linearly interpolates between `a` and `b`.
+///
+/// `t` is not clamped, so values outside of `[0, 1]` extrapolate.
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Linearly interpolates between two vectors.
+///
+/// `t` is not clamped, so values outside of `[0, 1]` extrapolate.
+pub fn lerp_vector(a: Vector2, b: Vector2, t: f32) -> Vector2 {
+    a + (b - a) * t
+}
+
+/// The inverse of [`lerp`]: how far `value` is between `a` and `b`.
+///
+/// Returns `0` if `a` and `b` are equal.
+pub fn inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+    if a == b {
+        0.
+    } else {
+        (value - a) / (b - a)
+    }
+}
+
+/// Hermite interpolation between `0` and `1` as `x` moves from `edge0` to
+/// `edge1`.
+///
+/// The result is clamped to `[0, 1]`.
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = inverse_lerp(edge0, edge1, x).clamp(0., 1.);
+
+    t * t * (3. - 2. * t)
+}
+
+/// Dead-reckoning for a networked position.
+///
+/// This holds the last position and velocity received from the network and
+/// predicts where the object should be until the next update arrives.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Extrapolator {
+    position: Vector2,
+    velocity: Vector2,
+    max_time: f32,
+}
+
+impl Extrapolator {
+    /// Create a new `Extrapolator`.
+    ///
+    /// `max_time` is how many seconds to keep predicting for after the last
+    /// update, after which the object is considered to have stopped. This
+    /// keeps objects from flying off into walls when packets are lost.
+    pub fn new(position: Vector2, velocity: Vector2, max_time: f32) -> Extrapolator {
+        Extrapolator {
+            position,
+            velocity,
+            max_time,
+        }
+    }
+
+    /// Updates with a new position and velocity from the network.
+    pub fn update(&mut self, position: Vector2, velocity: Vector2) {
+        self.position = position;
+        self.velocity = velocity;
+    }
+
+    /// The last position received.
+    pub fn position(&self) -> Vector2 {
+        self.position
+    }
+
+    /// The last velocity received.
+    pub fn velocity(&self) -> Vector2 {
+        self.velocity
+    }
+
+    /// Predicts the position `elapsed` seconds after the last update.
+    pub fn predict(&self, elapsed: f32) -> Vector2 {
+        self.position + self.velocity * elapsed.clamp(0., self.max_time)
+    }
+
+    /// Moves a displayed position towards the predicted position.
+    ///
+    /// Snapping straight to the prediction makes objects jitter whenever an
+    /// update corrects it, so this instead closes `smoothing` of the gap,
+    /// where `smoothing` is in `[0, 1]`.
+    pub fn smooth(&self, displayed: Vector2, elapsed: f32, smoothing: f32) -> Vector2 {
+        lerp_vector(displayed, self.predict(elapsed), smoothing.clamp(0., 1.))
+    }
+}
@@ -0,0 +1,137 @@
+//! Math types.
+//!
+//! The official client works in `f32`s, so everything here uses `f32` to avoid
+//! lossy conversions between simulation and networking.
+
+pub mod angle;
+pub mod grid;
+pub mod interp;
+pub mod quantize;
+pub mod rng;
+pub mod transform;
+
+/// A 2D vector.
+pub type Vector2 = nalgebra::Vector2<f32>;
+
+/// A 3D vector.
+pub type Vector3 = nalgebra::Vector3<f32>;
+
+/// A 3x3 matrix.
+///
+/// These are mostly 2D affine transforms, built with the helpers in
+/// [`transform`].
+pub type Matrix3 = nalgebra::Matrix3<f32>;
+
+/// An axis-aligned rectangle.
+///
+/// The rectangle is stored as its minimum and maximum corners. Camera bounds,
+/// minimap regions and UI hit areas are all described with this, and it is
+/// the bounding box of every shape in [`collide`](crate::collide). It turns
+/// into a collision [`Polygon`](crate::collide::Polygon) with `From`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    min: Vector2,
+    max: Vector2,
+}
+
+impl Rect {
+    /// Create a new `Rect` from two opposite corners.
+    ///
+    /// The corners do not need to be in any particular order.
+    pub fn new(a: Vector2, b: Vector2) -> Rect {
+        Rect {
+            min: Vector2::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Vector2::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    /// Create a new `Rect` from its minimum and maximum corners.
+    ///
+    /// Unlike [`new`](Rect::new), this can be used in constants, but the
+    /// corners have to be in order.
+    pub const fn from_min_max(min: Vector2, max: Vector2) -> Rect {
+        Rect { min, max }
+    }
+
+    /// Create a new `Rect` from its origin (minimum corner) and size.
+    pub fn from_origin_size(origin: Vector2, size: Vector2) -> Rect {
+        Rect::new(origin, origin + size)
+    }
+
+    /// Create a new `Rect` from its center and half extents.
+    pub fn from_center(center: Vector2, half_extents: Vector2) -> Rect {
+        Rect::new(center - half_extents, center + half_extents)
+    }
+
+    /// The minimum corner.
+    pub fn min(&self) -> Vector2 {
+        self.min
+    }
+
+    /// The maximum corner.
+    pub fn max(&self) -> Vector2 {
+        self.max
+    }
+
+    /// The size of the rectangle.
+    pub fn size(&self) -> Vector2 {
+        self.max - self.min
+    }
+
+    /// The center of the rectangle.
+    pub fn center(&self) -> Vector2 {
+        (self.min + self.max) / 2.
+    }
+
+    /// The four corners of the rectangle, in counter-clockwise order starting
+    /// from the minimum corner.
+    pub fn corners(&self) -> [Vector2; 4] {
+        [
+            self.min,
+            Vector2::new(self.max.x, self.min.y),
+            self.max,
+            Vector2::new(self.min.x, self.max.y),
+        ]
+    }
+
+    /// Checks if a point is inside of the rectangle.
+    ///
+    /// Points on the edge are considered inside.
+    pub fn contains(&self, point: Vector2) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    /// Checks if another rectangle is entirely inside of this rectangle.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        self.contains(other.min) && self.contains(other.max)
+    }
+
+    /// Checks if two rectangles overlap.
+    ///
+    /// Rectangles that only touch on an edge are considered intersecting.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+
+    /// The overlapping region of two rectangles, if they overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if self.intersects(other) {
+            Some(Rect {
+                min: Vector2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+                max: Vector2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min: Vector2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Vector2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+}
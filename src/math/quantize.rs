@@ -0,0 +1,67 @@
+//! Float quantization.
+//!
+//! The official client doesn't send positions as floats. Instead it maps them
+//! from a fixed range onto the full range of a `u16`, saving four bytes per
+//! vector. These helpers do the same math, including the truncation, so
+//! encoded values match the official client byte-for-byte.
+
+use super::Vector2;
+
+/// The lowest position the official client can send on either axis.
+pub const POSITION_MIN: f32 = -50.;
+/// The highest position the official client can send on either axis.
+pub const POSITION_MAX: f32 = 50.;
+
+/// Quantizes a value in `[min, max]` to a `u16`.
+///
+/// Values outside of the range are clamped.
+pub fn quantize(value: f32, min: f32, max: f32) -> u16 {
+    let t = ((value - min) / (max - min)).clamp(0., 1.);
+
+    // the official client truncates here instead of rounding
+    (t * u16::MAX as f32) as u16
+}
+
+/// Dequantizes a `u16` back to a value in `[min, max]`.
+pub fn dequantize(value: u16, min: f32, max: f32) -> f32 {
+    let t = value as f32 / u16::MAX as f32;
+
+    min + (max - min) * t
+}
+
+/// A position that is quantized on the wire.
+///
+/// Each axis is quantized with [`POSITION_MIN`] and [`POSITION_MAX`], which
+/// is how the official client sends every position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantizedVector2(pub Vector2);
+
+impl QuantizedVector2 {
+    /// Quantizes the vector into its wire representation.
+    pub fn quantize(&self) -> [u16; 2] {
+        [
+            quantize(self.0.x, POSITION_MIN, POSITION_MAX),
+            quantize(self.0.y, POSITION_MIN, POSITION_MAX),
+        ]
+    }
+
+    /// Dequantizes the wire representation of a vector.
+    pub fn dequantize(raw: [u16; 2]) -> QuantizedVector2 {
+        QuantizedVector2(Vector2::new(
+            dequantize(raw[0], POSITION_MIN, POSITION_MAX),
+            dequantize(raw[1], POSITION_MIN, POSITION_MAX),
+        ))
+    }
+}
+
+impl From<Vector2> for QuantizedVector2 {
+    fn from(vector: Vector2) -> QuantizedVector2 {
+        QuantizedVector2(vector)
+    }
+}
+
+impl From<QuantizedVector2> for Vector2 {
+    fn from(vector: QuantizedVector2) -> Vector2 {
+        vector.0
+    }
+}
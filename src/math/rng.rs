@@ -0,0 +1,222 @@
+//! Seeded random number generation.
+//!
+//! Impostor selection, task assignment and spawn offsets are all random, but
+//! servers need to be able to reproduce a game from its seed for replays and
+//! testing. This uses [xoshiro256\*\*], which is fast, small and has a
+//! well-defined output for any given seed on every platform.
+//!
+//! This is **not** cryptographically secure.
+//!
+//! [xoshiro256\*\*]: https://prng.di.unimi.it/
+
+/// A seeded xoshiro256\*\* random number generator.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Create a new `Rng` from a seed.
+    ///
+    /// The seed is expanded into the full state with SplitMix64, as
+    /// recommended by the xoshiro authors, so any seed (including zero) is
+    /// valid.
+    pub fn new(seed: u64) -> Rng {
+        let mut seed = seed;
+        let mut state = [0; 4];
+
+        for s in state.iter_mut() {
+            *s = splitmix64(&mut seed);
+        }
+
+        Rng { state }
+    }
+
+    /// Generates a random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /// Generates a random `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        // the upper bits are the better quality ones
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Generates a random `f32` in the range `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 * (1. / (1u32 << 24) as f32)
+    }
+
+    /// Generates a random `f64` in the range `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1. / (1u64 << 53) as f64)
+    }
+
+    /// Generates a random `bool` with a `chance` of being `true`.
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.next_f32() < chance
+    }
+
+    /// Generates a random number in the range `[0, bound)` without bias.
+    ///
+    /// # Panics
+    /// Panics if `bound` is zero.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "bound must be greater than zero");
+
+        // Lemire's nearly divisionless method
+        let mut m = self.next_u32() as u64 * bound as u64;
+
+        if (m as u32) < bound {
+            let threshold = bound.wrapping_neg() % bound;
+
+            while (m as u32) < threshold {
+                m = self.next_u32() as u64 * bound as u64;
+            }
+        }
+
+        (m >> 32) as u32
+    }
+
+    /// Generates a random index into a collection of `len` items.
+    ///
+    /// # Panics
+    /// Panics if `len` is zero or does not fit in a `u32`.
+    pub fn index(&mut self, len: usize) -> usize {
+        assert!(len <= u32::MAX as usize, "len is too large");
+
+        self.below(len as u32) as usize
+    }
+
+    /// Picks a random element from a slice.
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        if slice.is_empty() {
+            None
+        } else {
+            Some(&slice[self.index(slice.len())])
+        }
+    }
+
+    /// Shuffles a slice in place.
+    ///
+    /// This is a Fisher-Yates shuffle, so the result for a given seed is
+    /// stable.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.index(i + 1));
+        }
+    }
+}
+
+/// Advances a SplitMix64 generator.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitmix64_seeding() {
+        // the first outputs of SplitMix64 seeded with zero
+        assert_eq!(Rng::new(0).state, [
+            0xe220a8397b1dcdaf,
+            0x6e789e6aa1b965f4,
+            0x06c45d188009454f,
+            0xf88bb8a8724c81ec,
+        ]);
+    }
+
+    #[test]
+    fn xoshiro256_reference() {
+        // the reference outputs for a state of 1, 2, 3, 4
+        let mut rng = Rng { state: [1, 2, 3, 4] };
+        let expected = [
+            11520,
+            0,
+            1509978240,
+            1215971899390074240,
+            1216172134540287360,
+            607988272756665600,
+            16172922978634559625,
+            8476171486693032832,
+            10595114339597558777,
+            2904607092377533576,
+        ];
+
+        for &value in expected.iter() {
+            assert_eq!(rng.next_u64(), value);
+        }
+    }
+
+    #[test]
+    fn seeded_outputs() {
+        let mut rng = Rng::new(0);
+
+        assert_eq!(rng.next_u64(), 0x99ec5f36cb75f2b4);
+        assert_eq!(rng.next_u64(), 0xbf6e1f784956452a);
+        assert_eq!(rng.next_u32(), 0x1a5f849d);
+    }
+
+    #[test]
+    fn seeded_below() {
+        let mut rng = Rng::new(0);
+
+        let dice: Vec<u32> = (0..8).map(|_| rng.below(6)).collect();
+        assert_eq!(dice, [3, 4, 0, 2, 4, 5, 2, 3]);
+
+        let wide: Vec<u32> = (0..4).map(|_| rng.below(1000)).collect();
+        assert_eq!(wide, [855, 918, 114, 67]);
+    }
+
+    #[test]
+    fn below_in_bounds() {
+        let mut rng = Rng::new(37);
+
+        for &bound in [1, 2, 3, 7, 100, 1 << 31, u32::MAX].iter() {
+            for _ in 0..1000 {
+                assert!(rng.below(bound) < bound);
+            }
+        }
+    }
+
+    #[test]
+    fn below_is_uniform() {
+        let mut rng = Rng::new(37);
+        let mut counts = [0u32; 6];
+
+        for _ in 0..60000 {
+            counts[rng.below(6) as usize] += 1;
+        }
+
+        for &count in counts.iter() {
+            assert!((9000..11000).contains(&count), "{:?}", counts);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn below_zero() {
+        Rng::new(0).below(0);
+    }
+}
@@ -0,0 +1,107 @@
+//! 2D affine transforms.
+//!
+//! Transforms are [`Matrix3`]s working on homogeneous coordinates, so a
+//! rotation and a translation can be combined into one matrix by multiplying
+//! them. The rightmost matrix is applied first.
+//!
+//! A [`Transform`] is the usual case of a position, rotation and scale kept
+//! apart, which is what placing colliders authored around the origin needs.
+
+use super::angle::Angle;
+use super::{Matrix3, Vector2};
+
+/// A transform that moves points by an offset.
+pub fn translation(offset: Vector2) -> Matrix3 {
+    Matrix3::new_translation(&offset)
+}
+
+/// A transform that rotates points counter-clockwise around the origin.
+pub fn rotation(angle: Angle) -> Matrix3 {
+    Matrix3::new_rotation(angle.radians())
+}
+
+/// Transforms a point.
+///
+/// Unlike multiplying a direction, this applies the translation part of the
+/// transform too.
+pub fn apply_to_point(transform: &Matrix3, point: Vector2) -> Vector2 {
+    let point = transform * point.push(1.);
+
+    Vector2::new(point.x, point.y) / point.z
+}
+
+/// A position, rotation and uniform scale.
+///
+/// Points are scaled first, then rotated around the origin, then moved to
+/// the position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub position: Vector2,
+    pub rotation: Angle,
+    pub scale: f32,
+}
+
+impl Transform {
+    /// Create a new `Transform`.
+    pub fn new(position: Vector2, rotation: Angle, scale: f32) -> Transform {
+        Transform { position, rotation, scale }
+    }
+
+    /// The transform as a matrix.
+    pub fn to_matrix(&self) -> Matrix3 {
+        translation(self.position) * rotation(self.rotation) * Matrix3::new_scaling(self.scale)
+    }
+
+    /// Transforms a point.
+    pub fn apply_to_point(&self, point: Vector2) -> Vector2 {
+        self.position + self.apply_to_vector(point)
+    }
+
+    /// Transforms a direction, which is scaled and rotated but not moved.
+    pub fn apply_to_vector(&self, vector: Vector2) -> Vector2 {
+        let (sin, cos) = self.rotation.radians().sin_cos();
+
+        Vector2::new(vector.x * cos - vector.y * sin, vector.x * sin + vector.y * cos) * self.scale
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::new(Vector2::zeros(), Angle::ZERO, 1.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vector2, b: Vector2) {
+        assert!((a - b).norm() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn rotate_then_translate() {
+        let transform =
+            translation(Vector2::new(2., 0.)) * rotation(Angle::from_degrees(90.));
+
+        assert_near(apply_to_point(&transform, Vector2::new(1., 0.)), Vector2::new(2., 1.));
+        assert_near(
+            apply_to_point(&Matrix3::identity(), Vector2::new(3., 4.)),
+            Vector2::new(3., 4.),
+        );
+    }
+
+    #[test]
+    fn transforms() {
+        let transform = Transform::new(Vector2::new(2., 0.), Angle::from_degrees(90.), 2.);
+
+        assert_near(transform.apply_to_point(Vector2::new(1., 0.)), Vector2::new(2., 2.));
+        assert_near(transform.apply_to_vector(Vector2::new(1., 0.)), Vector2::new(0., 2.));
+        assert_near(
+            apply_to_point(&transform.to_matrix(), Vector2::new(1., 1.)),
+            transform.apply_to_point(Vector2::new(1., 1.)),
+        );
+        let identity = Transform::default();
+        assert_near(identity.apply_to_point(Vector2::new(3., 4.)), Vector2::new(3., 4.));
+    }
+}
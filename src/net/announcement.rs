@@ -0,0 +1,196 @@
+//! The announcement server.
+//!
+//! This is a separate server from matchmaking, which only sends the news shown
+//! on the main menu. The client says hello with the id of the last
+//! announcement it saw, the server answers with a few root messages, then
+//! disconnects.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::net::binary::{decode, encode, message, packed::{PackedI32, PackedU32}};
+use crate::net::connection::{self, Connection};
+use crate::net::transport::Config;
+
+/// The port of the announcement server.
+pub const PORT: u16 = 22024;
+
+pub const USE_CACHE: u8 = 0;
+pub const ANNOUNCEMENT: u8 = 1;
+pub const FREE_WEEKEND: u8 = 2;
+
+/// The payload of a hello to the announcement server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnnouncementHello {
+    /// The version of Hazel, which is always zero.
+    pub hazel_version: u8,
+    /// The game version of the client.
+    pub client_version: i32,
+    /// The id of the last announcement the client saw, or `0` for none.
+    pub last_id: i32,
+    /// The language to get the announcement in.
+    pub language: u32,
+}
+
+impl decode::Decode for AnnouncementHello {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(AnnouncementHello {
+            hazel_version: cursor.decode()?,
+            client_version: cursor.decode::<PackedI32>()?.0,
+            last_id: cursor.decode::<PackedI32>()?.0,
+            language: cursor.decode::<PackedU32>()?.0,
+        })
+    }
+}
+
+impl encode::Encode for AnnouncementHello {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.hazel_version)?;
+        cursor.encode(&PackedI32(self.client_version))?;
+        cursor.encode(&PackedI32(self.last_id))?;
+        cursor.encode(&PackedU32(self.language))
+    }
+}
+
+/// A message from the announcement server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnnouncementMessage {
+    /// The client already has the latest announcement.
+    UseCache,
+    /// A new announcement.
+    Announcement {
+        id: i32,
+        text: String,
+    },
+    /// Whether the game is free this weekend.
+    FreeWeekend(u8),
+    /// A message with a tag this crate doesn't know about.
+    Unknown {
+        tag: u8,
+        payload: Vec<u8>,
+    },
+}
+
+impl AnnouncementMessage {
+    /// The tag of the message.
+    pub fn tag(&self) -> u8 {
+        match self {
+            AnnouncementMessage::UseCache => USE_CACHE,
+            AnnouncementMessage::Announcement { .. } => ANNOUNCEMENT,
+            AnnouncementMessage::FreeWeekend(_) => FREE_WEEKEND,
+            AnnouncementMessage::Unknown { tag, .. } => *tag,
+        }
+    }
+}
+
+impl decode::Decode for AnnouncementMessage {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut message = message::Message::read(cursor)?;
+        let tag = message.tag();
+        let cursor = message.cursor();
+
+        let message = match tag {
+            USE_CACHE => AnnouncementMessage::UseCache,
+            ANNOUNCEMENT => AnnouncementMessage::Announcement {
+                id: cursor.decode::<PackedI32>()?.0,
+                text: cursor.decode()?,
+            },
+            FREE_WEEKEND => AnnouncementMessage::FreeWeekend(cursor.decode()?),
+            tag => AnnouncementMessage::Unknown {
+                tag,
+                payload: cursor.decode_rest().to_vec(),
+            },
+        };
+
+        Ok(message)
+    }
+}
+
+impl encode::Encode for AnnouncementMessage {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut message = message::MessageWriter::start(cursor, self.tag());
+
+        match self {
+            AnnouncementMessage::UseCache => (),
+            AnnouncementMessage::Announcement { id, text } => {
+                message.encode(&PackedI32(*id))?;
+                message.encode(text)?;
+            }
+            AnnouncementMessage::FreeWeekend(state) => message.encode(state)?,
+            AnnouncementMessage::Unknown { payload, .. } => message.write(payload),
+        }
+
+        message.end()
+    }
+}
+
+/// A client for the announcement server.
+///
+/// Like [`Connection`], this does no IO itself.
+pub struct AnnouncementClient {
+    connection: Connection,
+    messages: VecDeque<AnnouncementMessage>,
+}
+
+impl AnnouncementClient {
+    /// Connects to the announcement server.
+    pub fn connect(hello: &AnnouncementHello, config: Config, now: Instant) -> AnnouncementClient {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(hello).expect("hello failed to encode");
+
+        AnnouncementClient {
+            connection: Connection::connect_raw(cursor.as_slice(), config, now),
+            messages: VecDeque::new(),
+        }
+    }
+
+    /// The underlying connection.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Whether the server is done, and no more messages will arrive.
+    pub fn is_finished(&self) -> bool {
+        self.connection.state() == connection::State::Disconnected
+    }
+
+    /// Handles a datagram from the server.
+    pub fn handle_datagram(&mut self, datagram: &[u8], now: Instant) -> Result<(), decode::Error> {
+        let result = self.connection.handle_datagram(datagram, now);
+
+        while let Some(event) = self.connection.poll_event() {
+            if let connection::Event::Data { payload, .. } = event {
+                let mut cursor = decode::Cursor::new(&payload);
+
+                while cursor.remaining() > 0 {
+                    self.messages.push_back(cursor.decode()?);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// The next time [`handle_timeout`](AnnouncementClient::handle_timeout)
+    /// should be called.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        self.connection.next_timeout()
+    }
+
+    /// Resends and sends keepalives that are due.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        self.connection.handle_timeout(now);
+    }
+
+    /// Takes the next datagram to send.
+    pub fn poll_transmit(&mut self) -> Option<Vec<u8>> {
+        self.connection.poll_transmit()
+    }
+
+    /// Takes the next message from the server.
+    pub fn poll_message(&mut self) -> Option<AnnouncementMessage> {
+        self.messages.pop_front()
+    }
+}
@@ -0,0 +1,132 @@
+//! Bit-level reading and writing.
+//!
+//! Some fields are smaller than a byte, like vote states, door bitmasks and
+//! system flags. These pack them least significant bit first, and always pad
+//! out to a whole byte so they line up with the byte cursors again.
+
+use super::{decode, encode};
+
+/// Reads bits from a [`Cursor`](decode::Cursor).
+///
+/// Bytes are only pulled from the cursor as they are needed, and the rest of a
+/// partially read byte is discarded when the reader is dropped.
+pub struct BitReader<'a, 'b, T>
+where T: AsRef<[u8]> {
+    cursor: &'a mut decode::Cursor<'b, T>,
+    byte: u8,
+    // bits left in `byte`
+    left: u32,
+}
+
+impl<'a, 'b, T> BitReader<'a, 'b, T>
+where T: AsRef<[u8]> {
+    /// Create a new `BitReader`.
+    pub fn new(cursor: &'a mut decode::Cursor<'b, T>) -> BitReader<'a, 'b, T> {
+        BitReader {
+            cursor,
+            byte: 0,
+            left: 0,
+        }
+    }
+
+    /// Reads `n` bits, least significant first.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than 32.
+    pub fn read_bits(&mut self, n: u32) -> Result<u32, decode::Error> {
+        assert!(n <= 32, "cannot read more than 32 bits at once");
+
+        let mut value = 0u32;
+        let mut read = 0;
+
+        while read < n {
+            if self.left == 0 {
+                self.byte = self.cursor.decode::<u8>()?;
+                self.left = 8;
+            }
+
+            let take = (n - read).min(self.left);
+            let bits = self.byte as u32 & ((1 << take) - 1);
+
+            value |= bits << read;
+            read += take;
+
+            self.byte = (self.byte as u32 >> take) as u8;
+            self.left -= take;
+        }
+
+        Ok(value)
+    }
+
+    /// Reads a single bit.
+    pub fn read_bit(&mut self) -> Result<bool, decode::Error> {
+        self.read_bits(1).map(|b| b != 0)
+    }
+}
+
+/// Writes bits to a [`CursorMut`](encode::CursorMut).
+///
+/// A partially written byte is padded with zeroes and written out when the
+/// writer is [finished](BitWriter::finish) or dropped.
+pub struct BitWriter<'a> {
+    cursor: &'a mut encode::CursorMut,
+    byte: u8,
+    // bits used in `byte`
+    used: u32,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Create a new `BitWriter`.
+    pub fn new(cursor: &'a mut encode::CursorMut) -> BitWriter<'a> {
+        BitWriter {
+            cursor,
+            byte: 0,
+            used: 0,
+        }
+    }
+
+    /// Writes the lowest `n` bits of `value`, least significant first.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than 32.
+    pub fn write_bits(&mut self, value: u32, n: u32) {
+        assert!(n <= 32, "cannot write more than 32 bits at once");
+
+        let mut written = 0;
+
+        while written < n {
+            let take = (n - written).min(8 - self.used);
+            let bits = (value >> written) & ((1 << take) - 1);
+
+            self.byte |= (bits << self.used) as u8;
+            self.used += take;
+            written += take;
+
+            if self.used == 8 {
+                self.flush();
+            }
+        }
+    }
+
+    /// Writes a single bit.
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write_bits(bit as u32, 1);
+    }
+
+    /// Pads out the last byte and finishes writing.
+    pub fn finish(self) {}
+
+    fn flush(&mut self) {
+        if self.used > 0 {
+            self.cursor.write(&[self.byte]);
+            self.byte = 0;
+            self.used = 0;
+        }
+    }
+}
+
+impl<'a> Drop for BitWriter<'a> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
@@ -0,0 +1,117 @@
+//! Packet checksums.
+//!
+//! Hazel relies on UDP's own checksum, but bridged or custom transports might
+//! not have one. These append a checksum to the end of an encoded packet, and
+//! verify and strip it again before the packet is decoded.
+
+use super::{decode, encode};
+
+/// A checksum algorithm.
+pub trait Checksum {
+    /// The checksum itself.
+    type Output: encode::Encode + decode::Decode + PartialEq;
+
+    /// How many bytes the checksum takes on the wire.
+    const LEN: usize;
+
+    /// Computes the checksum of some bytes.
+    fn checksum(bytes: &[u8]) -> Self::Output;
+}
+
+/// The CRC-32 (IEEE 802.3) checksum, as used by zlib and Ethernet.
+pub struct Crc32;
+
+impl Crc32 {
+    /// Computes the CRC-32 of some bytes.
+    pub fn compute(bytes: &[u8]) -> u32 {
+        !bytes.iter().fold(!0u32, |crc, &b| {
+            CRC32_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+        })
+    }
+}
+
+impl Checksum for Crc32 {
+    type Output = u32;
+
+    const LEN: usize = 4;
+
+    fn checksum(bytes: &[u8]) -> u32 {
+        Crc32::compute(bytes)
+    }
+}
+
+/// A 16-bit wrapping sum of every byte.
+///
+/// This is much weaker than [`Crc32`], but cheap, and catches truncation and
+/// most single-byte corruption.
+pub struct Sum16;
+
+impl Sum16 {
+    /// Computes the sum of some bytes.
+    pub fn compute(bytes: &[u8]) -> u16 {
+        bytes.iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16))
+    }
+}
+
+impl Checksum for Sum16 {
+    type Output = u16;
+
+    const LEN: usize = 2;
+
+    fn checksum(bytes: &[u8]) -> u16 {
+        Sum16::compute(bytes)
+    }
+}
+
+/// Appends the checksum of everything written so far.
+pub fn append<C>(cursor: &mut encode::CursorMut) -> Result<(), encode::Error>
+where C: Checksum {
+    let checksum = C::checksum(cursor.as_slice());
+
+    cursor.encode(&checksum)
+}
+
+/// Verifies the checksum at the end of some bytes, returning the bytes
+/// without it.
+pub fn verify<C>(bytes: &[u8]) -> Result<&[u8], decode::Error>
+where C: Checksum {
+    if bytes.len() < C::LEN {
+        return Err(decode::Error::unexpected_end());
+    }
+
+    let (payload, checksum) = bytes.split_at(bytes.len() - C::LEN);
+
+    if C::checksum(payload) == decode::Cursor::new(checksum).decode()? {
+        Ok(payload)
+    } else {
+        Err(decode::Error::checksum_mismatch())
+    }
+}
+
+/// The lookup table for [`Crc32`], generated at compile time.
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
@@ -0,0 +1,138 @@
+//! zlib compression.
+//!
+//! Hazel doesn't compress anything, but a full game state sync or a stored
+//! replay is mostly the same few bytes over and over. A [`Compressed`] value
+//! is encoded, then deflated, and written with its lengths in front so the
+//! reader knows how much to inflate before trusting it.
+//!
+//! Both sides have to agree to use this; the official client won't.
+
+use core::convert::TryInto as _;
+
+use alloc::vec::Vec;
+
+use super::packed::PackedU32;
+use super::{decode, encode};
+
+/// The compression level used, out of 10.
+const LEVEL: u8 = 6;
+
+/// Compresses some bytes into a zlib stream.
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(bytes, LEVEL)
+}
+
+/// Decompresses a zlib stream, failing if it inflates past `max_len` bytes.
+pub fn decompress(bytes: &[u8], max_len: usize) -> Result<Vec<u8>, decode::Error> {
+    use miniz_oxide::inflate::{self, TINFLStatus};
+
+    inflate::decompress_to_vec_zlib_with_limit(bytes, max_len).map_err(|err| match err.status {
+        TINFLStatus::HasMoreOutput => decode::Error::limit_exceeded(),
+        _ => decode::Error::invalid_compression(),
+    })
+}
+
+/// A value that is compressed on the wire.
+///
+/// The value is written as its length once encoded, then the length of the
+/// compressed stream, then the stream, with the lengths packed. The decoded
+/// length is claimed against the [`Limits`](decode::Limits) of the cursor
+/// before anything is inflated, and the value is decoded from the inflated
+/// bytes with what is left of the cursor's budget.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Compressed<T>(pub T);
+
+impl<T> Compressed<T> {
+    /// Consumes the `Compressed`, returning the value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<U> decode::Decode for Compressed<U>
+where U: decode::Decode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let len = cursor.decode::<PackedU32>()?.0 as usize;
+        let compressed_len = cursor.decode::<PackedU32>()?.0 as usize;
+
+        // the inflated bytes count against what is left of the budget, and so
+        // does anything decoded out of them
+        cursor.claim_bytes(len)?;
+
+        let bytes = decompress(cursor.decode_bytes(compressed_len)?, len)?;
+
+        if bytes.len() != len {
+            return Err(decode::Error::unexpected_end());
+        }
+
+        let mut inner = cursor.nested(bytes);
+        let value = inner.decode()?;
+        inner.finish()?;
+
+        Ok(Compressed(value))
+    }
+}
+
+impl<U> encode::Encode for Compressed<U>
+where U: encode::Encode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut inner = encode::CursorMut::new();
+        inner.encode(&self.0)?;
+
+        let compressed = compress(inner.as_slice());
+
+        let (len, compressed_len) = match (inner.len().try_into(), compressed.len().try_into()) {
+            (Ok(len), Ok(compressed_len)) => (len, compressed_len),
+            _ => return Err(encode::Error),
+        };
+
+        cursor.encode(&PackedU32(len))?;
+        cursor.encode(&PackedU32(compressed_len))?;
+        cursor.write(&compressed);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+
+    use super::*;
+
+    fn limits(max_alloc: usize) -> decode::Limits {
+        decode::Limits {
+            max_alloc,
+            ..decode::Limits::default()
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = Compressed(vec![String::from("hello"); 64]);
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&value).unwrap();
+
+        let decoded = decode::parse::<Compressed<Vec<String>>>(cursor.as_slice()).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn charges_outer_budget() {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&Compressed(String::from("abcdefgh"))).unwrap();
+
+        // 9 inflated bytes and the 8 byte string, on top of what was used
+        let mut outer = decode::Cursor::with_limits(cursor.as_slice(), limits(24));
+        outer.claim_bytes(8).unwrap();
+        assert!(outer.decode::<Compressed<String>>().is_err());
+
+        let mut outer = decode::Cursor::with_limits(cursor.as_slice(), limits(25));
+        outer.claim_bytes(8).unwrap();
+        outer.decode::<Compressed<String>>().unwrap();
+        assert_eq!(outer.allocated(), 25);
+    }
+}
@@ -1,21 +1,224 @@
-use std::cmp::min;
+use core::cell::Cell;
+use core::cmp::min;
+#[cfg(feature = "std")]
+use std::io;
+
+use alloc::vec::Vec;
+
+use super::{packed::PackedU32, prefix::LengthPrefix};
+
+/// Limits on what a [`Cursor`] will decode.
+///
+/// Lengths on the wire are untrusted. Without limits, a malicious packet can
+/// claim a huge string or list and make the receiver allocate far more memory
+/// than the packet is worth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The longest string, in bytes.
+    pub max_string_len: usize,
+    /// The most items in a single collection.
+    pub max_collection_len: usize,
+    /// The most bytes that strings and collections can allocate in total.
+    pub max_alloc: usize,
+}
+
+impl Limits {
+    /// No limits at all.
+    ///
+    /// Only use this with trusted input.
+    pub fn unlimited() -> Limits {
+        Limits {
+            max_string_len: usize::MAX,
+            max_collection_len: usize::MAX,
+            max_alloc: usize::MAX,
+        }
+    }
+}
+
+impl Default for Limits {
+    /// Limits generous enough for any packet the official client sends.
+    fn default() -> Limits {
+        Limits {
+            max_string_len: u16::MAX as usize,
+            max_collection_len: 4096,
+            max_alloc: 1 << 20,
+        }
+    }
+}
 
 /// The binary cursor.
 ///
 /// The `Cursor` is designed to read a sequence of bytes sequentially.
-pub struct Cursor<T>
+///
+/// The lifetime is that of the allocation budget. A cursor made with
+/// [`Cursor::new`] owns its budget, and cursors split off of it borrow it.
+pub struct Cursor<'b, T>
 where T: AsRef<[u8]> {
     inner: T,
     cursor: usize,
+    limits: Limits,
+    allocated: Budget<'b>,
+    // the offset of this cursor in its parent, for error reporting
+    base: usize,
+}
+
+// the root cursor keeps the budget inline, and every cursor split off of it
+// borrows the same counter, so nesting can't reset it
+enum Budget<'b> {
+    Root(Cell<usize>),
+    Shared(&'b Cell<usize>),
+}
+
+impl Budget<'_> {
+    fn counter(&self) -> &Cell<usize> {
+        match self {
+            Budget::Root(counter) => counter,
+            Budget::Shared(counter) => counter,
+        }
+    }
+}
+
+impl<T> Cursor<'static, T>
+where T: AsRef<[u8]> {
+    /// Create a new binary cursor with the default [`Limits`].
+    pub fn new(inner: T) -> Cursor<'static, T> {
+        Cursor::with_limits(inner, Limits::default())
+    }
+
+    /// Create a new binary cursor with custom [`Limits`].
+    pub fn with_limits(inner: T, limits: Limits) -> Cursor<'static, T> {
+        Cursor {
+            inner,
+            cursor: 0,
+            limits,
+            allocated: Budget::Root(Cell::new(0)),
+            base: 0,
+        }
+    }
 }
 
-impl<T> Cursor<T>
+impl<'b, T> Cursor<'b, T>
 where T: AsRef<[u8]> {
-    /// Create a new binary cursor.
-    pub fn new(inner: T) -> Cursor<T> {
+    /// The limits of the cursor.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// How many bytes strings and collections have allocated so far, counting
+    /// every cursor that shares this one's budget.
+    pub fn allocated(&self) -> usize {
+        self.allocated.counter().get()
+    }
+
+    /// The position of the cursor, in bytes from the start.
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    /// How many bytes are left to read.
+    pub fn remaining(&self) -> usize {
+        self.inner.as_ref().len() - self.cursor
+    }
+
+    /// Moves the cursor to an absolute position.
+    ///
+    /// Seeking to the very end is allowed, but not past it.
+    pub fn seek(&mut self, position: usize) -> Result<(), Error> {
+        if position > self.inner.as_ref().len() {
+            Err(Error::unexpected_end())
+        } else {
+            self.cursor = position;
+            Ok(())
+        }
+    }
+
+    /// Skips over `len` bytes.
+    pub fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.decode_bytes(len).map(|_| ())
+    }
+
+    /// Splits off the next `len` bytes into their own cursor.
+    ///
+    /// This is how length-delimited messages should be read: the child cursor
+    /// can't read past the end of its message, and the parent skips over the
+    /// whole message whether the child reads all of it or not. The child
+    /// keeps the parent's limits and shares its allocation budget, so what
+    /// the child allocates counts against the parent too.
+    pub fn sub_cursor(&mut self, len: usize) -> Result<Cursor<'_, &[u8]>, Error> {
+        if len > self.remaining() {
+            return Err(Error::unexpected_end());
+        }
+
+        let start = self.cursor;
+        self.cursor += len;
+
+        Ok(Cursor {
+            inner: &self.inner.as_ref()[start..self.cursor],
+            cursor: 0,
+            limits: self.limits,
+            allocated: Budget::Shared(self.allocated.counter()),
+            base: self.base + start,
+        })
+    }
+
+    /// Create a cursor over other bytes that shares this cursor's limits and
+    /// allocation budget.
+    ///
+    /// This is for bytes that came out of this cursor's, but aren't a slice
+    /// of them, like a decompressed payload.
+    pub fn nested<U>(&self, inner: U) -> Cursor<'_, U>
+    where U: AsRef<[u8]> {
         Cursor {
             inner,
             cursor: 0,
+            limits: self.limits,
+            allocated: Budget::Shared(self.allocated.counter()),
+            base: 0,
+        }
+    }
+
+    /// Checks a string length read off the wire before allocating it.
+    ///
+    /// A string can't be longer than the bytes left, so this fails early
+    /// instead of allocating for bytes that will never arrive.
+    pub fn claim_string(&mut self, len: usize) -> Result<(), Error> {
+        if len > self.limits.max_string_len {
+            Err(Error::limit_exceeded())
+        } else if len > self.remaining() {
+            Err(Error::unexpected_end())
+        } else {
+            self.claim_alloc(len)
+        }
+    }
+
+    /// Checks a collection length read off the wire before allocating it.
+    ///
+    /// Every item takes at least a byte on the wire, so a collection can't
+    /// have more items than there are bytes left.
+    pub fn claim_collection<U>(&mut self, count: usize) -> Result<(), Error> {
+        if count > self.limits.max_collection_len {
+            Err(Error::limit_exceeded())
+        } else if count > self.remaining() {
+            Err(Error::unexpected_end())
+        } else {
+            self.claim_alloc(count.saturating_mul(core::mem::size_of::<U>()))
+        }
+    }
+
+    /// Checks the length of bytes that will be made, not read, before
+    /// allocating them, like a decompressed payload.
+    pub fn claim_bytes(&mut self, len: usize) -> Result<(), Error> {
+        self.claim_alloc(len)
+    }
+
+    fn claim_alloc(&mut self, bytes: usize) -> Result<(), Error> {
+        let allocated = self.allocated().saturating_add(bytes);
+
+        if allocated > self.limits.max_alloc {
+            Err(Error::limit_exceeded())
+        } else {
+            self.allocated.counter().set(allocated);
+            Ok(())
         }
     }
 
@@ -28,42 +231,334 @@ where T: AsRef<[u8]> {
         let inner = self.inner.as_ref();
 
         // get the end point of the buffer
-        let end = min(self.cursor + buf.len(), inner.len());
+        let end = min(self.cursor.saturating_add(buf.len()), inner.len());
 
         // get the slice
         let slice = &inner[self.cursor..end];
 
         // copy the slice
-        (&mut buf[..slice.len()]).copy_from_slice(slice);
+        buf[..slice.len()].copy_from_slice(slice);
+
+        // advance the cursor
+        self.cursor = end;
 
         // return the length
         slice.len()
     }
 
+    /// Reads `len` bytes without copying them.
+    ///
+    /// Unlike [`Cursor::read`], this fails if there aren't enough bytes left.
+    pub fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        if len > self.remaining() {
+            return Err(Error::unexpected_end());
+        }
+
+        let start = self.cursor;
+        self.cursor += len;
+
+        Ok(&self.inner.as_ref()[start..self.cursor])
+    }
+
+    /// Reads every byte left without copying them.
+    pub fn decode_rest(&mut self) -> &[u8] {
+        let start = self.cursor;
+        self.cursor = self.inner.as_ref().len();
+
+        &self.inner.as_ref()[start..]
+    }
+
+    /// Decodes a string without copying it.
+    ///
+    /// This expects the same layout as the `String` codec. The string
+    /// borrows from the underlying buffer, so it doesn't count towards the
+    /// allocation [`Limits`].
+    pub fn decode_str(&mut self) -> Result<&str, Error> {
+        self.decode_str_prefixed::<PackedU32>()
+    }
+
+    /// Decodes a string with a specific length prefix without copying it.
+    pub fn decode_str_prefixed<L>(&mut self) -> Result<&str, Error>
+    where L: LengthPrefix {
+        let len = self.decode::<L>()?.to_len();
+        let bytes = self.decode_bytes(len)?;
+
+        core::str::from_utf8(bytes).map_err(Error::utf8)
+    }
+
     /// Decode a type from the `Cursor`.
+    ///
+    /// If this fails, the error is tagged with where the value started and
+    /// what type it was, unless something more specific already did.
     pub fn decode<U>(&mut self) -> Result<U, Error> 
     where U: Decode {
-        U::decode(self)
+        let start = self.base + self.cursor;
+
+        U::decode(self).map_err(|e| e.at(start, core::any::type_name::<U>()))
+    }
+
+    /// Checks that every byte has been read.
+    pub fn finish(&self) -> Result<(), Error> {
+        match self.remaining() {
+            0 => Ok(()),
+            n => Err(Error::trailing_bytes(n)),
+        }
+    }
+
+    /// Decode a type from the `Cursor`, adding context to any error.
+    ///
+    /// The context should name what is being decoded, like a field name, so
+    /// errors read like `GameOptions → KillCooldown`.
+    pub fn decode_context<U>(&mut self, context: &'static str) -> Result<U, Error>
+    where U: Decode {
+        self.decode().map_err(|e| e.context(context))
+    }
+}
+
+impl<'a> Cursor<'_, &'a [u8]> {
+    /// Reads `len` bytes that borrow from the underlying slice, rather than
+    /// from the cursor.
+    ///
+    /// This is [`Cursor::decode_bytes`], but the bytes can outlive the
+    /// cursor.
+    pub fn decode_borrowed_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.remaining() {
+            return Err(Error::unexpected_end());
+        }
+
+        let start = self.cursor;
+        self.cursor += len;
+
+        Ok(&self.inner[start..self.cursor])
+    }
+}
+
+#[cfg(feature = "std")]
+impl Cursor<'static, Vec<u8>> {
+    /// Create a new binary cursor over everything left in a reader.
+    ///
+    /// Decoding needs random access for things like sub-cursors, so the
+    /// reader is read to the end up front. Only use this on sources that end,
+    /// like a single datagram or a decompression stream.
+    pub fn from_reader<R>(mut reader: R) -> io::Result<Cursor<'static, Vec<u8>>>
+    where R: io::Read {
+        let mut inner = Vec::new();
+        reader.read_to_end(&mut inner)?;
+
+        Ok(Cursor::new(inner))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> io::Read for Cursor<'_, T>
+where T: AsRef<[u8]> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(Cursor::read(self, buf))
     }
 }
 
+/// Decodes a type from a whole buffer, with the default [`Limits`].
+///
+/// This is the entry point for untrusted input, and what fuzzers should
+/// target: no input makes it panic or allocate past the limits, and bytes
+/// left over after the value are an error rather than silently ignored.
+pub fn parse<U>(bytes: &[u8]) -> Result<U, Error>
+where U: Decode {
+    parse_with_limits(bytes, Limits::default())
+}
+
+/// Decodes a type from a whole buffer, with custom [`Limits`].
+pub fn parse_with_limits<U>(bytes: &[u8], limits: Limits) -> Result<U, Error>
+where U: Decode {
+    let mut cursor = Cursor::with_limits(bytes, limits);
+    let value = cursor.decode()?;
+
+    cursor.finish()?;
+
+    Ok(value)
+}
+
 /// An error that can occur during decoding.
-pub enum Error {
+///
+/// Besides what went wrong, this tracks where it went wrong: the byte offset
+/// and type of the innermost value that failed, and a stack of context added
+/// with [`Cursor::decode_context`] on the way out.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    offset: Option<usize>,
+    expected: Option<&'static str>,
+    // innermost first
+    context: Vec<&'static str>,
+}
+
+/// What went wrong during decoding.
+#[derive(Debug)]
+pub enum ErrorKind {
     /// An unexpected end to the bytes was reached.
     UnexpectedEnd,
     /// A Utf-8 error was found.
-    Utf8(std::str::Utf8Error),
+    Utf8(core::str::Utf8Error),
+    /// A value was too large for its type.
+    Overflow,
+    /// A `char` was not a valid Unicode scalar value.
+    InvalidChar(u32),
+    /// A length went over the [`Limits`] of the cursor.
+    LimitExceeded,
+    /// A checksum didn't match its data.
+    ChecksumMismatch,
+    /// Bytes were left over after decoding.
+    TrailingBytes(usize),
+    /// A tag or discriminant byte didn't match any known variant.
+    InvalidTag(u8),
+    /// Compressed data was corrupt.
+    InvalidCompression,
 }
 
 impl Error {
+    fn new(kind: ErrorKind) -> Error {
+        Error {
+            kind,
+            offset: None,
+            expected: None,
+            context: Vec::new(),
+        }
+    }
+
     /// Create a new unexpected end error.
     pub fn unexpected_end() -> Error {
-        Error::UnexpectedEnd
+        Error::new(ErrorKind::UnexpectedEnd)
     }
 
     /// Create a new Utf8 error.
-    pub fn utf8(error: std::str::Utf8Error) -> Error {
-        Error::Utf8(error)
+    pub fn utf8(error: core::str::Utf8Error) -> Error {
+        Error::new(ErrorKind::Utf8(error))
+    }
+
+    /// Create a new overflow error.
+    pub fn overflow() -> Error {
+        Error::new(ErrorKind::Overflow)
+    }
+
+    /// Create a new invalid char error.
+    pub fn invalid_char(code: u32) -> Error {
+        Error::new(ErrorKind::InvalidChar(code))
+    }
+
+    /// Create a new limit exceeded error.
+    pub fn limit_exceeded() -> Error {
+        Error::new(ErrorKind::LimitExceeded)
+    }
+
+    /// Create a new checksum mismatch error.
+    pub fn checksum_mismatch() -> Error {
+        Error::new(ErrorKind::ChecksumMismatch)
+    }
+
+    /// Create a new trailing bytes error.
+    pub fn trailing_bytes(count: usize) -> Error {
+        Error::new(ErrorKind::TrailingBytes(count))
+    }
+
+    /// Create a new invalid tag error.
+    pub fn invalid_tag(tag: u8) -> Error {
+        Error::new(ErrorKind::InvalidTag(tag))
+    }
+
+    /// Create a new invalid compression error.
+    pub fn invalid_compression() -> Error {
+        Error::new(ErrorKind::InvalidCompression)
+    }
+
+    /// Adds context to the error.
+    pub fn context(mut self, context: &'static str) -> Error {
+        self.context.push(context);
+        self
+    }
+
+    /// Tags the error with the offset and type being decoded, if it isn't
+    /// already.
+    fn at(mut self, offset: usize, expected: &'static str) -> Error {
+        if self.offset.is_none() {
+            self.offset = Some(offset);
+            self.expected = Some(expected);
+        }
+
+        self
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The byte offset of the value that failed to decode, from the start of
+    /// the outermost cursor.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// The name of the type that failed to decode.
+    pub fn expected(&self) -> Option<&'static str> {
+        self.expected
+    }
+
+    /// The context of the error, outermost first.
+    pub fn context_stack(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.context.iter().rev().copied()
+    }
+}
+
+impl core::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEnd => f.write_str("unexpected end of input"),
+            ErrorKind::Utf8(e) => write!(f, "invalid utf-8: {}", e),
+            ErrorKind::Overflow => f.write_str("value overflowed its type"),
+            ErrorKind::InvalidChar(code) => write!(f, "invalid char {:#x}", code),
+            ErrorKind::LimitExceeded => f.write_str("decode limit exceeded"),
+            ErrorKind::ChecksumMismatch => f.write_str("checksum mismatch"),
+            ErrorKind::TrailingBytes(n) => write!(f, "{} trailing bytes", n),
+            ErrorKind::InvalidTag(tag) => write!(f, "invalid tag {:#04x}", tag),
+            ErrorKind::InvalidCompression => f.write_str("invalid compressed data"),
+        }
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        if let Some(offset) = self.offset {
+            write!(f, " at byte {}", offset)?;
+        }
+
+        if let Some(expected) = self.expected {
+            write!(f, " while decoding `{}`", expected)?;
+        }
+
+        let mut context = self.context_stack();
+
+        if let Some(first) = context.next() {
+            write!(f, " ({}", first)?;
+
+            for next in context {
+                write!(f, " → {}", next)?;
+            }
+
+            f.write_str(")")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Utf8(e) => Some(e),
+            _ => None,
+        }
     }
 }
 
@@ -73,3 +568,71 @@ pub trait Decode: Sized {
     fn decode<T>(cursor: &mut Cursor<T>) -> Result<Self, Error>
     where T: AsRef<[u8]>;
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::*;
+    use crate::net::binary::message::Message;
+
+    // two 8 byte strings, each in their own message
+    const NESTED: &[u8] = &[
+        9, 0, 1, 8, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h',
+        9, 0, 1, 8, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h',
+    ];
+
+    fn limits(max_alloc: usize) -> Limits {
+        Limits {
+            max_alloc,
+            ..Limits::default()
+        }
+    }
+
+    #[test]
+    fn sub_cursor_shares_budget() {
+        let mut cursor = Cursor::with_limits(NESTED, limits(10));
+
+        let first = Message::read(&mut cursor).unwrap().into_cursor().decode::<String>();
+        assert_eq!(first.unwrap(), "abcdefgh");
+        assert_eq!(cursor.allocated(), 8);
+
+        let second = Message::read(&mut cursor).unwrap().into_cursor().decode::<String>();
+        assert!(matches!(second.unwrap_err().kind(), ErrorKind::LimitExceeded));
+    }
+
+    #[test]
+    fn nested_matches_flat() {
+        let flat: Vec<u8> = NESTED.chunks(12).flat_map(|message| message[3..].to_vec()).collect();
+
+        let mut cursor = Cursor::with_limits(&flat[..], limits(10));
+        cursor.decode::<String>().unwrap();
+        assert!(cursor.decode::<String>().is_err());
+
+        let mut cursor = Cursor::with_limits(NESTED, limits(16));
+        for _ in 0..2 {
+            Message::read(&mut cursor).unwrap().into_cursor().decode::<String>().unwrap();
+        }
+        assert_eq!(cursor.allocated(), 16);
+    }
+
+    #[test]
+    fn nested_cursor_shares_budget() {
+        let mut cursor = Cursor::with_limits(&[][..], limits(10));
+        cursor.claim_bytes(4).unwrap();
+
+        let mut nested = cursor.nested(&NESTED[3..12]);
+        assert!(nested.decode::<String>().is_err());
+        assert_eq!(cursor.allocated(), 4);
+    }
+
+    #[test]
+    fn collection_longer_than_input() {
+        // claims 1000 items with two bytes left
+        let mut cursor = Cursor::new(&[0xe8, 0x07, 1, 2][..]);
+
+        let error = cursor.decode::<Vec<u32>>().unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::UnexpectedEnd));
+        assert_eq!(cursor.allocated(), 0);
+    }
+}
@@ -1,4 +1,14 @@
+use alloc::vec::Vec;
+
+use super::pool::BufferPool;
+
 /// A newtype struct that encapsulates a `Vec<u8>`.
+///
+/// Servers encode a lot of packets, so instead of allocating a new `Vec` for
+/// each one, a `CursorMut` can be [cleared](CursorMut::clear) and reused,
+/// built on top of an existing buffer with [`CursorMut::from_vec`], or take
+/// one from a [`BufferPool`] with [`CursorMut::from_pool`].
+#[derive(Default)]
 pub struct CursorMut {
     inner: Vec<u8>,
 }
@@ -11,11 +21,67 @@ impl CursorMut {
         }
     }
 
+    /// Create a new, empty `CursorMut` with room for `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> CursorMut {
+        CursorMut {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Create a new `CursorMut` that writes into an existing buffer.
+    ///
+    /// The buffer is cleared, but keeps its capacity.
+    pub fn from_vec(mut inner: Vec<u8>) -> CursorMut {
+        inner.clear();
+
+        CursorMut { inner }
+    }
+
+    /// Create a new `CursorMut` that writes into a buffer from a pool.
+    ///
+    /// Give the buffer back with [`BufferPool::release`] once it is done with.
+    pub fn from_pool(pool: &mut BufferPool) -> CursorMut {
+        CursorMut::from_vec(pool.acquire())
+    }
+
+    /// The bytes written so far.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.inner
+    }
+
+    /// Clears the written bytes, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Consumes the `CursorMut`, returning the buffer.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.inner
+    }
+
     /// Write a series of bytes to the `CursorMut`.
     pub fn write(&mut self, buf: &[u8]) {
         self.inner.extend(buf);
     }
 
+    /// How many bytes have been written.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Checks if nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Overwrites already written bytes, starting at `offset`.
+    ///
+    /// # Panics
+    /// Panics if the bytes don't fit inside what has been written so far.
+    pub fn patch(&mut self, offset: usize, buf: &[u8]) {
+        self.inner[offset..offset + buf.len()].copy_from_slice(buf);
+    }
+
     /// Encodes a type into the `CursorMut`.
     pub fn encode<T>(&mut self, ty: &T) -> Result<(), Error> 
     where T: Encode + ?Sized {
@@ -23,19 +89,46 @@ impl CursorMut {
     }
 }
 
-impl Into<Vec<u8>> for CursorMut {
-    fn into(self) -> Vec<u8> {
-        self.inner
+impl From<CursorMut> for Vec<u8> {
+    fn from(cursor: CursorMut) -> Vec<u8> {
+        cursor.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for CursorMut {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        CursorMut::write(self, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRef<[u8]> for CursorMut {
+    fn as_ref(&self) -> &[u8] {
+        &self.inner
     }
 }
 
 /// An error type.
 ///
 /// There isn't really anything that can go wrong, as bytes are a superset of
-/// Rust types in this sense. This is only here for easy additions if it is
-/// needed.
+/// Rust types in this sense. The only thing that can fail is a length that is
+/// too large for its prefix.
+#[derive(Debug)]
 pub struct Error;
 
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("value too large to encode")
+    }
+}
+
+impl core::error::Error for Error {}
+
 /// A type that can be encoded to a [`CursorMut`].
 pub trait Encode {
     fn encode(&self, cursor: &mut CursorMut) -> Result<(), Error>;
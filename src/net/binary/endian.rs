@@ -0,0 +1,48 @@
+//! Big-endian values.
+//!
+//! Almost everything on the wire is little-endian, which is what the plain
+//! numeric codecs use. The few fields that aren't can be wrapped in
+//! [`BigEndian`].
+
+use super::{decode, encode};
+
+/// A number that is big-endian on the wire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BigEndian<T>(pub T);
+
+macro_rules! impl_big_endian {
+    ($N:ty) => {
+        impl decode::Decode for BigEndian<$N> {
+            fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+            where T: AsRef<[u8]> {
+                let mut buf = [0; ::core::mem::size_of::<$N>()];
+
+                if cursor.read(&mut buf) < ::core::mem::size_of::<$N>() {
+                    Err(decode::Error::unexpected_end())
+                } else {
+                    Ok(BigEndian(<$N>::from_be_bytes(buf)))
+                }
+            }
+        }
+
+        impl encode::Encode for BigEndian<$N> {
+            fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+                cursor.write(&self.0.to_be_bytes());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl_big_endian!(u16);
+impl_big_endian!(u32);
+impl_big_endian!(u64);
+impl_big_endian!(u128);
+
+impl_big_endian!(i16);
+impl_big_endian!(i32);
+impl_big_endian!(i64);
+impl_big_endian!(i128);
+
+impl_big_endian!(f32);
+impl_big_endian!(f64);
@@ -0,0 +1,126 @@
+//! Hazel message framing.
+//!
+//! Almost everything Hazel sends is wrapped in a message: a `u16` length, a
+//! `u8` tag saying what kind of message it is, and then `length` bytes of
+//! payload. Messages nest, so a GameData message is a message full of more
+//! messages.
+//!
+//! The length isn't known until the payload has been written, so
+//! [`MessageWriter`] writes a placeholder and back-patches it when the message
+//! is closed.
+
+use core::ops::{Deref, DerefMut};
+
+use super::{decode, encode};
+
+/// The size of the message header.
+const HEADER_LEN: usize = 3;
+
+/// A message being read.
+pub struct Message<'a> {
+    tag: u8,
+    cursor: decode::Cursor<'a, &'a [u8]>,
+}
+
+impl<'a> Message<'a> {
+    /// Reads the next message from a cursor.
+    ///
+    /// The parent cursor skips over the whole message, no matter how much of
+    /// the payload is read.
+    pub fn read<T>(cursor: &'a mut decode::Cursor<T>) -> Result<Message<'a>, decode::Error>
+    where T: AsRef<[u8]> {
+        let len = cursor.decode::<u16>()? as usize;
+        let tag = cursor.decode::<u8>()?;
+
+        Ok(Message {
+            tag,
+            cursor: cursor.sub_cursor(len)?,
+        })
+    }
+
+    /// The tag of the message.
+    pub fn tag(&self) -> u8 {
+        self.tag
+    }
+
+    /// The payload of the message.
+    pub fn cursor(&mut self) -> &mut decode::Cursor<'a, &'a [u8]> {
+        &mut self.cursor
+    }
+
+    /// Consumes the message, returning the payload.
+    pub fn into_cursor(self) -> decode::Cursor<'a, &'a [u8]> {
+        self.cursor
+    }
+}
+
+/// A message being written.
+///
+/// This dereferences to the [`CursorMut`](encode::CursorMut) it writes to,
+/// so the payload, including nested messages, is written through it as
+/// usual.
+pub struct MessageWriter<'a> {
+    cursor: &'a mut encode::CursorMut,
+    start: usize,
+    closed: bool,
+}
+
+impl<'a> MessageWriter<'a> {
+    /// Starts writing a message with a tag.
+    pub fn start(cursor: &'a mut encode::CursorMut, tag: u8) -> MessageWriter<'a> {
+        let start = cursor.len();
+
+        // the length is patched in later
+        cursor.write(&[0, 0, tag]);
+
+        MessageWriter {
+            cursor,
+            start,
+            closed: false,
+        }
+    }
+
+    /// Closes the message, writing its length.
+    ///
+    /// This fails if the payload is too long for a `u16`. Dropping the writer
+    /// also closes the message, but can't report this.
+    pub fn end(mut self) -> Result<(), encode::Error> {
+        self.close()
+    }
+
+    fn close(&mut self) -> Result<(), encode::Error> {
+        self.closed = true;
+
+        let len = self.cursor.len() - self.start - HEADER_LEN;
+
+        if len > u16::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        self.cursor.patch(self.start, &(len as u16).to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl<'a> Deref for MessageWriter<'a> {
+    type Target = encode::CursorMut;
+
+    fn deref(&self) -> &encode::CursorMut {
+        self.cursor
+    }
+}
+
+impl<'a> DerefMut for MessageWriter<'a> {
+    fn deref_mut(&mut self) -> &mut encode::CursorMut {
+        self.cursor
+    }
+}
+
+impl<'a> Drop for MessageWriter<'a> {
+    fn drop(&mut self) {
+        if !self.closed {
+            let _ = self.close();
+        }
+    }
+}
@@ -1,14 +1,28 @@
 pub mod encode;
+pub mod bits;
+pub mod checksum;
+#[cfg(feature = "compress")]
+pub mod compress;
 pub mod decode;
+pub mod endian;
+pub mod message;
+pub mod packed;
+pub mod pool;
+pub mod prefix;
+#[cfg(feature = "serde")]
+pub mod serde;
+
+#[cfg(feature = "derive")]
+pub use among_us_derive::{Decode, Encode};
 
 macro_rules! impl_num_decode {
     ($N:ty) => {
         impl decode::Decode for $N {
             fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error> 
             where T: AsRef<[u8]> {
-                let mut buf = [0; ::std::mem::size_of::<$N>()];
+                let mut buf = [0; ::core::mem::size_of::<$N>()];
 
-                if cursor.read(&mut buf) < ::std::mem::size_of::<$N>() {
+                if cursor.read(&mut buf) < ::core::mem::size_of::<$N>() {
                     Err(decode::Error::unexpected_end())
                 } else {
                     Ok(<$N>::from_le_bytes(buf))
@@ -30,6 +44,9 @@ impl_num_decode!(i32);
 impl_num_decode!(i64);
 impl_num_decode!(i128);
 
+impl_num_decode!(f32);
+impl_num_decode!(f64);
+
 macro_rules! impl_num_encode {
     ($N:ty) => {
         impl encode::Encode for $N {
@@ -53,37 +70,439 @@ impl_num_encode!(i32);
 impl_num_encode!(i64);
 impl_num_encode!(i128);
 
-use std::iter::FromIterator as _;
+impl_num_encode!(f32);
+impl_num_encode!(f64);
+
+// bools are a single byte, and like Hazel, anything but zero is true
+impl decode::Decode for bool {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode::<u8>().map(|b| b != 0)
+    }
+}
+
+impl encode::Encode for bool {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&(*self as u8))
+    }
+}
+
+// chars are sent as their u32 code point
+impl decode::Decode for char {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let code = cursor.decode::<u32>()?;
 
-use std::convert::TryInto as _;
+        core::char::from_u32(code).ok_or_else(|| decode::Error::invalid_char(code))
+    }
+}
 
-// TODO: fix a memory allocation security flaw here. It is possible to tell
-// clients to allocate 65535 bytes in memory, which isn't too much of a problem,
-// but with many, many packets, this could easily overflow memory.
+impl encode::Encode for char {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&(*self as u32))
+    }
+}
+
+use core::convert::TryInto as _;
+use core::mem::{ManuallyDrop, MaybeUninit};
+
+use alloc::{string::String, vec::Vec};
+
+// strings are prefixed with their length in bytes, packed like Hazel does.
+// messages that use another prefix can use the `prefix` helpers instead.
 impl decode::Decode for String {
     fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error> 
     where T: AsRef<[u8]> {
-        let count = cursor.decode::<u16>()? as usize;
-        let mut buf = Vec::from_iter((0..count).map(|_| 0));
+        prefix::decode_prefixed::<packed::PackedU32, String, T>(cursor)
+    }
+}
 
-        if cursor.read(&mut buf[..]) < count {
-            Err(decode::Error::unexpected_end())
-        } else {
-            String::from_utf8(buf).map_err(|e| decode::Error::utf8(e.utf8_error()))
-        }
+impl encode::Encode for str {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        prefix::encode_str::<packed::PackedU32>(cursor, self)
     }
 }
 
 impl encode::Encode for String {
     fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
-        let count: u16 = match self.len().try_into() {
+        cursor.encode(self.as_str())
+    }
+}
+
+impl<U> encode::Encode for &U
+where U: encode::Encode + ?Sized {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        (**self).encode(cursor)
+    }
+}
+
+impl<'a, U> decode::Decode for alloc::borrow::Cow<'a, U>
+where U: alloc::borrow::ToOwned + ?Sized, U::Owned: decode::Decode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode().map(alloc::borrow::Cow::Owned)
+    }
+}
+
+impl<'a, U> encode::Encode for alloc::borrow::Cow<'a, U>
+where U: alloc::borrow::ToOwned + encode::Encode + ?Sized {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&**self)
+    }
+}
+
+#[cfg(feature = "std")]
+macro_rules! impl_vector_codec {
+    ($V:ty, $($field:ident),+) => {
+        impl decode::Decode for $V {
+            fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+            where T: AsRef<[u8]> {
+                $(let $field = cursor.decode::<f32>()?;)+
+
+                Ok(<$V>::new($($field),+))
+            }
+        }
+
+        impl encode::Encode for $V {
+            fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+                $(cursor.encode(&self.$field)?;)+
+                Ok(())
+            }
+        }
+    }
+}
+
+// vectors are sent as their f32 components, in order
+#[cfg(feature = "std")]
+impl_vector_codec!(crate::math::Vector2, x, y);
+#[cfg(feature = "std")]
+impl_vector_codec!(crate::math::Vector3, x, y, z);
+
+#[cfg(feature = "std")]
+impl decode::Decode for crate::math::quantize::QuantizedVector2 {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let x = cursor.decode::<u16>()?;
+        let y = cursor.decode::<u16>()?;
+
+        Ok(Self::dequantize([x, y]))
+    }
+}
+
+#[cfg(feature = "std")]
+impl encode::Encode for crate::math::quantize::QuantizedVector2 {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let [x, y] = self.quantize();
+
+        cursor.encode(&x)?;
+        cursor.encode(&y)
+    }
+}
+
+// lists are prefixed with their length, packed like Hazel does
+impl<U> decode::Decode for Vec<U>
+where U: decode::Decode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let count = cursor.decode::<packed::PackedU32>()?.0 as usize;
+        cursor.claim_collection::<U>(count)?;
+
+        // even within the limits, grow as items actually decode instead of
+        // allocating it all up front
+        let mut items = Vec::new();
+
+        for _ in 0..count {
+            items.push(cursor.decode()?);
+        }
+
+        Ok(items)
+    }
+}
+
+impl<U> encode::Encode for [U]
+where U: encode::Encode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let count: u32 = match self.len().try_into() {
             Ok(count) => count,
             Err(_) => return Err(encode::Error),
         };
 
-        cursor.encode(&count)?;
-        cursor.write(self.as_bytes());
+        cursor.encode(&packed::PackedU32(count))?;
+
+        for item in self {
+            cursor.encode(item)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<U> encode::Encode for Vec<U>
+where U: encode::Encode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self[..])
+    }
+}
+
+// fixed-size arrays have no length prefix, and decode in place
+impl<U, const N: usize> decode::Decode for [U; N]
+where U: decode::Decode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut items = PartialArray::new();
+
+        while items.len < N {
+            items.push(cursor.decode()?);
+        }
+
+        Ok(items.finish())
+    }
+}
+
+/// An array that is only filled in up to `len`.
+///
+/// If decoding fails partway, dropping it drops the items decoded so far.
+struct PartialArray<U, const N: usize> {
+    items: [MaybeUninit<U>; N],
+    len: usize,
+}
+
+impl<U, const N: usize> PartialArray<U, N> {
+    fn new() -> PartialArray<U, N> {
+        PartialArray {
+            items: core::array::from_fn(|_| MaybeUninit::uninit()),
+            len: 0,
+        }
+    }
+
+    /// Adds an item. The array must not be full.
+    fn push(&mut self, item: U) {
+        self.items[self.len].write(item);
+        self.len += 1;
+    }
+
+    /// Takes the items out of a full array.
+    fn finish(self) -> [U; N] {
+        debug_assert_eq!(self.len, N);
+
+        let this = ManuallyDrop::new(self);
+
+        // SAFETY: all `N` items are initialized, `MaybeUninit<U>` has the
+        // same layout as `U`, and `this` is never dropped, so the items are
+        // only ever owned by the returned array.
+        unsafe { (&this.items as *const [MaybeUninit<U>; N]).cast::<[U; N]>().read() }
+    }
+}
+
+impl<U, const N: usize> Drop for PartialArray<U, N> {
+    fn drop(&mut self) {
+        for item in &mut self.items[..self.len] {
+            // SAFETY: the first `len` items are initialized
+            unsafe { item.assume_init_drop() }
+        }
+    }
+}
+
+impl<U, const N: usize> encode::Encode for [U; N]
+where U: encode::Encode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        for item in self {
+            cursor.encode(item)?;
+        }
 
         Ok(())
     }
 }
+
+// options are prefixed with a presence byte
+impl<U> decode::Decode for Option<U>
+where U: decode::Decode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        if cursor.decode::<bool>()? {
+            cursor.decode().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<U> encode::Encode for Option<U>
+where U: encode::Encode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            Some(value) => {
+                cursor.encode(&true)?;
+                cursor.encode(value)
+            }
+            None => cursor.encode(&false),
+        }
+    }
+}
+
+macro_rules! impl_tuple_codec {
+    ($($U:ident),+) => {
+        impl<$($U),+> decode::Decode for ($($U,)+)
+        where $($U: decode::Decode),+ {
+            fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+            where T: AsRef<[u8]> {
+                Ok(($(cursor.decode::<$U>()?,)+))
+            }
+        }
+
+        impl<$($U),+> encode::Encode for ($($U,)+)
+        where $($U: encode::Encode),+ {
+            #[allow(non_snake_case)]
+            fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+                let ($($U,)+) = self;
+                $(cursor.encode($U)?;)+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl_tuple_codec!(A);
+impl_tuple_codec!(A, B);
+impl_tuple_codec!(A, B, C);
+impl_tuple_codec!(A, B, C, D);
+impl_tuple_codec!(A, B, C, D, E);
+impl_tuple_codec!(A, B, C, D, E, F);
+impl_tuple_codec!(A, B, C, D, E, F, G);
+impl_tuple_codec!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use core::fmt::Debug;
+
+    use super::*;
+    use crate::net::binary::decode::parse;
+
+    fn round_trip<T>(value: T, bytes: &[u8])
+    where T: encode::Encode + decode::Decode + PartialEq + Debug {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&value).unwrap();
+
+        assert_eq!(cursor.as_slice(), bytes);
+        assert_eq!(parse::<T>(bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn arrays() {
+        round_trip([1u16, 2, 3], &[0x01, 0x00, 0x02, 0x00, 0x03, 0x00]);
+        round_trip([String::from("a"), String::from("bc")], &[0x01, b'a', 0x02, b'b', b'c']);
+        round_trip([0u8; 0], &[]);
+    }
+
+    #[test]
+    fn array_too_short() {
+        assert!(parse::<[u16; 3]>(&[0x01, 0x00, 0x02, 0x00]).is_err());
+        assert!(parse::<[String; 2]>(&[0x01, b'a', 0x02, b'b']).is_err());
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use alloc::vec;
+        use alloc::vec::Vec;
+
+        use super::*;
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Named {
+            a: u8,
+            b: u16,
+            name: String,
+        }
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Tuple(u8, i32);
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Unit;
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Generic<T> {
+            first: T,
+            rest: Vec<T>,
+        }
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Attributes {
+            #[binary(skip)]
+            cached: u32,
+            #[binary(packed)]
+            id: u32,
+            #[binary(packed)]
+            delta: i32,
+            #[binary(big_endian)]
+            port: u16,
+            #[binary(len = "u8")]
+            short: Vec<u8>,
+            #[binary(len = "u16")]
+            name: String,
+        }
+
+        #[test]
+        fn derive_named() {
+            let value = Named { a: 1, b: 0x0302, name: "hi".into() };
+
+            round_trip(value, &[0x01, 0x02, 0x03, 0x02, b'h', b'i']);
+        }
+
+        #[test]
+        fn derive_tuple() {
+            round_trip(Tuple(7, -2), &[0x07, 0xfe, 0xff, 0xff, 0xff]);
+        }
+
+        #[test]
+        fn derive_unit() {
+            round_trip(Unit, &[]);
+        }
+
+        #[test]
+        fn derive_generic() {
+            let value = Generic { first: 1u16, rest: vec![2, 3] };
+
+            round_trip(value, &[0x01, 0x00, 0x02, 0x02, 0x00, 0x03, 0x00]);
+        }
+
+        #[test]
+        fn derive_attributes() {
+            let value = Attributes {
+                cached: 0,
+                id: 300,
+                delta: -1,
+                port: 22023,
+                short: vec![9],
+                name: "a".into(),
+            };
+
+            round_trip(value, &[
+                0xac, 0x02, // id, packed
+                0xff, 0xff, 0xff, 0xff, 0x0f, // delta, packed
+                0x56, 0x07, // port, big endian
+                0x01, 0x09, // short, u8 length
+                0x01, 0x00, b'a', // name, u16 length
+            ]);
+        }
+
+        #[test]
+        fn derive_skip_decodes_default() {
+            let value = Attributes {
+                cached: 5,
+                id: 0,
+                delta: 0,
+                port: 0,
+                short: Vec::new(),
+                name: String::new(),
+            };
+
+            let mut cursor = encode::CursorMut::new();
+            cursor.encode(&value).unwrap();
+
+            let decoded = parse::<Attributes>(cursor.as_slice()).unwrap();
+            assert_eq!(decoded.cached, 0);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Packed integers.
+//!
+//! Hazel writes lengths and net IDs as variable-length integers: seven bits at
+//! a time, least significant group first, with the high bit of each byte set
+//! if there are more bytes to come. Small values, which are by far the most
+//! common, only take a single byte.
+
+use super::{decode, encode};
+
+/// The most bytes a packed `u32` can take.
+const MAX_LEN: usize = 5;
+
+/// A `u32` that is packed on the wire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedU32(pub u32);
+
+/// An `i32` that is packed on the wire.
+///
+/// Hazel packs these by reinterpreting them as a `u32`, so negative values
+/// always take the full five bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedI32(pub i32);
+
+impl decode::Decode for PackedU32 {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut value = 0u32;
+
+        for i in 0..MAX_LEN {
+            let byte = cursor.decode::<u8>()?;
+            let bits = (byte & 0x7f) as u32;
+
+            // the last byte can only hold the top four bits
+            if i == MAX_LEN - 1 && bits > 0x0f {
+                return Err(decode::Error::overflow());
+            }
+
+            value |= bits << (i * 7);
+
+            if byte & 0x80 == 0 {
+                return Ok(PackedU32(value));
+            }
+        }
+
+        Err(decode::Error::overflow())
+    }
+}
+
+impl encode::Encode for PackedU32 {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut value = self.0;
+
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                cursor.write(&[byte]);
+                return Ok(());
+            } else {
+                cursor.write(&[byte | 0x80]);
+            }
+        }
+    }
+}
+
+impl decode::Decode for PackedI32 {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode::<PackedU32>().map(|PackedU32(v)| PackedI32(v as i32))
+    }
+}
+
+impl encode::Encode for PackedI32 {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&PackedU32(self.0 as u32))
+    }
+}
+
+impl From<u32> for PackedU32 {
+    fn from(value: u32) -> PackedU32 {
+        PackedU32(value)
+    }
+}
+
+impl From<PackedU32> for u32 {
+    fn from(value: PackedU32) -> u32 {
+        value.0
+    }
+}
+
+impl From<i32> for PackedI32 {
+    fn from(value: i32) -> PackedI32 {
+        PackedI32(value)
+    }
+}
+
+impl From<PackedI32> for i32 {
+    fn from(value: PackedI32) -> i32 {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::net::binary::decode::{parse, ErrorKind};
+
+    fn encode<T>(value: &T) -> Vec<u8>
+    where T: encode::Encode {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(value).unwrap();
+        cursor.as_slice().to_vec()
+    }
+
+    #[test]
+    fn u32_round_trip() {
+        let cases: &[(u32, &[u8])] = &[
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (16383, &[0xff, 0x7f]),
+            (16384, &[0x80, 0x80, 0x01]),
+            (u32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ];
+
+        for &(value, bytes) in cases {
+            assert_eq!(encode(&PackedU32(value)), bytes, "encoding {}", value);
+            assert_eq!(parse::<PackedU32>(bytes).unwrap(), PackedU32(value));
+        }
+    }
+
+    #[test]
+    fn i32_round_trip() {
+        let cases: &[(i32, &[u8])] = &[
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (i32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+            (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+        ];
+
+        for &(value, bytes) in cases {
+            assert_eq!(encode(&PackedI32(value)), bytes, "encoding {}", value);
+            assert_eq!(parse::<PackedI32>(bytes).unwrap(), PackedI32(value));
+        }
+    }
+
+    #[test]
+    fn rejects_overlong() {
+        // six bytes, which no u32 needs
+        let err = parse::<PackedU32>(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Overflow));
+
+        // five bytes, but more than 32 bits
+        let err = parse::<PackedU32>(&[0xff, 0xff, 0xff, 0xff, 0x1f]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Overflow));
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        for bytes in [&[][..], &[0x80], &[0xff, 0xff, 0xff, 0xff]] {
+            let err = parse::<PackedU32>(bytes).unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::UnexpectedEnd), "{:?}", bytes);
+        }
+    }
+}
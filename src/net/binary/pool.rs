@@ -0,0 +1,72 @@
+//! Reusing buffers.
+//!
+//! A server encodes hundreds of datagrams a tick, and most are about the same
+//! size. A [`BufferPool`] keeps the buffers of datagrams that were already
+//! sent, so the next ones can be encoded without allocating.
+
+use alloc::vec::Vec;
+
+/// A pool of byte buffers of about the same capacity.
+pub struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+    capacity: usize,
+    max_buffers: usize,
+    allocations: u64,
+}
+
+impl BufferPool {
+    /// Create a new, empty `BufferPool` of buffers with room for `capacity`
+    /// bytes, keeping at most `max_buffers` spare.
+    pub fn new(capacity: usize, max_buffers: usize) -> BufferPool {
+        BufferPool {
+            buffers: Vec::new(),
+            capacity,
+            max_buffers,
+            allocations: 0,
+        }
+    }
+
+    /// The capacity new buffers are made with.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// How many spare buffers there are.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Whether there are no spare buffers.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// How many buffers the pool has had to allocate.
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    /// Takes an empty buffer, allocating one if there are none spare.
+    pub fn acquire(&mut self) -> Vec<u8> {
+        match self.buffers.pop() {
+            Some(buffer) => buffer,
+            None => {
+                self.allocations += 1;
+                Vec::with_capacity(self.capacity)
+            }
+        }
+    }
+
+    /// Gives a buffer back to the pool.
+    ///
+    /// Buffers too small to be worth keeping are dropped, as are any over
+    /// the limit of spare buffers.
+    pub fn release(&mut self, mut buffer: Vec<u8>) {
+        if buffer.capacity() < self.capacity || self.buffers.len() >= self.max_buffers {
+            return;
+        }
+
+        buffer.clear();
+        self.buffers.push(buffer);
+    }
+}
@@ -0,0 +1,152 @@
+//! Length-prefixed sequences.
+//!
+//! Lists and strings are prefixed with their length, but not every message
+//! agrees on how that length is written. These let a sequence be encoded with
+//! any [`LengthPrefix`], which is what the `len` attribute of the derive
+//! macros uses.
+
+use super::{decode, encode, packed::PackedU32};
+
+use core::convert::TryInto as _;
+
+use alloc::{string::String, vec, vec::Vec};
+
+/// A type that can be used as the length prefix of a [`Sequence`].
+pub trait LengthPrefix: encode::Encode + decode::Decode {
+    /// Converts a length to the prefix, if it fits.
+    fn from_len(len: usize) -> Option<Self>;
+
+    /// Converts the prefix to a length.
+    fn to_len(&self) -> usize;
+}
+
+macro_rules! impl_length_prefix {
+    ($N:ty) => {
+        impl LengthPrefix for $N {
+            fn from_len(len: usize) -> Option<Self> {
+                len.try_into().ok()
+            }
+
+            fn to_len(&self) -> usize {
+                *self as usize
+            }
+        }
+    }
+}
+
+impl_length_prefix!(u8);
+impl_length_prefix!(u16);
+impl_length_prefix!(u32);
+
+impl LengthPrefix for PackedU32 {
+    fn from_len(len: usize) -> Option<Self> {
+        len.try_into().ok().map(PackedU32)
+    }
+
+    fn to_len(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A sequence of items that is encoded after a length prefix.
+pub trait Sequence: Sized {
+    /// The number of items in the sequence.
+    fn seq_len(&self) -> usize;
+
+    /// Encodes the items, without the length prefix.
+    fn encode_items(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error>;
+
+    /// Decodes `count` items, without the length prefix.
+    fn decode_items<T>(cursor: &mut decode::Cursor<T>, count: usize) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]>;
+}
+
+impl<U> Sequence for Vec<U>
+where U: encode::Encode + decode::Decode {
+    fn seq_len(&self) -> usize {
+        self.len()
+    }
+
+    fn encode_items(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        for item in self {
+            cursor.encode(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode_items<T>(cursor: &mut decode::Cursor<T>, count: usize) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.claim_collection::<U>(count)?;
+
+        // even within the limits, grow as items actually decode instead of
+        // allocating it all up front
+        let mut items = Vec::new();
+
+        for _ in 0..count {
+            items.push(cursor.decode()?);
+        }
+
+        Ok(items)
+    }
+}
+
+impl Sequence for String {
+    fn seq_len(&self) -> usize {
+        self.len()
+    }
+
+    fn encode_items(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.write(self.as_bytes());
+        Ok(())
+    }
+
+    fn decode_items<T>(cursor: &mut decode::Cursor<T>, count: usize) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.claim_string(count)?;
+
+        let mut bytes = vec![0; count];
+
+        if cursor.read(&mut bytes) < count {
+            return Err(decode::Error::unexpected_end());
+        }
+
+        String::from_utf8(bytes).map_err(|e| decode::Error::utf8(e.utf8_error()))
+    }
+}
+
+/// Encodes a sequence with a specific length prefix.
+pub fn encode_prefixed<L, S>(cursor: &mut encode::CursorMut, seq: &S) -> Result<(), encode::Error>
+where L: LengthPrefix, S: Sequence {
+    let len = match L::from_len(seq.seq_len()) {
+        Some(len) => len,
+        None => return Err(encode::Error),
+    };
+
+    cursor.encode(&len)?;
+    seq.encode_items(cursor)
+}
+
+/// Decodes a sequence with a specific length prefix.
+pub fn decode_prefixed<L, S, T>(cursor: &mut decode::Cursor<T>) -> Result<S, decode::Error>
+where L: LengthPrefix, S: Sequence, T: AsRef<[u8]> {
+    let len = cursor.decode::<L>()?.to_len();
+
+    S::decode_items(cursor, len)
+}
+
+/// Encodes a string with a specific length prefix.
+///
+/// This is [`encode_prefixed`] for borrowed strings.
+pub fn encode_str<L>(cursor: &mut encode::CursorMut, s: &str) -> Result<(), encode::Error>
+where L: LengthPrefix {
+    let len = match L::from_len(s.len()) {
+        Some(len) => len,
+        None => return Err(encode::Error),
+    };
+
+    cursor.encode(&len)?;
+    cursor.write(s.as_bytes());
+
+    Ok(())
+}
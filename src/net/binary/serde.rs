@@ -0,0 +1,599 @@
+//! Serde support for the binary format.
+//!
+//! This lets types that already derive `Serialize` and `Deserialize` be
+//! written with the same layout as the hand-written codecs:
+//!
+//! * Numbers are little-endian and fixed width; `bool`s are one byte and
+//!   `char`s are their `u32` code point.
+//! * Strings, byte arrays, sequences and maps are prefixed with a packed
+//!   length.
+//! * Options are prefixed with a presence byte.
+//! * Tuples and structs are their fields in order, without a prefix.
+//! * Enum variants are prefixed with their packed index.
+//!
+//! The format isn't self-describing, so `deserialize_any` and
+//! `deserialize_ignored_any` are not supported.
+
+use core::fmt;
+
+use alloc::{string::{String, ToString}, vec::Vec};
+
+use ::serde::{de, ser};
+
+use super::{decode, encode, packed::PackedU32};
+
+/// An error during serde (de)serialization.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying codec failed to encode.
+    Encode(encode::Error),
+    /// The underlying codec failed to decode.
+    Decode(decode::Error),
+    /// A sequence or map didn't know its length up front.
+    UnknownLength,
+    /// The format can't support this, like `deserialize_any`.
+    Unsupported(&'static str),
+    /// An error from a `Serialize` or `Deserialize` impl.
+    Custom(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Encode(e) => e.fmt(f),
+            Error::Decode(e) => e.fmt(f),
+            Error::UnknownLength => f.write_str("sequence length must be known up front"),
+            Error::Unsupported(what) => write!(f, "{} is not supported", what),
+            Error::Custom(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::Encode(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T>(msg: T) -> Error
+    where T: fmt::Display {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T>(msg: T) -> Error
+    where T: fmt::Display {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl From<encode::Error> for Error {
+    fn from(e: encode::Error) -> Error {
+        Error::Encode(e)
+    }
+}
+
+impl From<decode::Error> for Error {
+    fn from(e: decode::Error) -> Error {
+        Error::Decode(e)
+    }
+}
+
+/// Serializes a value into a new buffer.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, Error>
+where T: ser::Serialize + ?Sized {
+    let mut cursor = encode::CursorMut::new();
+    value.serialize(&mut Serializer::new(&mut cursor))?;
+
+    Ok(cursor.into())
+}
+
+/// Deserializes a value from a buffer.
+///
+/// Trailing bytes are ignored.
+pub fn from_slice<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
+where T: de::Deserialize<'de> {
+    T::deserialize(&mut Deserializer::new(decode::Cursor::new(bytes)))
+}
+
+/// A serde `Serializer` that writes to a [`CursorMut`](encode::CursorMut).
+pub struct Serializer<'a> {
+    cursor: &'a mut encode::CursorMut,
+}
+
+impl<'a> Serializer<'a> {
+    /// Create a new `Serializer`.
+    pub fn new(cursor: &'a mut encode::CursorMut) -> Serializer<'a> {
+        Serializer { cursor }
+    }
+
+    fn encode<T>(&mut self, value: &T) -> Result<(), Error>
+    where T: encode::Encode + ?Sized {
+        self.cursor.encode(value).map_err(Error::Encode)
+    }
+
+    fn encode_len(&mut self, len: Option<usize>) -> Result<(), Error> {
+        let len = len.ok_or(Error::UnknownLength)?;
+
+        match core::convert::TryInto::<u32>::try_into(len) {
+            Ok(len) => self.encode(&PackedU32(len)),
+            Err(_) => Err(Error::Encode(encode::Error)),
+        }
+    }
+}
+
+macro_rules! serialize_codec {
+    ($($method:ident($T:ty);)+) => {
+        $(fn $method(self, v: $T) -> Result<(), Error> {
+            self.encode(&v)
+        })+
+    }
+}
+
+impl<'a, 'b> ser::Serializer for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    serialize_codec! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.encode_len(Some(v.len()))?;
+        self.cursor.write(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.encode(&false)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        self.encode(&true)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), Error> {
+        self.encode(&PackedU32(variant_index))
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        self.encode(&PackedU32(variant_index))?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+        self.encode_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, Error> {
+        self.encode(&PackedU32(variant_index))?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, Error> {
+        self.encode_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, Error> {
+        self.encode(&PackedU32(variant_index))?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! serialize_compound {
+    ($($Trait:ident::$method:ident),+) => {
+        $(impl<'a, 'b> ser::$Trait for &'a mut Serializer<'b> {
+            type Ok = ();
+            type Error = Error;
+
+            fn $method<T>(&mut self, value: &T) -> Result<(), Error>
+            where T: ser::Serialize + ?Sized {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<(), Error> {
+                Ok(())
+            }
+        })+
+    }
+}
+
+serialize_compound!(
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field
+);
+
+impl<'a, 'b> ser::SerializeMap for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStruct for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStructVariant for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Error>
+    where T: ser::Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A serde `Deserializer` that reads from a [`Cursor`](decode::Cursor).
+///
+/// Strings and bytes borrow from the buffer when the target type allows it.
+pub struct Deserializer<'de> {
+    cursor: decode::Cursor<'de, &'de [u8]>,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Create a new `Deserializer`.
+    pub fn new(cursor: decode::Cursor<'de, &'de [u8]>) -> Deserializer<'de> {
+        Deserializer { cursor }
+    }
+
+    /// Consumes the `Deserializer`, returning the cursor.
+    pub fn into_cursor(self) -> decode::Cursor<'de, &'de [u8]> {
+        self.cursor
+    }
+
+    fn decode<T>(&mut self) -> Result<T, Error>
+    where T: decode::Decode {
+        self.cursor.decode().map_err(Error::Decode)
+    }
+
+    fn decode_len(&mut self) -> Result<usize, Error> {
+        Ok(self.decode::<PackedU32>()?.0 as usize)
+    }
+
+    fn decode_bytes(&mut self) -> Result<&'de [u8], Error> {
+        let len = self.decode_len()?;
+
+        self.cursor.decode_borrowed_bytes(len).map_err(Error::Decode)
+    }
+
+    fn seq(&mut self, len: usize) -> Result<Access<'_, 'de>, Error> {
+        self.cursor.claim_collection::<()>(len)?;
+
+        Ok(Access { de: self, len })
+    }
+}
+
+macro_rules! deserialize_codec {
+    ($($method:ident($T:ty) => $visit:ident;)+) => {
+        $(fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+        where V: de::Visitor<'de> {
+            visitor.$visit(self.decode::<$T>()?)
+        })+
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    deserialize_codec! {
+        deserialize_bool(bool) => visit_bool;
+        deserialize_i8(i8) => visit_i8;
+        deserialize_i16(i16) => visit_i16;
+        deserialize_i32(i32) => visit_i32;
+        deserialize_i64(i64) => visit_i64;
+        deserialize_i128(i128) => visit_i128;
+        deserialize_u8(u8) => visit_u8;
+        deserialize_u16(u16) => visit_u16;
+        deserialize_u32(u32) => visit_u32;
+        deserialize_u64(u64) => visit_u64;
+        deserialize_u128(u128) => visit_u128;
+        deserialize_f32(f32) => visit_f32;
+        deserialize_f64(f64) => visit_f64;
+        deserialize_char(char) => visit_char;
+        deserialize_identifier(u32) => visit_u32;
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        Err(Error::Unsupported("deserialize_any"))
+    }
+
+    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        Err(Error::Unsupported("deserialize_ignored_any"))
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        let bytes = self.decode_bytes()?;
+
+        match core::str::from_utf8(bytes) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(e) => Err(Error::Decode(decode::Error::utf8(e))),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        visitor.visit_borrowed_bytes(self.decode_bytes()?)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        if self.decode::<bool>()? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        let len = self.decode_len()?;
+
+        visitor.visit_seq(self.seq(len)?)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        visitor.visit_seq(Access { de: self, len })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        let len = self.decode_len()?;
+
+        visitor.visit_map(self.seq(len)?)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        visitor.visit_enum(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Access to the elements of a sequence, tuple or map.
+struct Access<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    len: usize,
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Access<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where T: de::DeserializeSeed<'de> {
+        if self.len == 0 {
+            Ok(None)
+        } else {
+            self.len -= 1;
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'a, 'de> de::MapAccess<'de> for Access<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where K: de::DeserializeSeed<'de> {
+        de::SeqAccess::next_element_seed(self, seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where V: de::DeserializeSeed<'de> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), Error>
+    where V: de::DeserializeSeed<'de> {
+        let index = self.decode::<PackedU32>()?.0;
+        let value = seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(index))?;
+
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where T: de::DeserializeSeed<'de> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error>
+    where V: de::Visitor<'de> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
@@ -0,0 +1,93 @@
+//! Tunneling Hazel over WebSockets.
+//!
+//! Browsers can't send UDP, so a WASM client or tool can't reach a game
+//! server on its own. A bridge accepts WebSocket connections and opens a UDP
+//! socket to the server for each one. Every binary message is one datagram,
+//! passed on unchanged both ways, so Hazel's acks and resends still run end
+//! to end and the server never knows.
+//!
+//! In a browser, drive a [`Connection`](crate::net::Connection) with the
+//! page's WebSocket, sending each datagram from
+//! [`poll_transmit`](crate::net::Connection::poll_transmit) as a binary
+//! message. The [`Client`](crate::net::client::Client) can go through a bridge
+//! with [`connect_websocket`](crate::net::client::Client::connect_websocket).
+//!
+//! WebRTC data channels, opened unordered and without retransmits, carry
+//! datagrams the same way, but aren't bridged here.
+
+use std::io;
+use std::net::SocketAddr;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// The largest datagram the bridge will pass on.
+const MAX_DATAGRAM: usize = 65507;
+
+/// Binds a listener and runs a bridge to `server` on it.
+pub async fn bind(addr: SocketAddr, server: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+
+    run(listener, server).await
+}
+
+/// Accepts WebSocket connections and tunnels each to `server`, forever or
+/// until the listener fails.
+pub async fn run(listener: TcpListener, server: SocketAddr) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+
+        // one client failing is no reason to stop
+        tokio::spawn(async move {
+            let _ = tunnel(stream, server).await;
+        });
+    }
+}
+
+/// Tunnels one WebSocket connection to `server` until either side closes it.
+pub async fn tunnel(stream: TcpStream, server: SocketAddr) -> io::Result<()> {
+    let mut websocket = tokio_tungstenite::accept_async(stream).await.map_err(into_io)?;
+
+    let local: SocketAddr = if server.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(server).await?;
+
+    let mut buf = vec![0; MAX_DATAGRAM];
+
+    loop {
+        tokio::select! {
+            message = websocket.next() => {
+                match message.transpose().map_err(into_io)? {
+                    Some(Message::Binary(datagram)) if datagram.len() <= MAX_DATAGRAM => {
+                        socket.send(&datagram).await?;
+                    }
+                    Some(Message::Close(_)) | None => return Ok(()),
+                    // pings are answered by tungstenite, and text means nothing
+                    Some(_) => (),
+                }
+            }
+            received = socket.recv(&mut buf) => {
+                let len = received?;
+
+                websocket.send(Message::Binary(buf[..len].to_vec())).await.map_err(into_io)?;
+            }
+        }
+    }
+}
+
+/// Turns a WebSocket error into an IO error.
+pub(crate) fn into_io(err: tungstenite::Error) -> io::Error {
+    match err {
+        tungstenite::Error::Io(err) => err,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            io::Error::from(io::ErrorKind::ConnectionReset)
+        }
+        err => io::Error::other(err),
+    }
+}
@@ -0,0 +1,428 @@
+//! An async client.
+//!
+//! [`Client`] drives a [`Connection`] over a UDP socket in a background task,
+//! and turns what the server sends into [`ClientEvent`]s. The rest of the
+//! crate does no IO; this is the part that does.
+//!
+//! With the `websocket` feature, the client can also go through a
+//! [bridge](crate::net::bridge) instead of sending UDP itself.
+
+pub use crate::net::Error;
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
+
+#[cfg(feature = "websocket")]
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::UdpSocket;
+#[cfg(feature = "websocket")]
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
+#[cfg(feature = "websocket")]
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::net::binary::encode;
+use crate::net::connection::{self, Connection, DisconnectReason, Hello};
+use crate::net::protocol::code::GameCode;
+use crate::net::protocol::game_data::GameDataMessage;
+use crate::net::protocol::root::{
+    AlterGame,
+    GameData,
+    GameDataTo,
+    GameListFilter,
+    GameListRequest,
+    GetGameListV2,
+    JoinGame,
+    JoinGameRequest,
+    JoinedGame,
+    PlayerJoined,
+    PlayerRemoved,
+    RemovePlayer,
+};
+use crate::net::protocol::{Direction, GameVersion, RootMessage, Rpc};
+use crate::net::transport::Config;
+
+/// The largest datagram the client will receive.
+const MAX_DATAGRAM: usize = 65507;
+
+/// Something that happened on a [`Client`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientEvent {
+    /// The client joined a game.
+    JoinedGame(JoinedGame),
+    /// The game couldn't be joined.
+    JoinFailed(DisconnectReason),
+    /// Someone else joined the game.
+    PlayerJoined(PlayerJoined),
+    /// Someone left the game.
+    PlayerLeft(PlayerRemoved),
+    /// A chat message was sent by a player object.
+    Chat {
+        net_id: u32,
+        message: String,
+    },
+    /// A meeting was started by a player object, for a body or
+    /// [`NO_BODY`](crate::net::protocol::rpc::NO_BODY).
+    MeetingStarted {
+        net_id: u32,
+        body: u8,
+    },
+    /// Any other game data.
+    GameData(GameDataMessage),
+    /// Any other root message.
+    Message(RootMessage),
+    /// The connection closed. No more events will arrive.
+    Disconnected(Option<DisconnectReason>),
+    /// The server stopped responding. No more events will arrive.
+    TimedOut,
+}
+
+enum Command {
+    Send(Vec<u8>),
+    Disconnect,
+}
+
+/// A client connected to a game server.
+pub struct Client {
+    commands: mpsc::UnboundedSender<Command>,
+    events: mpsc::UnboundedReceiver<connection::Event>,
+    pending: VecDeque<ClientEvent>,
+    code: Option<GameCode>,
+    client_id: Option<i32>,
+}
+
+impl Client {
+    /// Connects to a server, returning once it has accepted the hello.
+    pub async fn connect(
+        addr: SocketAddr,
+        name: &str,
+        version: GameVersion,
+    ) -> Result<Client, Error> {
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(addr).await?;
+
+        Client::start(Link::Udp(socket), name, version).await
+    }
+
+    /// Connects to a server through a [bridge](crate::net::bridge) at a
+    /// `ws://` or `wss://` url.
+    #[cfg(feature = "websocket")]
+    pub async fn connect_websocket(
+        url: &str,
+        name: &str,
+        version: GameVersion,
+    ) -> Result<Client, Error> {
+        let (websocket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(crate::net::bridge::into_io)?;
+
+        Client::start(Link::WebSocket(Box::new(websocket)), name, version).await
+    }
+
+    async fn start(link: Link, name: &str, version: GameVersion) -> Result<Client, Error> {
+        let hello = Hello::new(version, name);
+        let connection = Connection::connect(hello, Config::default(), Instant::now().into_std());
+
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (event_tx, mut events) = mpsc::unbounded_channel();
+
+        tokio::spawn(drive(link, connection, command_rx, event_tx));
+
+        match events.recv().await {
+            Some(connection::Event::Connected) => Ok(Client {
+                commands,
+                events,
+                pending: VecDeque::new(),
+                code: None,
+                client_id: None,
+            }),
+            Some(connection::Event::Disconnected(reason)) => Err(Error::Disconnected(reason)),
+            Some(connection::Event::TimedOut) => Err(Error::TimedOut),
+            // the task only ends early on socket errors
+            _ => Err(io::Error::from(io::ErrorKind::ConnectionReset).into()),
+        }
+    }
+
+    /// The game the client is in.
+    pub fn code(&self) -> Option<GameCode> {
+        self.code
+    }
+
+    /// The client id the server gave the client, once in a game.
+    pub fn client_id(&self) -> Option<i32> {
+        self.client_id
+    }
+
+    /// Asks to join a game.
+    ///
+    /// The answer arrives as [`ClientEvent::JoinedGame`] or
+    /// [`ClientEvent::JoinFailed`].
+    pub fn join_game(&self, code: GameCode) -> Result<(), Error> {
+        self.send_root(&RootMessage::JoinGame(JoinGame::Request(JoinGameRequest { code })))
+    }
+
+    /// Makes the game public or private. Only the host can do this.
+    pub fn set_public(&self, public: bool) -> Result<(), Error> {
+        let code = self.code.ok_or(Error::NotInGame)?;
+
+        self.send_root(&RootMessage::AlterGame(AlterGame::privacy(code, public)))
+    }
+
+    /// Asks for the public games that pass a filter.
+    ///
+    /// The answer arrives as a [`ClientEvent::Message`].
+    pub fn request_game_list(&self, filter: GameListFilter) -> Result<(), Error> {
+        let request = GameListRequest::new(filter);
+
+        self.send_root(&RootMessage::GetGameListV2(GetGameListV2::Request(request)))
+    }
+
+    /// Sends a root message.
+    pub fn send_root(&self, message: &RootMessage) -> Result<(), Error> {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(message)?;
+
+        self.send(cursor.into_inner())
+    }
+
+    /// Sends game data messages to everyone in the game.
+    pub fn send_game_data(&self, messages: &[GameDataMessage]) -> Result<(), Error> {
+        let code = self.code.ok_or(Error::NotInGame)?;
+
+        self.send_root(&RootMessage::GameData(GameData {
+            code,
+            data: encode_game_data(messages)?,
+        }))
+    }
+
+    /// Sends game data messages to one player in the game.
+    pub fn send_game_data_to(
+        &self,
+        target: i32,
+        messages: &[GameDataMessage],
+    ) -> Result<(), Error> {
+        let code = self.code.ok_or(Error::NotInGame)?;
+
+        self.send_root(&RootMessage::GameDataTo(GameDataTo {
+            code,
+            target,
+            data: encode_game_data(messages)?,
+        }))
+    }
+
+    /// Calls an RPC on a net object.
+    pub fn send_rpc(&self, net_id: u32, rpc: Rpc) -> Result<(), Error> {
+        self.send_game_data(&[GameDataMessage::Rpc { net_id, rpc }])
+    }
+
+    /// Sends a chat message from a player object.
+    pub fn send_chat(&self, net_id: u32, message: &str) -> Result<(), Error> {
+        self.send_rpc(net_id, Rpc::SendChat(message.to_owned()))
+    }
+
+    /// Disconnects from the server.
+    ///
+    /// [`next_event`](Client::next_event) returns `None` once the server is
+    /// told.
+    pub fn disconnect(&self) {
+        // the task is already gone if this fails
+        let _ = self.commands.send(Command::Disconnect);
+    }
+
+    /// Waits for the next event, or `None` once the connection is gone.
+    pub async fn next_event(&mut self) -> Option<Result<ClientEvent, Error>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            match self.events.recv().await? {
+                connection::Event::Connected => (),
+                connection::Event::Data { payload, .. } => {
+                    if let Err(err) = self.handle_payload(&payload) {
+                        return Some(Err(err));
+                    }
+                }
+                connection::Event::Disconnected(reason) => {
+                    return Some(Ok(ClientEvent::Disconnected(reason)));
+                }
+                connection::Event::TimedOut => return Some(Ok(ClientEvent::TimedOut)),
+                // clients don't limit the server, but just in case
+                connection::Event::Limited => {
+                    return Some(Ok(ClientEvent::Disconnected(Some(DisconnectReason::Error))));
+                }
+            }
+        }
+    }
+
+    fn send(&self, payload: Vec<u8>) -> Result<(), Error> {
+        self.commands.send(Command::Send(payload))
+            .map_err(|_| Error::Disconnected(None))
+    }
+
+    fn handle_payload(&mut self, payload: &[u8]) -> Result<(), Error> {
+        for message in RootMessage::read_all(payload, Direction::ToClient)? {
+            match message {
+                RootMessage::JoinedGame(joined) => {
+                    self.code = Some(joined.code);
+                    self.client_id = Some(joined.client_id);
+                    self.pending.push_back(ClientEvent::JoinedGame(joined));
+                }
+                RootMessage::JoinGame(JoinGame::Joined(joined)) => {
+                    self.pending.push_back(ClientEvent::PlayerJoined(joined));
+                }
+                RootMessage::JoinGame(JoinGame::Error(reason)) => {
+                    self.pending.push_back(ClientEvent::JoinFailed(reason));
+                }
+                RootMessage::RemovePlayer(RemovePlayer::Removed(removed)) => {
+                    self.pending.push_back(ClientEvent::PlayerLeft(removed));
+                }
+                RootMessage::GameData(GameData { data, .. }) => self.handle_game_data(&data)?,
+                RootMessage::GameDataTo(m) => self.handle_game_data(&m.data)?,
+                message => self.pending.push_back(ClientEvent::Message(message)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_game_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        for message in GameDataMessage::read_all(data)? {
+            let event = match message {
+                GameDataMessage::Rpc { net_id, rpc: Rpc::SendChat(message) } => {
+                    ClientEvent::Chat { net_id, message }
+                }
+                GameDataMessage::Rpc { net_id, rpc: Rpc::StartMeeting { player } } => {
+                    ClientEvent::MeetingStarted { net_id, body: player }
+                }
+                message => ClientEvent::GameData(message),
+            };
+
+            self.pending.push_back(event);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+/// What datagrams go over.
+enum Link {
+    Udp(UdpSocket),
+    #[cfg(feature = "websocket")]
+    WebSocket(Box<WebSocketStream<MaybeTlsStream<TcpStream>>>),
+}
+
+impl Link {
+    async fn send(&mut self, datagram: &[u8]) -> io::Result<()> {
+        match self {
+            Link::Udp(socket) => socket.send(datagram).await.map(|_| ()),
+            #[cfg(feature = "websocket")]
+            Link::WebSocket(websocket) => websocket
+                .send(Message::Binary(datagram.to_vec()))
+                .await
+                .map_err(crate::net::bridge::into_io),
+        }
+    }
+
+    /// Receives a datagram into `buf`, returning its length.
+    async fn recv(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Link::Udp(socket) => socket.recv(buf).await,
+            #[cfg(feature = "websocket")]
+            Link::WebSocket(websocket) => loop {
+                let message = websocket.next()
+                    .await
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::ConnectionReset))?
+                    .map_err(crate::net::bridge::into_io)?;
+
+                match message {
+                    Message::Binary(datagram) if datagram.len() <= buf.len() => {
+                        buf[..datagram.len()].copy_from_slice(&datagram);
+                        return Ok(datagram.len());
+                    }
+                    Message::Close(_) => {
+                        return Err(io::Error::from(io::ErrorKind::ConnectionReset));
+                    }
+                    _ => (),
+                }
+            },
+        }
+    }
+}
+
+/// Runs a connection until it closes.
+async fn drive(
+    mut link: Link,
+    mut connection: Connection,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::UnboundedSender<connection::Event>,
+) -> io::Result<()> {
+    let mut buf = vec![0; MAX_DATAGRAM];
+
+    loop {
+        while let Some(datagram) = connection.poll_transmit() {
+            link.send(&datagram).await?;
+            connection.recycle(datagram);
+        }
+
+        while let Some(event) = connection.poll_event() {
+            // nobody listening is fine; the connection still closes properly
+            let _ = events.send(event);
+        }
+
+        if connection.state() == connection::State::Disconnected {
+            return Ok(());
+        }
+
+        let timeout = connection.next_timeout().map(Instant::from_std);
+
+        tokio::select! {
+            received = link.recv(&mut buf) => {
+                let len = received?;
+
+                // a bad datagram shouldn't take the connection down
+                let _ = connection.handle_datagram(&buf[..len], Instant::now().into_std());
+            }
+            command = commands.recv() => {
+                let now = Instant::now().into_std();
+
+                match command {
+                    Some(Command::Send(payload)) => {
+                        // sending while not connected is dropped, like Hazel
+                        let _ = connection.send_reliable(&payload, now);
+                    }
+                    Some(Command::Disconnect) | None => {
+                        connection.disconnect(Some(DisconnectReason::ExitGame), now);
+                    }
+                }
+            }
+            _ = time::sleep_until(timeout.unwrap_or_else(Instant::now)), if timeout.is_some() => {
+                connection.handle_timeout(Instant::now().into_std());
+            }
+        }
+    }
+}
+
+fn encode_game_data(messages: &[GameDataMessage]) -> Result<Vec<u8>, encode::Error> {
+    let mut data = encode::CursorMut::new();
+
+    for message in messages {
+        data.encode(message)?;
+    }
+
+    Ok(data.into_inner())
+}
@@ -0,0 +1,1011 @@
+//! Connection lifecycle.
+//!
+//! A [`Connection`] sits on top of a [`Transport`] and tracks where a single
+//! connection is in its life: saying hello, connected, and finally
+//! disconnecting, with a [`DisconnectReason`] for why. It can run over any
+//! other [`Link`] too, like DTLS for newer clients.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::net::binary::{decode, encode, message};
+use crate::net::limit::{Limiter, Policy, RateLimit};
+use crate::net::metrics::Metrics;
+use crate::net::protocol::version::{GameVersion, Protocol};
+use crate::net::transport::{self, Config, Link, TooLarge, Transport, packet};
+
+/// The payload of a hello packet.
+///
+/// The client version decides which fields follow the name; fields that
+/// appeared later note the [`Protocol`] they appeared in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hello {
+    /// The version of Hazel.
+    pub hazel_version: u8,
+    /// The game version of the client.
+    pub client_version: GameVersion,
+    /// The name the player is joining with.
+    pub name: String,
+    /// Since [`Protocol::V2021`].
+    pub last_nonce: u32,
+    /// The language of the client. Since [`Protocol::V2021`].
+    pub language: u32,
+    /// Whether the client can use free chat (`1`) or only quick chat (`2`).
+    /// Since [`Protocol::V2021`].
+    pub chat_mode: u8,
+    /// Platform data and anything after it, still encoded. Since
+    /// [`Protocol::V2022`].
+    pub extra: Vec<u8>,
+}
+
+impl Hello {
+    /// Create a new `Hello` for a version, with everything else left at its
+    /// default.
+    pub fn new(client_version: GameVersion, name: &str) -> Hello {
+        Hello {
+            hazel_version: client_version.protocol().hazel_version(),
+            client_version,
+            name: name.to_owned(),
+            last_nonce: 0,
+            language: 0,
+            chat_mode: 1,
+            extra: Vec::new(),
+        }
+    }
+
+    /// The protocol the client speaks.
+    pub fn protocol(&self) -> Protocol {
+        self.client_version.protocol()
+    }
+}
+
+impl decode::Decode for Hello {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut hello = Hello {
+            hazel_version: cursor.decode()?,
+            client_version: cursor.decode()?,
+            name: cursor.decode()?,
+            ..Hello::new(GameVersion::new(0, 0, 0, 0), "")
+        };
+
+        if hello.protocol().has_hello_settings() {
+            hello.last_nonce = cursor.decode()?;
+            hello.language = cursor.decode()?;
+            hello.chat_mode = cursor.decode()?;
+        }
+
+        if hello.protocol().has_hello_platform() {
+            hello.extra = cursor.decode_rest().to_vec();
+        }
+
+        Ok(hello)
+    }
+}
+
+impl encode::Encode for Hello {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.hazel_version)?;
+        cursor.encode(&self.client_version)?;
+        cursor.encode(&self.name)?;
+
+        if self.protocol().has_hello_settings() {
+            cursor.encode(&self.last_nonce)?;
+            cursor.encode(&self.language)?;
+            cursor.encode(&self.chat_mode)?;
+        }
+
+        if self.protocol().has_hello_platform() {
+            cursor.write(&self.extra);
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a connection was closed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    ExitGame,
+    GameFull,
+    GameStarted,
+    GameNotFound,
+    IncorrectVersion,
+    Banned,
+    Kicked,
+    /// A reason given as a message to show the player.
+    Custom(String),
+    InvalidName,
+    Hacking,
+    NotAuthorized,
+    Destroy,
+    Error,
+    IncorrectGame,
+    ServerRequest,
+    ServerFull,
+    /// A reason this crate doesn't know about.
+    Unknown(u8),
+}
+
+impl DisconnectReason {
+    /// The code of the reason on the wire.
+    pub fn code(&self) -> u8 {
+        match self {
+            DisconnectReason::ExitGame => 0,
+            DisconnectReason::GameFull => 1,
+            DisconnectReason::GameStarted => 2,
+            DisconnectReason::GameNotFound => 3,
+            DisconnectReason::IncorrectVersion => 5,
+            DisconnectReason::Banned => 6,
+            DisconnectReason::Kicked => 7,
+            DisconnectReason::Custom(_) => 8,
+            DisconnectReason::InvalidName => 9,
+            DisconnectReason::Hacking => 10,
+            DisconnectReason::NotAuthorized => 11,
+            DisconnectReason::Destroy => 16,
+            DisconnectReason::Error => 17,
+            DisconnectReason::IncorrectGame => 18,
+            DisconnectReason::ServerRequest => 19,
+            DisconnectReason::ServerFull => 20,
+            DisconnectReason::Unknown(code) => *code,
+        }
+    }
+
+    /// Decodes a reason from its code, reading the message of a custom
+    /// reason from the cursor.
+    pub fn decode_code<T>(
+        code: u8,
+        cursor: &mut decode::Cursor<T>,
+    ) -> Result<DisconnectReason, decode::Error>
+    where T: AsRef<[u8]> {
+        let reason = match code {
+            0 => DisconnectReason::ExitGame,
+            1 => DisconnectReason::GameFull,
+            2 => DisconnectReason::GameStarted,
+            3 => DisconnectReason::GameNotFound,
+            5 => DisconnectReason::IncorrectVersion,
+            6 => DisconnectReason::Banned,
+            7 => DisconnectReason::Kicked,
+            8 => DisconnectReason::Custom(cursor.decode()?),
+            9 => DisconnectReason::InvalidName,
+            10 => DisconnectReason::Hacking,
+            11 => DisconnectReason::NotAuthorized,
+            16 => DisconnectReason::Destroy,
+            17 => DisconnectReason::Error,
+            18 => DisconnectReason::IncorrectGame,
+            19 => DisconnectReason::ServerRequest,
+            20 => DisconnectReason::ServerFull,
+            code => DisconnectReason::Unknown(code),
+        };
+
+        Ok(reason)
+    }
+
+    /// Encodes the message of a custom reason, if this is one.
+    pub fn encode_message(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            DisconnectReason::Custom(message) => cursor.encode(message),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl decode::Decode for DisconnectReason {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let code = cursor.decode::<u8>()?;
+
+        DisconnectReason::decode_code(code, cursor)
+    }
+}
+
+impl encode::Encode for DisconnectReason {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.code())?;
+        self.encode_message(cursor)
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisconnectReason::ExitGame => f.write_str("left the game"),
+            DisconnectReason::GameFull => f.write_str("game is full"),
+            DisconnectReason::GameStarted => f.write_str("game already started"),
+            DisconnectReason::GameNotFound => f.write_str("game not found"),
+            DisconnectReason::IncorrectVersion => f.write_str("incorrect version"),
+            DisconnectReason::Banned => f.write_str("banned"),
+            DisconnectReason::Kicked => f.write_str("kicked"),
+            DisconnectReason::Custom(message) => f.write_str(message),
+            DisconnectReason::InvalidName => f.write_str("invalid name"),
+            DisconnectReason::Hacking => f.write_str("hacking"),
+            DisconnectReason::NotAuthorized => f.write_str("not authorized"),
+            DisconnectReason::Destroy => f.write_str("game destroyed"),
+            DisconnectReason::Error => f.write_str("error"),
+            DisconnectReason::IncorrectGame => f.write_str("incorrect game"),
+            DisconnectReason::ServerRequest => f.write_str("requested by server"),
+            DisconnectReason::ServerFull => f.write_str("server is full"),
+            DisconnectReason::Unknown(code) => write!(f, "unknown reason {}", code),
+        }
+    }
+}
+
+/// Decodes the payload of a disconnect packet.
+///
+/// The payload is empty if no reason was given. Otherwise it is a byte that is
+/// always one, then a message with tag zero holding the reason.
+pub fn decode_disconnect(payload: &[u8]) -> Result<Option<DisconnectReason>, decode::Error> {
+    let mut cursor = decode::Cursor::new(payload);
+
+    if cursor.remaining() < 2 {
+        return Ok(None);
+    }
+
+    cursor.skip(1)?;
+
+    let mut message = message::Message::read(&mut cursor)?;
+
+    message.cursor().decode().map(Some)
+}
+
+/// Encodes the payload of a disconnect packet.
+pub fn encode_disconnect(reason: Option<&DisconnectReason>) -> Result<Vec<u8>, encode::Error> {
+    let mut cursor = encode::CursorMut::new();
+
+    if let Some(reason) = reason {
+        cursor.encode(&1u8)?;
+
+        let mut message = message::MessageWriter::start(&mut cursor, 0);
+        message.encode(reason)?;
+        message.end()?;
+    }
+
+    Ok(cursor.into_inner())
+}
+
+/// Where a [`Connection`] is in its life.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// Waiting for the hello to be sent or acknowledged.
+    Hello,
+    /// Connected, and free to send data.
+    Connected,
+    /// A disconnect is queued, but hasn't been sent yet.
+    Disconnecting,
+    /// The connection is closed.
+    Disconnected,
+}
+
+/// Something that happened on a [`Connection`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The handshake finished.
+    Connected,
+    /// Data arrived.
+    Data {
+        payload: Vec<u8>,
+        reliable: bool,
+    },
+    /// The other side disconnected, maybe with a reason.
+    Disconnected(Option<DisconnectReason>),
+    /// The other side stopped acknowledging packets.
+    TimedOut,
+    /// The other side went over its [`RateLimit`] and is being disconnected.
+    Limited,
+}
+
+/// Data was sent on a connection that isn't connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotConnected(pub State);
+
+impl fmt::Display for NotConnected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "connection is not connected ({:?})", self.0)
+    }
+}
+
+impl std::error::Error for NotConnected {}
+
+/// Reliable data couldn't be sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendError {
+    /// The connection isn't connected.
+    NotConnected(State),
+    /// The payload needs too many fragments.
+    TooLarge(TooLarge),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SendError::NotConnected(state) => NotConnected(*state).fmt(f),
+            SendError::TooLarge(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+impl From<NotConnected> for SendError {
+    fn from(err: NotConnected) -> SendError {
+        SendError::NotConnected(err.0)
+    }
+}
+
+impl From<TooLarge> for SendError {
+    fn from(err: TooLarge) -> SendError {
+        SendError::TooLarge(err)
+    }
+}
+
+/// A single connection, from either side.
+pub struct Connection<L = Transport> {
+    link: L,
+    state: State,
+    // the id of our hello, if we're the client
+    hello_id: Option<u16>,
+    hello: Option<Hello>,
+    reason: Option<DisconnectReason>,
+    limiter: Option<Limiter>,
+    // datagrams held back by the limiter, oldest first
+    throttled: VecDeque<Vec<u8>>,
+    limited: u64,
+    events: VecDeque<Event>,
+}
+
+impl Connection {
+    /// Connects to a server, sending a hello.
+    pub fn connect(hello: Hello, config: Config, now: Instant) -> Connection {
+        Connection::connect_over(Transport::with_config(config), hello, now)
+    }
+
+    /// Connects to a server, sending a hello that isn't a game [`Hello`].
+    ///
+    /// This is for the other servers, like the announcement server, which
+    /// expect a different hello.
+    pub fn connect_raw(hello: &[u8], config: Config, now: Instant) -> Connection {
+        Connection::connect_raw_over(Transport::with_config(config), hello, now)
+    }
+
+    /// Accepts a connection from a client, waiting for its hello.
+    pub fn accept(config: Config) -> Connection {
+        Connection::accept_over(Transport::with_config(config))
+    }
+}
+
+impl<L> Connection<L>
+where L: Link {
+    /// Connects to a server over `link`, sending a hello.
+    pub fn connect_over(link: L, hello: Hello, now: Instant) -> Connection<L> {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&hello).expect("hello failed to encode");
+
+        let mut connection = Connection::connect_raw_over(link, cursor.as_slice(), now);
+        connection.hello = Some(hello);
+        connection
+    }
+
+    /// Connects to a server over `link`, sending a hello that isn't a game
+    /// [`Hello`].
+    pub fn connect_raw_over(mut link: L, hello: &[u8], now: Instant) -> Connection<L> {
+        let hello_id = link.transport_mut().send_hello(hello, now);
+
+        Connection {
+            link,
+            state: State::Hello,
+            hello_id: Some(hello_id),
+            hello: None,
+            reason: None,
+            limiter: None,
+            throttled: VecDeque::new(),
+            limited: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Accepts a connection from a client over `link`, waiting for its hello.
+    pub fn accept_over(link: L) -> Connection<L> {
+        Connection {
+            link,
+            state: State::Hello,
+            hello_id: None,
+            hello: None,
+            reason: None,
+            limiter: None,
+            throttled: VecDeque::new(),
+            limited: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// The state of the connection.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The hello of the client, once it is known.
+    pub fn hello(&self) -> Option<&Hello> {
+        self.hello.as_ref()
+    }
+
+    /// Why the connection was closed, if it was given.
+    pub fn disconnect_reason(&self) -> Option<&DisconnectReason> {
+        self.reason.as_ref()
+    }
+
+    /// The smoothed round trip time, once a ping or message has been
+    /// acknowledged.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.link.transport().rtt()
+    }
+
+    /// How much the round trip time varies.
+    pub fn jitter(&self) -> Option<Duration> {
+        self.link.transport().jitter()
+    }
+
+    /// The underlying transport.
+    pub fn transport(&self) -> &Transport {
+        self.link.transport()
+    }
+
+    /// The link the connection runs over.
+    pub fn link(&self) -> &L {
+        &self.link
+    }
+
+    /// The link the connection runs over, mutably.
+    pub fn link_mut(&mut self) -> &mut L {
+        &mut self.link
+    }
+
+    /// What has gone over the connection so far.
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            limited: self.limited,
+            ..self.link.transport().metrics()
+        }
+    }
+
+    /// The limit on how fast the other side can send, if any.
+    pub fn rate_limit(&self) -> Option<&RateLimit> {
+        self.limiter.as_ref().map(Limiter::limit)
+    }
+
+    /// Limits how fast the other side can send. Connections aren't limited
+    /// to begin with, and acknowledgements and pings are never limited.
+    pub fn set_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.limiter = limit.map(Limiter::new);
+
+        if self.limiter.is_none() {
+            self.throttled.clear();
+        }
+    }
+
+    /// Sends data that may be lost.
+    pub fn send_unreliable(&mut self, payload: &[u8], now: Instant) -> Result<(), NotConnected> {
+        self.check_connected()?;
+        self.link.transport_mut().send_unreliable(payload, now);
+        Ok(())
+    }
+
+    /// Sends data that is resent until acknowledged.
+    pub fn send_reliable(&mut self, payload: &[u8], now: Instant) -> Result<(), SendError> {
+        self.check_connected()?;
+        self.link.transport_mut().send_reliable(payload, now)?;
+        Ok(())
+    }
+
+    /// Starts disconnecting, telling the other side why.
+    ///
+    /// The connection is [disconnected](State::Disconnected) once the
+    /// disconnect is taken with [`poll_transmit`](Connection::poll_transmit).
+    pub fn disconnect(&mut self, reason: Option<DisconnectReason>, now: Instant) {
+        if let State::Disconnecting | State::Disconnected = self.state {
+            return;
+        }
+
+        let payload = encode_disconnect(reason.as_ref()).expect("disconnect failed to encode");
+
+        self.link.transport_mut().send_disconnect(&payload, now);
+        self.reason = reason;
+        self.state = State::Disconnecting;
+    }
+
+    /// Handles a datagram from the other side.
+    pub fn handle_datagram(&mut self, datagram: &[u8], now: Instant) -> Result<(), L::Error> {
+        if self.state == State::Disconnected {
+            return Ok(());
+        }
+
+        // acknowledgements and pings are tiny and hold the connection up, so
+        // holding them back would only cause resends and timeouts. over dtls
+        // the packet type is sealed, so nothing is exempt
+        let exempt = matches!(datagram.first(), Some(&packet::ACKNOWLEDGEMENT | &packet::PING));
+
+        if let (Some(limiter), false) = (&mut self.limiter, exempt) {
+            // anything behind held back datagrams waits too, to keep order
+            if !self.throttled.is_empty() || !limiter.allow(datagram.len(), now) {
+                self.limited(datagram, now);
+                return Ok(());
+            }
+        }
+
+        self.receive(datagram, now)
+    }
+
+    /// The next time [`handle_timeout`](Connection::handle_timeout) should be
+    /// called.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let throttled = match (&self.limiter, self.throttled.front()) {
+            (Some(limiter), Some(datagram)) => limiter.ready_at(datagram.len()),
+            _ => None,
+        };
+
+        match self.state {
+            State::Hello | State::Connected => {
+                [self.link.next_timeout(), throttled].iter().flatten().min().copied()
+            }
+            _ => None,
+        }
+    }
+
+    /// Handles held back datagrams the limit now allows, and resends and
+    /// sends keepalives that are due.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        while let (Some(limiter), Some(datagram)) = (&mut self.limiter, self.throttled.front()) {
+            if !limiter.allow(datagram.len(), now) {
+                break;
+            }
+
+            let datagram = self.throttled.pop_front().expect("datagram was just checked");
+
+            // a bad datagram would have been ignored just the same on arrival
+            let _ = self.receive(&datagram, now);
+        }
+
+        if let State::Hello | State::Connected = self.state {
+            self.link.handle_timeout(now);
+
+            // timeouts can't cause decode errors
+            let _ = self.drain_transport();
+        }
+    }
+
+    /// Takes the next datagram to send.
+    ///
+    /// If the link fails to make a datagram, the connection is closed with
+    /// [`DisconnectReason::Error`].
+    pub fn poll_transmit(&mut self) -> Option<Vec<u8>> {
+        let datagram = match self.link.poll_transmit() {
+            Ok(datagram) => datagram,
+            Err(_) => {
+                if self.state != State::Disconnected {
+                    self.reason = Some(DisconnectReason::Error);
+                    self.state = State::Disconnected;
+                    self.events.push_back(Event::Disconnected(self.reason.clone()));
+                }
+
+                return None;
+            }
+        };
+
+        if datagram.is_none() && self.state == State::Disconnecting {
+            self.state = State::Disconnected;
+        }
+
+        datagram
+    }
+
+    /// Gives back a datagram from [`poll_transmit`](Connection::poll_transmit)
+    /// once it is sent, so its buffer can be used again.
+    pub fn recycle(&mut self, datagram: Vec<u8>) {
+        self.link.recycle(datagram);
+    }
+
+    /// Takes the next event.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    fn receive(&mut self, datagram: &[u8], now: Instant) -> Result<(), L::Error> {
+        if self.state == State::Disconnected {
+            return Ok(());
+        }
+
+        let result = self.link.handle_datagram(datagram, now);
+
+        // the server acknowledging our hello is the whole handshake
+        if let Some(id) = self.hello_id {
+            if self.state == State::Hello && !self.link.transport().is_pending(id) {
+                self.connected();
+            }
+        }
+
+        self.drain_transport()?;
+
+        result
+    }
+
+    fn limited(&mut self, datagram: &[u8], now: Instant) {
+        let (policy, max_backlog) = match &self.limiter {
+            Some(limiter) => (limiter.limit().policy, limiter.limit().max_backlog),
+            None => return,
+        };
+
+        self.limited += 1;
+
+        match policy {
+            Policy::Drop => (),
+            Policy::Throttle => {
+                let unreliable = datagram.first() == Some(&packet::UNRELIABLE);
+
+                if !unreliable && self.throttled.len() < max_backlog {
+                    self.throttled.push_back(datagram.to_vec());
+                }
+            }
+            Policy::Disconnect => {
+                if let State::Hello | State::Connected = self.state {
+                    self.disconnect(Some(DisconnectReason::Error), now);
+                    self.events.push_back(Event::Limited);
+                }
+            }
+        }
+    }
+
+    fn check_connected(&self) -> Result<(), NotConnected> {
+        match self.state {
+            State::Connected => Ok(()),
+            state => Err(NotConnected(state)),
+        }
+    }
+
+    fn connected(&mut self) {
+        self.state = State::Connected;
+        self.events.push_back(Event::Connected);
+    }
+
+    fn drain_transport(&mut self) -> Result<(), decode::Error> {
+        while let Some(event) = self.link.transport_mut().poll_event() {
+            match event {
+                transport::Event::Hello(payload) => {
+                    if self.hello_id.is_none() && self.state == State::Hello {
+                        self.hello = Some(decode::Cursor::new(&payload).decode()?);
+                        self.connected();
+                    }
+                }
+                transport::Event::Data { payload, reliable } => {
+                    // the hello's acknowledgement can arrive after the
+                    // server's first data, but either means we're in
+                    if self.hello_id.is_some() && self.state == State::Hello {
+                        self.connected();
+                    }
+
+                    if self.state == State::Connected {
+                        self.events.push_back(Event::Data { payload, reliable });
+                    }
+                }
+                transport::Event::Disconnected(payload) => {
+                    // a garbled reason is no reason to stay connected
+                    self.reason = decode_disconnect(&payload).ok().flatten();
+                    self.state = State::Disconnected;
+                    self.events.push_back(Event::Disconnected(self.reason.clone()));
+                }
+                transport::Event::TimedOut => {
+                    self.state = State::Disconnected;
+                    self.events.push_back(Event::TimedOut);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            keepalive_interval: None,
+            disconnect_timeout: None,
+            ..Config::default()
+        }
+    }
+
+    /// Delivers everything `from` has to send to `to`.
+    fn deliver(from: &mut Connection, to: &mut Connection, now: Instant) {
+        while let Some(datagram) = from.poll_transmit() {
+            to.handle_datagram(&datagram, now).unwrap();
+        }
+    }
+
+    fn events(connection: &mut Connection) -> Vec<Event> {
+        std::iter::from_fn(|| connection.poll_event()).collect()
+    }
+
+    fn connected(config: Config, now: Instant) -> (Connection, Connection) {
+        let hello = Hello::new(GameVersion::V2021_12_14, "player");
+        let mut client = Connection::connect(hello, config.clone(), now);
+        let mut server = Connection::accept(config);
+
+        deliver(&mut client, &mut server, now);
+        deliver(&mut server, &mut client, now);
+        events(&mut client);
+        events(&mut server);
+
+        (client, server)
+    }
+
+    #[test]
+    fn handshake() {
+        let now = Instant::now();
+        let hello = Hello::new(GameVersion::V2021_12_14, "player");
+        let mut client = Connection::connect(hello.clone(), config(), now);
+        let mut server = Connection::accept(config());
+
+        assert_eq!(client.state(), State::Hello);
+        assert_eq!(client.send_reliable(&[1], now), Err(SendError::NotConnected(State::Hello)));
+
+        deliver(&mut client, &mut server, now);
+        assert_eq!(server.state(), State::Connected);
+        assert_eq!(server.hello(), Some(&hello));
+        assert_eq!(events(&mut server), [Event::Connected]);
+
+        // the acknowledgement of the hello is the rest of the handshake
+        deliver(&mut server, &mut client, now);
+        assert_eq!(client.state(), State::Connected);
+        assert_eq!(events(&mut client), [Event::Connected]);
+
+        client.send_reliable(&[1], now).unwrap();
+        deliver(&mut client, &mut server, now);
+        assert_eq!(events(&mut server), [Event::Data { payload: vec![1], reliable: true }]);
+    }
+
+    #[test]
+    fn disconnect() {
+        let now = Instant::now();
+        let (mut client, mut server) = connected(config(), now);
+
+        client.disconnect(Some(DisconnectReason::ExitGame), now);
+        assert_eq!(client.state(), State::Disconnecting);
+        assert!(client.send_unreliable(&[1], now).is_err());
+
+        deliver(&mut client, &mut server, now);
+        assert_eq!(client.state(), State::Disconnected);
+        assert_eq!(client.next_timeout(), None);
+
+        assert_eq!(server.state(), State::Disconnected);
+        assert_eq!(server.disconnect_reason(), Some(&DisconnectReason::ExitGame));
+        assert_eq!(events(&mut server), [Event::Disconnected(Some(DisconnectReason::ExitGame))]);
+    }
+
+    #[test]
+    fn disconnect_payload_round_trip() {
+        let reasons = [
+            None,
+            Some(DisconnectReason::Banned),
+            Some(DisconnectReason::Custom("bye".into())),
+        ];
+
+        for reason in reasons {
+            let payload = encode_disconnect(reason.as_ref()).unwrap();
+            assert_eq!(decode_disconnect(&payload).unwrap(), reason);
+        }
+    }
+
+    #[test]
+    fn silent_server_times_out() {
+        let start = Instant::now();
+        let config = Config { disconnect_timeout: Some(Duration::from_secs(2)), ..config() };
+        let hello = Hello::new(GameVersion::V2021_12_14, "player");
+        let mut client = Connection::connect(hello, config, start);
+
+        client.handle_timeout(start + Duration::from_millis(1999));
+        assert_eq!(client.state(), State::Hello);
+        assert!(events(&mut client).is_empty());
+
+        client.handle_timeout(start + Duration::from_secs(2));
+        assert_eq!(client.state(), State::Disconnected);
+        assert_eq!(events(&mut client), [Event::TimedOut]);
+        assert_eq!(client.next_timeout(), None);
+    }
+
+    #[test]
+    fn keepalives_hold_the_connection_up() {
+        let start = Instant::now();
+        let config = Config {
+            keepalive_interval: Some(Duration::from_millis(1500)),
+            disconnect_timeout: Some(Duration::from_secs(10)),
+            ..config()
+        };
+        let (mut client, mut server) = connected(config, start);
+
+        // nothing but keepalives for a while
+        for tick in 1..=60 {
+            let now = start + Duration::from_millis(500 * tick);
+
+            client.handle_timeout(now);
+            server.handle_timeout(now);
+            deliver(&mut client, &mut server, now);
+            deliver(&mut server, &mut client, now);
+        }
+
+        assert_eq!(client.state(), State::Connected);
+        assert_eq!(server.state(), State::Connected);
+        assert!(client.rtt().is_some());
+
+        // then the server goes quiet
+        let silent = start + Duration::from_secs(30);
+
+        client.handle_timeout(silent + Duration::from_millis(9999));
+        assert_eq!(client.state(), State::Connected);
+
+        client.handle_timeout(silent + Duration::from_secs(10));
+        assert_eq!(client.state(), State::Disconnected);
+        assert_eq!(events(&mut client), [Event::TimedOut]);
+    }
+
+    fn limited(policy: Policy, now: Instant) -> (Connection, Connection) {
+        let (client, mut server) = connected(config(), now);
+
+        server.set_rate_limit(Some(RateLimit {
+            packets_per_sec: Some(1),
+            bytes_per_sec: None,
+            max_backlog: 4,
+            policy,
+        }));
+
+        (client, server)
+    }
+
+    fn data(connection: &mut Connection) -> Vec<Vec<u8>> {
+        events(connection)
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Data { payload, .. } => Some(payload),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn throttle_holds_back_datagrams() {
+        let start = Instant::now();
+        let (mut client, mut server) = limited(Policy::Throttle, start);
+
+        for i in 0..3 {
+            client.send_reliable(&[i], start).unwrap();
+        }
+        client.send_unreliable(&[9], start).unwrap();
+
+        deliver(&mut client, &mut server, start);
+        assert_eq!(data(&mut server), [[0]]);
+        assert_eq!(server.metrics().limited, 3);
+        assert_eq!(server.next_timeout(), Some(start + Duration::from_secs(1)));
+
+        // one a second, in order, and the unreliable one is gone
+        server.handle_timeout(start + Duration::from_secs(1));
+        assert_eq!(data(&mut server), [[1]]);
+
+        server.handle_timeout(start + Duration::from_secs(2));
+        assert_eq!(data(&mut server), [[2]]);
+
+        server.handle_timeout(start + Duration::from_secs(3));
+        assert!(data(&mut server).is_empty());
+        assert_eq!(server.state(), State::Connected);
+    }
+
+    #[test]
+    fn drop_policy() {
+        let now = Instant::now();
+        let (mut client, mut server) = limited(Policy::Drop, now);
+
+        client.send_reliable(&[0], now).unwrap();
+        client.send_reliable(&[1], now).unwrap();
+        deliver(&mut client, &mut server, now);
+
+        assert_eq!(data(&mut server), [[0]]);
+        assert_eq!(server.metrics().limited, 1);
+
+        // the dropped one wasn't acknowledged, so the client resends it
+        deliver(&mut server, &mut client, now);
+
+        let later = now + Duration::from_secs(1);
+        client.handle_timeout(later);
+        deliver(&mut client, &mut server, later);
+        assert_eq!(data(&mut server), [[1]]);
+    }
+
+    #[test]
+    fn disconnect_policy() {
+        let now = Instant::now();
+        let (mut client, mut server) = limited(Policy::Disconnect, now);
+
+        client.send_reliable(&[0], now).unwrap();
+        client.send_reliable(&[1], now).unwrap();
+        deliver(&mut client, &mut server, now);
+
+        assert_eq!(server.state(), State::Disconnecting);
+        assert_eq!(events(&mut server), [
+            Event::Data { payload: vec![0], reliable: true },
+            Event::Limited,
+        ]);
+
+        deliver(&mut server, &mut client, now);
+        assert_eq!(client.state(), State::Disconnected);
+        assert_eq!(client.disconnect_reason(), Some(&DisconnectReason::Error));
+    }
+
+    #[test]
+    fn acknowledgements_and_pings_are_not_limited() {
+        let now = Instant::now();
+        let (mut client, mut server) = limited(Policy::Disconnect, now);
+
+        // the server acknowledging its own sends, and the client's pings
+        for _ in 0..8 {
+            server.send_reliable(&[], now).unwrap();
+            client.link.send_ping(now);
+        }
+
+        deliver(&mut server, &mut client, now);
+        deliver(&mut client, &mut server, now);
+
+        assert_eq!(server.link.pending(), 0);
+        assert_eq!(server.metrics().limited, 0);
+        assert_eq!(server.state(), State::Connected);
+    }
+
+    fn hello_round_trip(bytes: &[u8], hello: &Hello) {
+        let mut cursor = decode::Cursor::new(bytes);
+        assert_eq!(&cursor.decode::<Hello>().unwrap(), hello);
+        assert_eq!(cursor.remaining(), 0);
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(hello).unwrap();
+        assert_eq!(cursor.as_slice(), bytes);
+    }
+
+    #[test]
+    fn hello_versions() {
+        hello_round_trip(
+            &fixture!("hello/v2020"),
+            &Hello::new(GameVersion::new(2020, 9, 7, 1), "Red"),
+        );
+        hello_round_trip(
+            &fixture!("hello/v2021"),
+            &Hello {
+                last_nonce: 42,
+                language: 1,
+                ..Hello::new(GameVersion::new(2021, 6, 30, 0), "Red")
+            },
+        );
+        hello_round_trip(
+            &fixture!("hello/v2022"),
+            &Hello {
+                last_nonce: 42,
+                language: 1,
+                chat_mode: 2,
+                extra: vec![3, 0, 2, 0x53, 0x53],
+                ..Hello::new(GameVersion::new(2022, 3, 29, 0), "Red")
+            },
+        );
+    }
+
+    #[test]
+    fn hello_fields_follow_the_version() {
+        // fields a version doesn't know about aren't sent
+        let hello = Hello {
+            last_nonce: 42,
+            language: 1,
+            extra: vec![1, 2, 3],
+            ..Hello::new(GameVersion::new(2020, 9, 7, 1), "Red")
+        };
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&hello).unwrap();
+        assert_eq!(cursor.as_slice(), &fixture!("hello/v2020")[..]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Errors.
+//!
+//! Each layer of `net` has its own small error type. [`Error`] can hold any of
+//! them, so an application can use `?` across layers and get one type out.
+
+use std::fmt;
+use std::io;
+
+use crate::net::binary::{decode, encode};
+use crate::net::connection::{DisconnectReason, NotConnected, SendError, State};
+use crate::net::protocol::code::ParseGameCodeError;
+
+/// An error from anywhere in `net`.
+#[derive(Debug)]
+pub enum Error {
+    /// The socket underneath failed.
+    Io(io::Error),
+    /// Something couldn't be decoded.
+    Decode(decode::Error),
+    /// Something couldn't be encoded.
+    Encode(encode::Error),
+    /// Serde couldn't go through the codec.
+    #[cfg(feature = "serde")]
+    Serde(crate::net::binary::serde::Error),
+    /// A game code couldn't be parsed.
+    GameCode(ParseGameCodeError),
+    /// The other side closed the connection, maybe with a reason.
+    Disconnected(Option<DisconnectReason>),
+    /// The other side stopped responding.
+    TimedOut,
+    /// Data was sent on a connection that isn't connected.
+    NotConnected(State),
+    /// A reliable payload of this many bytes needed too many fragments.
+    TooLarge(usize),
+    /// A game message was sent before joining a game.
+    NotInGame,
+}
+
+impl Error {
+    /// Whether the connection this came from is gone for good.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Error::Io(_) | Error::Disconnected(_) | Error::TimedOut)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Decode(err) => write!(f, "decode error: {}", err),
+            Error::Encode(err) => write!(f, "encode error: {}", err),
+            #[cfg(feature = "serde")]
+            Error::Serde(err) => write!(f, "serde error: {}", err),
+            Error::GameCode(err) => err.fmt(f),
+            Error::Disconnected(Some(reason)) => write!(f, "disconnected: {}", reason),
+            Error::Disconnected(None) => write!(f, "disconnected"),
+            Error::TimedOut => write!(f, "timed out"),
+            Error::NotConnected(state) => write!(f, "not connected ({:?})", state),
+            Error::TooLarge(len) => write!(f, "payload of {} bytes is too large", len),
+            Error::NotInGame => write!(f, "not in a game"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Decode(err) => Some(err),
+            Error::Encode(err) => Some(err),
+            #[cfg(feature = "serde")]
+            Error::Serde(err) => Some(err),
+            Error::GameCode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<decode::Error> for Error {
+    fn from(err: decode::Error) -> Error {
+        Error::Decode(err)
+    }
+}
+
+impl From<encode::Error> for Error {
+    fn from(err: encode::Error) -> Error {
+        Error::Encode(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<crate::net::binary::serde::Error> for Error {
+    fn from(err: crate::net::binary::serde::Error) -> Error {
+        Error::Serde(err)
+    }
+}
+
+impl From<ParseGameCodeError> for Error {
+    fn from(err: ParseGameCodeError) -> Error {
+        Error::GameCode(err)
+    }
+}
+
+impl From<NotConnected> for Error {
+    fn from(err: NotConnected) -> Error {
+        Error::NotConnected(err.0)
+    }
+}
+
+impl From<SendError> for Error {
+    fn from(err: SendError) -> Error {
+        match err {
+            SendError::NotConnected(state) => Error::NotConnected(state),
+            SendError::TooLarge(err) => Error::TooLarge(err.0),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Rate limits.
+//!
+//! A [`RateLimit`] caps how fast the other side of a
+//! [`Connection`](crate::net::Connection) can send, so a misbehaving client
+//! can't trivially flood a public server. Limits are token buckets that hold
+//! up to one second's worth.
+//!
+//! Acknowledgements and pings don't count against the limit. Dropping or
+//! holding them back would only make both sides resend and time out.
+
+use std::time::{Duration, Instant};
+
+/// What to do with datagrams over the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Drop them. Reliable ones are resent by the other side anyway.
+    Drop,
+    /// Hold anything but unreliable data until the limit allows it, up to
+    /// [`max_backlog`](RateLimit::max_backlog) datagrams, and drop the rest.
+    Throttle,
+    /// Disconnect the other side.
+    Disconnect,
+}
+
+/// Limits on how fast the other side can send.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    /// How many datagrams can arrive each second, if limited.
+    pub packets_per_sec: Option<u32>,
+    /// How many bytes can arrive each second, if limited.
+    pub bytes_per_sec: Option<u32>,
+    /// How many reliable datagrams can be held back when throttling.
+    pub max_backlog: usize,
+    pub policy: Policy,
+}
+
+impl Default for RateLimit {
+    fn default() -> RateLimit {
+        RateLimit {
+            packets_per_sec: Some(200),
+            bytes_per_sec: Some(64 * 1024),
+            max_backlog: 64,
+            policy: Policy::Throttle,
+        }
+    }
+}
+
+/// Keeps track of a [`RateLimit`].
+#[derive(Clone, Debug)]
+pub struct Limiter {
+    limit: RateLimit,
+    packets: Option<Bucket>,
+    bytes: Option<Bucket>,
+}
+
+impl Limiter {
+    /// Create a new `Limiter` with full buckets.
+    pub fn new(limit: RateLimit) -> Limiter {
+        Limiter {
+            packets: limit.packets_per_sec.map(Bucket::new),
+            bytes: limit.bytes_per_sec.map(Bucket::new),
+            limit,
+        }
+    }
+
+    /// The limit being kept track of.
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Takes a datagram of `len` bytes out of the buckets, returning `false`
+    /// if it is over the limit.
+    pub fn allow(&mut self, len: usize, now: Instant) -> bool {
+        for bucket in self.packets.iter_mut().chain(self.bytes.iter_mut()) {
+            bucket.refill(now);
+        }
+
+        let packets_ok = self.packets.as_ref().is_none_or(|b| b.has(1.0));
+        let bytes_ok = self.bytes.as_ref().is_none_or(|b| b.has(len as f64));
+
+        if !packets_ok || !bytes_ok {
+            return false;
+        }
+
+        if let Some(bucket) = &mut self.packets {
+            bucket.take(1.0);
+        }
+
+        if let Some(bucket) = &mut self.bytes {
+            bucket.take(len as f64);
+        }
+
+        true
+    }
+
+    /// When a datagram of `len` bytes will be allowed, or `None` if it never
+    /// will be.
+    pub fn ready_at(&self, len: usize) -> Option<Instant> {
+        let mut ready_at = None;
+
+        for (bucket, cost) in [(&self.packets, 1.0), (&self.bytes, len as f64)] {
+            if let Some(bucket) = bucket {
+                ready_at = ready_at.max(Some(bucket.ready_at(cost)?));
+            }
+        }
+
+        ready_at
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(rate: u32) -> Bucket {
+        Bucket {
+            rate: rate as f64,
+            tokens: rate as f64,
+            updated: None,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(updated) = self.updated {
+            let elapsed = now.saturating_duration_since(updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        }
+
+        self.updated = Some(now);
+    }
+
+    /// Whether there are enough tokens. Anything bigger than the bucket
+    /// only needs a full one.
+    fn has(&self, cost: f64) -> bool {
+        self.tokens >= cost.min(self.rate)
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost.min(self.rate);
+    }
+
+    fn ready_at(&self, cost: f64) -> Option<Instant> {
+        if self.rate <= 0.0 {
+            return None;
+        }
+
+        let missing = (cost.min(self.rate) - self.tokens).max(0.0);
+
+        self.updated.map(|updated| updated + Duration::from_secs_f64(missing / self.rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(packets_per_sec: Option<u32>, bytes_per_sec: Option<u32>) -> RateLimit {
+        RateLimit {
+            packets_per_sec,
+            bytes_per_sec,
+            ..RateLimit::default()
+        }
+    }
+
+    #[test]
+    fn packet_bucket() {
+        let start = Instant::now();
+        let mut limiter = Limiter::new(limit(Some(2), None));
+
+        assert!(limiter.allow(100, start));
+        assert!(limiter.allow(100, start));
+        assert!(!limiter.allow(100, start));
+        assert_eq!(limiter.ready_at(100), Some(start + Duration::from_millis(500)));
+
+        // refills at the rate, up to a second's worth
+        assert!(limiter.allow(100, start + Duration::from_millis(500)));
+        assert!(!limiter.allow(100, start + Duration::from_millis(500)));
+
+        let later = start + Duration::from_secs(10);
+        assert!(limiter.allow(100, later));
+        assert!(limiter.allow(100, later));
+        assert!(!limiter.allow(100, later));
+    }
+
+    #[test]
+    fn byte_bucket() {
+        let start = Instant::now();
+        let mut limiter = Limiter::new(limit(None, Some(1000)));
+
+        assert!(limiter.allow(600, start));
+        assert!(!limiter.allow(600, start));
+        assert!(limiter.allow(400, start));
+
+        // anything bigger than the bucket only needs a full one
+        let later = start + Duration::from_secs(1);
+        assert!(limiter.allow(5000, later));
+        assert!(!limiter.allow(1, later));
+    }
+
+    #[test]
+    fn unlimited() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(limit(None, None));
+
+        for _ in 0..1000 {
+            assert!(limiter.allow(usize::MAX, now));
+        }
+
+        assert_eq!(limiter.ready_at(1), None);
+    }
+}
@@ -0,0 +1,41 @@
+//! Counters for monitoring connections.
+
+use core::ops::AddAssign;
+
+/// Counts of what went over a connection, or through a room.
+///
+/// A [`Connection`](crate::net::Connection) counts datagrams. A server room
+/// counts the payloads that pass through it, and leaves the rest at zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Reliable packets sent again for lack of an acknowledgement. These are
+    /// counted in `packets_sent` too.
+    pub resends: u64,
+    pub acks_sent: u64,
+    pub acks_received: u64,
+    /// Reliable packets that arrived more than once and were dropped.
+    pub duplicates: u64,
+    /// Datagrams that couldn't be decoded.
+    pub malformed: u64,
+    /// Datagrams over the rate limit, whether they were dropped or held back.
+    pub limited: u64,
+}
+
+impl AddAssign for Metrics {
+    fn add_assign(&mut self, other: Metrics) {
+        self.packets_sent += other.packets_sent;
+        self.packets_received += other.packets_received;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.resends += other.resends;
+        self.acks_sent += other.acks_sent;
+        self.acks_received += other.acks_received;
+        self.duplicates += other.duplicates;
+        self.malformed += other.malformed;
+        self.limited += other.limited;
+    }
+}
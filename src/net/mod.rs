@@ -1 +1,33 @@
 pub mod binary;
+#[cfg(feature = "std")]
+pub mod announcement;
+#[cfg(feature = "websocket")]
+pub mod bridge;
+#[cfg(feature = "tokio")]
+pub mod client;
+#[cfg(feature = "std")]
+pub mod connection;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod limit;
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod objects;
+#[cfg(feature = "std")]
+pub mod pcap;
+#[cfg(feature = "std")]
+pub mod protocol;
+#[cfg(feature = "std")]
+pub mod proxy;
+pub mod seq;
+#[cfg(feature = "tokio")]
+pub mod server;
+#[cfg(feature = "std")]
+pub mod transport;
+
+#[cfg(feature = "std")]
+pub use connection::Connection;
+#[cfg(feature = "std")]
+pub use error::Error;
+pub use seq::SeqNum;
@@ -0,0 +1,347 @@
+//! The GameData object.
+//!
+//! This holds what everyone needs to know about every player: their name and
+//! cosmetics, whether they are an impostor or dead, and their tasks. When
+//! spawned, every player is sent. After that, only the players that changed
+//! are sent, each in a message tagged with their player id, both in Data
+//! messages and the UpdateGameData RPC.
+
+use crate::net::binary::{decode, encode, message, packed::PackedU32};
+
+use super::Component;
+
+/// The player disconnected.
+pub const DISCONNECTED: u8 = 1;
+/// The player is an impostor.
+pub const IMPOSTOR: u8 = 2;
+/// The player is dead.
+pub const DEAD: u8 = 4;
+
+/// A task assigned to a player.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TaskInfo {
+    /// The index of the task in the player's task list.
+    pub id: u32,
+    pub complete: bool,
+}
+
+impl decode::Decode for TaskInfo {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(TaskInfo {
+            id: cursor.decode::<PackedU32>()?.0,
+            complete: cursor.decode()?,
+        })
+    }
+}
+
+impl encode::Encode for TaskInfo {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&PackedU32(self.id))?;
+        cursor.encode(&self.complete)
+    }
+}
+
+/// The info of a single player.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerInfo {
+    pub player_id: u8,
+    pub name: String,
+    pub color: u8,
+    pub hat: u32,
+    pub pet: u32,
+    pub skin: u32,
+    pub disconnected: bool,
+    pub impostor: bool,
+    pub dead: bool,
+    pub tasks: Vec<TaskInfo>,
+}
+
+impl PlayerInfo {
+    /// Create a new `PlayerInfo` with nothing set.
+    pub fn new(player_id: u8) -> PlayerInfo {
+        PlayerInfo {
+            player_id,
+            ..PlayerInfo::default()
+        }
+    }
+
+    /// The flags byte of the player.
+    pub fn flags(&self) -> u8 {
+        let mut flags = 0;
+
+        if self.disconnected {
+            flags |= DISCONNECTED;
+        }
+
+        if self.impostor {
+            flags |= IMPOSTOR;
+        }
+
+        if self.dead {
+            flags |= DEAD;
+        }
+
+        flags
+    }
+
+    /// Reads the info, except for the player id, which is sent separately.
+    pub fn deserialize<T>(&mut self, cursor: &mut decode::Cursor<T>) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.name = cursor.decode()?;
+        self.color = cursor.decode()?;
+        self.hat = cursor.decode::<PackedU32>()?.0;
+        self.pet = cursor.decode::<PackedU32>()?.0;
+        self.skin = cursor.decode::<PackedU32>()?.0;
+
+        let flags = cursor.decode::<u8>()?;
+        self.disconnected = flags & DISCONNECTED != 0;
+        self.impostor = flags & IMPOSTOR != 0;
+        self.dead = flags & DEAD != 0;
+
+        let count = cursor.decode::<u8>()? as usize;
+        cursor.claim_collection::<TaskInfo>(count)?;
+
+        self.tasks.clear();
+
+        for _ in 0..count {
+            self.tasks.push(cursor.decode()?);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the info, except for the player id.
+    pub fn serialize(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        if self.tasks.len() > u8::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        cursor.encode(&self.name)?;
+        cursor.encode(&self.color)?;
+        cursor.encode(&PackedU32(self.hat))?;
+        cursor.encode(&PackedU32(self.pet))?;
+        cursor.encode(&PackedU32(self.skin))?;
+        cursor.encode(&self.flags())?;
+        cursor.encode(&(self.tasks.len() as u8))?;
+
+        for task in &self.tasks {
+            cursor.encode(task)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Holds the info of every player in the game.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameData {
+    pub players: Vec<PlayerInfo>,
+}
+
+impl GameData {
+    /// The info of a player.
+    pub fn player(&self, player_id: u8) -> Option<&PlayerInfo> {
+        self.players.iter().find(|p| p.player_id == player_id)
+    }
+
+    /// The info of a player, mutably.
+    pub fn player_mut(&mut self, player_id: u8) -> Option<&mut PlayerInfo> {
+        self.players.iter_mut().find(|p| p.player_id == player_id)
+    }
+
+    /// Removes a player, returning their info.
+    pub fn remove_player(&mut self, player_id: u8) -> Option<PlayerInfo> {
+        let index = self.players.iter().position(|p| p.player_id == player_id)?;
+
+        Some(self.players.remove(index))
+    }
+
+    /// Reads updated players, adding any that are new.
+    ///
+    /// This is the payload of both Data messages and the UpdateGameData RPC.
+    pub fn read_update<T>(&mut self, cursor: &mut decode::Cursor<T>) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        while cursor.remaining() > 0 {
+            let mut message = message::Message::read(cursor)?;
+            let player_id = message.tag();
+
+            self.player_entry(player_id).deserialize(message.cursor())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes an update with some of the players.
+    ///
+    /// Players that aren't in the game are skipped.
+    pub fn write_update<I>(
+        &self,
+        cursor: &mut encode::CursorMut,
+        player_ids: I,
+    ) -> Result<(), encode::Error>
+    where I: IntoIterator<Item = u8> {
+        for player in player_ids.into_iter().filter_map(|id| self.player(id)) {
+            let mut message = message::MessageWriter::start(cursor, player.player_id);
+            player.serialize(&mut message)?;
+            message.end()?;
+        }
+
+        Ok(())
+    }
+
+    fn player_entry(&mut self, player_id: u8) -> &mut PlayerInfo {
+        match self.players.iter().position(|p| p.player_id == player_id) {
+            Some(index) => &mut self.players[index],
+            None => {
+                self.players.push(PlayerInfo::new(player_id));
+                self.players.last_mut().expect("player was just pushed")
+            }
+        }
+    }
+}
+
+impl Component for GameData {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        if !initial {
+            return self.read_update(cursor);
+        }
+
+        let count = cursor.decode::<PackedU32>()?.0 as usize;
+        cursor.claim_collection::<PlayerInfo>(count)?;
+
+        self.players.clear();
+
+        for _ in 0..count {
+            let mut player = PlayerInfo::new(cursor.decode()?);
+            player.deserialize(cursor)?;
+
+            self.players.push(player);
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error> {
+        if !initial {
+            return self.write_update(cursor, self.players.iter().map(|p| p.player_id));
+        }
+
+        cursor.encode(&PackedU32(self.players.len() as u32))?;
+
+        for player in &self.players {
+            cursor.encode(&player.player_id)?;
+            player.serialize(cursor)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawned() -> GameData {
+        GameData {
+            players: vec![
+                PlayerInfo {
+                    name: "Red".into(),
+                    hat: 5,
+                    skin: 2,
+                    impostor: true,
+                    dead: true,
+                    tasks: vec![
+                        TaskInfo { id: 0, complete: true },
+                        TaskInfo { id: 3, complete: false },
+                    ],
+                    ..PlayerInfo::new(0)
+                },
+                PlayerInfo {
+                    name: "Blue".into(),
+                    color: 1,
+                    pet: 10,
+                    skin: 200,
+                    disconnected: true,
+                    ..PlayerInfo::new(1)
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn spawn() {
+        let bytes = fixture!("objects/game_data_spawn");
+
+        let mut game_data = GameData::default();
+        game_data.deserialize(&mut decode::Cursor::new(&bytes[..]), true).unwrap();
+        assert_eq!(game_data, spawned());
+
+        let mut cursor = encode::CursorMut::new();
+        game_data.serialize(&mut cursor, true).unwrap();
+        assert_eq!(cursor.as_slice(), &bytes[..]);
+    }
+
+    #[test]
+    fn update() {
+        let bytes = fixture!("objects/game_data_update");
+
+        let mut game_data = spawned();
+        game_data.deserialize(&mut decode::Cursor::new(&bytes[..]), false).unwrap();
+
+        // player 0 isn't in the update, and player 2 is new
+        let mut expected = spawned();
+        expected.players[1].disconnected = false;
+        expected.players[1].tasks = vec![TaskInfo { id: 5, complete: true }];
+        expected.players.push(PlayerInfo {
+            name: "Lime".into(),
+            color: 11,
+            ..PlayerInfo::new(2)
+        });
+
+        assert_eq!(game_data, expected);
+
+        // players that aren't in the game are skipped
+        let mut cursor = encode::CursorMut::new();
+        game_data.write_update(&mut cursor, vec![1, 2, 9]).unwrap();
+        assert_eq!(cursor.as_slice(), &bytes[..]);
+    }
+
+    #[test]
+    fn flags() {
+        let mut player = PlayerInfo::new(0);
+        assert_eq!(player.flags(), 0);
+
+        player.disconnected = true;
+        player.dead = true;
+        assert_eq!(player.flags(), DISCONNECTED | DEAD);
+    }
+
+    #[test]
+    fn too_many_tasks() {
+        let player = PlayerInfo {
+            tasks: vec![TaskInfo::default(); 256],
+            ..PlayerInfo::new(0)
+        };
+
+        assert!(player.serialize(&mut encode::CursorMut::new()).is_err());
+    }
+
+    #[test]
+    fn remove_player() {
+        let mut game_data = spawned();
+
+        assert_eq!(game_data.remove_player(0).map(|p| p.name), Some("Red".into()));
+        assert_eq!(game_data.remove_player(0), None);
+        assert!(game_data.player(1).is_some());
+    }
+}
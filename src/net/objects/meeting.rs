@@ -0,0 +1,445 @@
+//! The meeting screen.
+//!
+//! Every player gets a vote area, in the order of the GameData players. Each
+//! area is sent as a single state byte: the low four bits are the vote target
+//! plus one, with `0` being nobody, and the high bits are flags. After the
+//! meeting spawns, updates start with a packed mask of the areas that changed.
+
+use crate::net::binary::{decode, encode, packed::PackedU32};
+use crate::net::protocol::Rpc;
+
+use super::Component;
+
+/// The player is dead, and can't vote.
+pub const DEAD: u8 = 128;
+/// The player has voted.
+pub const DID_VOTE: u8 = 64;
+/// The player called the meeting.
+pub const DID_REPORT: u8 = 32;
+
+const TARGET_MASK: u8 = 15;
+
+/// The state of a single vote area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VoteState {
+    pub dead: bool,
+    pub did_vote: bool,
+    pub did_report: bool,
+    /// Who the player voted for, or `None` if they skipped or haven't voted
+    /// yet.
+    pub voted_for: Option<u8>,
+}
+
+impl VoteState {
+    /// Reads a state byte.
+    pub fn from_byte(state: u8) -> VoteState {
+        VoteState {
+            dead: state & DEAD != 0,
+            did_vote: state & DID_VOTE != 0,
+            did_report: state & DID_REPORT != 0,
+            voted_for: (state & TARGET_MASK).checked_sub(1),
+        }
+    }
+
+    /// The state byte, or `None` if the vote target doesn't fit in it.
+    pub fn to_byte(self) -> Option<u8> {
+        let target = match self.voted_for {
+            Some(target) if target < TARGET_MASK => target + 1,
+            Some(_) => return None,
+            None => 0,
+        };
+
+        let mut state = target;
+
+        if self.dead {
+            state |= DEAD;
+        }
+
+        if self.did_vote {
+            state |= DID_VOTE;
+        }
+
+        if self.did_report {
+            state |= DID_REPORT;
+        }
+
+        Some(state)
+    }
+
+    /// Whether the player skipped voting.
+    pub fn skipped(self) -> bool {
+        self.did_vote && self.voted_for.is_none()
+    }
+}
+
+impl decode::Decode for VoteState {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode().map(VoteState::from_byte)
+    }
+}
+
+impl encode::Encode for VoteState {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.to_byte().ok_or(encode::Error)?)
+    }
+}
+
+/// The vote area of a player.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayerVoteArea {
+    /// The player the area is for. This isn't sent, so areas read from a spawn
+    /// are numbered by their index until they are given the GameData players.
+    pub player_id: u8,
+    pub state: VoteState,
+}
+
+/// The meeting screen, and the votes cast in it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MeetingHud {
+    pub areas: Vec<PlayerVoteArea>,
+    /// The areas that changed since the last update, by index.
+    pub dirty: u32,
+}
+
+impl MeetingHud {
+    /// Create a new meeting for some players.
+    ///
+    /// The players should be in the order of the GameData players.
+    pub fn new<I>(player_ids: I) -> MeetingHud
+    where I: IntoIterator<Item = u8> {
+        MeetingHud {
+            areas: player_ids.into_iter()
+                .map(|player_id| PlayerVoteArea {
+                    player_id,
+                    state: VoteState::default(),
+                })
+                .collect(),
+            dirty: 0,
+        }
+    }
+
+    /// The vote area of a player.
+    pub fn area(&self, player_id: u8) -> Option<&PlayerVoteArea> {
+        self.areas.iter().find(|a| a.player_id == player_id)
+    }
+
+    /// Changes the state of a player's area, marking it dirty.
+    ///
+    /// Returns `false` if the player has no area.
+    pub fn set_state(&mut self, player_id: u8, state: VoteState) -> bool {
+        match self.areas.iter().position(|a| a.player_id == player_id) {
+            Some(index) => {
+                self.areas[index].state = state;
+                self.dirty |= 1u32.checked_shl(index as u32).unwrap_or(0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counts a vote, as the host does for CastVote.
+    ///
+    /// `suspect` is `None` for a skip. Returns `false` if the vote doesn't
+    /// count, because the voter is dead, has already voted or has no area.
+    pub fn cast_vote(&mut self, voter: u8, suspect: Option<u8>) -> bool {
+        let state = match self.area(voter) {
+            Some(area) if !area.state.dead && !area.state.did_vote => area.state,
+            _ => return false,
+        };
+
+        self.set_state(voter, VoteState {
+            did_vote: true,
+            voted_for: suspect,
+            ..state
+        })
+    }
+
+    /// Whether everyone alive has voted.
+    pub fn all_voted(&self) -> bool {
+        self.areas.iter().all(|a| a.state.dead || a.state.did_vote)
+    }
+
+    /// Tallies the votes, returning who is exiled and whether it was a tie.
+    ///
+    /// Nobody is exiled on a tie, or if skipping got the most votes.
+    pub fn tally(&self) -> (Option<u8>, bool) {
+        let mut skips = 0;
+        let mut counts = Vec::with_capacity(self.areas.len());
+
+        for area in &self.areas {
+            counts.push((area.player_id, 0usize));
+        }
+
+        for state in self.areas.iter().map(|a| a.state).filter(|s| s.did_vote) {
+            match state.voted_for {
+                Some(target) => {
+                    if let Some(count) = counts.iter_mut().find(|(id, _)| *id == target) {
+                        count.1 += 1;
+                    }
+                }
+                None => skips += 1,
+            }
+        }
+
+        let mut exiled = None;
+        let mut max = skips;
+        let mut tie = false;
+
+        for (player_id, count) in counts {
+            if count > max {
+                exiled = Some(player_id);
+                max = count;
+                tie = false;
+            } else if count == max && count > 0 {
+                tie = true;
+            }
+        }
+
+        if tie {
+            (None, true)
+        } else {
+            (exiled, false)
+        }
+    }
+
+    /// Ends the voting, returning the VotingComplete RPC to send.
+    pub fn voting_complete(&self) -> Rpc {
+        let (exiled, tie) = self.tally();
+
+        Rpc::VotingComplete {
+            states: self.areas.iter().map(|a| a.state).collect(),
+            exiled,
+            tie,
+        }
+    }
+
+    /// Applies a VotingComplete RPC's states to the areas.
+    pub fn handle_voting_complete(&mut self, states: &[VoteState]) {
+        for (area, state) in self.areas.iter_mut().zip(states) {
+            area.state = *state;
+        }
+    }
+
+    /// Forgets what changed, after an update was sent.
+    pub fn clear_dirty(&mut self) {
+        self.dirty = 0;
+    }
+
+    fn area_entry(&mut self, index: usize) -> &mut PlayerVoteArea {
+        while self.areas.len() <= index {
+            let player_id = self.areas.len() as u8;
+
+            self.areas.push(PlayerVoteArea {
+                player_id,
+                state: VoteState::default(),
+            });
+        }
+
+        &mut self.areas[index]
+    }
+}
+
+impl Component for MeetingHud {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        if initial {
+            // the number of areas isn't sent, so a spawn has exactly one byte
+            // per area
+            let mut index = 0;
+
+            while cursor.remaining() > 0 {
+                self.area_entry(index).state = cursor.decode()?;
+                index += 1;
+            }
+
+            self.areas.truncate(index);
+            return Ok(());
+        }
+
+        let dirty = cursor.decode::<PackedU32>()?.0;
+
+        for index in (0..32).filter(|i| dirty & (1 << i) != 0) {
+            self.area_entry(index).state = cursor.decode()?;
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error> {
+        if initial {
+            for area in &self.areas {
+                cursor.encode(&area.state)?;
+            }
+
+            return Ok(());
+        }
+
+        // only areas that exist can be sent
+        let len = self.areas.len().min(32) as u32;
+        let dirty = self.dirty & u32::MAX.checked_shr(32 - len).unwrap_or(0);
+
+        cursor.encode(&PackedU32(dirty))?;
+
+        for (index, area) in self.areas.iter().enumerate().take(32) {
+            if dirty & (1 << index) != 0 {
+                cursor.encode(&area.state)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voted(voted_for: Option<u8>) -> VoteState {
+        VoteState {
+            did_vote: true,
+            voted_for,
+            ..VoteState::default()
+        }
+    }
+
+    /// The meeting in `tests/fixtures/objects/meeting_spawn`.
+    fn spawned() -> MeetingHud {
+        let mut meeting = MeetingHud::new(0..4);
+        meeting.areas[1].state.dead = true;
+        meeting.areas[2].state.did_report = true;
+        meeting
+    }
+
+    #[test]
+    fn state_bytes() {
+        let known = [
+            (0x00, VoteState::default()),
+            (0x80, VoteState { dead: true, ..VoteState::default() }),
+            (0x41, voted(Some(0))),
+            (0x4f, voted(Some(14))),
+            (0x60, VoteState { did_report: true, ..voted(None) }),
+        ];
+
+        for &(byte, state) in &known {
+            assert_eq!(VoteState::from_byte(byte), state);
+            assert_eq!(state.to_byte(), Some(byte));
+        }
+
+        // the target has to fit in the low four bits
+        assert_eq!(voted(Some(15)).to_byte(), None);
+        assert!(voted(None).skipped());
+        assert!(!voted(Some(0)).skipped());
+    }
+
+    #[test]
+    fn spawn() {
+        let bytes = fixture!("objects/meeting_spawn");
+
+        let mut meeting = MeetingHud::default();
+        meeting.deserialize(&mut decode::Cursor::new(&bytes[..]), true).unwrap();
+        assert_eq!(meeting, spawned());
+
+        let mut cursor = encode::CursorMut::new();
+        meeting.serialize(&mut cursor, true).unwrap();
+        assert_eq!(cursor.as_slice(), &bytes[..]);
+    }
+
+    #[test]
+    fn update() {
+        let bytes = fixture!("objects/meeting_update");
+
+        let mut meeting = spawned();
+        assert!(meeting.cast_vote(0, Some(2)));
+        assert!(meeting.cast_vote(2, None));
+        assert_eq!(meeting.dirty, 0b101);
+
+        let mut cursor = encode::CursorMut::new();
+        meeting.serialize(&mut cursor, false).unwrap();
+        assert_eq!(cursor.as_slice(), &bytes[..]);
+
+        let mut other = spawned();
+        other.deserialize(&mut decode::Cursor::new(&bytes[..]), false).unwrap();
+        assert_eq!(other.areas, meeting.areas);
+    }
+
+    #[test]
+    fn dirty_areas_that_dont_exist() {
+        let mut meeting = MeetingHud::new(0..2);
+        meeting.dirty = u32::MAX;
+
+        let mut cursor = encode::CursorMut::new();
+        meeting.serialize(&mut cursor, false).unwrap();
+        assert_eq!(cursor.as_slice(), [0b11, 0, 0]);
+    }
+
+    #[test]
+    fn cast_vote() {
+        let mut meeting = spawned();
+
+        // dead players and players without an area can't vote
+        assert!(!meeting.cast_vote(1, Some(0)));
+        assert!(!meeting.cast_vote(9, Some(0)));
+
+        assert!(meeting.cast_vote(0, Some(3)));
+        assert!(!meeting.cast_vote(0, Some(2)), "voted twice");
+        assert_eq!(meeting.area(0).unwrap().state.voted_for, Some(3));
+
+        assert!(!meeting.all_voted());
+        assert!(meeting.cast_vote(2, Some(3)));
+        assert!(meeting.cast_vote(3, None));
+        assert!(meeting.all_voted());
+    }
+
+    #[test]
+    fn tally() {
+        let tally = |votes: &[(u8, Option<u8>)]| {
+            let mut meeting = MeetingHud::new(0..4);
+
+            for &(voter, suspect) in votes {
+                assert!(meeting.cast_vote(voter, suspect));
+            }
+
+            meeting.tally()
+        };
+
+        assert_eq!(tally(&[]), (None, false));
+        assert_eq!(tally(&[(0, Some(3)), (1, Some(3)), (2, Some(1))]), (Some(3), false));
+        assert_eq!(tally(&[(0, Some(3)), (1, Some(2))]), (None, true));
+        // skipping is counted like a player
+        assert_eq!(tally(&[(0, None), (1, None), (2, Some(1))]), (None, false));
+        assert_eq!(tally(&[(0, None), (2, Some(1))]), (None, true));
+    }
+
+    #[test]
+    fn voting_complete() {
+        let mut meeting = spawned();
+        meeting.cast_vote(0, Some(3));
+        meeting.cast_vote(2, Some(3));
+        meeting.cast_vote(3, None);
+
+        let rpc = meeting.voting_complete();
+        let expected_states: Vec<_> = meeting.areas.iter().map(|a| a.state).collect();
+
+        match &rpc {
+            Rpc::VotingComplete { states, exiled, tie } => {
+                assert_eq!(states, &expected_states);
+                assert_eq!(*exiled, Some(3));
+                assert!(!tie);
+            }
+            rpc => panic!("expected VotingComplete, got {:?}", rpc),
+        }
+
+        let mut other = spawned();
+        other.handle_voting_complete(&expected_states);
+        assert_eq!(other.areas, meeting.areas);
+    }
+}
@@ -0,0 +1,222 @@
+//! Inner net objects.
+//!
+//! Everything synced in a game is a net object made of one or more
+//! [`Component`]s, each with its own net id. Components read their whole
+//! state when spawned, and partial updates from Data messages afterwards,
+//! the same way the official client does.
+
+pub mod game_data;
+pub mod meeting;
+pub mod player;
+pub mod ship;
+pub mod systems;
+pub mod transform;
+pub mod vote_ban;
+
+pub use game_data::GameData;
+pub use meeting::MeetingHud;
+pub use player::{PlayerControl, PlayerPhysics};
+pub use ship::{Map, ShipStatus};
+pub use transform::NetworkTransform;
+pub use vote_ban::VoteBanSystem;
+
+use crate::net::binary::{decode, encode};
+use crate::net::protocol::game_data::{Spawn, SpawnComponent, SpawnType};
+
+/// A component of a net object.
+pub trait Component {
+    /// Reads the state of the component.
+    ///
+    /// `initial` is set when the component is being spawned, and the whole
+    /// state is sent instead of just what changed.
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]>;
+
+    /// Writes the state of the component.
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error>;
+}
+
+/// A component and its net id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spawned<C> {
+    pub net_id: u32,
+    pub component: C,
+}
+
+impl<C> Spawned<C>
+where C: Component + Default {
+    /// Reads a component from its spawn data.
+    pub fn from_spawn(spawn: &SpawnComponent) -> Result<Spawned<C>, decode::Error> {
+        Spawned::read_spawn(C::default(), spawn)
+    }
+}
+
+impl<C> Spawned<C>
+where C: Component {
+    /// Reads spawn data into a component that was already created.
+    ///
+    /// This is for components that need to know something before reading,
+    /// like which map a ship is.
+    pub fn read_spawn(
+        mut component: C,
+        spawn: &SpawnComponent,
+    ) -> Result<Spawned<C>, decode::Error> {
+        component.deserialize(&mut decode::Cursor::new(&spawn.data), true)?;
+
+        Ok(Spawned {
+            net_id: spawn.net_id,
+            component,
+        })
+    }
+
+    /// Writes the component as spawn data.
+    pub fn to_spawn(&self) -> Result<SpawnComponent, encode::Error> {
+        let mut cursor = encode::CursorMut::new();
+        self.component.serialize(&mut cursor, true)?;
+
+        Ok(SpawnComponent {
+            net_id: self.net_id,
+            data: cursor.into_inner(),
+        })
+    }
+}
+
+/// A component this crate doesn't know how to read.
+///
+/// Its state is kept encoded, and replaced by every update.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawComponent(pub Vec<u8>);
+
+impl Component for RawComponent {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.0 = cursor.decode_rest().to_vec();
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.write(&self.0);
+        Ok(())
+    }
+}
+
+/// The lobby. It has no state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LobbyBehaviour;
+
+impl Component for LobbyBehaviour {
+    fn deserialize<T>(
+        &mut self,
+        _cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        _cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        Ok(())
+    }
+}
+
+/// A spawned net object, with its components read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpawnedObject {
+    /// The ship of any map.
+    ShipStatus(Spawned<ShipStatus>),
+    MeetingHud(Spawned<MeetingHud>),
+    LobbyBehaviour(Spawned<LobbyBehaviour>),
+    GameData {
+        game_data: Spawned<GameData>,
+        vote_ban: Spawned<VoteBanSystem>,
+    },
+    /// A player.
+    Player {
+        control: Spawned<PlayerControl>,
+        physics: Spawned<PlayerPhysics>,
+        transform: Spawned<NetworkTransform>,
+    },
+    /// An object this crate doesn't know about.
+    Unknown(Vec<Spawned<RawComponent>>),
+}
+
+impl SpawnedObject {
+    /// Reads the components of a spawn message.
+    pub fn from_spawn(spawn: &Spawn) -> Result<SpawnedObject, decode::Error> {
+        let object = match spawn.spawn_type {
+            SpawnType::ShipStatus => ship(spawn, Map::Skeld)?,
+            SpawnType::HeadQuarters => ship(spawn, Map::MiraHq)?,
+            SpawnType::PlanetMap => ship(spawn, Map::Polus)?,
+            SpawnType::AprilShipStatus => ship(spawn, Map::AprilSkeld)?,
+            SpawnType::Airship => ship(spawn, Map::Airship)?,
+            SpawnType::MeetingHud => {
+                let [meeting] = components(spawn)?;
+                SpawnedObject::MeetingHud(Spawned::from_spawn(meeting)?)
+            }
+            SpawnType::LobbyBehaviour => {
+                let [lobby] = components(spawn)?;
+                SpawnedObject::LobbyBehaviour(Spawned::from_spawn(lobby)?)
+            }
+            SpawnType::GameData => {
+                let [game_data, vote_ban] = components(spawn)?;
+
+                SpawnedObject::GameData {
+                    game_data: Spawned::from_spawn(game_data)?,
+                    vote_ban: Spawned::from_spawn(vote_ban)?,
+                }
+            }
+            SpawnType::PlayerControl => {
+                let [control, physics, transform] = components(spawn)?;
+
+                SpawnedObject::Player {
+                    control: Spawned::from_spawn(control)?,
+                    physics: Spawned::from_spawn(physics)?,
+                    transform: Spawned::from_spawn(transform)?,
+                }
+            }
+            SpawnType::Unknown(_) => SpawnedObject::Unknown(
+                spawn.components.iter()
+                    .map(Spawned::from_spawn)
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+
+        Ok(object)
+    }
+}
+
+/// Reads the ship of a map.
+fn ship(spawn: &Spawn, map: Map) -> Result<SpawnedObject, decode::Error> {
+    let [ship] = components(spawn)?;
+
+    Ok(SpawnedObject::ShipStatus(Spawned::read_spawn(ShipStatus::new(map), ship)?))
+}
+
+/// Takes exactly `N` components from a spawn.
+fn components<const N: usize>(spawn: &Spawn) -> Result<[&SpawnComponent; N], decode::Error> {
+    if spawn.components.len() < N {
+        return Err(decode::Error::unexpected_end().context("spawn components"));
+    }
+
+    Ok(core::array::from_fn(|i| &spawn.components[i]))
+}
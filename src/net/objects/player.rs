@@ -0,0 +1,66 @@
+//! Player components.
+
+use crate::net::binary::{decode, encode};
+
+use super::Component;
+
+/// The main component of a player.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerControl {
+    /// Whether the player just joined, rather than coming back from a game.
+    pub is_new: bool,
+    pub player_id: u8,
+}
+
+impl Component for PlayerControl {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        if initial {
+            self.is_new = cursor.decode()?;
+        }
+
+        self.player_id = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error> {
+        if initial {
+            cursor.encode(&self.is_new)?;
+        }
+
+        cursor.encode(&self.player_id)
+    }
+}
+
+/// Handles player movement, like walking and venting.
+///
+/// It has no state of its own, and only sends RPCs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayerPhysics;
+
+impl Component for PlayerPhysics {
+    fn deserialize<T>(
+        &mut self,
+        _cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        _cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        Ok(())
+    }
+}
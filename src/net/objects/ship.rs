@@ -0,0 +1,365 @@
+//! The ship and its systems.
+//!
+//! When spawned, every system is sent in order of system type. After that,
+//! updates start with a packed mask of the system types that changed, and only
+//! those systems follow.
+
+use std::collections::BTreeMap;
+
+use crate::net::binary::{decode, encode, packed::PackedU32};
+use crate::net::protocol::game_data::SpawnType;
+
+use super::Component;
+use super::systems::{self, System};
+
+/// How many doors the Skeld has.
+pub const SKELD_DOORS: usize = 13;
+/// How many doors Polus has.
+pub const POLUS_DOORS: usize = 12;
+/// How many doors the Airship has.
+pub const AIRSHIP_DOORS: usize = 21;
+
+/// A map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Map {
+    Skeld,
+    MiraHq,
+    Polus,
+    /// The upside down Skeld from April Fools.
+    AprilSkeld,
+    Airship,
+}
+
+impl Map {
+    /// The id of the map, as in the game options.
+    pub fn id(self) -> u8 {
+        match self {
+            Map::Skeld => 0,
+            Map::MiraHq => 1,
+            Map::Polus => 2,
+            Map::AprilSkeld => 3,
+            Map::Airship => 4,
+        }
+    }
+
+    /// The map of an id.
+    pub fn from_id(id: u8) -> Option<Map> {
+        match id {
+            0 => Some(Map::Skeld),
+            1 => Some(Map::MiraHq),
+            2 => Some(Map::Polus),
+            3 => Some(Map::AprilSkeld),
+            4 => Some(Map::Airship),
+            _ => None,
+        }
+    }
+
+    /// The spawn type of the map's ship.
+    pub fn spawn_type(self) -> SpawnType {
+        match self {
+            Map::Skeld => SpawnType::ShipStatus,
+            Map::MiraHq => SpawnType::HeadQuarters,
+            Map::Polus => SpawnType::PlanetMap,
+            Map::AprilSkeld => SpawnType::AprilShipStatus,
+            Map::Airship => SpawnType::Airship,
+        }
+    }
+
+    /// The map a ship was spawned as, if it is a ship.
+    pub fn from_spawn_type(spawn_type: SpawnType) -> Option<Map> {
+        match spawn_type {
+            SpawnType::ShipStatus => Some(Map::Skeld),
+            SpawnType::HeadQuarters => Some(Map::MiraHq),
+            SpawnType::PlanetMap => Some(Map::Polus),
+            SpawnType::AprilShipStatus => Some(Map::AprilSkeld),
+            SpawnType::Airship => Some(Map::Airship),
+            _ => None,
+        }
+    }
+}
+
+/// The ship of a map.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShipStatus {
+    pub map: Map,
+    /// The systems of the ship, by system type.
+    pub systems: BTreeMap<u8, System>,
+    /// The system types that changed since the last update, as bits.
+    pub dirty: u32,
+}
+
+impl ShipStatus {
+    /// Create a new ship with the systems of a map.
+    pub fn new(map: Map) -> ShipStatus {
+        let mut ship = ShipStatus {
+            map,
+            systems: BTreeMap::new(),
+            dirty: 0,
+        };
+
+        ship.add(systems::ELECTRICAL, System::Switch(Default::default()));
+        ship.add(systems::MED_BAY, System::MedScan(Default::default()));
+        ship.add(systems::SABOTAGE, System::Sabotage(Default::default()));
+
+        match map {
+            Map::Skeld | Map::AprilSkeld => {
+                ship.add(systems::REACTOR, System::Reactor(Default::default()));
+                ship.add(systems::LIFE_SUPP, System::LifeSupp(Default::default()));
+                ship.add(systems::SECURITY, System::SecurityCamera(Default::default()));
+                ship.add(systems::COMMS, System::HudOverride(Default::default()));
+                ship.add(
+                    systems::DOORS,
+                    System::AutoDoors(systems::AutoDoorsSystem::new(SKELD_DOORS)),
+                );
+            }
+            Map::MiraHq => {
+                ship.add(systems::REACTOR, System::Reactor(Default::default()));
+                ship.add(systems::LIFE_SUPP, System::LifeSupp(Default::default()));
+                ship.add(systems::COMMS, System::HqHud(Default::default()));
+                ship.add(systems::DECONTAMINATION, System::Decon(Default::default()));
+            }
+            Map::Polus => {
+                ship.add(systems::LABORATORY, System::Reactor(Default::default()));
+                ship.add(systems::SECURITY, System::SecurityCamera(Default::default()));
+                ship.add(systems::COMMS, System::HudOverride(Default::default()));
+                ship.add(
+                    systems::DOORS,
+                    System::Doors(systems::DoorsSystem::new(POLUS_DOORS)),
+                );
+                ship.add(systems::DECONTAMINATION, System::Decon(Default::default()));
+                ship.add(systems::DECONTAMINATION_2, System::Decon(Default::default()));
+            }
+            Map::Airship => {
+                ship.add(systems::REACTOR, System::HeliSabotage(Default::default()));
+                ship.add(systems::SECURITY, System::SecurityCamera(Default::default()));
+                ship.add(systems::COMMS, System::HudOverride(Default::default()));
+                ship.add(
+                    systems::DOORS,
+                    System::Doors(systems::DoorsSystem::new(AIRSHIP_DOORS)),
+                );
+            }
+        }
+
+        ship
+    }
+
+    /// A system of the ship.
+    pub fn system(&self, system_type: u8) -> Option<&System> {
+        self.systems.get(&system_type)
+    }
+
+    /// A system of the ship, mutably. This marks it dirty.
+    pub fn system_mut(&mut self, system_type: u8) -> Option<&mut System> {
+        let system = self.systems.get_mut(&system_type)?;
+        self.dirty |= 1u32.checked_shl(system_type as u32).unwrap_or(0);
+
+        Some(system)
+    }
+
+    /// Forgets what changed, after an update was sent.
+    pub fn clear_dirty(&mut self) {
+        self.dirty = 0;
+
+        for system in self.systems.values_mut() {
+            if let System::AutoDoors(doors) = system {
+                doors.dirty = 0;
+            }
+        }
+    }
+
+    fn add(&mut self, system_type: u8, system: System) {
+        self.systems.insert(system_type, system);
+    }
+}
+
+impl Component for ShipStatus {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        let dirty = if initial {
+            u32::MAX
+        } else {
+            cursor.decode::<PackedU32>()?.0
+        };
+
+        for (&system_type, system) in self.systems.iter_mut() {
+            if dirty & 1u32.checked_shl(system_type as u32).unwrap_or(0) != 0 {
+                system.deserialize(cursor, initial)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error> {
+        let dirty = if initial {
+            u32::MAX
+        } else {
+            // only systems the ship has can be sent
+            let dirty = self.systems.keys()
+                .map(|&t| 1u32.checked_shl(t as u32).unwrap_or(0))
+                .fold(0, |mask, bit| mask | bit)
+                & self.dirty;
+
+            cursor.encode(&PackedU32(dirty))?;
+            dirty
+        };
+
+        for (&system_type, system) in &self.systems {
+            if dirty & 1u32.checked_shl(system_type as u32).unwrap_or(0) != 0 {
+                system.serialize(cursor, initial)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use systems::*;
+
+    const MAPS: [Map; 5] = [Map::Skeld, Map::MiraHq, Map::Polus, Map::AprilSkeld, Map::Airship];
+
+    fn read(map: Map, bytes: &[u8]) -> ShipStatus {
+        let mut ship = ShipStatus::new(map);
+        ship.deserialize(&mut decode::Cursor::new(bytes), true).unwrap();
+        ship
+    }
+
+    fn write(ship: &ShipStatus, initial: bool) -> Vec<u8> {
+        let mut cursor = encode::CursorMut::new();
+        ship.serialize(&mut cursor, initial).unwrap();
+        cursor.into()
+    }
+
+    /// The ship in `tests/fixtures/objects/ship_skeld_spawn`.
+    fn skeld() -> ShipStatus {
+        let mut ship = ShipStatus::new(Map::Skeld);
+
+        ship.systems.insert(REACTOR, System::Reactor(ReactorSystem {
+            countdown: 30.0,
+            user_consoles: vec![(1, 0)],
+        }));
+        ship.systems.insert(ELECTRICAL, System::Switch(SwitchSystem {
+            expected: 0x1f,
+            actual: 0x0a,
+            value: 0x55,
+        }));
+        ship.systems.insert(MED_BAY, System::MedScan(MedScanSystem { users: vec![2] }));
+        ship.systems.insert(COMMS, System::HudOverride(HudOverrideSystem { active: true }));
+        ship.systems.insert(SABOTAGE, System::Sabotage(SabotageSystem { timer: 30.0 }));
+
+        let mut doors = AutoDoorsSystem::new(SKELD_DOORS);
+        doors.doors[2] = false;
+        ship.systems.insert(DOORS, System::AutoDoors(doors));
+
+        ship
+    }
+
+    #[test]
+    fn systems_per_map() {
+        let types = |map| ShipStatus::new(map).systems.keys().copied().collect::<Vec<_>>();
+
+        assert_eq!(types(Map::Skeld), [3, 7, 8, 10, 11, 14, 16, 17]);
+        assert_eq!(types(Map::AprilSkeld), types(Map::Skeld));
+        assert_eq!(types(Map::MiraHq), [3, 7, 8, 10, 14, 17, 18]);
+        assert_eq!(types(Map::Polus), [7, 10, 11, 14, 16, 17, 18, 21, 26]);
+        assert_eq!(types(Map::Airship), [3, 7, 10, 11, 14, 16, 17]);
+    }
+
+    #[test]
+    fn map_ids() {
+        for &map in &MAPS {
+            assert_eq!(Map::from_id(map.id()), Some(map));
+            assert_eq!(Map::from_spawn_type(map.spawn_type()), Some(map));
+        }
+
+        assert_eq!(Map::from_id(5), None);
+        assert_eq!(Map::from_spawn_type(SpawnType::GameData), None);
+    }
+
+    #[test]
+    fn spawn_round_trip() {
+        for &map in &MAPS {
+            let ship = ShipStatus::new(map);
+            assert_eq!(read(map, &write(&ship, true)), ship, "{:?}", map);
+        }
+    }
+
+    #[test]
+    fn skeld_spawn() {
+        let bytes = fixture!("objects/ship_skeld_spawn");
+
+        assert_eq!(read(Map::Skeld, &bytes), skeld());
+        assert_eq!(write(&skeld(), true), bytes);
+    }
+
+    #[test]
+    fn mira_spawn() {
+        let bytes = fixture!("objects/ship_mira_spawn");
+
+        let mut ship = ShipStatus::new(Map::MiraHq);
+        ship.systems.insert(LIFE_SUPP, System::LifeSupp(LifeSuppSystem {
+            countdown: 45.0,
+            completed_consoles: vec![1],
+        }));
+        ship.systems.insert(COMMS, System::HqHud(HqHudSystem {
+            active_consoles: vec![(3, 0)],
+            completed_consoles: vec![1],
+        }));
+        ship.systems.insert(DECONTAMINATION, System::Decon(DeconSystem {
+            timer: 3,
+            state: DECON_CLOSED | DECON_EXIT,
+        }));
+
+        assert_eq!(read(Map::MiraHq, &bytes), ship);
+        assert_eq!(write(&ship, true), bytes);
+    }
+
+    #[test]
+    fn update() {
+        let bytes = fixture!("objects/ship_skeld_update");
+
+        let mut ship = skeld();
+
+        if let Some(System::Switch(switch)) = ship.system_mut(ELECTRICAL) {
+            switch.actual = switch.expected;
+            switch.value = u8::MAX;
+        }
+
+        if let Some(System::AutoDoors(doors)) = ship.system_mut(DOORS) {
+            doors.set_door(2, true);
+        }
+
+        assert_eq!(write(&ship, false), bytes);
+
+        ship.clear_dirty();
+        assert_eq!(ship.dirty, 0);
+
+        // the doors forget what changed too, so the ships compare equal
+        let mut other = skeld();
+        other.deserialize(&mut decode::Cursor::new(&bytes[..]), false).unwrap();
+        assert_eq!(other, ship);
+
+        assert_eq!(write(&ship, false), [0]);
+    }
+
+    #[test]
+    fn missing_systems_arent_sent() {
+        let mut ship = ShipStatus::new(Map::Skeld);
+        assert!(ship.system_mut(LABORATORY).is_none());
+
+        ship.dirty = 1 << LABORATORY | 1 << COMMS;
+        assert_eq!(write(&ship, false), [0x80, 0x80, 0x01, 0x00]);
+    }
+}
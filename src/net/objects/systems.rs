@@ -0,0 +1,641 @@
+//! The systems of a ship.
+//!
+//! Each system is identified by its system type, the same id RepairSystem
+//! uses. Which systems a ship has depends on the map, and most are sent whole
+//! every time they change.
+//!
+//! Rooms are identified by system type too, even the ones without a system.
+
+use crate::net::binary::{decode, encode, packed::PackedU32};
+
+use super::Component;
+
+pub const HALLWAY: u8 = 0;
+pub const STORAGE: u8 = 1;
+pub const CAFETERIA: u8 = 2;
+pub const REACTOR: u8 = 3;
+pub const UPPER_ENGINE: u8 = 4;
+pub const NAV: u8 = 5;
+pub const ADMIN: u8 = 6;
+pub const ELECTRICAL: u8 = 7;
+pub const LIFE_SUPP: u8 = 8;
+pub const SHIELDS: u8 = 9;
+pub const MED_BAY: u8 = 10;
+pub const SECURITY: u8 = 11;
+pub const WEAPONS: u8 = 12;
+pub const LOWER_ENGINE: u8 = 13;
+pub const COMMS: u8 = 14;
+pub const SHIP_TASKS: u8 = 15;
+pub const DOORS: u8 = 16;
+pub const SABOTAGE: u8 = 17;
+pub const DECONTAMINATION: u8 = 18;
+pub const LAUNCHPAD: u8 = 19;
+pub const LOCKER_ROOM: u8 = 20;
+pub const LABORATORY: u8 = 21;
+pub const BALCONY: u8 = 22;
+pub const OFFICE: u8 = 23;
+pub const GREENHOUSE: u8 = 24;
+pub const DROPSHIP: u8 = 25;
+pub const DECONTAMINATION_2: u8 = 26;
+pub const OUTSIDE: u8 = 27;
+pub const SPECIMENS: u8 = 28;
+pub const BOILER_ROOM: u8 = 29;
+pub const VAULT_ROOM: u8 = 30;
+pub const COCKPIT: u8 = 31;
+pub const ARMORY: u8 = 32;
+pub const KITCHEN: u8 = 33;
+pub const VIEWING_DECK: u8 = 34;
+pub const HALL_OF_PORTRAITS: u8 = 35;
+pub const CARGO_BAY: u8 = 36;
+pub const VENTILATION: u8 = 37;
+pub const SHOWERS: u8 = 38;
+pub const ENGINE: u8 = 39;
+pub const BRIG: u8 = 40;
+pub const MEETING_ROOM: u8 = 41;
+pub const RECORDS: u8 = 42;
+pub const LOUNGE: u8 = 43;
+pub const GAP_ROOM: u8 = 44;
+pub const MAIN_HALL: u8 = 45;
+pub const MEDICAL: u8 = 46;
+
+/// The countdown of a critical sabotage that isn't happening.
+pub const NOT_SABOTAGED: f32 = 10000.0;
+
+/// A system of a ship.
+#[derive(Clone, Debug, PartialEq)]
+pub enum System {
+    Reactor(ReactorSystem),
+    /// The oxygen of the Skeld and Mira HQ.
+    LifeSupp(LifeSuppSystem),
+    /// The lights.
+    Switch(SwitchSystem),
+    /// The comms of every map but Mira HQ.
+    HudOverride(HudOverrideSystem),
+    /// The comms of Mira HQ.
+    HqHud(HqHudSystem),
+    MedScan(MedScanSystem),
+    SecurityCamera(SecurityCameraSystem),
+    /// The doors of the Skeld.
+    AutoDoors(AutoDoorsSystem),
+    /// The doors of Polus and the Airship.
+    Doors(DoorsSystem),
+    Sabotage(SabotageSystem),
+    Decon(DeconSystem),
+    /// The crash course of the Airship.
+    HeliSabotage(HeliSabotageSystem),
+}
+
+impl Component for System {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        match self {
+            System::Reactor(system) => system.deserialize(cursor, initial),
+            System::LifeSupp(system) => system.deserialize(cursor, initial),
+            System::Switch(system) => system.deserialize(cursor, initial),
+            System::HudOverride(system) => system.deserialize(cursor, initial),
+            System::HqHud(system) => system.deserialize(cursor, initial),
+            System::MedScan(system) => system.deserialize(cursor, initial),
+            System::SecurityCamera(system) => system.deserialize(cursor, initial),
+            System::AutoDoors(system) => system.deserialize(cursor, initial),
+            System::Doors(system) => system.deserialize(cursor, initial),
+            System::Sabotage(system) => system.deserialize(cursor, initial),
+            System::Decon(system) => system.deserialize(cursor, initial),
+            System::HeliSabotage(system) => system.deserialize(cursor, initial),
+        }
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error> {
+        match self {
+            System::Reactor(system) => system.serialize(cursor, initial),
+            System::LifeSupp(system) => system.serialize(cursor, initial),
+            System::Switch(system) => system.serialize(cursor, initial),
+            System::HudOverride(system) => system.serialize(cursor, initial),
+            System::HqHud(system) => system.serialize(cursor, initial),
+            System::MedScan(system) => system.serialize(cursor, initial),
+            System::SecurityCamera(system) => system.serialize(cursor, initial),
+            System::AutoDoors(system) => system.serialize(cursor, initial),
+            System::Doors(system) => system.serialize(cursor, initial),
+            System::Sabotage(system) => system.serialize(cursor, initial),
+            System::Decon(system) => system.serialize(cursor, initial),
+            System::HeliSabotage(system) => system.serialize(cursor, initial),
+        }
+    }
+}
+
+/// A sabotage that has to be fixed from two consoles at once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReactorSystem {
+    /// Seconds until the impostors win, or [`NOT_SABOTAGED`].
+    pub countdown: f32,
+    /// The players holding a console, and which console.
+    pub user_consoles: Vec<(u8, u8)>,
+}
+
+impl Default for ReactorSystem {
+    fn default() -> ReactorSystem {
+        ReactorSystem {
+            countdown: NOT_SABOTAGED,
+            user_consoles: Vec::new(),
+        }
+    }
+}
+
+impl Component for ReactorSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.countdown = cursor.decode()?;
+        self.user_consoles = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.countdown)?;
+        cursor.encode(&self.user_consoles)
+    }
+}
+
+/// The oxygen sabotage.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LifeSuppSystem {
+    /// Seconds until the impostors win, or [`NOT_SABOTAGED`].
+    pub countdown: f32,
+    /// The consoles that have been fixed.
+    pub completed_consoles: Vec<u32>,
+}
+
+impl Default for LifeSuppSystem {
+    fn default() -> LifeSuppSystem {
+        LifeSuppSystem {
+            countdown: NOT_SABOTAGED,
+            completed_consoles: Vec::new(),
+        }
+    }
+}
+
+impl Component for LifeSuppSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.countdown = cursor.decode()?;
+        self.completed_consoles = cursor.decode::<Vec<PackedU32>>()?
+            .into_iter()
+            .map(|c| c.0)
+            .collect();
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        let consoles = self.completed_consoles.iter()
+            .map(|&c| PackedU32(c))
+            .collect::<Vec<_>>();
+
+        cursor.encode(&self.countdown)?;
+        cursor.encode(&consoles)
+    }
+}
+
+/// The lights, and the switches that fix them.
+///
+/// The switches are bitmasks, one bit per switch. The lights are on when every
+/// switch matches what is expected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwitchSystem {
+    pub expected: u8,
+    pub actual: u8,
+    /// How bright the lights are, from `0` to `255`.
+    pub value: u8,
+}
+
+impl Default for SwitchSystem {
+    fn default() -> SwitchSystem {
+        SwitchSystem {
+            expected: 0,
+            actual: 0,
+            value: u8::MAX,
+        }
+    }
+}
+
+impl Component for SwitchSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.expected = cursor.decode()?;
+        self.actual = cursor.decode()?;
+        self.value = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.expected)?;
+        cursor.encode(&self.actual)?;
+        cursor.encode(&self.value)
+    }
+}
+
+/// The comms sabotage, fixed from a single console.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HudOverrideSystem {
+    pub active: bool,
+}
+
+impl Component for HudOverrideSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.active = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.active)
+    }
+}
+
+/// The comms sabotage of Mira HQ, fixed from two consoles.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HqHudSystem {
+    /// The players using a console, and which console.
+    pub active_consoles: Vec<(u8, u8)>,
+    /// The consoles that have been fixed.
+    pub completed_consoles: Vec<u8>,
+}
+
+impl Component for HqHudSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.active_consoles = cursor.decode()?;
+        self.completed_consoles = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.active_consoles)?;
+        cursor.encode(&self.completed_consoles)
+    }
+}
+
+/// The queue for the medbay scanner.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MedScanSystem {
+    pub users: Vec<u8>,
+}
+
+impl Component for MedScanSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.users = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.users)
+    }
+}
+
+/// The players watching the cameras.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SecurityCameraSystem {
+    pub users: Vec<u8>,
+}
+
+impl Component for SecurityCameraSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        // unlike most lists, this one has a byte for its length
+        let count = cursor.decode::<u8>()? as usize;
+        cursor.claim_collection::<u8>(count)?;
+
+        self.users.clear();
+
+        for _ in 0..count {
+            self.users.push(cursor.decode()?);
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        if self.users.len() > u8::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        cursor.encode(&(self.users.len() as u8))?;
+        cursor.write(&self.users);
+        Ok(())
+    }
+}
+
+/// The doors of the Skeld, which open by themselves after a while.
+///
+/// Like the ship, updates start with a packed mask of the doors that changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AutoDoorsSystem {
+    /// Whether each door is open.
+    pub doors: Vec<bool>,
+    /// The doors that changed since the last update, by index.
+    pub dirty: u32,
+}
+
+impl AutoDoorsSystem {
+    /// Create a new system with every door open.
+    pub fn new(count: usize) -> AutoDoorsSystem {
+        AutoDoorsSystem {
+            doors: vec![true; count],
+            dirty: 0,
+        }
+    }
+
+    /// Opens or closes a door, marking it dirty.
+    pub fn set_door(&mut self, index: usize, open: bool) {
+        if let Some(door) = self.doors.get_mut(index) {
+            *door = open;
+            self.dirty |= 1u32.checked_shl(index as u32).unwrap_or(0);
+        }
+    }
+}
+
+impl Component for AutoDoorsSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        let dirty = if initial {
+            u32::MAX
+        } else {
+            cursor.decode::<PackedU32>()?.0
+        };
+
+        for (index, door) in self.doors.iter_mut().enumerate() {
+            if index >= 32 || dirty & (1 << index) != 0 {
+                *door = cursor.decode()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        initial: bool,
+    ) -> Result<(), encode::Error> {
+        if !initial {
+            cursor.encode(&PackedU32(self.dirty))?;
+        }
+
+        for (index, door) in self.doors.iter().enumerate() {
+            if initial || index >= 32 || self.dirty & (1 << index) != 0 {
+                cursor.encode(door)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The doors of Polus and the Airship, which have to be opened by hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DoorsSystem {
+    /// The rooms whose doors were closed recently, and the seconds until they
+    /// can be closed again.
+    pub timers: Vec<(u8, f32)>,
+    /// Whether each door is open.
+    pub doors: Vec<bool>,
+}
+
+impl DoorsSystem {
+    /// Create a new system with every door open.
+    pub fn new(count: usize) -> DoorsSystem {
+        DoorsSystem {
+            timers: Vec::new(),
+            doors: vec![true; count],
+        }
+    }
+}
+
+impl Component for DoorsSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        let count = cursor.decode::<u8>()? as usize;
+        cursor.claim_collection::<(u8, f32)>(count)?;
+
+        self.timers.clear();
+
+        for _ in 0..count {
+            self.timers.push(cursor.decode()?);
+        }
+
+        for door in self.doors.iter_mut() {
+            *door = cursor.decode()?;
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        if self.timers.len() > u8::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        cursor.encode(&(self.timers.len() as u8))?;
+
+        for timer in &self.timers {
+            cursor.encode(timer)?;
+        }
+
+        for door in &self.doors {
+            cursor.encode(door)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The cooldown between sabotages.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SabotageSystem {
+    /// Seconds until the impostors can sabotage again.
+    pub timer: f32,
+}
+
+impl Component for SabotageSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.timer = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.timer)
+    }
+}
+
+/// A decontamination door is opening to let players in.
+pub const DECON_ENTER: u8 = 1;
+/// Both decontamination doors are closed.
+pub const DECON_CLOSED: u8 = 2;
+/// A decontamination door is opening to let players out.
+pub const DECON_EXIT: u8 = 4;
+/// Players in the decontamination room are heading up, not down.
+pub const DECON_HEADING_UP: u8 = 8;
+
+/// A decontamination room.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeconSystem {
+    /// Seconds left in the current state, rounded up.
+    pub timer: u8,
+    /// The state of the doors, as `DECON_*` flags.
+    pub state: u8,
+}
+
+impl Component for DeconSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.timer = cursor.decode()?;
+        self.state = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.timer)?;
+        cursor.encode(&self.state)
+    }
+}
+
+/// The crash course sabotage, fixed from two consoles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeliSabotageSystem {
+    /// Seconds until the impostors win, or [`NOT_SABOTAGED`].
+    pub countdown: f32,
+    /// Seconds until a half-entered code resets.
+    pub timer: f32,
+    /// The players using a console, and which console.
+    pub active_consoles: Vec<(u8, u8)>,
+    /// The consoles that have been fixed.
+    pub completed_consoles: Vec<u8>,
+}
+
+impl Default for HeliSabotageSystem {
+    fn default() -> HeliSabotageSystem {
+        HeliSabotageSystem {
+            countdown: NOT_SABOTAGED,
+            timer: 0.0,
+            active_consoles: Vec::new(),
+            completed_consoles: Vec::new(),
+        }
+    }
+}
+
+impl Component for HeliSabotageSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        self.countdown = cursor.decode()?;
+        self.timer = cursor.decode()?;
+        self.active_consoles = cursor.decode()?;
+        self.completed_consoles = cursor.decode()?;
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.countdown)?;
+        cursor.encode(&self.timer)?;
+        cursor.encode(&self.active_consoles)?;
+        cursor.encode(&self.completed_consoles)
+    }
+}
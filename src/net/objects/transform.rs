@@ -0,0 +1,104 @@
+//! Player movement.
+//!
+//! Movement is sent unreliably, so updates can arrive late or out of order.
+//! Each update carries a [`SeqNum`], and anything not newer than the last one
+//! applied is ignored.
+
+use crate::math::Vector2;
+use crate::math::quantize::QuantizedVector2;
+use crate::net::binary::{decode, encode};
+use crate::net::protocol::Rpc;
+use crate::net::seq::SeqNum;
+
+use super::Component;
+
+/// How far a snap jumps the sequence id ahead, so movement sent just before
+/// it can't undo it.
+const SNAP_SKIP: u16 = 5;
+
+/// Syncs the position of a player.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkTransform {
+    /// The sequence id of the last update.
+    pub sequence: SeqNum,
+    pub position: Vector2,
+    pub velocity: Vector2,
+}
+
+impl NetworkTransform {
+    /// Moves the player locally, returning the sequence id of the update.
+    ///
+    /// The update should then be sent with [`serialize`](Component::serialize).
+    pub fn update(&mut self, position: Vector2, velocity: Vector2) -> SeqNum {
+        self.sequence = self.sequence.next();
+        self.position = position;
+        self.velocity = velocity;
+        self.sequence
+    }
+
+    /// Teleports the player locally, returning the RPC to send.
+    pub fn snap_to(&mut self, position: Vector2) -> Rpc {
+        self.sequence = self.sequence + SNAP_SKIP;
+        self.position = position;
+        self.velocity = Vector2::zeros();
+
+        Rpc::SnapTo {
+            position,
+            sequence: self.sequence,
+        }
+    }
+
+    /// Handles a SnapTo RPC, returning whether it was applied.
+    pub fn handle_snap_to(&mut self, position: Vector2, sequence: SeqNum) -> bool {
+        if !sequence.is_after(self.sequence) {
+            return false;
+        }
+
+        self.sequence = sequence;
+        self.position = position;
+        self.velocity = Vector2::zeros();
+        true
+    }
+}
+
+impl Default for NetworkTransform {
+    fn default() -> NetworkTransform {
+        NetworkTransform {
+            sequence: SeqNum(0),
+            position: Vector2::zeros(),
+            velocity: Vector2::zeros(),
+        }
+    }
+}
+
+impl Component for NetworkTransform {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        let sequence = cursor.decode::<SeqNum>()?;
+        let position = cursor.decode::<QuantizedVector2>()?.0;
+        let velocity = cursor.decode::<QuantizedVector2>()?.0;
+
+        // stale updates are still read, just not applied
+        if initial || sequence.is_after(self.sequence) {
+            self.sequence = sequence;
+            self.position = position;
+            self.velocity = velocity;
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        cursor.encode(&self.sequence)?;
+        cursor.encode(&QuantizedVector2(self.position))?;
+        cursor.encode(&QuantizedVector2(self.velocity))
+    }
+}
@@ -0,0 +1,116 @@
+//! Vote kicking.
+
+use std::collections::BTreeMap;
+
+use crate::net::binary::{decode, encode, packed::PackedI32};
+
+use super::Component;
+
+/// How many votes it takes to kick a player.
+pub const VOTES_TO_KICK: usize = 3;
+
+/// What happened when a vote was added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoteResult {
+    /// The vote was counted.
+    Added,
+    /// The voter already voted against this player.
+    AlreadyVoted,
+    /// The vote was the last one needed, and the player should be kicked.
+    Kick,
+}
+
+/// Tracks votes to kick players from the lobby.
+///
+/// This is the second component of the GameData object. Only the host counts
+/// votes; everyone else just mirrors them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VoteBanSystem {
+    /// The voters against each client. Empty slots are `0`.
+    pub votes: BTreeMap<i32, [i32; VOTES_TO_KICK]>,
+}
+
+impl VoteBanSystem {
+    /// Counts a vote from one client against another, as AddVote does.
+    pub fn add_vote(&mut self, voter: i32, target: i32) -> VoteResult {
+        let votes = self.votes.entry(target).or_insert([0; VOTES_TO_KICK]);
+
+        if votes.contains(&voter) {
+            return VoteResult::AlreadyVoted;
+        }
+
+        match votes.iter().position(|&v| v == 0) {
+            Some(slot) => {
+                votes[slot] = voter;
+
+                if slot == VOTES_TO_KICK - 1 {
+                    VoteResult::Kick
+                } else {
+                    VoteResult::Added
+                }
+            }
+            // already kicked, but the player hasn't left yet
+            None => VoteResult::AlreadyVoted,
+        }
+    }
+
+    /// Forgets the votes against and by a client that left.
+    pub fn remove_client(&mut self, client_id: i32) {
+        self.votes.remove(&client_id);
+
+        for votes in self.votes.values_mut() {
+            for vote in votes.iter_mut().filter(|v| **v == client_id) {
+                *vote = 0;
+            }
+        }
+    }
+}
+
+impl Component for VoteBanSystem {
+    fn deserialize<T>(
+        &mut self,
+        cursor: &mut decode::Cursor<T>,
+        _initial: bool,
+    ) -> Result<(), decode::Error>
+    where T: AsRef<[u8]> {
+        let count = cursor.decode::<u8>()? as usize;
+        cursor.claim_collection::<(i32, [i32; VOTES_TO_KICK])>(count)?;
+
+        self.votes.clear();
+
+        for _ in 0..count {
+            let target = cursor.decode()?;
+            let mut votes = [0; VOTES_TO_KICK];
+
+            for vote in votes.iter_mut() {
+                *vote = cursor.decode::<PackedI32>()?.0;
+            }
+
+            self.votes.insert(target, votes);
+        }
+
+        Ok(())
+    }
+
+    fn serialize(
+        &self,
+        cursor: &mut encode::CursorMut,
+        _initial: bool,
+    ) -> Result<(), encode::Error> {
+        if self.votes.len() > u8::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        cursor.encode(&(self.votes.len() as u8))?;
+
+        for (target, votes) in &self.votes {
+            cursor.encode(target)?;
+
+            for vote in votes {
+                cursor.encode(&PackedI32(*vote))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Reading `.pcap` files.
+//!
+//! Only the classic libpcap format is read, not pcapng. UDP over IPv4 or IPv6
+//! is taken out of Ethernet, Linux cooked, loopback and raw IP captures, and
+//! everything else is skipped. IP fragments are skipped too.
+
+use std::io::{self, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use super::Datagram;
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const IP_PROTO_UDP: u8 = 17;
+
+/// Reads the UDP datagrams out of a `.pcap` file.
+pub struct PcapReader<R> {
+    reader: R,
+    swapped: bool,
+    nanos: bool,
+    link_type: u32,
+}
+
+impl<R> PcapReader<R>
+where R: Read {
+    /// Reads the header of a capture.
+    pub fn new(mut reader: R) -> io::Result<PcapReader<R>> {
+        let mut header = [0; 24];
+        reader.read_exact(&mut header)?;
+
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+
+        let (swapped, nanos) = if magic == MAGIC_MICROS {
+            (false, false)
+        } else if magic == MAGIC_NANOS {
+            (false, true)
+        } else if magic.swap_bytes() == MAGIC_MICROS {
+            (true, false)
+        } else if magic.swap_bytes() == MAGIC_NANOS {
+            (true, true)
+        } else {
+            return Err(invalid("not a pcap file"));
+        };
+
+        let mut pcap = PcapReader {
+            reader,
+            swapped,
+            nanos,
+            link_type: 0,
+        };
+
+        pcap.link_type = pcap.u32_at(&header, 20);
+        Ok(pcap)
+    }
+
+    /// The link type of the capture.
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    /// Reads the next UDP datagram, or `None` at the end of the file.
+    pub fn next_datagram(&mut self) -> io::Result<Option<Datagram>> {
+        loop {
+            let mut header = [0; 16];
+
+            match self.reader.read_exact(&mut header) {
+                Ok(()) => (),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err),
+            }
+
+            let seconds = self.u32_at(&header, 0) as u64;
+            let fraction = self.u32_at(&header, 4);
+            let len = self.u32_at(&header, 8) as usize;
+
+            let mut frame = vec![0; len];
+            self.reader.read_exact(&mut frame)?;
+
+            let time = if self.nanos {
+                Duration::new(seconds, fraction)
+            } else {
+                Duration::new(seconds, fraction.saturating_mul(1000))
+            };
+
+            if let Some(datagram) = self.parse_frame(&frame, time) {
+                return Ok(Some(datagram));
+            }
+        }
+    }
+
+    fn parse_frame(&self, frame: &[u8], time: Duration) -> Option<Datagram> {
+        let (ethertype, ip) = match self.link_type {
+            LINKTYPE_NULL => {
+                // the family is in the byte order of the capturing machine,
+                // but the ip version is in the packet anyway
+                (None, frame.get(4..)?)
+            }
+            LINKTYPE_ETHERNET => {
+                let mut ethertype = u16_be(frame, 12)?;
+                let mut offset = 14;
+
+                if ethertype == ETHERTYPE_VLAN {
+                    ethertype = u16_be(frame, 16)?;
+                    offset = 18;
+                }
+
+                (Some(ethertype), frame.get(offset..)?)
+            }
+            LINKTYPE_RAW => (None, frame),
+            LINKTYPE_LINUX_SLL => (Some(u16_be(frame, 14)?), frame.get(16..)?),
+            LINKTYPE_LINUX_SLL2 => (Some(u16_be(frame, 0)?), frame.get(20..)?),
+            _ => return None,
+        };
+
+        let version = ip.first()? >> 4;
+
+        match (ethertype, version) {
+            (None, 4) | (Some(ETHERTYPE_IPV4), 4) => parse_ipv4(ip, time),
+            (None, 6) | (Some(ETHERTYPE_IPV6), 6) => parse_ipv6(ip, time),
+            _ => None,
+        }
+    }
+
+    fn u32_at(&self, bytes: &[u8], at: usize) -> u32 {
+        let value = u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+
+        if self.swapped {
+            value.swap_bytes()
+        } else {
+            value
+        }
+    }
+}
+
+impl<R> Iterator for PcapReader<R>
+where R: Read {
+    type Item = io::Result<Datagram>;
+
+    fn next(&mut self) -> Option<io::Result<Datagram>> {
+        self.next_datagram().transpose()
+    }
+}
+
+fn parse_ipv4(ip: &[u8], time: Duration) -> Option<Datagram> {
+    let header_len = (*ip.first()? & 0x0f) as usize * 4;
+    let total_len = u16_be(ip, 2)? as usize;
+    let fragment = u16_be(ip, 6)?;
+
+    // more fragments, or not the first one
+    if fragment & 0x3fff != 0 || *ip.get(9)? != IP_PROTO_UDP {
+        return None;
+    }
+
+    let addrs = ip.get(12..20)?;
+    let source = Ipv4Addr::new(addrs[0], addrs[1], addrs[2], addrs[3]);
+    let destination = Ipv4Addr::new(addrs[4], addrs[5], addrs[6], addrs[7]);
+    let udp = ip.get(header_len..total_len.min(ip.len()))?;
+
+    parse_udp(udp, source.into(), destination.into(), time)
+}
+
+fn parse_ipv6(ip: &[u8], time: Duration) -> Option<Datagram> {
+    // extension headers aren't followed
+    if *ip.get(6)? != IP_PROTO_UDP {
+        return None;
+    }
+
+    let payload_len = u16_be(ip, 4)? as usize;
+    let source = ipv6_at(ip, 8)?;
+    let destination = ipv6_at(ip, 24)?;
+    let udp = ip.get(40..(40 + payload_len).min(ip.len()))?;
+
+    parse_udp(udp, source.into(), destination.into(), time)
+}
+
+fn parse_udp(
+    udp: &[u8],
+    source: IpAddr,
+    destination: IpAddr,
+    time: Duration,
+) -> Option<Datagram> {
+    let len = u16_be(udp, 4)? as usize;
+
+    Some(Datagram {
+        time,
+        source: SocketAddr::new(source, u16_be(udp, 0)?),
+        destination: SocketAddr::new(destination, u16_be(udp, 2)?),
+        payload: udp.get(8..len.max(8).min(udp.len()))?.to_vec(),
+    })
+}
+
+fn ipv6_at(bytes: &[u8], at: usize) -> Option<Ipv6Addr> {
+    let mut octets = [0; 16];
+    octets.copy_from_slice(bytes.get(at..at + 16)?);
+
+    Some(octets.into())
+}
+
+fn u16_be(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
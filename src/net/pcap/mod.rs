@@ -0,0 +1,190 @@
+//! Packet captures.
+//!
+//! An [`Inspector`] takes raw UDP payloads, from a socket or a capture file,
+//! and decodes them into packets and the messages inside them. Whatever it
+//! decodes can be encoded back with [`Decoded::encode`], which should give the
+//! same bytes: anything else is a bug in a codec.
+//!
+//! Reading `.pcap` files needs the `pcap` feature.
+
+#[cfg(feature = "pcap")]
+pub mod file;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::net::binary::{decode, encode};
+use crate::net::connection::{self, DisconnectReason, Hello};
+use crate::net::protocol::{Direction, RootMessage};
+use crate::net::transport::fragment::Reassembler;
+use crate::net::transport::packet::Packet;
+use crate::net::transport::Config;
+
+/// The port game servers listen on.
+pub const GAME_PORT: u16 = 22023;
+
+/// A captured UDP datagram.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Datagram {
+    /// When the datagram was captured. For capture files, this is since the
+    /// Unix epoch.
+    pub time: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    /// The UDP payload.
+    pub payload: Vec<u8>,
+}
+
+/// What a packet carried, decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Contents {
+    /// Nothing, or a fragment of a payload that isn't complete yet.
+    Nothing,
+    /// A game hello.
+    Hello(Hello),
+    /// Root messages. For fragments, these are from the whole payload, once
+    /// the last fragment arrives.
+    Messages(Vec<RootMessage>),
+    /// Why the connection was closed.
+    Disconnect(Option<DisconnectReason>),
+}
+
+/// A decoded packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decoded {
+    pub direction: Direction,
+    pub packet: Packet,
+    pub contents: Contents,
+}
+
+impl Decoded {
+    /// Decodes a single datagram, without putting fragments together.
+    pub fn decode(datagram: &[u8], direction: Direction) -> Result<Decoded, decode::Error> {
+        let packet = decode::parse::<Packet>(datagram)?;
+
+        let contents = match &packet {
+            Packet::Unreliable(payload) | Packet::Reliable { payload, .. } => {
+                Contents::Messages(RootMessage::read_all(payload, direction)?)
+            }
+            Packet::Hello { payload, .. } => {
+                Contents::Hello(decode::Cursor::new(payload).decode()?)
+            }
+            Packet::Disconnect(payload) => {
+                Contents::Disconnect(connection::decode_disconnect(payload)?)
+            }
+            _ => Contents::Nothing,
+        };
+
+        Ok(Decoded { direction, packet, contents })
+    }
+
+    /// Encodes the packet again, from its decoded contents.
+    ///
+    /// Fragments are encoded as they arrived.
+    pub fn encode(&self) -> Result<Vec<u8>, encode::Error> {
+        let mut packet = self.packet.clone();
+
+        match (&mut packet, &self.contents) {
+            (Packet::Unreliable(payload), Contents::Messages(messages))
+            | (Packet::Reliable { payload, .. }, Contents::Messages(messages)) => {
+                let mut cursor = encode::CursorMut::new();
+
+                for message in messages {
+                    cursor.encode(message)?;
+                }
+
+                *payload = cursor.into_inner();
+            }
+            (Packet::Hello { payload, .. }, Contents::Hello(hello)) => {
+                let mut cursor = encode::CursorMut::new();
+                cursor.encode(hello)?;
+
+                *payload = cursor.into_inner();
+            }
+            (Packet::Disconnect(payload), Contents::Disconnect(reason)) => {
+                *payload = connection::encode_disconnect(reason.as_ref())?;
+            }
+            _ => (),
+        }
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&packet)?;
+
+        Ok(cursor.into_inner())
+    }
+}
+
+/// Decodes captured datagrams, keeping track of fragments.
+pub struct Inspector {
+    server_ports: Vec<u16>,
+    config: Config,
+    flows: HashMap<(SocketAddr, SocketAddr), Reassembler>,
+    // capture times are turned into instants for the reassemblers, counting
+    // from the first datagram
+    start: Instant,
+    first_time: Option<Duration>,
+}
+
+impl Inspector {
+    /// Create a new `Inspector` that takes anything sent from
+    /// [`GAME_PORT`] as going to the client.
+    pub fn new() -> Inspector {
+        Inspector::with_server_ports(vec![GAME_PORT])
+    }
+
+    /// Create a new `Inspector` that takes anything sent from one of
+    /// `server_ports` as going to the client.
+    pub fn with_server_ports(server_ports: Vec<u16>) -> Inspector {
+        Inspector {
+            server_ports,
+            config: Config::default(),
+            flows: HashMap::new(),
+            start: Instant::now(),
+            first_time: None,
+        }
+    }
+
+    /// Which way a datagram is going.
+    pub fn direction(&self, datagram: &Datagram) -> Direction {
+        if self.server_ports.contains(&datagram.source.port()) {
+            Direction::ToClient
+        } else {
+            Direction::ToServer
+        }
+    }
+
+    /// Decodes a datagram.
+    ///
+    /// Fragments decode to [`Contents::Nothing`] until the last one of their
+    /// payload arrives.
+    pub fn inspect(&mut self, datagram: &Datagram) -> Result<Decoded, decode::Error> {
+        let direction = self.direction(datagram);
+        let mut decoded = Decoded::decode(&datagram.payload, direction)?;
+
+        if let Packet::Fragment { group, index, count, payload, .. } = &decoded.packet {
+            let first_time = *self.first_time.get_or_insert(datagram.time);
+            let now = self.start + datagram.time.saturating_sub(first_time);
+            let reassembler = self.flows
+                .entry((datagram.source, datagram.destination))
+                .or_default();
+
+            reassembler.expire(now);
+
+            let payload = reassembler
+                .insert(&self.config, *group, *index, *count, payload.clone(), now)?;
+
+            if let Some(payload) = payload {
+                decoded.contents = Contents::Messages(RootMessage::read_all(&payload, direction)?);
+            }
+        }
+
+        Ok(decoded)
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Inspector {
+        Inspector::new()
+    }
+}
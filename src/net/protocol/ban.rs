@@ -0,0 +1,48 @@
+//! Bans.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use crate::net::connection::DisconnectReason;
+
+use super::root::JoinGame;
+
+/// The addresses banned from a game.
+///
+/// Bans are by address, like the official server, since a banned player can
+/// just change their name.
+#[derive(Clone, Debug, Default)]
+pub struct BanList {
+    addresses: HashSet<IpAddr>,
+}
+
+impl BanList {
+    /// Create a new, empty `BanList`.
+    pub fn new() -> BanList {
+        BanList::default()
+    }
+
+    /// Bans an address.
+    pub fn ban(&mut self, address: IpAddr) {
+        self.addresses.insert(address);
+    }
+
+    /// Lifts a ban, returning whether there was one.
+    pub fn unban(&mut self, address: IpAddr) -> bool {
+        self.addresses.remove(&address)
+    }
+
+    /// Whether an address is banned.
+    pub fn is_banned(&self, address: IpAddr) -> bool {
+        self.addresses.contains(&address)
+    }
+
+    /// Checks whether an address can join, giving the reply to send if not.
+    pub fn check_join(&self, address: IpAddr) -> Result<(), JoinGame> {
+        if self.is_banned(address) {
+            Err(JoinGame::Error(DisconnectReason::Banned))
+        } else {
+            Ok(())
+        }
+    }
+}
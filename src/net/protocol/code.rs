@@ -0,0 +1,216 @@
+//! Game codes.
+//!
+//! Games are joined by a code of letters, but sent as an `i32`. Old (V1) codes
+//! are four letters stored as their ASCII bytes. Newer (V2) codes are six
+//! letters packed into 30 bits, and always have the sign bit set.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::net::binary::{decode, encode};
+
+/// The V2 alphabet. A letter's index here is its value.
+const V2_ALPHABET: &[u8; 26] = b"QWXRTYLPESDFGHUJKZOCVBINMA";
+
+/// A game code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GameCode(i32);
+
+impl GameCode {
+    /// Wraps a code as it is sent on the wire.
+    pub fn from_raw(raw: i32) -> GameCode {
+        GameCode(raw)
+    }
+
+    /// The code as it is sent on the wire.
+    pub fn raw(self) -> i32 {
+        self.0
+    }
+
+    /// Whether the code is a six letter V2 code.
+    pub fn is_v2(self) -> bool {
+        self.0 < 0
+    }
+
+    /// The letters of the code, if it has any.
+    ///
+    /// Not every `i32` is a valid code, but anything can be sent.
+    pub fn letters(self) -> Option<String> {
+        if self.is_v2() {
+            let low = self.0 as u32 & 0x3ff;
+            let high = (self.0 as u32 >> 10) & 0xfffff;
+
+            if low >= 26 * 26 || high >= 26 * 26 * 26 * 26 {
+                return None;
+            }
+
+            let values = [
+                low % 26,
+                low / 26,
+                high % 26,
+                high / 26 % 26,
+                high / 676 % 26,
+                high / 17576,
+            ];
+
+            Some(values.iter().map(|&v| V2_ALPHABET[v as usize] as char).collect())
+        } else {
+            let bytes = self.0.to_le_bytes();
+
+            if bytes.iter().all(u8::is_ascii_uppercase) {
+                Some(bytes.iter().map(|&b| b as char).collect())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for GameCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.letters() {
+            Some(letters) => f.write_str(&letters),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl FromStr for GameCode {
+    type Err = ParseGameCodeError;
+
+    fn from_str(s: &str) -> Result<GameCode, ParseGameCodeError> {
+        let mut bytes = [0u8; 6];
+        let mut len = 0;
+
+        for c in s.chars() {
+            if !c.is_ascii_alphabetic() {
+                return Err(ParseGameCodeError::InvalidChar(c));
+            }
+
+            if len == bytes.len() {
+                return Err(ParseGameCodeError::InvalidLength);
+            }
+
+            bytes[len] = c.to_ascii_uppercase() as u8;
+            len += 1;
+        }
+
+        match len {
+            4 => Ok(GameCode(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))),
+            6 => {
+                let mut values = [0u32; 6];
+
+                for (value, &b) in values.iter_mut().zip(&bytes) {
+                    let index = V2_ALPHABET.iter().position(|&l| l == b);
+                    *value = index.expect("letter is in the alphabet") as u32;
+                }
+
+                let low = (values[0] + 26 * values[1]) & 0x3ff;
+                let high = values[2] + 26 * (values[3] + 26 * (values[4] + 26 * values[5]));
+
+                Ok(GameCode((low | (high << 10) & 0x3fff_fc00 | 0x8000_0000) as i32))
+            }
+            _ => Err(ParseGameCodeError::InvalidLength),
+        }
+    }
+}
+
+impl decode::Decode for GameCode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode().map(GameCode)
+    }
+}
+
+impl encode::Encode for GameCode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.0)
+    }
+}
+
+/// A game code couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseGameCodeError {
+    /// The code wasn't four or six letters long.
+    InvalidLength,
+    /// The code had something other than a letter in it.
+    InvalidChar(char),
+}
+
+impl fmt::Display for ParseGameCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseGameCodeError::InvalidLength => f.write_str("game codes are 4 or 6 letters"),
+            ParseGameCodeError::InvalidChar(c) => {
+                write!(f, "invalid character {:?} in game code", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseGameCodeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> GameCode {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn v1() {
+        assert_eq!(parse("ABCD").raw(), 0x4443_4241);
+        assert_eq!(parse("reds").raw(), i32::from_le_bytes(*b"REDS"));
+        assert!(!parse("ABCD").is_v2());
+
+        assert_eq!(GameCode::from_raw(0x4443_4241).to_string(), "ABCD");
+    }
+
+    #[test]
+    fn v2() {
+        let known = [
+            ("REDSUS", 0x8a3f_50d3_u32),
+            ("QQQQQQ", 0x8000_0000),
+            ("AAAAAA", 0x9be4_3ea3),
+            ("ABCDEF", 0x8c25_be3b),
+        ];
+
+        for &(letters, raw) in &known {
+            let code = parse(letters);
+
+            assert_eq!(code.raw(), raw as i32, "{}", letters);
+            assert!(code.is_v2());
+            assert_eq!(GameCode::from_raw(raw as i32).to_string(), letters);
+        }
+
+        assert_eq!(parse("redsus"), parse("REDSUS"));
+    }
+
+    #[test]
+    fn invalid_letters() {
+        // neither four ASCII capitals nor a V2 code in range
+        assert_eq!(GameCode::from_raw(32).letters(), None);
+        assert_eq!(GameCode::from_raw(32).to_string(), "32");
+        assert_eq!(GameCode::from_raw(0x8000_03ffu32 as i32).letters(), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("ABC".parse::<GameCode>(), Err(ParseGameCodeError::InvalidLength));
+        assert_eq!("ABCDE".parse::<GameCode>(), Err(ParseGameCodeError::InvalidLength));
+        assert_eq!("ABCDEFG".parse::<GameCode>(), Err(ParseGameCodeError::InvalidLength));
+        assert_eq!("AB1D".parse::<GameCode>(), Err(ParseGameCodeError::InvalidChar('1')));
+        assert_eq!("ABÇD".parse::<GameCode>(), Err(ParseGameCodeError::InvalidChar('Ç')));
+    }
+
+    #[test]
+    fn codec() {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&parse("REDSUS")).unwrap();
+        assert_eq!(cursor.as_slice(), [0xd3, 0x50, 0x3f, 0x8a]);
+
+        let mut cursor = decode::Cursor::new(&[0x41, 0x42, 0x43, 0x44][..]);
+        assert_eq!(cursor.decode::<GameCode>().unwrap(), parse("ABCD"));
+    }
+}
@@ -0,0 +1,276 @@
+//! GameData messages.
+//!
+//! The payload of a [`GameData`](super::root::GameData) or
+//! [`GameDataTo`](super::root::GameDataTo) root message is a list of these.
+//! They spawn, update and despawn the net objects that make up the game, and
+//! carry [`Rpc`]s between them.
+
+use crate::net::binary::{decode, encode, message, packed::{PackedI32, PackedU32}};
+
+use super::Rpc;
+
+pub const DATA: u8 = 1;
+pub const RPC: u8 = 2;
+pub const SPAWN: u8 = 4;
+pub const DESPAWN: u8 = 5;
+pub const SCENE_CHANGE: u8 = 6;
+pub const READY: u8 = 7;
+
+/// The spawn flag set on a player's own character.
+pub const CLIENT_CHARACTER: u8 = 1;
+
+/// A GameData message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameDataMessage {
+    /// Updates the state of a net object.
+    Data {
+        net_id: u32,
+        /// The update, still encoded. It is read by the object itself.
+        data: Vec<u8>,
+    },
+    /// Calls an RPC on a net object.
+    Rpc {
+        net_id: u32,
+        rpc: Rpc,
+    },
+    Spawn(Spawn),
+    Despawn {
+        net_id: u32,
+    },
+    /// A client changed scenes, usually to the lobby.
+    SceneChange {
+        client_id: i32,
+        scene: String,
+    },
+    /// A client finished loading.
+    Ready {
+        client_id: i32,
+    },
+    /// A message with a tag this crate doesn't know about.
+    Unknown {
+        tag: u8,
+        payload: Vec<u8>,
+    },
+}
+
+impl GameDataMessage {
+    /// The tag of the message.
+    pub fn tag(&self) -> u8 {
+        match self {
+            GameDataMessage::Data { .. } => DATA,
+            GameDataMessage::Rpc { .. } => RPC,
+            GameDataMessage::Spawn(_) => SPAWN,
+            GameDataMessage::Despawn { .. } => DESPAWN,
+            GameDataMessage::SceneChange { .. } => SCENE_CHANGE,
+            GameDataMessage::Ready { .. } => READY,
+            GameDataMessage::Unknown { tag, .. } => *tag,
+        }
+    }
+
+    /// Decodes every message in a GameData payload.
+    pub fn read_all(data: &[u8]) -> Result<Vec<GameDataMessage>, decode::Error> {
+        let mut cursor = decode::Cursor::new(data);
+        let mut messages = Vec::new();
+
+        while cursor.remaining() > 0 {
+            messages.push(cursor.decode()?);
+        }
+
+        Ok(messages)
+    }
+}
+
+impl decode::Decode for GameDataMessage {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut message = message::Message::read(cursor)?;
+        let tag = message.tag();
+        let cursor = message.cursor();
+
+        let message = match tag {
+            DATA => GameDataMessage::Data {
+                net_id: cursor.decode::<PackedU32>()?.0,
+                data: cursor.decode_rest().to_vec(),
+            },
+            RPC => GameDataMessage::Rpc {
+                net_id: cursor.decode::<PackedU32>()?.0,
+                rpc: cursor.decode()?,
+            },
+            SPAWN => GameDataMessage::Spawn(cursor.decode()?),
+            DESPAWN => GameDataMessage::Despawn {
+                net_id: cursor.decode::<PackedU32>()?.0,
+            },
+            SCENE_CHANGE => GameDataMessage::SceneChange {
+                client_id: cursor.decode::<PackedI32>()?.0,
+                scene: cursor.decode()?,
+            },
+            READY => GameDataMessage::Ready {
+                client_id: cursor.decode::<PackedI32>()?.0,
+            },
+            tag => GameDataMessage::Unknown {
+                tag,
+                payload: cursor.decode_rest().to_vec(),
+            },
+        };
+
+        Ok(message)
+    }
+}
+
+impl encode::Encode for GameDataMessage {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut message = message::MessageWriter::start(cursor, self.tag());
+
+        match self {
+            GameDataMessage::Data { net_id, data } => {
+                message.encode(&PackedU32(*net_id))?;
+                message.write(data);
+            }
+            GameDataMessage::Rpc { net_id, rpc } => {
+                message.encode(&PackedU32(*net_id))?;
+                message.encode(rpc)?;
+            }
+            GameDataMessage::Spawn(spawn) => message.encode(spawn)?,
+            GameDataMessage::Despawn { net_id } => message.encode(&PackedU32(*net_id))?,
+            GameDataMessage::SceneChange { client_id, scene } => {
+                message.encode(&PackedI32(*client_id))?;
+                message.encode(scene)?;
+            }
+            GameDataMessage::Ready { client_id } => message.encode(&PackedI32(*client_id))?,
+            GameDataMessage::Unknown { payload, .. } => message.write(payload),
+        }
+
+        message.end()
+    }
+}
+
+/// What kind of object is being spawned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnType {
+    /// The Skeld.
+    ShipStatus,
+    MeetingHud,
+    LobbyBehaviour,
+    GameData,
+    PlayerControl,
+    /// Mira HQ.
+    HeadQuarters,
+    /// Polus.
+    PlanetMap,
+    /// The upside down Skeld from April Fools.
+    AprilShipStatus,
+    Airship,
+    Unknown(u32),
+}
+
+impl SpawnType {
+    /// The id of the spawn type on the wire.
+    pub fn id(self) -> u32 {
+        match self {
+            SpawnType::ShipStatus => 0,
+            SpawnType::MeetingHud => 1,
+            SpawnType::LobbyBehaviour => 2,
+            SpawnType::GameData => 3,
+            SpawnType::PlayerControl => 4,
+            SpawnType::HeadQuarters => 5,
+            SpawnType::PlanetMap => 6,
+            SpawnType::AprilShipStatus => 7,
+            SpawnType::Airship => 8,
+            SpawnType::Unknown(id) => id,
+        }
+    }
+
+    /// The spawn type of an id.
+    pub fn from_id(id: u32) -> SpawnType {
+        match id {
+            0 => SpawnType::ShipStatus,
+            1 => SpawnType::MeetingHud,
+            2 => SpawnType::LobbyBehaviour,
+            3 => SpawnType::GameData,
+            4 => SpawnType::PlayerControl,
+            5 => SpawnType::HeadQuarters,
+            6 => SpawnType::PlanetMap,
+            7 => SpawnType::AprilShipStatus,
+            8 => SpawnType::Airship,
+            id => SpawnType::Unknown(id),
+        }
+    }
+}
+
+/// Spawns a net object.
+///
+/// The components are left encoded, since how to read them depends on the
+/// spawn type. See [`objects`](crate::net::objects) for reading them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spawn {
+    pub spawn_type: SpawnType,
+    /// The client that owns the object, or `-2` for the server.
+    pub owner: i32,
+    pub flags: u8,
+    pub components: Vec<SpawnComponent>,
+}
+
+/// A single component of a spawned object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpawnComponent {
+    pub net_id: u32,
+    /// The initial state of the component, still encoded.
+    pub data: Vec<u8>,
+}
+
+impl decode::Decode for Spawn {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let spawn_type = SpawnType::from_id(cursor.decode::<PackedU32>()?.0);
+        let owner = cursor.decode::<PackedI32>()?.0;
+        let flags = cursor.decode()?;
+
+        let count = cursor.decode::<PackedU32>()?.0 as usize;
+        cursor.claim_collection::<SpawnComponent>(count)?;
+
+        let mut components = Vec::new();
+
+        for _ in 0..count {
+            components.push(cursor.decode()?);
+        }
+
+        Ok(Spawn {
+            spawn_type,
+            owner,
+            flags,
+            components,
+        })
+    }
+}
+
+impl encode::Encode for Spawn {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&PackedU32(self.spawn_type.id()))?;
+        cursor.encode(&PackedI32(self.owner))?;
+        cursor.encode(&self.flags)?;
+        cursor.encode(&self.components)
+    }
+}
+
+impl decode::Decode for SpawnComponent {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let net_id = cursor.decode::<PackedU32>()?.0;
+        let mut message = message::Message::read(cursor)?;
+
+        Ok(SpawnComponent {
+            net_id,
+            data: message.cursor().decode_rest().to_vec(),
+        })
+    }
+}
+
+impl encode::Encode for SpawnComponent {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&PackedU32(self.net_id))?;
+
+        let mut message = message::MessageWriter::start(cursor, 1);
+        message.write(&self.data);
+        message.end()
+    }
+}
@@ -0,0 +1,28 @@
+//! The Among Us protocol, on top of Hazel.
+//!
+//! Every reliable or unreliable payload is a list of root messages, and most
+//! of the game itself happens inside [`GameData`](root::GameData) messages.
+
+pub mod ban;
+pub mod code;
+pub mod game_data;
+pub mod options;
+pub mod root;
+pub mod rpc;
+pub mod version;
+
+pub use root::RootMessage;
+pub use rpc::Rpc;
+pub use version::{GameVersion, Protocol};
+
+/// Which way a message is going.
+///
+/// Some messages share a tag but have a different payload depending on
+/// whether the client or the server sent them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by a client to the server.
+    ToServer,
+    /// Sent by the server to a client.
+    ToClient,
+}
@@ -0,0 +1,498 @@
+//! Game options.
+//!
+//! The lobby settings are versioned, and each version only appends fields to
+//! the last. Versions newer than this crate knows keep their extra fields as
+//! bytes, so they survive being decoded and encoded again.
+
+use core::convert::TryInto as _;
+
+use crate::net::binary::{decode, encode, packed::PackedU32};
+
+/// The newest options version this crate knows every field of.
+pub const LATEST_VERSION: u8 = 5;
+
+/// How close an impostor has to be to kill.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KillDistance {
+    Short,
+    Medium,
+    Long,
+}
+
+impl decode::Decode for KillDistance {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        match cursor.decode::<u8>()? {
+            0 => Ok(KillDistance::Short),
+            1 => Ok(KillDistance::Medium),
+            2 => Ok(KillDistance::Long),
+            tag => Err(decode::Error::invalid_tag(tag)),
+        }
+    }
+}
+
+impl encode::Encode for KillDistance {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&(*self as u8))
+    }
+}
+
+/// When the task bar updates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskbarMode {
+    /// Every time a task is completed.
+    Normal,
+    /// Only during meetings.
+    Meetings,
+    /// Never, the bar is hidden.
+    Invisible,
+}
+
+impl decode::Decode for TaskbarMode {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        match cursor.decode::<u8>()? {
+            0 => Ok(TaskbarMode::Normal),
+            1 => Ok(TaskbarMode::Meetings),
+            2 => Ok(TaskbarMode::Invisible),
+            tag => Err(decode::Error::invalid_tag(tag)),
+        }
+    }
+}
+
+impl encode::Encode for TaskbarMode {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&(*self as u8))
+    }
+}
+
+/// How often a role is handed out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoleRate {
+    /// The id of the role.
+    pub role: u16,
+    /// The most players that can get the role.
+    pub max_count: u8,
+    /// The chance of each of those players getting it, in percent.
+    pub chance: u8,
+}
+
+impl decode::Decode for RoleRate {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(RoleRate {
+            role: cursor.decode()?,
+            max_count: cursor.decode()?,
+            chance: cursor.decode()?,
+        })
+    }
+}
+
+impl encode::Encode for RoleRate {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.role)?;
+        cursor.encode(&self.max_count)?;
+        cursor.encode(&self.chance)
+    }
+}
+
+/// The settings of the roles beyond crewmate and impostor.
+///
+/// Times are in whole seconds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoleOptions {
+    pub rates: Vec<RoleRate>,
+    /// Whether a shapeshifter leaves their skin behind when they shift.
+    pub shapeshifter_leave_skin: bool,
+    pub shapeshifter_cooldown: u8,
+    pub shapeshifter_duration: u8,
+    pub scientist_cooldown: u8,
+    pub guardian_angel_cooldown: u8,
+    pub engineer_cooldown: u8,
+    /// Whether impostors can see who is protected.
+    pub impostors_see_protect: bool,
+    /// How long a scientist can look at vitals on a full battery.
+    pub scientist_battery: u8,
+    pub protect_duration: u8,
+    /// How long an engineer can stay in a vent.
+    pub engineer_vent_time: u8,
+}
+
+impl RoleOptions {
+    /// The rate of a role, if it has one.
+    pub fn rate(&self, role: u16) -> Option<RoleRate> {
+        self.rates.iter().copied().find(|rate| rate.role == role)
+    }
+}
+
+impl Default for RoleOptions {
+    fn default() -> RoleOptions {
+        RoleOptions {
+            rates: Vec::new(),
+            shapeshifter_leave_skin: false,
+            shapeshifter_cooldown: 10,
+            shapeshifter_duration: 30,
+            scientist_cooldown: 15,
+            guardian_angel_cooldown: 60,
+            engineer_cooldown: 30,
+            impostors_see_protect: false,
+            scientist_battery: 5,
+            protect_duration: 10,
+            engineer_vent_time: 15,
+        }
+    }
+}
+
+impl decode::Decode for RoleOptions {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let count: usize = cursor
+            .decode::<i32>()?
+            .try_into()
+            .map_err(|_| decode::Error::overflow())?;
+        cursor.claim_collection::<RoleRate>(count)?;
+
+        let mut rates = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            rates.push(cursor.decode()?);
+        }
+
+        Ok(RoleOptions {
+            rates,
+            shapeshifter_leave_skin: cursor.decode()?,
+            shapeshifter_cooldown: cursor.decode()?,
+            shapeshifter_duration: cursor.decode()?,
+            scientist_cooldown: cursor.decode()?,
+            guardian_angel_cooldown: cursor.decode()?,
+            engineer_cooldown: cursor.decode()?,
+            impostors_see_protect: cursor.decode()?,
+            scientist_battery: cursor.decode()?,
+            protect_duration: cursor.decode()?,
+            engineer_vent_time: cursor.decode()?,
+        })
+    }
+}
+
+impl encode::Encode for RoleOptions {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        if self.rates.len() > i32::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        cursor.encode(&(self.rates.len() as i32))?;
+
+        for rate in &self.rates {
+            cursor.encode(rate)?;
+        }
+
+        cursor.encode(&self.shapeshifter_leave_skin)?;
+        cursor.encode(&self.shapeshifter_cooldown)?;
+        cursor.encode(&self.shapeshifter_duration)?;
+        cursor.encode(&self.scientist_cooldown)?;
+        cursor.encode(&self.guardian_angel_cooldown)?;
+        cursor.encode(&self.engineer_cooldown)?;
+        cursor.encode(&self.impostors_see_protect)?;
+        cursor.encode(&self.scientist_battery)?;
+        cursor.encode(&self.protect_duration)?;
+        cursor.encode(&self.engineer_vent_time)
+    }
+}
+
+/// The lobby settings of a game.
+///
+/// Fields added after version 1 note the version they appeared in. They are
+/// left at their defaults when decoding older versions, and aren't written
+/// when encoding them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameOptions {
+    pub version: u8,
+    pub max_players: u8,
+    /// The chat language, as a bit flag.
+    pub keywords: u32,
+    pub map: u8,
+    pub player_speed: f32,
+    pub crewmate_vision: f32,
+    pub impostor_vision: f32,
+    pub kill_cooldown: f32,
+    pub common_tasks: u8,
+    pub long_tasks: u8,
+    pub short_tasks: u8,
+    pub emergency_meetings: i32,
+    pub impostors: u8,
+    pub kill_distance: KillDistance,
+    pub discussion_time: i32,
+    pub voting_time: i32,
+    pub is_defaults: bool,
+    /// Since version 2.
+    pub emergency_cooldown: u8,
+    /// Since version 3.
+    pub confirm_ejects: bool,
+    /// Since version 3.
+    pub visual_tasks: bool,
+    /// Since version 4.
+    pub anonymous_votes: bool,
+    /// Since version 4.
+    pub taskbar_mode: TaskbarMode,
+    /// Since version 5.
+    pub roles: RoleOptions,
+    /// Fields from versions newer than [`LATEST_VERSION`], still encoded.
+    pub extra: Vec<u8>,
+}
+
+impl GameOptions {
+    /// Decodes options with the packed length prefix they are sent with.
+    pub fn decode_prefixed<T>(cursor: &mut decode::Cursor<T>) -> Result<GameOptions, decode::Error>
+    where T: AsRef<[u8]> {
+        let len = cursor.decode::<PackedU32>()?.0 as usize;
+
+        cursor.sub_cursor(len)?.decode()
+    }
+
+    /// Encodes options with the packed length prefix they are sent with.
+    pub fn encode_prefixed(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut options = encode::CursorMut::new();
+        options.encode(self)?;
+
+        cursor.encode(options.as_slice())
+    }
+}
+
+impl Default for GameOptions {
+    fn default() -> GameOptions {
+        GameOptions {
+            version: LATEST_VERSION,
+            max_players: 10,
+            // english
+            keywords: 256,
+            map: 0,
+            player_speed: 1.0,
+            crewmate_vision: 1.0,
+            impostor_vision: 1.5,
+            kill_cooldown: 15.0,
+            common_tasks: 1,
+            long_tasks: 1,
+            short_tasks: 2,
+            emergency_meetings: 1,
+            impostors: 1,
+            kill_distance: KillDistance::Medium,
+            discussion_time: 15,
+            voting_time: 120,
+            is_defaults: true,
+            emergency_cooldown: 15,
+            confirm_ejects: true,
+            visual_tasks: true,
+            anonymous_votes: false,
+            taskbar_mode: TaskbarMode::Normal,
+            roles: RoleOptions::default(),
+            extra: Vec::new(),
+        }
+    }
+}
+
+impl decode::Decode for GameOptions {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut options = GameOptions {
+            version: cursor.decode()?,
+            max_players: cursor.decode()?,
+            keywords: cursor.decode()?,
+            map: cursor.decode()?,
+            player_speed: cursor.decode()?,
+            crewmate_vision: cursor.decode()?,
+            impostor_vision: cursor.decode()?,
+            kill_cooldown: cursor.decode()?,
+            common_tasks: cursor.decode()?,
+            long_tasks: cursor.decode()?,
+            short_tasks: cursor.decode()?,
+            emergency_meetings: cursor.decode()?,
+            impostors: cursor.decode()?,
+            kill_distance: cursor.decode()?,
+            discussion_time: cursor.decode()?,
+            voting_time: cursor.decode()?,
+            is_defaults: cursor.decode()?,
+            ..GameOptions::default()
+        };
+
+        if options.version >= 2 {
+            options.emergency_cooldown = cursor.decode()?;
+        }
+
+        if options.version >= 3 {
+            options.confirm_ejects = cursor.decode()?;
+            options.visual_tasks = cursor.decode()?;
+        }
+
+        if options.version >= 4 {
+            options.anonymous_votes = cursor.decode()?;
+            options.taskbar_mode = cursor.decode()?;
+        }
+
+        if options.version >= 5 {
+            options.roles = cursor.decode()?;
+        }
+
+        if options.version > LATEST_VERSION {
+            let len = cursor.remaining();
+            options.extra = cursor.decode_bytes(len)?.to_vec();
+        }
+
+        Ok(options)
+    }
+}
+
+impl encode::Encode for GameOptions {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.version)?;
+        cursor.encode(&self.max_players)?;
+        cursor.encode(&self.keywords)?;
+        cursor.encode(&self.map)?;
+        cursor.encode(&self.player_speed)?;
+        cursor.encode(&self.crewmate_vision)?;
+        cursor.encode(&self.impostor_vision)?;
+        cursor.encode(&self.kill_cooldown)?;
+        cursor.encode(&self.common_tasks)?;
+        cursor.encode(&self.long_tasks)?;
+        cursor.encode(&self.short_tasks)?;
+        cursor.encode(&self.emergency_meetings)?;
+        cursor.encode(&self.impostors)?;
+        cursor.encode(&self.kill_distance)?;
+        cursor.encode(&self.discussion_time)?;
+        cursor.encode(&self.voting_time)?;
+        cursor.encode(&self.is_defaults)?;
+
+        if self.version >= 2 {
+            cursor.encode(&self.emergency_cooldown)?;
+        }
+
+        if self.version >= 3 {
+            cursor.encode(&self.confirm_ejects)?;
+            cursor.encode(&self.visual_tasks)?;
+        }
+
+        if self.version >= 4 {
+            cursor.encode(&self.anonymous_votes)?;
+            cursor.encode(&self.taskbar_mode)?;
+        }
+
+        if self.version >= 5 {
+            cursor.encode(&self.roles)?;
+        }
+
+        if self.version > LATEST_VERSION {
+            cursor.write(&self.extra);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8], options: &GameOptions) {
+        let mut cursor = decode::Cursor::new(bytes);
+        assert_eq!(&cursor.decode::<GameOptions>().unwrap(), options);
+        assert_eq!(cursor.remaining(), 0);
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(options).unwrap();
+        assert_eq!(cursor.as_slice(), bytes);
+    }
+
+    fn v1() -> GameOptions {
+        GameOptions {
+            version: 1,
+            max_players: 15,
+            keywords: 256,
+            map: 2,
+            player_speed: 1.25,
+            crewmate_vision: 0.5,
+            impostor_vision: 1.5,
+            kill_cooldown: 30.0,
+            common_tasks: 2,
+            long_tasks: 3,
+            short_tasks: 5,
+            emergency_meetings: 9,
+            impostors: 3,
+            kill_distance: KillDistance::Long,
+            discussion_time: 0,
+            voting_time: 300,
+            is_defaults: false,
+            ..GameOptions::default()
+        }
+    }
+
+    fn v5() -> GameOptions {
+        GameOptions {
+            version: 5,
+            confirm_ejects: false,
+            anonymous_votes: true,
+            taskbar_mode: TaskbarMode::Meetings,
+            roles: RoleOptions {
+                rates: vec![
+                    RoleRate { role: 1, max_count: 2, chance: 50 },
+                    RoleRate { role: 2, max_count: 1, chance: 100 },
+                ],
+                shapeshifter_leave_skin: true,
+                ..RoleOptions::default()
+            },
+            ..v1()
+        }
+    }
+
+    #[test]
+    fn version_1() {
+        // the fields from later versions keep their defaults
+        round_trip(&fixture!("options/v1"), &v1());
+    }
+
+    #[test]
+    fn version_5() {
+        let options = v5();
+
+        round_trip(&fixture!("options/v5"), &options);
+        assert_eq!(options.roles.rate(2).map(|rate| rate.chance), Some(100));
+        assert_eq!(options.roles.rate(3), None);
+    }
+
+    #[test]
+    fn newer_version() {
+        round_trip(
+            &fixture!("options/v6"),
+            &GameOptions {
+                version: 6,
+                extra: vec![0xaa, 0xbb],
+                ..v5()
+            },
+        );
+    }
+
+    #[test]
+    fn older_versions_drop_newer_fields() {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&GameOptions { version: 1, ..v5() }).unwrap();
+
+        assert_eq!(cursor.as_slice(), &fixture!("options/v1")[..]);
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = fixture!("options/v5");
+
+        // one byte short of the engineer vent time
+        let mut cursor = decode::Cursor::new(&bytes[..bytes.len() - 1]);
+        assert!(cursor.decode::<GameOptions>().is_err());
+    }
+
+    #[test]
+    fn invalid_kill_distance() {
+        let mut bytes = fixture!("options/v1");
+        // the kill distance comes after 31 bytes of earlier fields
+        bytes[31] = 3;
+
+        let mut cursor = decode::Cursor::new(&bytes[..]);
+        assert!(cursor.decode::<GameOptions>().is_err());
+    }
+}
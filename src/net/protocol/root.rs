@@ -0,0 +1,1089 @@
+//! Root messages.
+//!
+//! These are the outermost messages in every payload, and mostly deal with
+//! matchmaking: hosting, joining and leaving games. The game itself is
+//! carried in [`GameData`] and [`GameDataTo`].
+
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+use crate::net::binary::{decode, encode, message, packed::{PackedI32, PackedU32}};
+use crate::net::binary::{Decode, Encode};
+use crate::net::connection::DisconnectReason;
+
+use super::Direction;
+use super::code::GameCode;
+use super::options::GameOptions;
+
+pub const HOST_GAME: u8 = 0;
+pub const JOIN_GAME: u8 = 1;
+pub const START_GAME: u8 = 2;
+pub const REMOVE_GAME: u8 = 3;
+pub const REMOVE_PLAYER: u8 = 4;
+pub const GAME_DATA: u8 = 5;
+pub const GAME_DATA_TO: u8 = 6;
+pub const JOINED_GAME: u8 = 7;
+pub const END_GAME: u8 = 8;
+pub const ALTER_GAME: u8 = 10;
+pub const KICK_PLAYER: u8 = 11;
+pub const WAIT_FOR_HOST: u8 = 12;
+pub const REDIRECT: u8 = 13;
+pub const RESELECT_SERVER: u8 = 14;
+pub const GET_GAME_LIST_V2: u8 = 16;
+
+/// A root message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RootMessage {
+    HostGame(HostGame),
+    JoinGame(JoinGame),
+    StartGame(StartGame),
+    RemoveGame(RemoveGame),
+    RemovePlayer(RemovePlayer),
+    GameData(GameData),
+    GameDataTo(GameDataTo),
+    JoinedGame(JoinedGame),
+    EndGame(EndGame),
+    AlterGame(AlterGame),
+    KickPlayer(KickPlayer),
+    WaitForHost(WaitForHost),
+    Redirect(Redirect),
+    ReselectServer(ReselectServer),
+    GetGameListV2(GetGameListV2),
+    /// A message with a tag this crate doesn't know about.
+    Unknown {
+        tag: u8,
+        payload: Vec<u8>,
+    },
+}
+
+impl RootMessage {
+    /// The tag of the message.
+    pub fn tag(&self) -> u8 {
+        match self {
+            RootMessage::HostGame(_) => HOST_GAME,
+            RootMessage::JoinGame(_) => JOIN_GAME,
+            RootMessage::StartGame(_) => START_GAME,
+            RootMessage::RemoveGame(_) => REMOVE_GAME,
+            RootMessage::RemovePlayer(_) => REMOVE_PLAYER,
+            RootMessage::GameData(_) => GAME_DATA,
+            RootMessage::GameDataTo(_) => GAME_DATA_TO,
+            RootMessage::JoinedGame(_) => JOINED_GAME,
+            RootMessage::EndGame(_) => END_GAME,
+            RootMessage::AlterGame(_) => ALTER_GAME,
+            RootMessage::KickPlayer(_) => KICK_PLAYER,
+            RootMessage::WaitForHost(_) => WAIT_FOR_HOST,
+            RootMessage::Redirect(_) => REDIRECT,
+            RootMessage::ReselectServer(_) => RESELECT_SERVER,
+            RootMessage::GetGameListV2(_) => GET_GAME_LIST_V2,
+            RootMessage::Unknown { tag, .. } => *tag,
+        }
+    }
+
+    /// Decodes the next root message from a cursor.
+    ///
+    /// The direction decides how messages that have a different payload each
+    /// way are read.
+    pub fn read<T>(
+        cursor: &mut decode::Cursor<T>,
+        direction: Direction,
+    ) -> Result<RootMessage, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut message = message::Message::read(cursor)?;
+        let tag = message.tag();
+        let cursor = message.cursor();
+
+        let root = match (tag, direction) {
+            (HOST_GAME, Direction::ToServer) => {
+                RootMessage::HostGame(HostGame::Request(cursor.decode()?))
+            }
+            (HOST_GAME, Direction::ToClient) => {
+                RootMessage::HostGame(HostGame::Response(cursor.decode()?))
+            }
+            (JOIN_GAME, Direction::ToServer) => {
+                RootMessage::JoinGame(JoinGame::Request(cursor.decode()?))
+            }
+            (JOIN_GAME, Direction::ToClient) => {
+                RootMessage::JoinGame(JoinGame::decode_to_client(cursor)?)
+            }
+            (START_GAME, _) => RootMessage::StartGame(cursor.decode()?),
+            (REMOVE_GAME, _) => RootMessage::RemoveGame(cursor.decode()?),
+            (REMOVE_PLAYER, Direction::ToServer) => {
+                RootMessage::RemovePlayer(RemovePlayer::Request(cursor.decode()?))
+            }
+            (REMOVE_PLAYER, Direction::ToClient) => {
+                RootMessage::RemovePlayer(RemovePlayer::Removed(cursor.decode()?))
+            }
+            (GAME_DATA, _) => RootMessage::GameData(cursor.decode()?),
+            (GAME_DATA_TO, _) => RootMessage::GameDataTo(cursor.decode()?),
+            (JOINED_GAME, _) => RootMessage::JoinedGame(cursor.decode()?),
+            (END_GAME, _) => RootMessage::EndGame(cursor.decode()?),
+            (ALTER_GAME, _) => RootMessage::AlterGame(cursor.decode()?),
+            (KICK_PLAYER, _) => RootMessage::KickPlayer(cursor.decode()?),
+            (WAIT_FOR_HOST, _) => RootMessage::WaitForHost(cursor.decode()?),
+            (REDIRECT, _) => RootMessage::Redirect(cursor.decode()?),
+            (RESELECT_SERVER, _) => RootMessage::ReselectServer(cursor.decode()?),
+            (GET_GAME_LIST_V2, Direction::ToServer) => {
+                RootMessage::GetGameListV2(GetGameListV2::Request(cursor.decode()?))
+            }
+            (GET_GAME_LIST_V2, Direction::ToClient) => {
+                RootMessage::GetGameListV2(GetGameListV2::Response(cursor.decode()?))
+            }
+            (tag, _) => RootMessage::Unknown {
+                tag,
+                payload: cursor.decode_rest().to_vec(),
+            },
+        };
+
+        Ok(root)
+    }
+
+    /// Decodes every root message in a payload.
+    pub fn read_all(
+        payload: &[u8],
+        direction: Direction,
+    ) -> Result<Vec<RootMessage>, decode::Error> {
+        let mut cursor = decode::Cursor::new(payload);
+        let mut messages = Vec::new();
+
+        while cursor.remaining() > 0 {
+            messages.push(RootMessage::read(&mut cursor, direction)?);
+        }
+
+        Ok(messages)
+    }
+
+    /// Where the client should reconnect to, if this is a [`Redirect`].
+    ///
+    /// The matchmaker redirects clients that host or join a game to the server
+    /// the game is on. To follow it, disconnect and send the same hello and
+    /// request to the new address.
+    pub fn redirect(&self) -> Option<SocketAddrV4> {
+        match self {
+            RootMessage::Redirect(redirect) => Some(redirect.address),
+            _ => None,
+        }
+    }
+}
+
+impl encode::Encode for RootMessage {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut message = message::MessageWriter::start(cursor, self.tag());
+
+        match self {
+            RootMessage::HostGame(m) => message.encode(m)?,
+            RootMessage::JoinGame(m) => message.encode(m)?,
+            RootMessage::StartGame(m) => message.encode(m)?,
+            RootMessage::RemoveGame(m) => message.encode(m)?,
+            RootMessage::RemovePlayer(m) => message.encode(m)?,
+            RootMessage::GameData(m) => message.encode(m)?,
+            RootMessage::GameDataTo(m) => message.encode(m)?,
+            RootMessage::JoinedGame(m) => message.encode(m)?,
+            RootMessage::EndGame(m) => message.encode(m)?,
+            RootMessage::AlterGame(m) => message.encode(m)?,
+            RootMessage::KickPlayer(m) => message.encode(m)?,
+            RootMessage::WaitForHost(m) => message.encode(m)?,
+            RootMessage::Redirect(m) => message.encode(m)?,
+            RootMessage::ReselectServer(m) => message.encode(m)?,
+            RootMessage::GetGameListV2(m) => message.encode(m)?,
+            RootMessage::Unknown { payload, .. } => message.write(payload),
+        }
+
+        message.end()
+    }
+}
+
+/// Hosting a new game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HostGame {
+    Request(HostGameRequest),
+    Response(HostGameResponse),
+}
+
+impl encode::Encode for HostGame {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            HostGame::Request(m) => cursor.encode(m),
+            HostGame::Response(m) => cursor.encode(m),
+        }
+    }
+}
+
+/// Asks the server to host a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostGameRequest {
+    pub options: GameOptions,
+}
+
+impl decode::Decode for HostGameRequest {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(HostGameRequest {
+            options: GameOptions::decode_prefixed(cursor)?,
+        })
+    }
+}
+
+impl encode::Encode for HostGameRequest {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        self.options.encode_prefixed(cursor)
+    }
+}
+
+/// The code of a newly hosted game.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct HostGameResponse {
+    pub code: GameCode,
+}
+
+/// Joining a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JoinGame {
+    /// Asks the server to join a game.
+    Request(JoinGameRequest),
+    /// Tells everyone in a game that a player joined.
+    Joined(PlayerJoined),
+    /// The game couldn't be joined.
+    Error(DisconnectReason),
+}
+
+impl JoinGame {
+    /// Decodes a join game message sent by the server.
+    ///
+    /// Errors and joins share a tag. Game codes are never small positive
+    /// numbers, so a first field in the range of reasons is an error.
+    pub fn decode_to_client<T>(cursor: &mut decode::Cursor<T>) -> Result<JoinGame, decode::Error>
+    where T: AsRef<[u8]> {
+        let first = cursor.decode::<i32>()?;
+
+        if (0..=u8::MAX as i32).contains(&first) {
+            return DisconnectReason::decode_code(first as u8, cursor).map(JoinGame::Error);
+        }
+
+        Ok(JoinGame::Joined(PlayerJoined {
+            code: GameCode::from_raw(first),
+            client_id: cursor.decode()?,
+            host_id: cursor.decode()?,
+        }))
+    }
+}
+
+impl encode::Encode for JoinGame {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            JoinGame::Request(m) => cursor.encode(m),
+            JoinGame::Joined(m) => cursor.encode(m),
+            JoinGame::Error(reason) => {
+                cursor.encode(&(reason.code() as i32))?;
+                reason.encode_message(cursor)
+            }
+        }
+    }
+}
+
+/// Asks the server to join a game.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct JoinGameRequest {
+    pub code: GameCode,
+}
+
+/// A player joined a game.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PlayerJoined {
+    pub code: GameCode,
+    pub client_id: i32,
+    pub host_id: i32,
+}
+
+/// The game is starting.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct StartGame {
+    pub code: GameCode,
+}
+
+/// The game the client was in is gone.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RemoveGame {
+    pub reason: DisconnectReason,
+}
+
+/// A player leaving a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemovePlayer {
+    Request(RemovePlayerRequest),
+    Removed(PlayerRemoved),
+}
+
+impl encode::Encode for RemovePlayer {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            RemovePlayer::Request(m) => cursor.encode(m),
+            RemovePlayer::Removed(m) => cursor.encode(m),
+        }
+    }
+}
+
+/// Asks the server to remove a player.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RemovePlayerRequest {
+    pub code: GameCode,
+    #[binary(packed)]
+    pub client_id: i32,
+    pub reason: DisconnectReason,
+}
+
+/// A player left a game, and who the host is now.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PlayerRemoved {
+    pub code: GameCode,
+    pub client_id: i32,
+    pub host_id: i32,
+    pub reason: DisconnectReason,
+}
+
+/// Game data for everyone in a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameData {
+    pub code: GameCode,
+    /// The game data messages, still encoded.
+    pub data: Vec<u8>,
+}
+
+impl decode::Decode for GameData {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(GameData {
+            code: cursor.decode()?,
+            data: cursor.decode_rest().to_vec(),
+        })
+    }
+}
+
+impl encode::Encode for GameData {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.code)?;
+        cursor.write(&self.data);
+        Ok(())
+    }
+}
+
+/// Game data for a single client in a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameDataTo {
+    pub code: GameCode,
+    pub target: i32,
+    /// The game data messages, still encoded.
+    pub data: Vec<u8>,
+}
+
+impl decode::Decode for GameDataTo {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(GameDataTo {
+            code: cursor.decode()?,
+            target: cursor.decode::<PackedI32>()?.0,
+            data: cursor.decode_rest().to_vec(),
+        })
+    }
+}
+
+impl encode::Encode for GameDataTo {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.code)?;
+        cursor.encode(&PackedI32(self.target))?;
+        cursor.write(&self.data);
+        Ok(())
+    }
+}
+
+/// The client joined a game, and who else is in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinedGame {
+    pub code: GameCode,
+    pub client_id: i32,
+    pub host_id: i32,
+    pub others: Vec<i32>,
+}
+
+impl decode::Decode for JoinedGame {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(JoinedGame {
+            code: cursor.decode()?,
+            client_id: cursor.decode()?,
+            host_id: cursor.decode()?,
+            others: cursor.decode::<Vec<PackedI32>>()?.into_iter().map(i32::from).collect(),
+        })
+    }
+}
+
+impl encode::Encode for JoinedGame {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.code)?;
+        cursor.encode(&self.client_id)?;
+        cursor.encode(&self.host_id)?;
+        cursor.encode(&self.others.iter().copied().map(PackedI32).collect::<Vec<_>>())
+    }
+}
+
+/// The game ended.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct EndGame {
+    pub code: GameCode,
+    pub reason: u8,
+    pub show_ad: bool,
+}
+
+/// Changes a setting of the game itself, like whether it is public.
+///
+/// Only the host may send this. The server applies it and sends it on to
+/// everyone in the game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlterGame {
+    pub code: GameCode,
+    pub tag: AlterGameTag,
+    pub value: bool,
+}
+
+impl AlterGame {
+    /// Makes the game public or private.
+    pub fn privacy(code: GameCode, public: bool) -> AlterGame {
+        AlterGame {
+            code,
+            tag: AlterGameTag::ChangePrivacy,
+            value: public,
+        }
+    }
+
+    /// Whether this makes the game public, if it changes the privacy at all.
+    pub fn public(&self) -> Option<bool> {
+        match self.tag {
+            AlterGameTag::ChangePrivacy => Some(self.value),
+            AlterGameTag::Unknown(_) => None,
+        }
+    }
+}
+
+impl decode::Decode for AlterGame {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        Ok(AlterGame {
+            code: cursor.decode()?,
+            tag: AlterGameTag::from_id(cursor.decode()?),
+            value: cursor.decode()?,
+        })
+    }
+}
+
+impl encode::Encode for AlterGame {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.code)?;
+        cursor.encode(&self.tag.id())?;
+        cursor.encode(&self.value)
+    }
+}
+
+/// What an [`AlterGame`] changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AlterGameTag {
+    /// Whether the game shows up in the game list.
+    ChangePrivacy,
+    Unknown(u8),
+}
+
+impl AlterGameTag {
+    /// The id of the tag on the wire.
+    pub fn id(self) -> u8 {
+        match self {
+            AlterGameTag::ChangePrivacy => 1,
+            AlterGameTag::Unknown(id) => id,
+        }
+    }
+
+    /// The tag of an id.
+    pub fn from_id(id: u8) -> AlterGameTag {
+        match id {
+            1 => AlterGameTag::ChangePrivacy,
+            id => AlterGameTag::Unknown(id),
+        }
+    }
+}
+
+/// Kicks or bans a player.
+///
+/// The host sends this to the server, which checks it came from the host,
+/// then disconnects the player with [`reason`](KickPlayer::reason), sends
+/// this on to everyone else, and follows with
+/// [`player_removed`](KickPlayer::player_removed).
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct KickPlayer {
+    pub code: GameCode,
+    #[binary(packed)]
+    pub client_id: i32,
+    pub banned: bool,
+}
+
+impl KickPlayer {
+    /// The reason given to the kicked player when it is disconnected.
+    pub fn reason(&self) -> DisconnectReason {
+        if self.banned {
+            DisconnectReason::Banned
+        } else {
+            DisconnectReason::Kicked
+        }
+    }
+
+    /// The message telling everyone else in the game the player is gone.
+    pub fn player_removed(&self, host_id: i32) -> PlayerRemoved {
+        PlayerRemoved {
+            code: self.code,
+            client_id: self.client_id,
+            host_id,
+            reason: self.reason(),
+        }
+    }
+}
+
+/// The client has to wait for the host to come back to the lobby.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct WaitForHost {
+    pub code: GameCode,
+    pub client_id: i32,
+}
+
+/// The client should reconnect to another server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Redirect {
+    pub address: SocketAddrV4,
+}
+
+impl decode::Decode for Redirect {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let ip = cursor.decode::<[u8; 4]>()?;
+        let port = cursor.decode::<u16>()?;
+
+        Ok(Redirect {
+            address: SocketAddrV4::new(Ipv4Addr::from(ip), port),
+        })
+    }
+}
+
+impl encode::Encode for Redirect {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.address.ip().octets())?;
+        cursor.encode(&self.address.port())
+    }
+}
+
+/// The list of servers the client can pick from.
+///
+/// This is sent by the master server to update the servers of a region.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ReselectServer {
+    /// Always `0` in practice.
+    pub version: u8,
+    pub servers: Vec<ServerInfo>,
+}
+
+impl ReselectServer {
+    /// The server with the fewest connections.
+    pub fn least_loaded(&self) -> Option<&ServerInfo> {
+        self.servers.iter().min_by_key(|s| s.connections)
+    }
+}
+
+/// A server in a [`ReselectServer`] list.
+///
+/// Each server is sent in its own message, tagged `0`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub name: String,
+    pub address: SocketAddrV4,
+    /// How many clients are connected to the server.
+    pub connections: u32,
+}
+
+impl decode::Decode for ServerInfo {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut message = message::Message::read(cursor)?;
+        let cursor = message.cursor();
+
+        let name = cursor.decode()?;
+        let ip = cursor.decode::<[u8; 4]>()?;
+        let port = cursor.decode::<u16>()?;
+
+        Ok(ServerInfo {
+            name,
+            address: SocketAddrV4::new(Ipv4Addr::from(ip), port),
+            connections: cursor.decode::<PackedU32>()?.0,
+        })
+    }
+}
+
+impl encode::Encode for ServerInfo {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut message = message::MessageWriter::start(cursor, 0);
+
+        message.encode(&self.name)?;
+        message.encode(&self.address.ip().octets())?;
+        message.encode(&self.address.port())?;
+        message.encode(&PackedU32(self.connections))?;
+        message.end()
+    }
+}
+
+/// Searching for public games.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GetGameListV2 {
+    Request(GameListRequest),
+    Response(GameList),
+}
+
+impl encode::Encode for GetGameListV2 {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            GetGameListV2::Request(m) => cursor.encode(m),
+            GetGameListV2::Response(m) => cursor.encode(m),
+        }
+    }
+}
+
+/// What games to search for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameListFilter {
+    /// The maps to search, one bit per map id.
+    pub maps: u8,
+    /// How many impostors the games should have, or `0` for any.
+    pub impostors: u8,
+    /// The chat language, as a bit flag like [`GameOptions::keywords`].
+    pub language: u32,
+}
+
+/// Asks the server for public games.
+///
+/// The filter is sent as a whole set of game options, and the server only
+/// looks at the fields that make up a [`GameListFilter`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameListRequest {
+    pub options: GameOptions,
+}
+
+impl GameListRequest {
+    /// Create a new request with a filter.
+    pub fn new(filter: GameListFilter) -> GameListRequest {
+        GameListRequest {
+            options: GameOptions {
+                map: filter.maps,
+                impostors: filter.impostors,
+                keywords: filter.language,
+                ..GameOptions::default()
+            },
+        }
+    }
+
+    /// The filter of the request.
+    pub fn filter(&self) -> GameListFilter {
+        GameListFilter {
+            maps: self.options.map,
+            impostors: self.options.impostors,
+            language: self.options.keywords,
+        }
+    }
+}
+
+impl decode::Decode for GameListRequest {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        // always zero
+        cursor.decode::<PackedI32>()?;
+
+        Ok(GameListRequest {
+            options: GameOptions::decode_prefixed(cursor)?,
+        })
+    }
+}
+
+impl encode::Encode for GameListRequest {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&PackedI32(0))?;
+        self.options.encode_prefixed(cursor)
+    }
+}
+
+/// The public games found.
+///
+/// The body is a list of messages: tag `0` holds the number of games on each
+/// map, and tag `1` holds the games, each in its own message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameList {
+    /// How many games there are on each map, by map id. Empty if not sent.
+    pub map_counts: Vec<i32>,
+    pub games: Vec<GameListing>,
+}
+
+impl decode::Decode for GameList {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut list = GameList::default();
+
+        while cursor.remaining() > 0 {
+            let mut message = message::Message::read(cursor)?;
+            let tag = message.tag();
+            let cursor = message.cursor();
+
+            match tag {
+                0 => {
+                    list.map_counts.clear();
+
+                    while cursor.remaining() > 0 {
+                        list.map_counts.push(cursor.decode()?);
+                    }
+                }
+                1 => {
+                    while cursor.remaining() > 0 {
+                        list.games.push(cursor.decode()?);
+                    }
+                }
+                // anything else is from a newer version
+                _ => (),
+            }
+        }
+
+        Ok(list)
+    }
+}
+
+impl encode::Encode for GameList {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        if !self.map_counts.is_empty() {
+            let mut message = message::MessageWriter::start(cursor, 0);
+
+            for count in &self.map_counts {
+                message.encode(count)?;
+            }
+
+            message.end()?;
+        }
+
+        let mut message = message::MessageWriter::start(cursor, 1);
+
+        for game in &self.games {
+            message.encode(game)?;
+        }
+
+        message.end()
+    }
+}
+
+/// A public game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameListing {
+    /// The server the game is on.
+    pub address: SocketAddrV4,
+    pub code: GameCode,
+    pub host_name: String,
+    pub players: u8,
+    /// How long ago the game was created.
+    pub age: u32,
+    pub map: u8,
+    pub impostors: u8,
+    pub max_players: u8,
+}
+
+impl decode::Decode for GameListing {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let mut message = message::Message::read(cursor)?;
+        let cursor = message.cursor();
+
+        let ip = cursor.decode::<[u8; 4]>()?;
+        let port = cursor.decode::<u16>()?;
+
+        Ok(GameListing {
+            address: SocketAddrV4::new(Ipv4Addr::from(ip), port),
+            code: cursor.decode()?,
+            host_name: cursor.decode()?,
+            players: cursor.decode()?,
+            age: cursor.decode::<PackedU32>()?.0,
+            map: cursor.decode()?,
+            impostors: cursor.decode()?,
+            max_players: cursor.decode()?,
+        })
+    }
+}
+
+impl encode::Encode for GameListing {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        let mut message = message::MessageWriter::start(cursor, 0);
+
+        message.encode(&self.address.ip().octets())?;
+        message.encode(&self.address.port())?;
+        message.encode(&self.code)?;
+        message.encode(&self.host_name)?;
+        message.encode(&self.players)?;
+        message.encode(&PackedU32(self.age))?;
+        message.encode(&self.map)?;
+        message.encode(&self.impostors)?;
+        message.encode(&self.max_players)?;
+        message.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::protocol::options::GameOptions;
+
+    fn code() -> GameCode {
+        "REDSUS".parse().unwrap()
+    }
+
+    /// Checks that the fixture decodes to the message, and the message
+    /// encodes back to the fixture.
+    fn round_trip(bytes: &[u8], direction: Direction, message: RootMessage) {
+        let decoded = RootMessage::read_all(bytes, direction).unwrap();
+        assert_eq!(decoded, vec![message.clone()]);
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&message).unwrap();
+        assert_eq!(cursor.as_slice(), bytes);
+    }
+
+    /// Like [`round_trip`], for messages that are the same both ways.
+    fn round_trip_both(bytes: &[u8], message: RootMessage) {
+        round_trip(bytes, Direction::ToServer, message.clone());
+        round_trip(bytes, Direction::ToClient, message);
+    }
+
+    fn options() -> GameOptions {
+        GameOptions {
+            version: 4,
+            ..GameOptions::default()
+        }
+    }
+
+    #[test]
+    fn host_game() {
+        round_trip(
+            &fixture!("root/host_game_request"),
+            Direction::ToServer,
+            RootMessage::HostGame(HostGame::Request(HostGameRequest { options: options() })),
+        );
+        round_trip(
+            &fixture!("root/host_game_response"),
+            Direction::ToClient,
+            RootMessage::HostGame(HostGame::Response(HostGameResponse { code: code() })),
+        );
+    }
+
+    #[test]
+    fn join_game() {
+        round_trip(
+            &fixture!("root/join_game_request"),
+            Direction::ToServer,
+            RootMessage::JoinGame(JoinGame::Request(JoinGameRequest { code: code() })),
+        );
+        round_trip(
+            &fixture!("root/join_game_joined"),
+            Direction::ToClient,
+            RootMessage::JoinGame(JoinGame::Joined(PlayerJoined {
+                code: code(),
+                client_id: 7,
+                host_id: 1,
+            })),
+        );
+        round_trip(
+            &fixture!("root/join_game_error"),
+            Direction::ToClient,
+            RootMessage::JoinGame(JoinGame::Error(DisconnectReason::GameFull)),
+        );
+        round_trip(
+            &fixture!("root/join_game_custom"),
+            Direction::ToClient,
+            RootMessage::JoinGame(JoinGame::Error(DisconnectReason::Custom("go away".into()))),
+        );
+    }
+
+    #[test]
+    fn start_game() {
+        round_trip_both(
+            &fixture!("root/start_game"),
+            RootMessage::StartGame(StartGame { code: code() }),
+        );
+    }
+
+    #[test]
+    fn remove_game() {
+        round_trip_both(
+            &fixture!("root/remove_game"),
+            RootMessage::RemoveGame(RemoveGame { reason: DisconnectReason::Destroy }),
+        );
+    }
+
+    #[test]
+    fn remove_player() {
+        round_trip(
+            &fixture!("root/remove_player_request"),
+            Direction::ToServer,
+            RootMessage::RemovePlayer(RemovePlayer::Request(RemovePlayerRequest {
+                code: code(),
+                client_id: 7,
+                reason: DisconnectReason::Kicked,
+            })),
+        );
+        round_trip(
+            &fixture!("root/remove_player_removed"),
+            Direction::ToClient,
+            RootMessage::RemovePlayer(RemovePlayer::Removed(PlayerRemoved {
+                code: code(),
+                client_id: 7,
+                host_id: 1,
+                reason: DisconnectReason::ExitGame,
+            })),
+        );
+    }
+
+    #[test]
+    fn game_data() {
+        round_trip_both(
+            &fixture!("root/game_data"),
+            RootMessage::GameData(GameData {
+                code: code(),
+                data: vec![0, 0, 4],
+            }),
+        );
+        round_trip_both(
+            &fixture!("root/game_data_to"),
+            RootMessage::GameDataTo(GameDataTo {
+                code: code(),
+                target: 300,
+                data: vec![1, 0, 2, 0xff],
+            }),
+        );
+    }
+
+    #[test]
+    fn joined_game() {
+        round_trip_both(
+            &fixture!("root/joined_game"),
+            RootMessage::JoinedGame(JoinedGame {
+                code: code(),
+                client_id: 7,
+                host_id: 1,
+                others: vec![1, 200],
+            }),
+        );
+    }
+
+    #[test]
+    fn end_game() {
+        round_trip_both(
+            &fixture!("root/end_game"),
+            RootMessage::EndGame(EndGame {
+                code: code(),
+                reason: 2,
+                show_ad: true,
+            }),
+        );
+    }
+
+    #[test]
+    fn alter_game() {
+        round_trip_both(
+            &fixture!("root/alter_game"),
+            RootMessage::AlterGame(AlterGame::privacy(code(), true)),
+        );
+    }
+
+    #[test]
+    fn kick_player() {
+        round_trip_both(
+            &fixture!("root/kick_player"),
+            RootMessage::KickPlayer(KickPlayer {
+                code: code(),
+                client_id: 7,
+                banned: true,
+            }),
+        );
+    }
+
+    #[test]
+    fn wait_for_host() {
+        round_trip_both(
+            &fixture!("root/wait_for_host"),
+            RootMessage::WaitForHost(WaitForHost {
+                code: code(),
+                client_id: 7,
+            }),
+        );
+    }
+
+    #[test]
+    fn redirect() {
+        let address = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 20), 22023);
+
+        round_trip_both(&fixture!("root/redirect"), RootMessage::Redirect(Redirect { address }));
+    }
+
+    #[test]
+    fn reselect_server() {
+        round_trip_both(
+            &fixture!("root/reselect_server"),
+            RootMessage::ReselectServer(ReselectServer {
+                version: 0,
+                servers: vec![
+                    ServerInfo {
+                        name: "Europe-1".into(),
+                        address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 22023),
+                        connections: 150,
+                    },
+                    ServerInfo {
+                        name: "Europe-2".into(),
+                        address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 22023),
+                        connections: 3,
+                    },
+                ],
+            }),
+        );
+    }
+
+    #[test]
+    fn get_game_list() {
+        round_trip(
+            &fixture!("root/get_game_list_request"),
+            Direction::ToServer,
+            RootMessage::GetGameListV2(GetGameListV2::Request(GameListRequest {
+                options: options(),
+            })),
+        );
+        round_trip(
+            &fixture!("root/get_game_list_response"),
+            Direction::ToClient,
+            RootMessage::GetGameListV2(GetGameListV2::Response(GameList {
+                map_counts: vec![3, 0, 5],
+                games: vec![GameListing {
+                    address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 22023),
+                    code: code(),
+                    host_name: "Red".into(),
+                    players: 4,
+                    age: 130,
+                    map: 2,
+                    impostors: 2,
+                    max_players: 15,
+                }],
+            })),
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        round_trip_both(
+            &fixture!("root/unknown"),
+            RootMessage::Unknown {
+                tag: 200,
+                payload: vec![1, 2, 3],
+            },
+        );
+    }
+}
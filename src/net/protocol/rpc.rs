@@ -0,0 +1,507 @@
+//! Remote procedure calls.
+//!
+//! An RPC is sent inside a GameData message to a specific net object. On the
+//! wire it is a one byte call id followed by a payload that depends on the
+//! call.
+
+use crate::math::Vector2;
+use crate::math::quantize::QuantizedVector2;
+use crate::net::binary::{decode, encode, packed::{PackedI32, PackedU32}};
+use crate::net::objects::meeting::VoteState;
+use crate::net::seq::SeqNum;
+
+use super::options::GameOptions;
+
+pub const PLAY_ANIMATION: u8 = 0;
+pub const COMPLETE_TASK: u8 = 1;
+pub const SYNC_SETTINGS: u8 = 2;
+pub const SET_INFECTED: u8 = 3;
+pub const EXILED: u8 = 4;
+pub const CHECK_NAME: u8 = 5;
+pub const SET_NAME: u8 = 6;
+pub const CHECK_COLOR: u8 = 7;
+pub const SET_COLOR: u8 = 8;
+pub const SET_HAT: u8 = 9;
+pub const SET_SKIN: u8 = 10;
+pub const REPORT_DEAD_BODY: u8 = 11;
+pub const MURDER_PLAYER: u8 = 12;
+pub const SEND_CHAT: u8 = 13;
+pub const START_MEETING: u8 = 14;
+pub const SET_SCANNER: u8 = 15;
+pub const SEND_CHAT_NOTE: u8 = 16;
+pub const SET_PET: u8 = 17;
+pub const SET_START_COUNTER: u8 = 18;
+pub const ENTER_VENT: u8 = 19;
+pub const EXIT_VENT: u8 = 20;
+pub const SNAP_TO: u8 = 21;
+pub const CLOSE: u8 = 22;
+pub const VOTING_COMPLETE: u8 = 23;
+pub const CAST_VOTE: u8 = 24;
+pub const CLEAR_VOTE: u8 = 25;
+pub const ADD_VOTE: u8 = 26;
+pub const CLOSE_DOORS_OF_TYPE: u8 = 27;
+pub const REPAIR_SYSTEM: u8 = 28;
+pub const SET_TASKS: u8 = 29;
+pub const UPDATE_GAME_DATA: u8 = 30;
+
+/// The player id a report uses for an emergency meeting.
+pub const NO_BODY: u8 = 255;
+/// The player id of a skipped vote, or of nobody being exiled.
+pub const NO_PLAYER: u8 = 255;
+
+/// A remote procedure call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rpc {
+    /// Plays the animation of a visual task.
+    PlayAnimation {
+        task_type: u8,
+    },
+    CompleteTask {
+        index: u32,
+    },
+    SyncSettings(GameOptions),
+    /// Chooses the impostors.
+    SetInfected {
+        players: Vec<u8>,
+    },
+    Exiled,
+    /// Asks the host for a name.
+    CheckName(String),
+    SetName(String),
+    /// Asks the host for a color.
+    CheckColor(u8),
+    SetColor(u8),
+    SetHat(u32),
+    SetSkin(u32),
+    /// Reports a body, or calls an emergency meeting if it is [`NO_BODY`].
+    ReportDeadBody {
+        player: u8,
+    },
+    MurderPlayer {
+        target: u32,
+    },
+    SendChat(String),
+    /// Starts a meeting, like [`ReportDeadBody`](Rpc::ReportDeadBody).
+    StartMeeting {
+        player: u8,
+    },
+    SetScanner {
+        on: bool,
+        count: u8,
+    },
+    SendChatNote {
+        player: u8,
+        note: u8,
+    },
+    SetPet(u32),
+    SetStartCounter {
+        sequence: i32,
+        seconds: i8,
+    },
+    EnterVent {
+        vent: u32,
+    },
+    ExitVent {
+        vent: u32,
+    },
+    /// Teleports a player.
+    SnapTo {
+        position: Vector2,
+        sequence: SeqNum,
+    },
+    /// Closes the meeting screen.
+    Close,
+    VotingComplete {
+        /// The vote area state of every player.
+        states: Vec<VoteState>,
+        exiled: Option<u8>,
+        tie: bool,
+    },
+    /// Votes for a player, or skips if `suspect` is [`NO_PLAYER`].
+    CastVote {
+        voter: u8,
+        suspect: u8,
+    },
+    ClearVote,
+    /// A vote to kick a player.
+    AddVote {
+        voter: i32,
+        target: i32,
+    },
+    CloseDoorsOfType {
+        system: u8,
+    },
+    RepairSystem {
+        system: u8,
+        player: u32,
+        amount: u8,
+    },
+    SetTasks {
+        player: u8,
+        tasks: Vec<u8>,
+    },
+    UpdateGameData {
+        /// The player info messages, still encoded. See
+        /// [`GameData::read_update`](crate::net::objects::GameData::read_update).
+        data: Vec<u8>,
+    },
+    /// An RPC this crate doesn't know about.
+    Unknown {
+        call_id: u8,
+        payload: Vec<u8>,
+    },
+}
+
+impl Rpc {
+    /// The call id of the RPC.
+    pub fn call_id(&self) -> u8 {
+        match self {
+            Rpc::PlayAnimation { .. } => PLAY_ANIMATION,
+            Rpc::CompleteTask { .. } => COMPLETE_TASK,
+            Rpc::SyncSettings(_) => SYNC_SETTINGS,
+            Rpc::SetInfected { .. } => SET_INFECTED,
+            Rpc::Exiled => EXILED,
+            Rpc::CheckName(_) => CHECK_NAME,
+            Rpc::SetName(_) => SET_NAME,
+            Rpc::CheckColor(_) => CHECK_COLOR,
+            Rpc::SetColor(_) => SET_COLOR,
+            Rpc::SetHat(_) => SET_HAT,
+            Rpc::SetSkin(_) => SET_SKIN,
+            Rpc::ReportDeadBody { .. } => REPORT_DEAD_BODY,
+            Rpc::MurderPlayer { .. } => MURDER_PLAYER,
+            Rpc::SendChat(_) => SEND_CHAT,
+            Rpc::StartMeeting { .. } => START_MEETING,
+            Rpc::SetScanner { .. } => SET_SCANNER,
+            Rpc::SendChatNote { .. } => SEND_CHAT_NOTE,
+            Rpc::SetPet(_) => SET_PET,
+            Rpc::SetStartCounter { .. } => SET_START_COUNTER,
+            Rpc::EnterVent { .. } => ENTER_VENT,
+            Rpc::ExitVent { .. } => EXIT_VENT,
+            Rpc::SnapTo { .. } => SNAP_TO,
+            Rpc::Close => CLOSE,
+            Rpc::VotingComplete { .. } => VOTING_COMPLETE,
+            Rpc::CastVote { .. } => CAST_VOTE,
+            Rpc::ClearVote => CLEAR_VOTE,
+            Rpc::AddVote { .. } => ADD_VOTE,
+            Rpc::CloseDoorsOfType { .. } => CLOSE_DOORS_OF_TYPE,
+            Rpc::RepairSystem { .. } => REPAIR_SYSTEM,
+            Rpc::SetTasks { .. } => SET_TASKS,
+            Rpc::UpdateGameData { .. } => UPDATE_GAME_DATA,
+            Rpc::Unknown { call_id, .. } => *call_id,
+        }
+    }
+}
+
+impl decode::Decode for Rpc {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let call_id = cursor.decode::<u8>()?;
+
+        let rpc = match call_id {
+            PLAY_ANIMATION => Rpc::PlayAnimation {
+                task_type: cursor.decode()?,
+            },
+            COMPLETE_TASK => Rpc::CompleteTask {
+                index: cursor.decode::<PackedU32>()?.0,
+            },
+            SYNC_SETTINGS => Rpc::SyncSettings(GameOptions::decode_prefixed(cursor)?),
+            SET_INFECTED => Rpc::SetInfected {
+                players: cursor.decode()?,
+            },
+            EXILED => Rpc::Exiled,
+            CHECK_NAME => Rpc::CheckName(cursor.decode()?),
+            SET_NAME => Rpc::SetName(cursor.decode()?),
+            CHECK_COLOR => Rpc::CheckColor(cursor.decode()?),
+            SET_COLOR => Rpc::SetColor(cursor.decode()?),
+            SET_HAT => Rpc::SetHat(cursor.decode::<PackedU32>()?.0),
+            SET_SKIN => Rpc::SetSkin(cursor.decode::<PackedU32>()?.0),
+            REPORT_DEAD_BODY => Rpc::ReportDeadBody {
+                player: cursor.decode()?,
+            },
+            MURDER_PLAYER => Rpc::MurderPlayer {
+                target: cursor.decode::<PackedU32>()?.0,
+            },
+            SEND_CHAT => Rpc::SendChat(cursor.decode()?),
+            START_MEETING => Rpc::StartMeeting {
+                player: cursor.decode()?,
+            },
+            SET_SCANNER => Rpc::SetScanner {
+                on: cursor.decode()?,
+                count: cursor.decode()?,
+            },
+            SEND_CHAT_NOTE => Rpc::SendChatNote {
+                player: cursor.decode()?,
+                note: cursor.decode()?,
+            },
+            SET_PET => Rpc::SetPet(cursor.decode::<PackedU32>()?.0),
+            SET_START_COUNTER => Rpc::SetStartCounter {
+                sequence: cursor.decode::<PackedI32>()?.0,
+                seconds: cursor.decode()?,
+            },
+            ENTER_VENT => Rpc::EnterVent {
+                vent: cursor.decode::<PackedU32>()?.0,
+            },
+            EXIT_VENT => Rpc::ExitVent {
+                vent: cursor.decode::<PackedU32>()?.0,
+            },
+            SNAP_TO => Rpc::SnapTo {
+                position: cursor.decode::<QuantizedVector2>()?.0,
+                sequence: cursor.decode()?,
+            },
+            CLOSE => Rpc::Close,
+            VOTING_COMPLETE => Rpc::VotingComplete {
+                states: cursor.decode()?,
+                exiled: match cursor.decode()? {
+                    NO_PLAYER => None,
+                    player => Some(player),
+                },
+                tie: cursor.decode()?,
+            },
+            CAST_VOTE => Rpc::CastVote {
+                voter: cursor.decode()?,
+                suspect: cursor.decode()?,
+            },
+            CLEAR_VOTE => Rpc::ClearVote,
+            ADD_VOTE => Rpc::AddVote {
+                voter: cursor.decode()?,
+                target: cursor.decode()?,
+            },
+            CLOSE_DOORS_OF_TYPE => Rpc::CloseDoorsOfType {
+                system: cursor.decode()?,
+            },
+            REPAIR_SYSTEM => Rpc::RepairSystem {
+                system: cursor.decode()?,
+                player: cursor.decode::<PackedU32>()?.0,
+                amount: cursor.decode()?,
+            },
+            SET_TASKS => Rpc::SetTasks {
+                player: cursor.decode()?,
+                tasks: cursor.decode()?,
+            },
+            UPDATE_GAME_DATA => Rpc::UpdateGameData {
+                data: cursor.decode_rest().to_vec(),
+            },
+            call_id => Rpc::Unknown {
+                call_id,
+                payload: cursor.decode_rest().to_vec(),
+            },
+        };
+
+        Ok(rpc)
+    }
+}
+
+impl encode::Encode for Rpc {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.call_id())?;
+
+        match self {
+            Rpc::PlayAnimation { task_type } => cursor.encode(task_type),
+            Rpc::CompleteTask { index } => cursor.encode(&PackedU32(*index)),
+            Rpc::SyncSettings(options) => options.encode_prefixed(cursor),
+            Rpc::SetInfected { players } => cursor.encode(players),
+            Rpc::Exiled | Rpc::Close | Rpc::ClearVote => Ok(()),
+            Rpc::CheckName(name) | Rpc::SetName(name) => cursor.encode(name),
+            Rpc::CheckColor(color) | Rpc::SetColor(color) => cursor.encode(color),
+            Rpc::SetHat(id) | Rpc::SetSkin(id) | Rpc::SetPet(id) => cursor.encode(&PackedU32(*id)),
+            Rpc::ReportDeadBody { player } | Rpc::StartMeeting { player } => cursor.encode(player),
+            Rpc::MurderPlayer { target } => cursor.encode(&PackedU32(*target)),
+            Rpc::SendChat(message) => cursor.encode(message),
+            Rpc::SetScanner { on, count } => {
+                cursor.encode(on)?;
+                cursor.encode(count)
+            }
+            Rpc::SendChatNote { player, note } => {
+                cursor.encode(player)?;
+                cursor.encode(note)
+            }
+            Rpc::SetStartCounter { sequence, seconds } => {
+                cursor.encode(&PackedI32(*sequence))?;
+                cursor.encode(seconds)
+            }
+            Rpc::EnterVent { vent } | Rpc::ExitVent { vent } => cursor.encode(&PackedU32(*vent)),
+            Rpc::SnapTo { position, sequence } => {
+                cursor.encode(&QuantizedVector2(*position))?;
+                cursor.encode(sequence)
+            }
+            Rpc::VotingComplete { states, exiled, tie } => {
+                cursor.encode(states)?;
+                cursor.encode(&exiled.unwrap_or(NO_PLAYER))?;
+                cursor.encode(tie)
+            }
+            Rpc::CastVote { voter, suspect } => {
+                cursor.encode(voter)?;
+                cursor.encode(suspect)
+            }
+            Rpc::AddVote { voter, target } => {
+                cursor.encode(voter)?;
+                cursor.encode(target)
+            }
+            Rpc::CloseDoorsOfType { system } => cursor.encode(system),
+            Rpc::RepairSystem { system, player, amount } => {
+                cursor.encode(system)?;
+                cursor.encode(&PackedU32(*player))?;
+                cursor.encode(amount)
+            }
+            Rpc::SetTasks { player, tasks } => {
+                cursor.encode(player)?;
+                cursor.encode(tasks)
+            }
+            Rpc::UpdateGameData { data } => {
+                cursor.write(data);
+                Ok(())
+            }
+            Rpc::Unknown { payload, .. } => {
+                cursor.write(payload);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::protocol::options::KillDistance;
+
+    /// One of every RPC, in call id order, as in `tests/fixtures/rpc/all`.
+    fn every_rpc() -> Vec<Rpc> {
+        let options = GameOptions {
+            version: 1,
+            max_players: 15,
+            map: 2,
+            player_speed: 1.25,
+            crewmate_vision: 0.5,
+            kill_cooldown: 30.0,
+            common_tasks: 2,
+            long_tasks: 3,
+            short_tasks: 5,
+            emergency_meetings: 9,
+            impostors: 3,
+            kill_distance: KillDistance::Long,
+            discussion_time: 0,
+            voting_time: 300,
+            is_defaults: false,
+            ..GameOptions::default()
+        };
+
+        let voted = |voted_for| VoteState {
+            did_vote: true,
+            voted_for,
+            ..VoteState::default()
+        };
+
+        vec![
+            Rpc::PlayAnimation { task_type: 6 },
+            Rpc::CompleteTask { index: 300 },
+            Rpc::SyncSettings(options),
+            Rpc::SetInfected { players: vec![2, 5] },
+            Rpc::Exiled,
+            Rpc::CheckName("Red".into()),
+            Rpc::SetName("Red".into()),
+            Rpc::CheckColor(3),
+            Rpc::SetColor(3),
+            Rpc::SetHat(10),
+            Rpc::SetSkin(200),
+            Rpc::ReportDeadBody { player: NO_BODY },
+            Rpc::MurderPlayer { target: 21 },
+            Rpc::SendChat("gg".into()),
+            Rpc::StartMeeting { player: 4 },
+            Rpc::SetScanner { on: true, count: 7 },
+            Rpc::SendChatNote { player: 4, note: 0 },
+            Rpc::SetPet(2),
+            Rpc::SetStartCounter { sequence: 5, seconds: -1 },
+            Rpc::EnterVent { vent: 3 },
+            Rpc::ExitVent { vent: 3 },
+            Rpc::SnapTo {
+                position: Vector2::new(-50., 50.),
+                sequence: SeqNum(0x102),
+            },
+            Rpc::Close,
+            Rpc::VotingComplete {
+                states: vec![
+                    VoteState { dead: true, ..VoteState::default() },
+                    voted(Some(0)),
+                    VoteState { did_report: true, ..voted(None) },
+                ],
+                exiled: Some(0),
+                tie: false,
+            },
+            Rpc::CastVote { voter: 1, suspect: NO_PLAYER },
+            Rpc::ClearVote,
+            Rpc::AddVote { voter: 7, target: 9 },
+            Rpc::CloseDoorsOfType { system: 16 },
+            Rpc::RepairSystem { system: 3, player: 21, amount: 128 },
+            Rpc::SetTasks { player: 2, tasks: vec![0, 4, 9] },
+            Rpc::UpdateGameData { data: vec![2, 0, 1, 0xaa, 0xbb] },
+        ]
+    }
+
+    fn encode(rpc: &Rpc) -> Vec<u8> {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(rpc).unwrap();
+        cursor.into()
+    }
+
+    #[test]
+    fn call_ids() {
+        let rpcs = every_rpc();
+        assert_eq!(rpcs.len(), UPDATE_GAME_DATA as usize + 1);
+
+        for (call_id, rpc) in rpcs.iter().enumerate() {
+            assert_eq!(rpc.call_id() as usize, call_id, "{:?}", rpc);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for rpc in every_rpc() {
+            let bytes = encode(&rpc);
+
+            let mut cursor = decode::Cursor::new(&bytes[..]);
+            assert_eq!(cursor.decode::<Rpc>().unwrap(), rpc);
+            assert_eq!(cursor.remaining(), 0, "{:?}", rpc);
+        }
+    }
+
+    #[test]
+    fn known_bytes() {
+        let bytes = fixture!("rpc/all");
+        let rpcs = every_rpc();
+
+        let mut cursor = decode::Cursor::new(&bytes[..]);
+
+        for rpc in &rpcs {
+            assert_eq!(&cursor.decode::<Rpc>().unwrap(), rpc);
+        }
+
+        assert_eq!(cursor.remaining(), 0);
+        assert_eq!(rpcs.iter().flat_map(encode).collect::<Vec<_>>(), bytes);
+    }
+
+    #[test]
+    fn unknown() {
+        let rpc = Rpc::Unknown {
+            call_id: 200,
+            payload: vec![1, 2, 3],
+        };
+
+        assert_eq!(encode(&rpc), [200, 1, 2, 3]);
+        assert_eq!(decode::parse::<Rpc>(&[200, 1, 2, 3]).unwrap(), rpc);
+    }
+
+    #[test]
+    fn unencodable_vote() {
+        let rpc = Rpc::VotingComplete {
+            states: vec![VoteState {
+                voted_for: Some(15),
+                ..VoteState::default()
+            }],
+            exiled: None,
+            tie: true,
+        };
+
+        assert!(encode::CursorMut::new().encode(&rpc).is_err());
+    }
+}
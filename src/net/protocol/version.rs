@@ -0,0 +1,164 @@
+//! Game versions.
+//!
+//! The client says hello with its version packed into an `i32`. Most messages
+//! look the same on every version, but a few grew fields over time, so the
+//! version is boiled down to a [`Protocol`] that the codecs can ask about.
+
+use std::fmt;
+
+use crate::net::binary::{decode, encode};
+
+/// The version of a game client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GameVersion {
+    pub year: i32,
+    pub month: i32,
+    pub day: i32,
+    pub revision: i32,
+}
+
+impl GameVersion {
+    /// The first version that sends its language and chat mode in the hello.
+    pub const V2021_4_12: GameVersion = GameVersion::new(2021, 4, 12, 0);
+    /// The first version that sends platform data in the hello.
+    pub const V2021_12_14: GameVersion = GameVersion::new(2021, 12, 14, 0);
+
+    /// Create a new `GameVersion`.
+    pub const fn new(year: i32, month: i32, day: i32, revision: i32) -> GameVersion {
+        GameVersion { year, month, day, revision }
+    }
+
+    /// Unpacks a version as it is sent on the wire.
+    pub fn from_raw(raw: i32) -> GameVersion {
+        let date = raw % 25000;
+
+        GameVersion {
+            year: raw / 25000,
+            month: date / 1800,
+            day: date % 1800 / 50,
+            revision: date % 1800 % 50,
+        }
+    }
+
+    /// The version as it is sent on the wire.
+    pub fn raw(self) -> i32 {
+        self.year * 25000 + self.month * 1800 + self.day * 50 + self.revision
+    }
+
+    /// The protocol the version speaks.
+    pub fn protocol(self) -> Protocol {
+        if self >= GameVersion::V2021_12_14 {
+            Protocol::V2022
+        } else if self >= GameVersion::V2021_4_12 {
+            Protocol::V2021
+        } else {
+            Protocol::V2020
+        }
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.year, self.month, self.day)?;
+
+        if self.revision != 0 {
+            write!(f, ".{}", self.revision)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl decode::Decode for GameVersion {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode().map(GameVersion::from_raw)
+    }
+}
+
+impl encode::Encode for GameVersion {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.raw())
+    }
+}
+
+/// The layout of the messages that changed between versions, oldest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Protocol {
+    /// Versions before 2021.4.12.
+    V2020,
+    /// Versions from 2021.4.12, with a longer hello.
+    V2021,
+    /// Versions from 2021.12.14, which speak Hazel 1 and send platform data.
+    V2022,
+}
+
+impl Protocol {
+    /// The newest protocol this crate knows.
+    pub const LATEST: Protocol = Protocol::V2022;
+
+    /// The Hazel version sent in the hello.
+    pub fn hazel_version(self) -> u8 {
+        match self {
+            Protocol::V2020 | Protocol::V2021 => 0,
+            Protocol::V2022 => 1,
+        }
+    }
+
+    /// Whether the hello has the last nonce, language and chat mode after
+    /// the name.
+    pub fn has_hello_settings(self) -> bool {
+        self >= Protocol::V2021
+    }
+
+    /// Whether the hello ends with platform data and more.
+    pub fn has_hello_platform(self) -> bool {
+        self >= Protocol::V2022
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw() {
+        let known = [
+            (GameVersion::new(2020, 9, 7, 1), 50_516_551),
+            (GameVersion::new(2021, 6, 30, 0), 50_537_300),
+            (GameVersion::V2021_12_14, 50_547_300),
+            (GameVersion::new(2022, 3, 29, 0), 50_556_850),
+        ];
+
+        for &(version, raw) in &known {
+            assert_eq!(version.raw(), raw, "{}", version);
+            assert_eq!(GameVersion::from_raw(raw), version);
+        }
+    }
+
+    #[test]
+    fn protocol() {
+        assert_eq!(GameVersion::new(2020, 9, 7, 1).protocol(), Protocol::V2020);
+        assert_eq!(GameVersion::new(2021, 4, 11, 49).protocol(), Protocol::V2020);
+        assert_eq!(GameVersion::V2021_4_12.protocol(), Protocol::V2021);
+        assert_eq!(GameVersion::new(2021, 12, 13, 0).protocol(), Protocol::V2021);
+        assert_eq!(GameVersion::V2021_12_14.protocol(), Protocol::V2022);
+        assert_eq!(GameVersion::new(2022, 3, 29, 0).protocol(), Protocol::LATEST);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(GameVersion::new(2021, 6, 30, 0).to_string(), "2021.6.30");
+        assert_eq!(GameVersion::new(2020, 9, 7, 1).to_string(), "2020.9.7.1");
+    }
+
+    #[test]
+    fn codec() {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&GameVersion::new(2021, 6, 30, 0)).unwrap();
+        assert_eq!(cursor.as_slice(), [0x54, 0x23, 0x03, 0x03]);
+
+        let mut cursor = decode::Cursor::new(&[0x47, 0xd2, 0x02, 0x03][..]);
+        assert_eq!(cursor.decode::<GameVersion>().unwrap(), GameVersion::new(2020, 9, 7, 1));
+    }
+}
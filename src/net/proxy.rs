@@ -0,0 +1,324 @@
+//! A relay between a real client and server.
+//!
+//! A [`Proxy`] decodes every datagram that goes through it and shows each root
+//! message to a [`Hook`], which can change it or drop it before the datagram
+//! is encoded again and passed on. Packet ids are left alone, so Hazel's acks
+//! keep working on both sides: a reliable packet whose messages were all
+//! dropped is still forwarded, just empty.
+//!
+//! Datagrams that can't be decoded are forwarded as they are. Fragmented
+//! payloads are shown to the hook once the last fragment arrives, but the
+//! fragments have already gone by then, so changes to them are lost.
+//!
+//! Like the rest of `net`, the proxy does no IO itself. With the `tokio`
+//! feature, [`run`](Proxy::run) puts it between a socket and a server.
+
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "tokio")]
+use std::io;
+use std::net::SocketAddr;
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+use std::time::Instant;
+
+#[cfg(feature = "tokio")]
+use tokio::net::UdpSocket;
+#[cfg(feature = "tokio")]
+use tokio::sync::mpsc;
+#[cfg(feature = "tokio")]
+use tokio::task::JoinHandle;
+
+use crate::net::connection::Hello;
+use crate::net::pcap::{Contents, Decoded};
+use crate::net::protocol::{Direction, RootMessage};
+use crate::net::transport::fragment::Reassembler;
+use crate::net::transport::packet::Packet;
+use crate::net::transport::Config;
+
+/// What to do with a message going through a [`Proxy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Pass the message on, with any changes.
+    Forward,
+    /// Leave the message out.
+    Drop,
+}
+
+/// Looks at, and maybe changes, the traffic going through a [`Proxy`].
+pub trait Hook {
+    /// Called for every root message. `client` is the client the message is
+    /// to or from.
+    fn message(
+        &mut self,
+        client: SocketAddr,
+        direction: Direction,
+        message: &mut RootMessage,
+    ) -> Action;
+
+    /// Called for the hello of every client. Nothing, by default.
+    fn hello(&mut self, client: SocketAddr, hello: &mut Hello) {
+        let _ = (client, hello);
+    }
+}
+
+impl<F> Hook for F
+where F: FnMut(SocketAddr, Direction, &mut RootMessage) -> Action {
+    fn message(
+        &mut self,
+        client: SocketAddr,
+        direction: Direction,
+        message: &mut RootMessage,
+    ) -> Action {
+        self(client, direction, message)
+    }
+}
+
+/// Relays datagrams between clients and a server through a [`Hook`].
+pub struct Proxy<H> {
+    hook: H,
+    config: Config,
+    flows: HashMap<(SocketAddr, Direction), Reassembler>,
+    closed: VecDeque<SocketAddr>,
+}
+
+impl<H> Proxy<H>
+where H: Hook {
+    /// Create a new `Proxy` around a hook.
+    pub fn new(hook: H) -> Proxy<H> {
+        Proxy {
+            hook,
+            config: Config::default(),
+            flows: HashMap::new(),
+            closed: VecDeque::new(),
+        }
+    }
+
+    /// The hook of the proxy.
+    pub fn hook(&self) -> &H {
+        &self.hook
+    }
+
+    /// The hook of the proxy, mutably.
+    pub fn hook_mut(&mut self) -> &mut H {
+        &mut self.hook
+    }
+
+    /// Takes a datagram to or from `client`, returning what to pass on in its
+    /// place, if anything.
+    pub fn relay(
+        &mut self,
+        client: SocketAddr,
+        direction: Direction,
+        datagram: &[u8],
+        now: Instant,
+    ) -> Option<Vec<u8>> {
+        let mut decoded = match Decoded::decode(datagram, direction) {
+            Ok(decoded) => decoded,
+            Err(_) => return Some(datagram.to_vec()),
+        };
+
+        match &decoded.packet {
+            Packet::Fragment { .. } => {
+                self.inspect_fragment(client, direction, &decoded.packet, now);
+                return Some(datagram.to_vec());
+            }
+            Packet::Disconnect(_) => {
+                self.forget(client);
+                self.closed.push_back(client);
+            }
+            _ => (),
+        }
+
+        let changed = match &mut decoded.contents {
+            Contents::Messages(messages) => {
+                let unreliable = matches!(decoded.packet, Packet::Unreliable(_));
+                let changed = self.filter(client, direction, messages);
+
+                // an empty reliable packet still needs to be acknowledged
+                if unreliable && messages.is_empty() {
+                    return None;
+                }
+
+                changed
+            }
+            Contents::Hello(hello) => {
+                let original = hello.clone();
+                self.hook.hello(client, hello);
+
+                *hello != original
+            }
+            _ => false,
+        };
+
+        if !changed {
+            return Some(datagram.to_vec());
+        }
+
+        // a message that won't encode can't be sent at all
+        decoded.encode().ok()
+    }
+
+    /// Forgets everything about a client, once it is gone.
+    pub fn forget(&mut self, client: SocketAddr) {
+        self.flows.remove(&(client, Direction::ToServer));
+        self.flows.remove(&(client, Direction::ToClient));
+    }
+
+    /// The next client whose connection was closed.
+    pub fn poll_closed(&mut self) -> Option<SocketAddr> {
+        self.closed.pop_front()
+    }
+
+    /// Runs messages by the hook, returning whether any changed.
+    fn filter(
+        &mut self,
+        client: SocketAddr,
+        direction: Direction,
+        messages: &mut Vec<RootMessage>,
+    ) -> bool {
+        let len = messages.len();
+        let mut changed = false;
+
+        messages.retain_mut(|message| {
+            let original = message.clone();
+            let action = self.hook.message(client, direction, message);
+
+            changed |= *message != original;
+            action == Action::Forward
+        });
+
+        changed || messages.len() != len
+    }
+
+    /// Shows the hook a fragmented payload, once it is whole.
+    fn inspect_fragment(
+        &mut self,
+        client: SocketAddr,
+        direction: Direction,
+        fragment: &Packet,
+        now: Instant,
+    ) {
+        let (group, index, count, payload) = match fragment {
+            Packet::Fragment { group, index, count, payload, .. } => {
+                (*group, *index, *count, payload.clone())
+            }
+            _ => return,
+        };
+
+        let reassembler = self.flows.entry((client, direction)).or_default();
+        reassembler.expire(now);
+
+        let payload = reassembler.insert(&self.config, group, index, count, payload, now);
+
+        if let Ok(Some(payload)) = payload {
+            let messages = RootMessage::read_all(&payload, direction).unwrap_or_default();
+
+            for mut message in messages {
+                self.hook.message(client, direction, &mut message);
+            }
+        }
+    }
+}
+
+/// The largest datagram the proxy will relay.
+#[cfg(feature = "tokio")]
+const MAX_DATAGRAM: usize = 65507;
+
+/// The socket a client is relayed to the server through.
+#[cfg(feature = "tokio")]
+struct Upstream {
+    socket: Arc<UdpSocket>,
+    task: JoinHandle<()>,
+}
+
+#[cfg(feature = "tokio")]
+impl<H> Proxy<H>
+where H: Hook {
+    /// Relays clients that send to `socket` to `server`, forever or until
+    /// the socket fails.
+    ///
+    /// Each client gets its own socket to the server, so the server sees
+    /// one address per client like it would without the proxy.
+    pub async fn run(mut self, socket: UdpSocket, server: SocketAddr) -> io::Result<()> {
+        let mut upstreams = HashMap::<SocketAddr, Upstream>::new();
+        let (from_server, mut received) = mpsc::unbounded_channel();
+        let mut buf = vec![0; MAX_DATAGRAM];
+
+        loop {
+            tokio::select! {
+                result = socket.recv_from(&mut buf) => {
+                    let (len, client) = result?;
+
+                    let upstream = match upstreams.get(&client) {
+                        Some(upstream) => upstream,
+                        None => {
+                            let upstream = connect(client, server, from_server.clone()).await?;
+                            upstreams.entry(client).or_insert(upstream)
+                        }
+                    };
+
+                    let datagram = self.relay(
+                        client,
+                        Direction::ToServer,
+                        &buf[..len],
+                        Instant::now(),
+                    );
+
+                    if let Some(datagram) = datagram {
+                        // the server being unreachable is the client's problem
+                        let _ = upstream.socket.send(&datagram).await;
+                    }
+                }
+                Some((client, datagram)) = received.recv() => {
+                    let datagram = self.relay(
+                        client,
+                        Direction::ToClient,
+                        &datagram,
+                        Instant::now(),
+                    );
+
+                    if let Some(datagram) = datagram {
+                        socket.send_to(&datagram, client).await?;
+                    }
+                }
+            }
+
+            while let Some(client) = self.poll_closed() {
+                if let Some(upstream) = upstreams.remove(&client) {
+                    upstream.task.abort();
+                }
+            }
+        }
+    }
+}
+
+/// Opens a socket to the server for a client, passing on what the server
+/// sends back.
+#[cfg(feature = "tokio")]
+async fn connect(
+    client: SocketAddr,
+    server: SocketAddr,
+    from_server: mpsc::UnboundedSender<(SocketAddr, Vec<u8>)>,
+) -> io::Result<Upstream> {
+    let local: SocketAddr = if server.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+
+    let socket = Arc::new(UdpSocket::bind(local).await?);
+    socket.connect(server).await?;
+
+    let receiver = socket.clone();
+    let task = tokio::spawn(async move {
+        let mut buf = vec![0; MAX_DATAGRAM];
+
+        while let Ok(len) = receiver.recv(&mut buf).await {
+            if from_server.send((client, buf[..len].to_vec())).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Upstream { socket, task })
+}
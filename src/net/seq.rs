@@ -0,0 +1,132 @@
+//! Sequence numbers.
+//!
+//! Both Hazel's reliable ids and movement updates count with a `u16` that
+//! wraps around. A number is newer than another if it is less than half the
+//! number space ahead of it, so comparing them with `>` is always wrong
+//! somewhere.
+
+use core::fmt;
+use core::ops::{Add, Sub};
+
+use crate::net::binary::{decode, encode};
+
+/// A `u16` sequence number that wraps around.
+///
+/// There is deliberately no `Ord`, since "after" isn't transitive: `a` can be
+/// after `b` and `b` after `c` without `a` being after `c`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SeqNum(pub u16);
+
+impl SeqNum {
+    /// The number after this one.
+    pub fn next(self) -> SeqNum {
+        self + 1
+    }
+
+    /// How far ahead of `other` this number is. Negative if it is behind.
+    pub fn distance(self, other: SeqNum) -> i16 {
+        self.0.wrapping_sub(other.0) as i16
+    }
+
+    /// Whether this number comes after `other`.
+    pub fn is_after(self, other: SeqNum) -> bool {
+        self.distance(other) > 0
+    }
+
+    /// Whether this number comes before `other`.
+    pub fn is_before(self, other: SeqNum) -> bool {
+        other.is_after(self)
+    }
+}
+
+// adding and subtracting always wraps
+impl Add<u16> for SeqNum {
+    type Output = SeqNum;
+
+    fn add(self, n: u16) -> SeqNum {
+        SeqNum(self.0.wrapping_add(n))
+    }
+}
+
+impl Sub<u16> for SeqNum {
+    type Output = SeqNum;
+
+    fn sub(self, n: u16) -> SeqNum {
+        SeqNum(self.0.wrapping_sub(n))
+    }
+}
+
+impl From<u16> for SeqNum {
+    fn from(n: u16) -> SeqNum {
+        SeqNum(n)
+    }
+}
+
+impl From<SeqNum> for u16 {
+    fn from(n: SeqNum) -> u16 {
+        n.0
+    }
+}
+
+impl fmt::Display for SeqNum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// sent as a plain u16, little-endian like everything else
+impl decode::Decode for SeqNum {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        cursor.decode().map(SeqNum)
+    }
+}
+
+impl encode::Encode for SeqNum {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        cursor.encode(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around() {
+        assert!(SeqNum(65535).is_before(SeqNum(0)));
+        assert!(SeqNum(0).is_after(SeqNum(65535)));
+        assert_eq!(SeqNum(0).distance(SeqNum(65535)), 1);
+        assert_eq!(SeqNum(65535).distance(SeqNum(0)), -1);
+        assert_eq!(SeqNum(65535).next(), SeqNum(0));
+        assert_eq!(SeqNum(1) - 2, SeqNum(65535));
+        assert_eq!(SeqNum(65530) + 10, SeqNum(4));
+    }
+
+    #[test]
+    fn half_the_space() {
+        // less than half ahead is after, and exactly half is neither
+        assert!(SeqNum(32767).is_after(SeqNum(0)));
+        assert!(!SeqNum(32768).is_after(SeqNum(0)));
+        assert!(!SeqNum(32768).is_before(SeqNum(0)));
+        assert!(SeqNum(32769).is_before(SeqNum(0)));
+        assert!(SeqNum(40000).is_after(SeqNum(10000)));
+        assert!(SeqNum(10000).is_after(SeqNum(50000)));
+    }
+
+    #[test]
+    fn equal_is_neither() {
+        assert!(!SeqNum(5).is_after(SeqNum(5)));
+        assert!(!SeqNum(5).is_before(SeqNum(5)));
+        assert_eq!(SeqNum(5).distance(SeqNum(5)), 0);
+    }
+
+    #[test]
+    fn codec() {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&SeqNum(0x1234)).unwrap();
+
+        assert_eq!(cursor.as_slice(), [0x34, 0x12]);
+        assert_eq!(decode::parse::<SeqNum>(cursor.as_slice()).unwrap(), SeqNum(0x1234));
+    }
+}
@@ -0,0 +1,40 @@
+//! Host migration.
+//!
+//! When the host leaves a room, someone else has to simulate the game. The
+//! server picks them with a [`HostPolicy`], and everyone learns who it is from
+//! the new host in the RemovePlayer for the old one.
+
+use super::room::{Room, RoomMessage};
+
+/// Picks who becomes the host when the host leaves.
+pub trait HostPolicy {
+    /// Picks the new host out of the clients still in `room`.
+    ///
+    /// Anything that isn't in the room falls back to [`FirstJoined`].
+    fn elect(&self, room: &Room, old_host: i32) -> Option<i32>;
+
+    /// Messages to send the new host once it knows it is host, for state only
+    /// the old host had. Nothing, by default.
+    fn replay(&self, room: &Room, new_host: i32) -> Vec<RoomMessage> {
+        let _ = (room, new_host);
+
+        Vec::new()
+    }
+}
+
+/// Makes the client that has been in the room the longest the host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FirstJoined;
+
+impl HostPolicy for FirstJoined {
+    fn elect(&self, room: &Room, _old_host: i32) -> Option<i32> {
+        room.players().first().copied()
+    }
+}
+
+impl<F> HostPolicy for F
+where F: Fn(&Room, i32) -> Option<i32> {
+    fn elect(&self, room: &Room, old_host: i32) -> Option<i32> {
+        self(room, old_host)
+    }
+}
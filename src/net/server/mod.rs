@@ -0,0 +1,951 @@
+//! A dedicated server.
+//!
+//! [`Server`] keeps a [`Connection`] for every client and a [`Room`] for every
+//! hosted game. Like the rest of `net`, it does no IO itself: feed it
+//! datagrams and send what it gives back, or hand it a socket with
+//! [`run`](Server::run).
+//!
+//! The server doesn't simulate the game. Game data is forwarded between the
+//! clients in a room, and the host is trusted with everything only the host
+//! may do: starting and ending the game, and kicking players.
+//!
+//! Hosts make their rooms public with an
+//! [`AlterGame`](crate::net::protocol::root::AlterGame), after which they show
+//! up in the [game list](Server::game_list) until the game starts.
+//!
+//! A client can host one room at a time unless the [room
+//! limit](Server::set_room_limit) says otherwise. Rooms the host never joins
+//! are closed when the host leaves, or after a short while.
+//!
+//! Clients picked by a [spectator filter](Server::set_spectator_filter) join
+//! rooms as spectators instead. They see all the game data, but can't send
+//! any, and the players don't know they are there.
+
+pub mod host;
+pub mod room;
+
+pub use host::{FirstJoined, HostPolicy};
+pub use room::{Outgoing, Room, RoomMessage, RoomState};
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::{Duration, Instant};
+
+use tokio::net::UdpSocket;
+use tokio::time;
+
+use crate::net::binary::{decode, encode};
+use crate::net::connection::{self, Connection, DisconnectReason, Hello};
+use crate::net::limit::RateLimit;
+use crate::net::metrics::Metrics;
+use crate::net::protocol::code::GameCode;
+use crate::net::protocol::root::{
+    GameData,
+    GameDataTo,
+    GameList,
+    GameListFilter,
+    GameListing,
+    GetGameListV2,
+    HostGame,
+    HostGameResponse,
+    JoinGame,
+    JoinedGame,
+    KickPlayer,
+    PlayerJoined,
+    PlayerRemoved,
+    RemovePlayer,
+};
+use crate::net::protocol::{Direction, RootMessage};
+use crate::net::transport::{Config, packet};
+
+/// The largest datagram the server will receive.
+const MAX_DATAGRAM: usize = 65507;
+
+/// How long a client has to join a room it hosted before the room is closed.
+const HOST_JOIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Picks the clients that join as spectators.
+type SpectatorFilter = dyn Fn(&Hello) -> bool + Send;
+
+/// A client connected to the server.
+struct Peer {
+    connection: Connection,
+    client_id: i32,
+    /// The room the client is in.
+    room: Option<GameCode>,
+    spectator: bool,
+}
+
+/// A dedicated server.
+pub struct Server {
+    config: Config,
+    rate_limit: Option<RateLimit>,
+    room_limit: usize,
+    host_policy: Box<dyn HostPolicy + Send>,
+    spectator_filter: Option<Box<SpectatorFilter>>,
+    public_addr: Option<SocketAddrV4>,
+    peers: HashMap<SocketAddr, Peer>,
+    clients: HashMap<i32, SocketAddr>,
+    rooms: HashMap<GameCode, Room>,
+    next_client_id: i32,
+    // seeds game codes
+    random: RandomState,
+    codes_made: u64,
+}
+
+impl Server {
+    /// Create a new server with no clients.
+    pub fn new(config: Config) -> Server {
+        Server {
+            config,
+            rate_limit: Some(RateLimit::default()),
+            room_limit: 1,
+            host_policy: Box::new(FirstJoined),
+            spectator_filter: None,
+            public_addr: None,
+            peers: HashMap::new(),
+            clients: HashMap::new(),
+            rooms: HashMap::new(),
+            next_client_id: 1,
+            random: RandomState::new(),
+            codes_made: 0,
+        }
+    }
+
+    /// The limit on how fast each client can send, if any.
+    pub fn rate_limit(&self) -> Option<&RateLimit> {
+        self.rate_limit.as_ref()
+    }
+
+    /// Limits how fast clients that connect after this can send. Clients are
+    /// limited by [`RateLimit::default`] to begin with.
+    pub fn set_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.rate_limit = limit;
+    }
+
+    /// How many rooms a client can host at once.
+    pub fn room_limit(&self) -> usize {
+        self.room_limit
+    }
+
+    /// Limits how many rooms a client can host at once. Clients can host one
+    /// room to begin with.
+    pub fn set_room_limit(&mut self, limit: usize) {
+        self.room_limit = limit;
+    }
+
+    /// Changes how a new host is picked when a host leaves. The client that
+    /// has been in the room the longest is picked to begin with.
+    pub fn set_host_policy<P>(&mut self, policy: P)
+    where P: HostPolicy + Send + 'static {
+        self.host_policy = Box::new(policy);
+    }
+
+    /// Makes clients whose hello passes `filter` join rooms as spectators.
+    pub fn set_spectator_filter<F>(&mut self, filter: F)
+    where F: Fn(&Hello) -> bool + Send + 'static {
+        self.spectator_filter = Some(Box::new(filter));
+    }
+
+    /// The address games are listed with. Unless it is set, [`run`](Server::run)
+    /// uses the address of its socket.
+    pub fn public_addr(&self) -> Option<SocketAddrV4> {
+        self.public_addr
+    }
+
+    /// Sets the address games are listed with, for servers behind a NAT.
+    pub fn set_public_addr(&mut self, addr: SocketAddrV4) {
+        self.public_addr = Some(addr);
+    }
+
+    /// The public games in the lobby that pass `filter`, as of `now`.
+    pub fn game_list(&self, filter: GameListFilter, now: Instant) -> GameList {
+        let address = self.public_addr
+            .unwrap_or_else(|| SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+
+        let games = self.rooms.values()
+            .filter(|room| room.is_public() && room.state() == RoomState::Lobby)
+            .filter(|room| list_filter(room, filter))
+            .filter_map(|room| {
+                let options = room.options();
+
+                // a room only has a name once its host has joined
+                let host_name = self.clients.get(&room.host_id())
+                    .and_then(|addr| self.peers.get(addr))
+                    .filter(|_| room.contains(room.host_id()))?
+                    .connection
+                    .hello()?
+                    .name
+                    .clone();
+
+                Some(GameListing {
+                    address,
+                    code: room.code(),
+                    host_name,
+                    players: room.players().len() as u8,
+                    age: now.saturating_duration_since(room.created()).as_secs() as u32,
+                    map: options.map,
+                    impostors: options.impostors,
+                    max_players: options.max_players,
+                })
+            })
+            .collect();
+
+        GameList {
+            map_counts: Vec::new(),
+            games,
+        }
+    }
+
+    /// A hosted room.
+    pub fn room(&self, code: GameCode) -> Option<&Room> {
+        self.rooms.get(&code)
+    }
+
+    /// Every hosted room.
+    pub fn rooms(&self) -> impl Iterator<Item = &Room> {
+        self.rooms.values()
+    }
+
+    /// What has gone over the connection to a client so far.
+    pub fn client_metrics(&self, client_id: i32) -> Option<Metrics> {
+        let addr = self.clients.get(&client_id)?;
+
+        self.peers.get(addr).map(|peer| peer.connection.metrics())
+    }
+
+    /// The address of a client.
+    pub fn client_addr(&self, client_id: i32) -> Option<SocketAddr> {
+        self.clients.get(&client_id).copied()
+    }
+
+    /// Handles a datagram from a client.
+    pub fn handle_datagram(
+        &mut self,
+        addr: SocketAddr,
+        datagram: &[u8],
+        now: Instant,
+    ) -> Result<(), decode::Error> {
+        if !self.peers.contains_key(&addr) {
+            // only a hello can start a connection
+            if datagram.first() != Some(&packet::HELLO) {
+                return Ok(());
+            }
+
+            let client_id = self.next_client_id;
+            self.next_client_id = self.next_client_id.wrapping_add(1).max(1);
+
+            let mut connection = Connection::accept(self.config.clone());
+            connection.set_rate_limit(self.rate_limit.clone());
+
+            self.clients.insert(client_id, addr);
+            self.peers.insert(addr, Peer {
+                connection,
+                client_id,
+                room: None,
+                spectator: false,
+            });
+        }
+
+        let peer = self.peers.get_mut(&addr).expect("peer was just added");
+        let result = peer.connection.handle_datagram(datagram, now);
+        let processed = self.process_events(addr, now);
+
+        self.flush_rooms(now);
+
+        processed.and(result)
+    }
+
+    /// The next time [`handle_timeout`](Server::handle_timeout) should be
+    /// called.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let unjoined = self.rooms.values()
+            .filter(|room| room.is_empty())
+            .map(|room| room.created() + HOST_JOIN_TIMEOUT);
+
+        self.peers.values()
+            .filter_map(|p| p.connection.next_timeout())
+            .chain(unjoined)
+            .min()
+    }
+
+    /// Resends and sends keepalives that are due, and drops clients that
+    /// timed out and rooms nobody joined in time.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        let addrs = self.peers.keys().copied().collect::<Vec<_>>();
+
+        for addr in addrs {
+            if let Some(peer) = self.peers.get_mut(&addr) {
+                peer.connection.handle_timeout(now);
+            }
+
+            // timeouts can't cause decode errors
+            let _ = self.process_events(addr, now);
+        }
+
+        let unjoined = self.rooms.values()
+            .filter(|room| room.is_empty() && now >= room.created() + HOST_JOIN_TIMEOUT)
+            .map(|room| room.code())
+            .collect::<Vec<_>>();
+
+        for code in unjoined {
+            self.close_room(code);
+        }
+
+        self.flush_rooms(now);
+    }
+
+    /// Takes the next datagram to send, and who to send it to.
+    pub fn poll_transmit(&mut self) -> Option<(SocketAddr, Vec<u8>)> {
+        for (addr, peer) in self.peers.iter_mut() {
+            if let Some(datagram) = peer.connection.poll_transmit() {
+                return Some((*addr, datagram));
+            }
+        }
+
+        // everything is sent, so closed connections can go
+        let clients = &mut self.clients;
+
+        self.peers.retain(|_, peer| {
+            let open = peer.connection.state() != connection::State::Disconnected;
+
+            if !open {
+                clients.remove(&peer.client_id);
+            }
+
+            open
+        });
+
+        None
+    }
+
+    /// Gives back a datagram from [`poll_transmit`](Server::poll_transmit)
+    /// once it is sent, so its buffer can be used again.
+    pub fn recycle(&mut self, addr: SocketAddr, datagram: Vec<u8>) {
+        if let Some(peer) = self.peers.get_mut(&addr) {
+            peer.connection.recycle(datagram);
+        }
+    }
+
+    /// Binds a socket and runs the server on it.
+    pub async fn bind(addr: SocketAddr, config: Config) -> io::Result<()> {
+        let socket = UdpSocket::bind(addr).await?;
+
+        Server::new(config).run(socket).await
+    }
+
+    /// Runs the server on a socket, forever or until the socket fails.
+    pub async fn run(mut self, socket: UdpSocket) -> io::Result<()> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+
+        if let (None, SocketAddr::V4(addr)) = (self.public_addr, socket.local_addr()?) {
+            self.public_addr = Some(addr);
+        }
+
+        loop {
+            while let Some((addr, datagram)) = self.poll_transmit() {
+                socket.send_to(&datagram, addr).await?;
+                self.recycle(addr, datagram);
+            }
+
+            let timeout = self.next_timeout().map(time::Instant::from_std);
+
+            tokio::select! {
+                received = socket.recv_from(&mut buf) => {
+                    let (len, addr) = received?;
+
+                    // one client sending garbage is no reason to stop
+                    let _ = self.handle_datagram(addr, &buf[..len], Instant::now());
+                }
+                _ = time::sleep_until(timeout.unwrap_or_else(time::Instant::now)),
+                    if timeout.is_some() =>
+                {
+                    self.handle_timeout(Instant::now());
+                }
+            }
+        }
+    }
+
+    fn process_events(&mut self, addr: SocketAddr, now: Instant) -> Result<(), decode::Error> {
+        let events = match self.peers.get_mut(&addr) {
+            Some(peer) => std::iter::from_fn(|| peer.connection.poll_event()).collect::<Vec<_>>(),
+            None => return Ok(()),
+        };
+
+        let mut result = Ok(());
+
+        for event in events {
+            match event {
+                connection::Event::Connected => {
+                    if let (Some(filter), Some(peer)) =
+                        (&self.spectator_filter, self.peers.get_mut(&addr))
+                    {
+                        peer.spectator = peer.connection.hello().is_some_and(filter);
+                    }
+                }
+                connection::Event::Data { payload, reliable } => {
+                    let code = self.peers.get(&addr).and_then(|peer| peer.room);
+
+                    if let Some(room) = code.and_then(|code| self.rooms.get_mut(&code)) {
+                        room.metrics_mut().packets_received += 1;
+                        room.metrics_mut().bytes_received += payload.len() as u64;
+                    }
+
+                    // a bad payload shouldn't keep the rest from being handled
+                    match RootMessage::read_all(&payload, Direction::ToServer) {
+                        Ok(messages) => {
+                            for message in messages {
+                                self.handle_message(addr, message, reliable, now);
+                            }
+                        }
+                        Err(err) => result = Err(err),
+                    }
+                }
+                connection::Event::Disconnected(_)
+                | connection::Event::TimedOut
+                | connection::Event::Limited => {
+                    self.leave_room(addr, DisconnectReason::ExitGame);
+                    self.close_unjoined(addr);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn handle_message(
+        &mut self,
+        addr: SocketAddr,
+        message: RootMessage,
+        reliable: bool,
+        now: Instant,
+    ) {
+        let client_id = match self.peers.get(&addr) {
+            Some(peer) => peer.client_id,
+            None => return,
+        };
+
+        match message {
+            RootMessage::HostGame(HostGame::Request(request)) => {
+                let hosting = self.rooms.values()
+                    .filter(|room| room.host_id() == client_id)
+                    .count();
+
+                if hosting >= self.room_limit {
+                    let reason = DisconnectReason::Custom("You are already hosting a game".into());
+                    self.send(client_id, &RootMessage::JoinGame(JoinGame::Error(reason)), now);
+                } else {
+                    let code = self.make_code();
+
+                    self.rooms.insert(code, Room::new(code, client_id, request.options, now));
+                    self.send(client_id, &RootMessage::HostGame(HostGame::Response(
+                        HostGameResponse { code },
+                    )), now);
+                }
+            }
+            RootMessage::JoinGame(JoinGame::Request(request)) => {
+                self.join_room(addr, request.code, now);
+            }
+            RootMessage::GameData(GameData { code, data }) => {
+                if let Some(room) = self.sender_room(addr, code) {
+                    let message = RootMessage::GameData(GameData { code, data });
+                    room.broadcast_except(client_id, RoomMessage { message, reliable });
+                }
+            }
+            RootMessage::GameDataTo(GameDataTo { code, target, data }) => {
+                if let Some(room) = self.sender_room(addr, code) {
+                    // only players can be sent to, but spectators see it too
+                    if room.contains(target) {
+                        let message = RoomMessage {
+                            message: RootMessage::GameDataTo(GameDataTo { code, target, data }),
+                            reliable,
+                        };
+
+                        room.send_to(target, message.clone());
+                        room.send_to_spectators(message);
+                    }
+                }
+            }
+            RootMessage::StartGame(start) => {
+                if let Some(room) = self.host_room(addr, start.code) {
+                    room.set_state(RoomState::Started);
+                    room.broadcast(RootMessage::StartGame(start));
+                }
+            }
+            RootMessage::EndGame(end) => {
+                if let Some(room) = self.host_room(addr, end.code) {
+                    room.set_state(RoomState::Lobby);
+                    room.broadcast(RootMessage::EndGame(end));
+                }
+            }
+            RootMessage::AlterGame(alter) => {
+                if let Some(room) = self.host_room(addr, alter.code) {
+                    if let Some(public) = alter.public() {
+                        room.set_public(public);
+                    }
+
+                    room.broadcast(RootMessage::AlterGame(alter));
+                }
+            }
+            RootMessage::GetGameListV2(GetGameListV2::Request(request)) => {
+                let list = self.game_list(request.filter(), now);
+                let message = RootMessage::GetGameListV2(GetGameListV2::Response(list));
+                self.send(client_id, &message, now);
+            }
+            RootMessage::KickPlayer(kick) => self.kick(addr, kick, now),
+            // nothing else is for the server
+            _ => (),
+        }
+    }
+
+    fn join_room(&mut self, addr: SocketAddr, code: GameCode, now: Instant) {
+        let client_id = self.peers[&addr].client_id;
+
+        // a client can only be in one room
+        if self.peers[&addr].room.is_some_and(|c| c != code) {
+            self.leave_room(addr, DisconnectReason::ExitGame);
+        }
+
+        if !self.rooms.contains_key(&code) {
+            let error = JoinGame::Error(DisconnectReason::GameNotFound);
+            self.send(client_id, &RootMessage::JoinGame(error), now);
+            return;
+        }
+
+        let room = self.rooms.get_mut(&code).expect("room was just checked");
+        let spectator = self.peers[&addr].spectator;
+
+        let check = room.bans()
+            .check_join(addr.ip())
+            .map_err(|_| DisconnectReason::Banned)
+            .and_then(|()| if spectator { Ok(()) } else { room.check_join(client_id) });
+
+        if let Err(reason) = check {
+            self.send(client_id, &RootMessage::JoinGame(JoinGame::Error(reason)), now);
+            return;
+        }
+
+        if spectator {
+            room.add_spectator(client_id);
+            room.send_to(client_id, RootMessage::JoinedGame(JoinedGame {
+                code,
+                client_id,
+                host_id: room.host_id(),
+                others: room.players().to_vec(),
+            }));
+
+            if let Some(peer) = self.peers.get_mut(&addr) {
+                peer.room = Some(code);
+            }
+
+            return;
+        }
+
+        let others = room.players().iter()
+            .copied()
+            .filter(|&p| p != client_id)
+            .collect::<Vec<_>>();
+
+        room.add_player(client_id);
+
+        let host_id = room.host_id();
+
+        room.broadcast_except(client_id, RootMessage::JoinGame(JoinGame::Joined(PlayerJoined {
+            code,
+            client_id,
+            host_id,
+        })));
+        room.send_to(client_id, RootMessage::JoinedGame(JoinedGame {
+            code,
+            client_id,
+            host_id,
+            others,
+        }));
+
+        if let Some(peer) = self.peers.get_mut(&addr) {
+            peer.room = Some(code);
+        }
+    }
+
+    fn kick(&mut self, addr: SocketAddr, kick: KickPlayer, now: Instant) {
+        let target_addr = match self.clients.get(&kick.client_id) {
+            Some(&target_addr) => target_addr,
+            None => return,
+        };
+
+        let room = match self.host_room(addr, kick.code) {
+            Some(room) if room.contains(kick.client_id) => room,
+            _ => return,
+        };
+
+        if kick.banned {
+            room.bans_mut().ban(target_addr.ip());
+        }
+
+        room.broadcast_except(kick.client_id, RootMessage::KickPlayer(kick.clone()));
+        self.leave_room(target_addr, kick.reason());
+
+        if let Some(peer) = self.peers.get_mut(&target_addr) {
+            peer.connection.disconnect(Some(kick.reason()), now);
+        }
+    }
+
+    /// Takes a client out of its room, telling everyone left.
+    fn leave_room(&mut self, addr: SocketAddr, reason: DisconnectReason) {
+        let (client_id, code) = match self.peers.get_mut(&addr) {
+            Some(peer) => match peer.room.take() {
+                Some(code) => (peer.client_id, code),
+                None => return,
+            },
+            None => return,
+        };
+
+        let room = match self.rooms.get_mut(&code) {
+            Some(room) => room,
+            None => return,
+        };
+
+        if room.remove_spectator(client_id) {
+            return;
+        }
+
+        let old_host = room.host_id();
+
+        if !room.remove_player(client_id, &*self.host_policy) {
+            return;
+        }
+
+        if room.is_empty() {
+            self.close_room(code);
+            return;
+        }
+
+        let host_id = room.host_id();
+
+        room.broadcast(RootMessage::RemovePlayer(RemovePlayer::Removed(PlayerRemoved {
+            code,
+            client_id,
+            host_id,
+            reason,
+        })));
+
+        if host_id != old_host {
+            for message in self.host_policy.replay(room, host_id) {
+                room.send_to(host_id, message);
+            }
+        }
+    }
+
+    /// Closes the rooms a client hosted but never joined.
+    fn close_unjoined(&mut self, addr: SocketAddr) {
+        let client_id = match self.peers.get(&addr) {
+            Some(peer) => peer.client_id,
+            None => return,
+        };
+
+        let unjoined = self.rooms.values()
+            .filter(|room| room.is_empty() && room.host_id() == client_id)
+            .map(|room| room.code())
+            .collect::<Vec<_>>();
+
+        for code in unjoined {
+            self.close_room(code);
+        }
+    }
+
+    /// Removes a room, taking its spectators out of it.
+    fn close_room(&mut self, code: GameCode) {
+        let room = match self.rooms.remove(&code) {
+            Some(room) => room,
+            None => return,
+        };
+
+        // nothing is left to watch
+        for spectator in room.spectators() {
+            let addr = match self.clients.get(spectator) {
+                Some(addr) => addr,
+                None => continue,
+            };
+
+            if let Some(peer) = self.peers.get_mut(addr) {
+                peer.room = None;
+            }
+        }
+    }
+
+    /// The room a message is about, if the sender is playing in it.
+    fn sender_room(&mut self, addr: SocketAddr, code: GameCode) -> Option<&mut Room> {
+        let peer = self.peers.get(&addr)?;
+
+        if peer.room != Some(code) {
+            return None;
+        }
+
+        self.rooms.get_mut(&code).filter(|room| room.contains(peer.client_id))
+    }
+
+    /// The room a message is about, if the sender is its host.
+    fn host_room(&mut self, addr: SocketAddr, code: GameCode) -> Option<&mut Room> {
+        let peer = self.peers.get(&addr)?;
+
+        if peer.room != Some(code) {
+            return None;
+        }
+
+        self.rooms.get_mut(&code).filter(|room| room.is_host(peer.client_id))
+    }
+
+    /// Sends a message to a client that may not be in a room.
+    fn send(&mut self, client_id: i32, message: &RootMessage, now: Instant) {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(message).expect("root message failed to encode");
+
+        Server::deliver(&self.clients, &mut self.peers, client_id, cursor.as_slice(), true, now);
+    }
+
+    /// Hands what the rooms queued to the connections.
+    fn flush_rooms(&mut self, now: Instant) {
+        for room in self.rooms.values_mut() {
+            while let Some(outgoing) = room.poll_outgoing() {
+                for &client_id in &outgoing.targets {
+                    Server::deliver(
+                        &self.clients,
+                        &mut self.peers,
+                        client_id,
+                        &outgoing.payload,
+                        outgoing.reliable,
+                        now,
+                    );
+                }
+            }
+        }
+    }
+
+    fn deliver(
+        clients: &HashMap<i32, SocketAddr>,
+        peers: &mut HashMap<SocketAddr, Peer>,
+        client_id: i32,
+        payload: &[u8],
+        reliable: bool,
+        now: Instant,
+    ) {
+        let peer = match clients.get(&client_id).and_then(|addr| peers.get_mut(addr)) {
+            Some(peer) => peer,
+            None => return,
+        };
+
+        // clients on their way out don't need anything else
+        if reliable {
+            let _ = peer.connection.send_reliable(payload, now);
+        } else {
+            let _ = peer.connection.send_unreliable(payload, now);
+        }
+    }
+
+    /// Makes a new V2 code that isn't in use.
+    fn make_code(&mut self) -> GameCode {
+        const LETTERS: &[u8; 26] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+        loop {
+            self.codes_made = self.codes_made.wrapping_add(1);
+
+            let mut seed = self.random.hash_one(self.codes_made);
+            let letters = (0..6)
+                .map(|_| {
+                    let letter = LETTERS[(seed % 26) as usize] as char;
+                    seed /= 26;
+                    letter
+                })
+                .collect::<String>();
+
+            let code = letters.parse().expect("generated code is valid");
+
+            if !self.rooms.contains_key(&code) {
+                return code;
+            }
+        }
+    }
+}
+
+/// Whether a room passes a game list filter.
+fn list_filter(room: &Room, filter: GameListFilter) -> bool {
+    let options = room.options();
+
+    // the language and impostors match anything when not set
+    filter.maps & 1u8.checked_shl(options.map as u32).unwrap_or(0) != 0
+        && (filter.impostors == 0 || filter.impostors == options.impostors)
+        && (filter.language == 0 || filter.language & options.keywords != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::protocol::options::GameOptions;
+    use crate::net::protocol::root::{HostGameRequest, JoinGameRequest};
+    use crate::net::protocol::version::GameVersion;
+
+    /// A client of the server under test.
+    struct Client {
+        addr: SocketAddr,
+        connection: Connection,
+    }
+
+    impl Client {
+        fn new(port: u16, now: Instant) -> Client {
+            let hello = Hello::new(GameVersion::V2021_12_14, "player");
+
+            Client {
+                addr: SocketAddr::from(([127, 0, 0, 1], port)),
+                connection: Connection::connect(hello, config(), now),
+            }
+        }
+
+        fn send(&mut self, message: RootMessage, now: Instant) {
+            let mut cursor = encode::CursorMut::new();
+            cursor.encode(&message).unwrap();
+
+            self.connection.send_reliable(cursor.as_slice(), now).unwrap();
+        }
+
+        fn messages(&mut self) -> Vec<RootMessage> {
+            std::iter::from_fn(|| self.connection.poll_event())
+                .filter_map(|event| match event {
+                    connection::Event::Data { payload, .. } => Some(payload),
+                    _ => None,
+                })
+                .flat_map(|payload| RootMessage::read_all(&payload, Direction::ToClient).unwrap())
+                .collect()
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            keepalive_interval: None,
+            disconnect_timeout: None,
+            ..Config::default()
+        }
+    }
+
+    /// Delivers datagrams between the server and its clients until there are
+    /// none left.
+    fn exchange(server: &mut Server, clients: &mut [&mut Client], now: Instant) {
+        loop {
+            let mut sent = false;
+
+            for client in clients.iter_mut() {
+                while let Some(datagram) = client.connection.poll_transmit() {
+                    server.handle_datagram(client.addr, &datagram, now).unwrap();
+                    sent = true;
+                }
+            }
+
+            while let Some((addr, datagram)) = server.poll_transmit() {
+                if let Some(client) = clients.iter_mut().find(|c| c.addr == addr) {
+                    client.connection.handle_datagram(&datagram, now).unwrap();
+                }
+
+                sent = true;
+            }
+
+            if !sent {
+                break;
+            }
+        }
+    }
+
+    fn host(server: &mut Server, client: &mut Client, now: Instant) -> RootMessage {
+        let options = GameOptions::default();
+
+        client.send(RootMessage::HostGame(HostGame::Request(HostGameRequest { options })), now);
+        exchange(server, &mut [client], now);
+
+        let mut messages = client.messages();
+        assert_eq!(messages.len(), 1);
+        messages.remove(0)
+    }
+
+    fn hosted_code(message: RootMessage) -> GameCode {
+        match message {
+            RootMessage::HostGame(HostGame::Response(response)) => response.code,
+            message => panic!("expected a hosted game, got {:?}", message),
+        }
+    }
+
+    #[test]
+    fn room_limit() {
+        let now = Instant::now();
+        let mut server = Server::new(config());
+        let mut client = Client::new(1, now);
+
+        exchange(&mut server, &mut [&mut client], now);
+        client.messages();
+
+        hosted_code(host(&mut server, &mut client, now));
+
+        let reason = DisconnectReason::Custom("You are already hosting a game".into());
+        assert_eq!(
+            host(&mut server, &mut client, now),
+            RootMessage::JoinGame(JoinGame::Error(reason)),
+        );
+        assert_eq!(server.rooms().count(), 1);
+
+        server.set_room_limit(2);
+        hosted_code(host(&mut server, &mut client, now));
+        assert_eq!(server.rooms().count(), 2);
+    }
+
+    #[test]
+    fn unjoined_rooms_close_when_the_host_leaves() {
+        let now = Instant::now();
+        let mut server = Server::new(config());
+        let mut joined = Client::new(1, now);
+        let mut unjoined = Client::new(2, now);
+
+        exchange(&mut server, &mut [&mut joined, &mut unjoined], now);
+        joined.messages();
+        unjoined.messages();
+
+        let code = hosted_code(host(&mut server, &mut joined, now));
+        joined.send(RootMessage::JoinGame(JoinGame::Request(JoinGameRequest { code })), now);
+        exchange(&mut server, &mut [&mut joined], now);
+        hosted_code(host(&mut server, &mut unjoined, now));
+        assert_eq!(server.rooms().count(), 2);
+
+        unjoined.connection.disconnect(None, now);
+        exchange(&mut server, &mut [&mut joined, &mut unjoined], now);
+
+        let codes = server.rooms().map(|room| room.code()).collect::<Vec<_>>();
+        assert_eq!(codes, [code]);
+    }
+
+    #[test]
+    fn unjoined_rooms_time_out() {
+        let now = Instant::now();
+        let mut server = Server::new(config());
+        let mut joined = Client::new(1, now);
+        let mut unjoined = Client::new(2, now);
+
+        exchange(&mut server, &mut [&mut joined, &mut unjoined], now);
+        joined.messages();
+        unjoined.messages();
+
+        let code = hosted_code(host(&mut server, &mut joined, now));
+        joined.send(RootMessage::JoinGame(JoinGame::Request(JoinGameRequest { code })), now);
+        exchange(&mut server, &mut [&mut joined], now);
+        hosted_code(host(&mut server, &mut unjoined, now));
+        assert_eq!(server.next_timeout(), Some(now + HOST_JOIN_TIMEOUT));
+
+        server.handle_timeout(now + HOST_JOIN_TIMEOUT - Duration::from_millis(1));
+        assert_eq!(server.rooms().count(), 2);
+
+        server.handle_timeout(now + HOST_JOIN_TIMEOUT);
+
+        let codes = server.rooms().map(|room| room.code()).collect::<Vec<_>>();
+        assert_eq!(codes, [code]);
+        assert_eq!(server.next_timeout(), None);
+    }
+}
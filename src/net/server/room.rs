@@ -0,0 +1,327 @@
+//! Rooms.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::net::binary::encode;
+use crate::net::connection::DisconnectReason;
+use crate::net::metrics::Metrics;
+use crate::net::protocol::ban::BanList;
+use crate::net::protocol::code::GameCode;
+use crate::net::protocol::options::GameOptions;
+use crate::net::protocol::RootMessage;
+
+use super::host::{FirstJoined, HostPolicy};
+
+/// Where a room is in its life.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoomState {
+    /// Waiting in the lobby. Players can join.
+    Lobby,
+    /// The game is being played.
+    Started,
+}
+
+/// A message to send to players in a room, and how.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomMessage {
+    pub message: RootMessage,
+    pub reliable: bool,
+}
+
+impl RoomMessage {
+    /// A message that is resent until it is acknowledged.
+    pub fn reliable(message: RootMessage) -> RoomMessage {
+        RoomMessage { message, reliable: true }
+    }
+
+    /// A message that is sent once, like movement.
+    pub fn unreliable(message: RootMessage) -> RoomMessage {
+        RoomMessage { message, reliable: false }
+    }
+}
+
+impl From<RootMessage> for RoomMessage {
+    fn from(message: RootMessage) -> RoomMessage {
+        RoomMessage::reliable(message)
+    }
+}
+
+/// An encoded message waiting to be sent out of a room.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outgoing {
+    /// The clients to send to.
+    pub targets: Vec<i32>,
+    pub payload: Vec<u8>,
+    pub reliable: bool,
+}
+
+/// A hosted game, and the clients in it.
+#[derive(Clone, Debug)]
+pub struct Room {
+    code: GameCode,
+    host_id: i32,
+    players: Vec<i32>,
+    spectators: Vec<i32>,
+    state: RoomState,
+    public: bool,
+    created: Instant,
+    options: GameOptions,
+    bans: BanList,
+    outgoing: VecDeque<Outgoing>,
+    metrics: Metrics,
+}
+
+impl Room {
+    /// Create a new, empty, private room.
+    ///
+    /// `host_id` is the client that asked to host it. Until they join, the
+    /// first client to join becomes the host instead.
+    pub fn new(code: GameCode, host_id: i32, options: GameOptions, now: Instant) -> Room {
+        Room {
+            code,
+            host_id,
+            players: Vec::new(),
+            spectators: Vec::new(),
+            state: RoomState::Lobby,
+            public: false,
+            created: now,
+            options,
+            bans: BanList::new(),
+            outgoing: VecDeque::new(),
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// The code of the room.
+    pub fn code(&self) -> GameCode {
+        self.code
+    }
+
+    /// The client id of the host.
+    pub fn host_id(&self) -> i32 {
+        self.host_id
+    }
+
+    /// Whether a client is the host.
+    pub fn is_host(&self, client_id: i32) -> bool {
+        self.host_id == client_id
+    }
+
+    /// The clients in the room, in the order they joined.
+    pub fn players(&self) -> &[i32] {
+        &self.players
+    }
+
+    /// Whether a client is in the room.
+    pub fn contains(&self, client_id: i32) -> bool {
+        self.players.contains(&client_id)
+    }
+
+    /// Whether the room has no players. Spectators don't count.
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// The clients watching the room without playing.
+    pub fn spectators(&self) -> &[i32] {
+        &self.spectators
+    }
+
+    /// Whether a client is spectating the room.
+    pub fn is_spectator(&self, client_id: i32) -> bool {
+        self.spectators.contains(&client_id)
+    }
+
+    /// Adds a spectator to the room.
+    ///
+    /// Spectators get everything sent to the room, including game data sent
+    /// to a single player, but the players never hear about them.
+    pub fn add_spectator(&mut self, client_id: i32) {
+        if !self.contains(client_id) && !self.is_spectator(client_id) {
+            self.spectators.push(client_id);
+        }
+    }
+
+    /// Removes a spectator from the room, returning whether it was there.
+    pub fn remove_spectator(&mut self, client_id: i32) -> bool {
+        let len = self.spectators.len();
+        self.spectators.retain(|&s| s != client_id);
+
+        self.spectators.len() != len
+    }
+
+    /// The state of the room.
+    pub fn state(&self) -> RoomState {
+        self.state
+    }
+
+    /// Changes the state of the room.
+    pub fn set_state(&mut self, state: RoomState) {
+        self.state = state;
+    }
+
+    /// Whether the room shows up in the game list while in the lobby.
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    /// Makes the room public or private.
+    pub fn set_public(&mut self, public: bool) {
+        self.public = public;
+    }
+
+    /// When the room was created.
+    pub fn created(&self) -> Instant {
+        self.created
+    }
+
+    /// The options the room was hosted with.
+    pub fn options(&self) -> &GameOptions {
+        &self.options
+    }
+
+    /// The addresses banned from the room.
+    pub fn bans(&self) -> &BanList {
+        &self.bans
+    }
+
+    /// The addresses banned from the room, mutably.
+    pub fn bans_mut(&mut self) -> &mut BanList {
+        &mut self.bans
+    }
+
+    /// The payloads that have passed through the room.
+    ///
+    /// Everything sent out of the room is counted once per client it goes to.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    /// The payloads that have passed through the room, mutably, to count
+    /// what clients send to it.
+    pub fn metrics_mut(&mut self) -> &mut Metrics {
+        &mut self.metrics
+    }
+
+    /// Checks whether a client can join, giving the reason if not.
+    pub fn check_join(&self, client_id: i32) -> Result<(), DisconnectReason> {
+        if self.contains(client_id) {
+            // rejoining after a game is always fine
+            Ok(())
+        } else if self.state == RoomState::Started {
+            Err(DisconnectReason::GameStarted)
+        } else if self.players.len() >= self.options.max_players as usize {
+            Err(DisconnectReason::GameFull)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds a client to the room.
+    pub fn add_player(&mut self, client_id: i32) {
+        if self.contains(client_id) {
+            return;
+        }
+
+        if self.players.is_empty() && !self.is_host(client_id) {
+            self.host_id = client_id;
+        }
+
+        self.players.push(client_id);
+    }
+
+    /// Makes a client in the room the host, returning whether they are in it.
+    pub fn set_host(&mut self, client_id: i32) -> bool {
+        if !self.contains(client_id) {
+            return false;
+        }
+
+        self.host_id = client_id;
+        true
+    }
+
+    /// Removes a client from the room, returning whether it was there.
+    ///
+    /// If the host leaves, `policy` picks the new one.
+    pub fn remove_player(&mut self, client_id: i32, policy: &dyn HostPolicy) -> bool {
+        let index = match self.players.iter().position(|&p| p == client_id) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        self.players.remove(index);
+
+        if self.is_host(client_id) && !self.is_empty() {
+            let elected = policy.elect(self, client_id)
+                .filter(|&host_id| self.contains(host_id))
+                .or_else(|| FirstJoined.elect(self, client_id));
+
+            if let Some(host_id) = elected {
+                self.host_id = host_id;
+            }
+        }
+
+        true
+    }
+
+    /// Sends a message to everyone in the room, spectators included.
+    ///
+    /// Messages are queued until [`poll_outgoing`](Room::poll_outgoing); the
+    /// connection of each client numbers them as they are sent.
+    pub fn broadcast(&mut self, message: impl Into<RoomMessage>) {
+        let targets = self.players.iter().chain(&self.spectators).copied().collect();
+        self.queue(targets, message.into());
+    }
+
+    /// Sends a message to one client in the room, returning whether they are
+    /// in it.
+    pub fn send_to(&mut self, client_id: i32, message: impl Into<RoomMessage>) -> bool {
+        if !self.contains(client_id) && !self.is_spectator(client_id) {
+            return false;
+        }
+
+        self.queue(vec![client_id], message.into());
+        true
+    }
+
+    /// Sends a message to everyone in the room but one client, usually the
+    /// one it came from.
+    pub fn broadcast_except(&mut self, client_id: i32, message: impl Into<RoomMessage>) {
+        let targets = self.players.iter()
+            .chain(&self.spectators)
+            .copied()
+            .filter(|&p| p != client_id)
+            .collect();
+
+        self.queue(targets, message.into());
+    }
+
+    /// Sends a message to the spectators only.
+    pub fn send_to_spectators(&mut self, message: impl Into<RoomMessage>) {
+        let targets = self.spectators.clone();
+        self.queue(targets, message.into());
+    }
+
+    /// Takes the next message to send out of the room.
+    pub fn poll_outgoing(&mut self) -> Option<Outgoing> {
+        self.outgoing.pop_front()
+    }
+
+    fn queue(&mut self, targets: Vec<i32>, message: RoomMessage) {
+        if targets.is_empty() {
+            return;
+        }
+
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(&message.message).expect("root message failed to encode");
+
+        self.metrics.packets_sent += targets.len() as u64;
+        self.metrics.bytes_sent += (cursor.as_slice().len() * targets.len()) as u64;
+        self.outgoing.push_back(Outgoing {
+            targets,
+            payload: cursor.into_inner(),
+            reliable: message.reliable,
+        });
+    }
+}
@@ -0,0 +1,500 @@
+//! DTLS on top of the Hazel transport.
+//!
+//! Official clients since 2021.6.30 wrap every Hazel datagram in DTLS 1.2.
+//! [`Dtls`] sits between the socket and a [`Transport`], and has the same
+//! sans-IO interface: datagrams go in with
+//! [`handle_datagram`](Dtls::handle_datagram) and come out of
+//! [`poll_transmit`](Dtls::poll_transmit), now as [records](record::Record).
+//!
+//! The handshake and the ciphers are up to a [`Backend`], so any TLS library
+//! can be plugged in. [`OpenSsl`] is one on top of OpenSSL. Hazel data sent
+//! before the handshake is done waits in the transport until it is.
+//!
+//! A [`Connection`](crate::net::connection::Connection) runs over DTLS just
+//! like it does over a plain transport, since both are a [`Link`].
+
+pub mod openssl;
+pub mod record;
+
+pub use self::openssl::OpenSsl;
+
+use std::fmt;
+use std::time::Instant;
+
+use crate::net::binary::{decode, encode};
+
+use super::{Config, Event, Link, TooLarge, Transport};
+
+use record::{ContentType, Record};
+
+/// The handshake and ciphers of a DTLS connection.
+///
+/// The backend decides whether it is the client or the server, and sends
+/// the first flight from [`poll_record`](Backend::poll_record) if it is the
+/// client.
+pub trait Backend {
+    /// Why the backend failed.
+    type Error: fmt::Debug + fmt::Display;
+
+    /// Whether the handshake is done, so application data can be sealed and
+    /// opened.
+    fn is_established(&self) -> bool;
+
+    /// Handles any record that isn't application data, like handshake
+    /// messages, cipher spec changes and alerts.
+    fn handle_record(&mut self, record: Record, now: Instant) -> Result<(), Self::Error>;
+
+    /// Takes the next record the backend wants to send.
+    fn poll_record(&mut self) -> Option<Record>;
+
+    /// The next time [`handle_timeout`](Backend::handle_timeout) should be
+    /// called, if the handshake is waiting on a resend.
+    fn next_timeout(&self) -> Option<Instant>;
+
+    /// Resends handshake flights that are due.
+    fn handle_timeout(&mut self, now: Instant);
+
+    /// Encrypts application data into a record.
+    fn seal(&mut self, plaintext: &[u8]) -> Result<Record, Self::Error>;
+
+    /// Decrypts an application data record.
+    fn open(&mut self, record: &Record) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// An error handling a datagram.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// A record or the Hazel packet inside it couldn't be decoded.
+    Decode(decode::Error),
+    /// The backend failed.
+    Backend(E),
+}
+
+impl<E> From<decode::Error> for Error<E> {
+    fn from(err: decode::Error) -> Error<E> {
+        Error::Decode(err)
+    }
+}
+
+impl<E> fmt::Display for Error<E>
+where E: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Decode(err) => write!(f, "decode error: {}", err),
+            Error::Backend(err) => write!(f, "dtls error: {}", err),
+        }
+    }
+}
+
+impl<E> std::error::Error for Error<E>
+where E: fmt::Debug + fmt::Display {}
+
+/// A [`Transport`] whose datagrams are sent over DTLS.
+pub struct Dtls<B> {
+    transport: Transport,
+    backend: B,
+}
+
+impl<B> Dtls<B>
+where B: Backend {
+    /// Create a new `Dtls` with the default [`Config`].
+    pub fn new(backend: B) -> Dtls<B> {
+        Dtls::with_config(Config::default(), backend)
+    }
+
+    /// Create a new `Dtls`.
+    pub fn with_config(config: Config, backend: B) -> Dtls<B> {
+        Dtls {
+            transport: Transport::with_config(config),
+            backend,
+        }
+    }
+
+    /// The transport underneath.
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    /// The transport underneath, mutably.
+    pub fn transport_mut(&mut self) -> &mut Transport {
+        &mut self.transport
+    }
+
+    /// The backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The backend, mutably.
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Whether the handshake is done.
+    pub fn is_established(&self) -> bool {
+        self.backend.is_established()
+    }
+
+    /// Sends a hello with the given payload, returning its id.
+    pub fn send_hello(&mut self, payload: &[u8], now: Instant) -> u16 {
+        self.transport.send_hello(payload, now)
+    }
+
+    /// Sends data that may be lost.
+    pub fn send_unreliable(&mut self, payload: &[u8], now: Instant) {
+        self.transport.send_unreliable(payload, now);
+    }
+
+    /// Sends data that is resent until acknowledged.
+    pub fn send_reliable(&mut self, payload: &[u8], now: Instant) -> Result<(), TooLarge> {
+        self.transport.send_reliable(payload, now)
+    }
+
+    /// Sends a ping, returning its id.
+    pub fn send_ping(&mut self, now: Instant) -> u16 {
+        self.transport.send_ping(now)
+    }
+
+    /// Sends a disconnect with the given reason, and stops resending.
+    pub fn send_disconnect(&mut self, payload: &[u8], now: Instant) {
+        self.transport.send_disconnect(payload, now);
+    }
+
+    /// Handles a datagram from the other side.
+    pub fn handle_datagram(
+        &mut self,
+        datagram: &[u8],
+        now: Instant,
+    ) -> Result<(), Error<B::Error>> {
+        for record in record::parse_datagram(datagram)? {
+            if record.content_type != ContentType::ApplicationData {
+                self.backend.handle_record(record, now).map_err(Error::Backend)?;
+                continue;
+            }
+
+            // data from before the handshake can't be opened, and is resent
+            // by the other side if it was reliable
+            if !self.backend.is_established() {
+                continue;
+            }
+
+            let plaintext = self.backend.open(&record).map_err(Error::Backend)?;
+            self.transport.handle_datagram(&plaintext, now)?;
+        }
+
+        Ok(())
+    }
+
+    /// The next time [`handle_timeout`](Dtls::handle_timeout) should be
+    /// called.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let transport = if self.backend.is_established() {
+            self.transport.next_timeout()
+        } else {
+            None
+        };
+
+        [self.backend.next_timeout(), transport].iter().flatten().min().copied()
+    }
+
+    /// Resends handshake flights and Hazel packets that are due.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        self.backend.handle_timeout(now);
+
+        // the transport's clock starts once there is a way to send
+        if self.backend.is_established() {
+            self.transport.handle_timeout(now);
+        }
+    }
+
+    /// Takes the next datagram to send.
+    ///
+    /// If the backend fails to seal a Hazel datagram, the datagram is lost
+    /// and the error is returned; reliable packets in it are resent later.
+    ///
+    /// # Panics
+    /// Panics if the backend makes a record that can't be encoded.
+    pub fn poll_transmit(&mut self) -> Result<Option<Vec<u8>>, Error<B::Error>> {
+        if let Some(record) = self.backend.poll_record() {
+            return Ok(Some(encode_record(&record)));
+        }
+
+        if !self.backend.is_established() {
+            return Ok(None);
+        }
+
+        let datagram = match self.transport.poll_transmit() {
+            Some(datagram) => datagram,
+            None => return Ok(None),
+        };
+
+        let sealed = self.backend.seal(&datagram);
+        self.transport.recycle(datagram);
+
+        Ok(Some(encode_record(&sealed.map_err(Error::Backend)?)))
+    }
+
+    /// Gives back a datagram from [`poll_transmit`](Dtls::poll_transmit)
+    /// once it is sent, so its buffer can be used again.
+    pub fn recycle(&mut self, datagram: Vec<u8>) {
+        self.transport.recycle(datagram);
+    }
+
+    /// Takes the next event.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.transport.poll_event()
+    }
+}
+
+impl<B> Link for Dtls<B>
+where B: Backend {
+    type Error = Error<B::Error>;
+
+    fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    fn transport_mut(&mut self) -> &mut Transport {
+        &mut self.transport
+    }
+
+    fn handle_datagram(&mut self, datagram: &[u8], now: Instant) -> Result<(), Self::Error> {
+        Dtls::handle_datagram(self, datagram, now)
+    }
+
+    fn next_timeout(&self) -> Option<Instant> {
+        Dtls::next_timeout(self)
+    }
+
+    fn handle_timeout(&mut self, now: Instant) {
+        Dtls::handle_timeout(self, now);
+    }
+
+    fn poll_transmit(&mut self) -> Result<Option<Vec<u8>>, Self::Error> {
+        Dtls::poll_transmit(self)
+    }
+
+    fn recycle(&mut self, datagram: Vec<u8>) {
+        Dtls::recycle(self, datagram);
+    }
+}
+
+fn encode_record(record: &Record) -> Vec<u8> {
+    let mut cursor = encode::CursorMut::with_capacity(record.len());
+
+    cursor.encode(record).expect("record failed to encode");
+    cursor.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::VecDeque;
+
+    use crate::net::connection::{self, Connection, DisconnectReason};
+
+    /// A backend that "handshakes" with one record each way, and xors data
+    /// with a key instead of encrypting it.
+    #[derive(Debug)]
+    struct Xor {
+        key: u8,
+        established: bool,
+        broken: bool,
+        sequence: u64,
+        outgoing: VecDeque<Record>,
+    }
+
+    impl Xor {
+        fn client() -> Xor {
+            let mut xor = Xor::server();
+            xor.outgoing.push_back(Record::new(ContentType::Handshake, 0, 0, vec![1]));
+            xor
+        }
+
+        fn server() -> Xor {
+            Xor {
+                key: 0x5a,
+                established: false,
+                broken: false,
+                sequence: 0,
+                outgoing: VecDeque::new(),
+            }
+        }
+    }
+
+    impl Backend for Xor {
+        type Error = &'static str;
+
+        fn is_established(&self) -> bool {
+            self.established
+        }
+
+        fn handle_record(&mut self, record: Record, _now: Instant) -> Result<(), &'static str> {
+            match (record.content_type, record.fragment.as_slice()) {
+                (ContentType::Handshake, [1]) => {
+                    self.outgoing.push_back(Record::new(ContentType::Handshake, 0, 0, vec![2]));
+                }
+                (ContentType::Handshake, [2]) => (),
+                _ => return Err("unexpected record"),
+            }
+
+            self.established = true;
+            Ok(())
+        }
+
+        fn poll_record(&mut self) -> Option<Record> {
+            self.outgoing.pop_front()
+        }
+
+        fn next_timeout(&self) -> Option<Instant> {
+            None
+        }
+
+        fn handle_timeout(&mut self, _now: Instant) {}
+
+        fn seal(&mut self, plaintext: &[u8]) -> Result<Record, &'static str> {
+            if self.broken {
+                return Err("broken");
+            }
+
+            let sealed = plaintext.iter().map(|b| b ^ self.key).collect();
+
+            self.sequence += 1;
+            Ok(Record::new(ContentType::ApplicationData, 1, self.sequence, sealed))
+        }
+
+        fn open(&mut self, record: &Record) -> Result<Vec<u8>, &'static str> {
+            Ok(record.fragment.iter().map(|b| b ^ self.key).collect())
+        }
+    }
+
+    fn deliver(from: &mut Dtls<Xor>, to: &mut Dtls<Xor>, now: Instant) -> usize {
+        let mut count = 0;
+
+        while let Some(datagram) = from.poll_transmit().unwrap() {
+            to.handle_datagram(&datagram, now).unwrap();
+            count += 1;
+        }
+
+        count
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let record = Record::new(ContentType::Handshake, 1, 0x0102_0304_0506, vec![0xaa, 0xbb]);
+        let bytes = encode_record(&record);
+
+        assert_eq!(bytes, [
+            0x16, // handshake
+            0xfe, 0xfd, // dtls 1.2
+            0x00, 0x01, // epoch
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, // sequence
+            0x00, 0x02, // length
+            0xaa, 0xbb,
+        ]);
+        assert_eq!(decode::parse::<Record>(&bytes).unwrap(), record);
+    }
+
+    #[test]
+    fn records_share_datagrams() {
+        let a = Record::new(ContentType::Handshake, 0, 1, vec![1, 2, 3]);
+        let b = Record::new(ContentType::ChangeCipherSpec, 0, 2, vec![1]);
+
+        let mut datagram = encode_record(&a);
+        datagram.extend(encode_record(&b));
+
+        assert_eq!(record::parse_datagram(&datagram).unwrap(), [a, b]);
+
+        // a length longer than the datagram
+        datagram.truncate(datagram.len() - 1);
+        assert!(record::parse_datagram(&datagram).is_err());
+    }
+
+    #[test]
+    fn rejects_long_sequences() {
+        let record = Record::new(ContentType::Alert, 0, record::MAX_SEQUENCE + 1, Vec::new());
+
+        assert!(encode::CursorMut::new().encode(&record).is_err());
+    }
+
+    #[test]
+    fn hazel_waits_for_the_handshake() {
+        let now = Instant::now();
+
+        let mut client = Dtls::new(Xor::client());
+        let mut server = Dtls::new(Xor::server());
+
+        client.send_hello(b"hi", now);
+
+        // only the handshake goes out at first
+        assert_eq!(deliver(&mut client, &mut server, now), 1);
+        assert!(server.is_established());
+        assert_eq!(server.poll_event(), None);
+
+        assert_eq!(deliver(&mut server, &mut client, now), 1);
+        assert!(client.is_established());
+
+        // now the hello can be sealed and sent
+        assert_eq!(deliver(&mut client, &mut server, now), 1);
+        assert_eq!(server.poll_event(), Some(Event::Hello(b"hi".to_vec())));
+
+        // and the acknowledgement comes back sealed too
+        deliver(&mut server, &mut client, now);
+        assert_eq!(client.transport().pending(), 0);
+    }
+
+    #[test]
+    fn application_data_is_sealed() {
+        let now = Instant::now();
+
+        let mut client = Dtls::new(Xor::client());
+        let mut server = Dtls::new(Xor::server());
+
+        deliver(&mut client, &mut server, now);
+        deliver(&mut server, &mut client, now);
+
+        client.send_unreliable(b"secret", now);
+
+        let datagram = client.poll_transmit().unwrap().unwrap();
+        let records = record::parse_datagram(&datagram).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].content_type, ContentType::ApplicationData);
+        assert!(!datagram.windows(6).any(|window| window == b"secret"));
+
+        server.handle_datagram(&datagram, now).unwrap();
+        assert_eq!(server.poll_event(), Some(Event::Data {
+            payload: b"secret".to_vec(),
+            reliable: false,
+        }));
+    }
+
+    #[test]
+    fn failed_seals() {
+        let now = Instant::now();
+
+        let mut client = Dtls::new(Xor::client());
+        let mut server = Dtls::new(Xor::server());
+
+        deliver(&mut client, &mut server, now);
+        deliver(&mut server, &mut client, now);
+
+        client.backend_mut().broken = true;
+        client.send_unreliable(b"lost", now);
+
+        assert!(matches!(client.poll_transmit(), Err(Error::Backend("broken"))));
+        assert_eq!(client.poll_transmit().unwrap(), None);
+
+        // a connection can't go on without a way to send
+        let mut connection = Connection::accept_over(client);
+        connection.link_mut().send_unreliable(b"lost", now);
+
+        assert_eq!(connection.poll_transmit(), None);
+        assert_eq!(connection.state(), connection::State::Disconnected);
+        assert_eq!(
+            connection.poll_event(),
+            Some(connection::Event::Disconnected(Some(DisconnectReason::Error))),
+        );
+    }
+}
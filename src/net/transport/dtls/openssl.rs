@@ -0,0 +1,419 @@
+//! A [`Backend`] on top of OpenSSL.
+//!
+//! OpenSSL does the handshake and the ciphers. [`OpenSsl`] hands it records
+//! through an in-memory channel instead of a socket, so it stays sans-IO like
+//! the rest of the transport. Certificates, verification and ciphers are set
+//! up on the [`Ssl`] before it is handed over.
+//!
+//! OpenSSL keeps the timer for resending handshake flights on the system
+//! clock itself. [`next_timeout`](Backend::next_timeout) only says when to
+//! check on it.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use openssl::error::ErrorStack;
+use openssl::ssl::{self, ErrorCode, Ssl, SslStream};
+
+use super::record::{self, ContentType, Record};
+use super::{Backend, encode_record};
+
+/// The largest datagram OpenSSL makes during the handshake.
+const MTU: u32 = 1200;
+/// How often a handshake waiting on the other side is checked on, so OpenSSL
+/// can resend flights it thinks were lost.
+const HANDSHAKE_CHECK: Duration = Duration::from_secs(1);
+/// The most plaintext a record can hold.
+const MAX_PLAINTEXT: usize = 1 << 14;
+
+/// An error from OpenSSL.
+#[derive(Debug)]
+pub enum Error {
+    /// The handshake or a record failed.
+    Ssl(ssl::Error),
+    /// A record was dropped instead of opened, because it was replayed,
+    /// tampered with or from an old epoch.
+    Unopened,
+    /// OpenSSL didn't seal the data into a record.
+    Unsealed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Ssl(err) => err.fmt(f),
+            Error::Unopened => f.write_str("record was dropped"),
+            Error::Unsealed => f.write_str("data wasn't sealed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Datagrams between OpenSSL and the [`Dtls`](super::Dtls) around it.
+#[derive(Debug, Default)]
+struct Channel {
+    incoming: VecDeque<Vec<u8>>,
+    outgoing: VecDeque<Vec<u8>>,
+}
+
+impl Read for Channel {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // openssl reads a whole datagram at a time
+        let datagram = self.incoming.pop_front().ok_or(io::ErrorKind::WouldBlock)?;
+        let len = datagram.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&datagram[..len]);
+        Ok(len)
+    }
+}
+
+impl Write for Channel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing.push_back(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A DTLS [`Backend`] on OpenSSL.
+pub struct OpenSsl {
+    stream: SslStream<Channel>,
+    established: bool,
+    // records openssl wrote that haven't been taken yet
+    records: VecDeque<Record>,
+    check_at: Option<Instant>,
+    buffer: Vec<u8>,
+}
+
+impl OpenSsl {
+    /// Starts a handshake as the client, sending the first flight.
+    pub fn connect(ssl: Ssl, now: Instant) -> Result<OpenSsl, ErrorStack> {
+        let mut openssl = OpenSsl::new(ssl, false)?;
+
+        // nothing has been read, so nothing can have gone wrong yet
+        let _ = openssl.handshake(now);
+
+        Ok(openssl)
+    }
+
+    /// Waits for a client to start a handshake.
+    pub fn accept(ssl: Ssl) -> Result<OpenSsl, ErrorStack> {
+        OpenSsl::new(ssl, true)
+    }
+
+    fn new(mut ssl: Ssl, server: bool) -> Result<OpenSsl, ErrorStack> {
+        if server {
+            ssl.set_accept_state();
+        } else {
+            ssl.set_connect_state();
+        }
+
+        ssl.set_mtu(MTU)?;
+
+        Ok(OpenSsl {
+            stream: SslStream::new(ssl, Channel::default())?,
+            established: false,
+            records: VecDeque::new(),
+            check_at: None,
+            buffer: vec![0; MAX_PLAINTEXT],
+        })
+    }
+
+    /// The OpenSSL connection, to look at the certificate of the other side
+    /// or the cipher in use.
+    pub fn ssl(&self) -> &ssl::SslRef {
+        self.stream.ssl()
+    }
+
+    /// Moves the handshake along as far as it can go.
+    fn handshake(&mut self, now: Instant) -> Result<(), Error> {
+        let result = self.stream.do_handshake();
+
+        self.take_records();
+
+        match result {
+            Ok(()) => {
+                self.established = true;
+                self.check_at = None;
+                Ok(())
+            }
+            Err(err) if err.code() == ErrorCode::WANT_READ => {
+                self.check_at = Some(now + HANDSHAKE_CHECK);
+                Ok(())
+            }
+            Err(err) => {
+                self.check_at = None;
+                Err(Error::Ssl(err))
+            }
+        }
+    }
+
+    /// Gives OpenSSL a record to read.
+    fn push(&mut self, record: &Record) {
+        self.stream.get_mut().incoming.push_back(encode_record(record));
+    }
+
+    /// Takes the records OpenSSL wrote out of the channel.
+    fn take_records(&mut self) {
+        for datagram in self.stream.get_mut().outgoing.drain(..) {
+            // a datagram that doesn't parse is as good as lost, and DTLS
+            // already copes with those
+            self.records.extend(record::parse_datagram(&datagram).into_iter().flatten());
+        }
+    }
+}
+
+impl Backend for OpenSsl {
+    type Error = Error;
+
+    fn is_established(&self) -> bool {
+        self.established
+    }
+
+    fn handle_record(&mut self, record: Record, now: Instant) -> Result<(), Error> {
+        self.push(&record);
+
+        if !self.established {
+            return self.handshake(now);
+        }
+
+        // a flight the other side resent, or an alert; openssl answers them
+        // while reading
+        let result = self.stream.ssl_read(&mut self.buffer);
+
+        self.take_records();
+
+        match result {
+            // application data only comes through `open`
+            Ok(_) => Ok(()),
+            Err(err) if matches!(err.code(), ErrorCode::WANT_READ | ErrorCode::ZERO_RETURN) => {
+                Ok(())
+            }
+            Err(err) => Err(Error::Ssl(err)),
+        }
+    }
+
+    fn poll_record(&mut self) -> Option<Record> {
+        self.records.pop_front()
+    }
+
+    fn next_timeout(&self) -> Option<Instant> {
+        self.check_at
+    }
+
+    fn handle_timeout(&mut self, now: Instant) {
+        if self.check_at.is_some_and(|at| at <= now) {
+            // a handshake that gave up stops being checked on
+            let _ = self.handshake(now);
+        }
+    }
+
+    fn seal(&mut self, plaintext: &[u8]) -> Result<Record, Error> {
+        self.stream.ssl_write(plaintext).map_err(Error::Ssl)?;
+        self.take_records();
+
+        let index = self.records.iter()
+            .rposition(|record| record.content_type == ContentType::ApplicationData)
+            .ok_or(Error::Unsealed)?;
+
+        Ok(self.records.remove(index).expect("index was just found"))
+    }
+
+    fn open(&mut self, record: &Record) -> Result<Vec<u8>, Error> {
+        self.push(record);
+
+        let result = self.stream.ssl_read(&mut self.buffer);
+
+        self.take_records();
+
+        match result {
+            Ok(len) => Ok(self.buffer[..len].to_vec()),
+            Err(err) if err.code() == ErrorCode::WANT_READ => Err(Error::Unopened),
+            Err(err) => Err(Error::Ssl(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::ssl::{SslContext, SslMethod, SslVerifyMode};
+    use openssl::x509::{X509Builder, X509NameBuilder};
+
+    use crate::net::connection::{self, Connection, Hello};
+    use crate::net::protocol::version::GameVersion;
+    use crate::net::transport::Event;
+    use crate::net::transport::dtls::{self, Dtls};
+
+    /// A client and a server, with the client trusting the server's
+    /// self-signed certificate.
+    fn pair(now: Instant) -> (OpenSsl, OpenSsl) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "among-us").unwrap();
+        let name = name.build();
+
+        let mut cert = X509Builder::new().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(&name).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = cert.build();
+
+        let mut server = SslContext::builder(SslMethod::dtls()).unwrap();
+        server.set_private_key(&key).unwrap();
+        server.set_certificate(&cert).unwrap();
+
+        let mut client = SslContext::builder(SslMethod::dtls()).unwrap();
+        client.set_verify(SslVerifyMode::PEER);
+        client.cert_store_mut().add_cert(cert).unwrap();
+
+        (
+            OpenSsl::connect(Ssl::new(&client.build()).unwrap(), now).unwrap(),
+            OpenSsl::accept(Ssl::new(&server.build()).unwrap()).unwrap(),
+        )
+    }
+
+    fn deliver(from: &mut Dtls<OpenSsl>, to: &mut Dtls<OpenSsl>, now: Instant) -> usize {
+        let mut count = 0;
+
+        while let Some(datagram) = from.poll_transmit().unwrap() {
+            to.handle_datagram(&datagram, now).unwrap();
+            count += 1;
+        }
+
+        count
+    }
+
+    fn handshake(client: &mut Dtls<OpenSsl>, server: &mut Dtls<OpenSsl>, now: Instant) {
+        for _ in 0..4 {
+            deliver(client, server, now);
+            deliver(server, client, now);
+        }
+
+        assert!(client.is_established());
+        assert!(server.is_established());
+    }
+
+    #[test]
+    fn handshake_then_data() {
+        let now = Instant::now();
+        let (client, server) = pair(now);
+
+        let mut client = Dtls::new(client);
+        let mut server = Dtls::new(server);
+
+        client.send_hello(b"hi", now);
+        handshake(&mut client, &mut server, now);
+
+        assert_eq!(server.poll_event(), Some(Event::Hello(b"hi".to_vec())));
+        assert_eq!(client.transport().pending(), 0);
+
+        server.send_unreliable(b"secret", now);
+
+        let datagram = server.poll_transmit().unwrap().unwrap();
+        assert!(!datagram.windows(6).any(|window| window == b"secret"));
+
+        client.handle_datagram(&datagram, now).unwrap();
+        assert_eq!(client.poll_event(), Some(Event::Data {
+            payload: b"secret".to_vec(),
+            reliable: false,
+        }));
+    }
+
+    #[test]
+    fn tampered_records_are_dropped() {
+        let now = Instant::now();
+        let (client, server) = pair(now);
+
+        let mut client = Dtls::new(client);
+        let mut server = Dtls::new(server);
+
+        handshake(&mut client, &mut server, now);
+
+        client.send_unreliable(b"secret", now);
+
+        let mut datagram = client.poll_transmit().unwrap().unwrap();
+        let last = datagram.len() - 1;
+        datagram[last] ^= 1;
+
+        assert!(matches!(
+            server.handle_datagram(&datagram, now),
+            Err(dtls::Error::Backend(Error::Unopened)),
+        ));
+        assert_eq!(server.poll_event(), None);
+    }
+
+    #[test]
+    fn untrusted_certificates_fail() {
+        let now = Instant::now();
+        let (_, server) = pair(now);
+        let (client, _) = pair(now);
+
+        let mut client = Dtls::new(client);
+        let mut server = Dtls::new(server);
+
+        deliver(&mut client, &mut server, now);
+
+        let mut failed = false;
+
+        while let Some(datagram) = server.poll_transmit().unwrap() {
+            failed |= client.handle_datagram(&datagram, now).is_err();
+        }
+
+        assert!(failed);
+        assert!(!client.is_established());
+    }
+
+    #[test]
+    fn connection() {
+        let now = Instant::now();
+        let (client, server) = pair(now);
+
+        let hello = Hello::new(GameVersion::V2021_12_14, "player");
+        let mut client = Connection::connect_over(Dtls::new(client), hello.clone(), now);
+        let mut server = Connection::accept_over(Dtls::new(server));
+
+        for _ in 0..4 {
+            while let Some(datagram) = client.poll_transmit() {
+                server.handle_datagram(&datagram, now).unwrap();
+            }
+
+            while let Some(datagram) = server.poll_transmit() {
+                client.handle_datagram(&datagram, now).unwrap();
+            }
+        }
+
+        assert_eq!(client.poll_event(), Some(connection::Event::Connected));
+        assert_eq!(server.poll_event(), Some(connection::Event::Connected));
+        assert_eq!(server.hello(), Some(&hello));
+
+        client.send_reliable(b"data", now).unwrap();
+
+        while let Some(datagram) = client.poll_transmit() {
+            server.handle_datagram(&datagram, now).unwrap();
+        }
+
+        assert_eq!(server.poll_event(), Some(connection::Event::Data {
+            payload: b"data".to_vec(),
+            reliable: true,
+        }));
+    }
+}
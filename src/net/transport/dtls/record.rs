@@ -0,0 +1,141 @@
+//! DTLS 1.2 records.
+//!
+//! A datagram holds one or more records back to back. Each record has a 13
+//! byte header: the content type, the protocol version, the epoch, a 48-bit
+//! sequence number and the length of the fragment that follows. All of it is
+//! big-endian.
+
+use alloc::vec::Vec;
+
+use crate::net::binary::{decode, encode, endian::BigEndian};
+
+/// The version of DTLS 1.2 on the wire.
+pub const DTLS_1_2: u16 = 0xfefd;
+/// The length of a record header.
+pub const HEADER_LEN: usize = 13;
+/// The largest sequence number, which is only 48 bits on the wire.
+pub const MAX_SEQUENCE: u64 = (1 << 48) - 1;
+
+/// What a record holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    ChangeCipherSpec,
+    Alert,
+    Handshake,
+    ApplicationData,
+    Unknown(u8),
+}
+
+impl ContentType {
+    /// The id of the content type on the wire.
+    pub fn id(self) -> u8 {
+        match self {
+            ContentType::ChangeCipherSpec => 20,
+            ContentType::Alert => 21,
+            ContentType::Handshake => 22,
+            ContentType::ApplicationData => 23,
+            ContentType::Unknown(id) => id,
+        }
+    }
+
+    /// The content type of an id.
+    pub fn from_id(id: u8) -> ContentType {
+        match id {
+            20 => ContentType::ChangeCipherSpec,
+            21 => ContentType::Alert,
+            22 => ContentType::Handshake,
+            23 => ContentType::ApplicationData,
+            id => ContentType::Unknown(id),
+        }
+    }
+}
+
+/// A single DTLS record.
+///
+/// The fragment is whatever the [`Backend`](super::Backend) put there, so it
+/// is encrypted once the handshake is done.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub content_type: ContentType,
+    pub version: u16,
+    /// Bumped every time the cipher changes.
+    pub epoch: u16,
+    /// Counts records within an epoch. Only the low 48 bits are sent.
+    pub sequence: u64,
+    pub fragment: Vec<u8>,
+}
+
+impl Record {
+    /// Create a new DTLS 1.2 record.
+    pub fn new(content_type: ContentType, epoch: u16, sequence: u64, fragment: Vec<u8>) -> Record {
+        Record {
+            content_type,
+            version: DTLS_1_2,
+            epoch,
+            sequence,
+            fragment,
+        }
+    }
+
+    /// The length of the record on the wire.
+    pub fn len(&self) -> usize {
+        HEADER_LEN + self.fragment.len()
+    }
+
+    /// Whether the record has an empty fragment.
+    pub fn is_empty(&self) -> bool {
+        self.fragment.is_empty()
+    }
+}
+
+impl decode::Decode for Record {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let content_type = ContentType::from_id(cursor.decode()?);
+        let version = cursor.decode::<BigEndian<u16>>()?.0;
+        let epoch = cursor.decode::<BigEndian<u16>>()?.0;
+
+        let mut sequence = [0; 8];
+        if cursor.read(&mut sequence[2..]) < 6 {
+            return Err(decode::Error::unexpected_end());
+        }
+
+        let len = cursor.decode::<BigEndian<u16>>()?.0 as usize;
+
+        Ok(Record {
+            content_type,
+            version,
+            epoch,
+            sequence: u64::from_be_bytes(sequence),
+            fragment: cursor.decode_bytes(len)?.to_vec(),
+        })
+    }
+}
+
+impl encode::Encode for Record {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        if self.sequence > MAX_SEQUENCE || self.fragment.len() > u16::MAX as usize {
+            return Err(encode::Error);
+        }
+
+        cursor.encode(&self.content_type.id())?;
+        cursor.encode(&BigEndian(self.version))?;
+        cursor.encode(&BigEndian(self.epoch))?;
+        cursor.write(&self.sequence.to_be_bytes()[2..]);
+        cursor.encode(&BigEndian(self.fragment.len() as u16))?;
+        cursor.write(&self.fragment);
+        Ok(())
+    }
+}
+
+/// Decodes every record in a datagram.
+pub fn parse_datagram(datagram: &[u8]) -> Result<Vec<Record>, decode::Error> {
+    let mut cursor = decode::Cursor::new(datagram);
+    let mut records = Vec::new();
+
+    while cursor.remaining() > 0 {
+        records.push(cursor.decode()?);
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,214 @@
+//! Splitting large payloads into fragments, and putting them back together.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::net::binary::decode;
+
+use super::Config;
+
+/// Splits a payload into chunks of at most `max_payload` bytes.
+///
+/// # Panics
+/// Panics if `max_payload` is zero.
+pub fn split(payload: &[u8], max_payload: usize) -> impl Iterator<Item = &[u8]> {
+    payload.chunks(max_payload)
+}
+
+/// Puts fragmented payloads back together.
+///
+/// Partial payloads are dropped if they don't complete within the
+/// [reassembly timeout](Config::reassembly_timeout), and fragments that would
+/// go over the limits in the [`Config`] are rejected.
+pub struct Reassembler {
+    groups: HashMap<u16, Group>,
+}
+
+struct Group {
+    parts: Vec<Option<Vec<u8>>>,
+    left: usize,
+    len: usize,
+    expires_at: Instant,
+}
+
+impl Reassembler {
+    /// Create a new, empty `Reassembler`.
+    pub fn new() -> Reassembler {
+        Reassembler {
+            groups: HashMap::new(),
+        }
+    }
+
+    /// How many payloads are partially reassembled.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether there are no partially reassembled payloads.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Adds a fragment, returning the whole payload if it was the last one.
+    pub fn insert(
+        &mut self,
+        config: &Config,
+        group: u16,
+        index: u16,
+        count: u16,
+        payload: Vec<u8>,
+        now: Instant,
+    ) -> Result<Option<Vec<u8>>, decode::Error> {
+        if count > config.max_fragments {
+            return Err(decode::Error::limit_exceeded());
+        }
+
+        if !self.groups.contains_key(&group) && self.groups.len() >= config.max_reassemblies {
+            return Err(decode::Error::limit_exceeded());
+        }
+
+        let entry = self.groups.entry(group).or_insert_with(|| Group {
+            parts: vec![None; count as usize],
+            left: count as usize,
+            len: 0,
+            expires_at: now + config.reassembly_timeout,
+        });
+
+        // a peer that disagrees with itself about the fragment count is
+        // broken, so don't bother trying to salvage the payload
+        if entry.parts.len() != count as usize || index >= count {
+            self.groups.remove(&group);
+            return Ok(None);
+        }
+
+        let part = &mut entry.parts[index as usize];
+
+        if part.is_some() {
+            return Ok(None);
+        }
+
+        entry.len += payload.len();
+
+        if entry.len > config.max_reassembly_len {
+            self.groups.remove(&group);
+            return Err(decode::Error::limit_exceeded());
+        }
+
+        *part = Some(payload);
+        entry.left -= 1;
+
+        if entry.left > 0 {
+            return Ok(None);
+        }
+
+        let entry = self.groups.remove(&group).expect("group exists");
+        let mut payload = Vec::with_capacity(entry.len);
+
+        for part in entry.parts {
+            payload.extend(part.expect("all parts received"));
+        }
+
+        Ok(Some(payload))
+    }
+
+    /// When the oldest partial payload expires, if there are any.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.groups.values().map(|g| g.expires_at).min()
+    }
+
+    /// Drops partial payloads that have expired.
+    pub fn expire(&mut self, now: Instant) {
+        self.groups.retain(|_, g| g.expires_at > now);
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Reassembler {
+        Reassembler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::net::binary::decode::ErrorKind;
+
+    fn config() -> Config {
+        Config {
+            max_fragments: 4,
+            max_reassembly_len: 8,
+            max_reassemblies: 2,
+            reassembly_timeout: Duration::from_secs(1),
+            ..Config::default()
+        }
+    }
+
+    fn is_limit(result: Result<Option<Vec<u8>>, decode::Error>) -> bool {
+        matches!(result, Err(e) if matches!(e.kind(), ErrorKind::LimitExceeded))
+    }
+
+    #[test]
+    fn reassembles_out_of_order() {
+        let (config, now) = (config(), Instant::now());
+        let mut reassembler = Reassembler::new();
+
+        assert_eq!(reassembler.insert(&config, 0, 2, 3, vec![5], now).unwrap(), None);
+        assert_eq!(reassembler.insert(&config, 0, 0, 3, vec![1, 2], now).unwrap(), None);
+        // a resent fragment changes nothing
+        assert_eq!(reassembler.insert(&config, 0, 0, 3, vec![9, 9], now).unwrap(), None);
+
+        let payload = reassembler.insert(&config, 0, 1, 3, vec![3, 4], now).unwrap();
+        assert_eq!(payload, Some(vec![1, 2, 3, 4, 5]));
+        assert!(reassembler.is_empty());
+    }
+
+    #[test]
+    fn limits() {
+        let (config, now) = (config(), Instant::now());
+        let mut reassembler = Reassembler::new();
+
+        // too many fragments
+        assert!(is_limit(reassembler.insert(&config, 0, 0, 5, vec![1], now)));
+
+        // too many groups at once
+        reassembler.insert(&config, 1, 0, 2, vec![1], now).unwrap();
+        reassembler.insert(&config, 2, 0, 2, vec![1], now).unwrap();
+        assert!(is_limit(reassembler.insert(&config, 3, 0, 2, vec![1], now)));
+        assert_eq!(reassembler.len(), 2);
+
+        // too long once put together, which drops the group
+        assert!(is_limit(reassembler.insert(&config, 1, 1, 2, vec![0; 8], now)));
+        assert_eq!(reassembler.len(), 1);
+    }
+
+    #[test]
+    fn mismatched_count_drops_group() {
+        let (config, now) = (config(), Instant::now());
+        let mut reassembler = Reassembler::new();
+
+        reassembler.insert(&config, 0, 0, 2, vec![1], now).unwrap();
+        assert_eq!(reassembler.insert(&config, 0, 1, 3, vec![2], now).unwrap(), None);
+        assert!(reassembler.is_empty());
+
+        assert_eq!(reassembler.insert(&config, 0, 4, 2, vec![2], now).unwrap(), None);
+        assert!(reassembler.is_empty());
+    }
+
+    #[test]
+    fn expires() {
+        let (config, now) = (config(), Instant::now());
+        let mut reassembler = Reassembler::new();
+
+        reassembler.insert(&config, 0, 0, 2, vec![1], now).unwrap();
+        assert_eq!(reassembler.next_expiry(), Some(now + Duration::from_secs(1)));
+
+        reassembler.expire(now + Duration::from_millis(999));
+        assert_eq!(reassembler.len(), 1);
+
+        reassembler.expire(now + Duration::from_secs(1));
+        assert!(reassembler.is_empty());
+        assert_eq!(reassembler.next_expiry(), None);
+    }
+}
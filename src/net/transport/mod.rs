@@ -0,0 +1,921 @@
+//! The Hazel reliable UDP transport.
+//!
+//! [`Transport`] doesn't own a socket. Datagrams are fed in with
+//! [`handle_datagram`](Transport::handle_datagram), and the datagrams it wants
+//! to send are taken out with [`poll_transmit`](Transport::poll_transmit).
+//! Time is passed in explicitly, so resends and keepalives happen when
+//! [`handle_timeout`](Transport::handle_timeout) is called at or after
+//! [`next_timeout`](Transport::next_timeout).
+//!
+//! Datagrams are encoded into buffers from a [`BufferPool`]. Handing them back
+//! with [`recycle`](Transport::recycle) once they are sent saves allocating
+//! new ones for every packet.
+//!
+//! Fragmenting large reliable payloads is off by default, because the
+//! official clients don't understand fragment packets. Turn it on with
+//! [`max_payload`](Config::max_payload) only when both sides are this crate.
+//! Fragments are reassembled either way.
+
+#[cfg(feature = "dtls")]
+pub mod dtls;
+pub mod fragment;
+pub mod packet;
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::net::binary::pool::BufferPool;
+use crate::net::binary::{decode, encode};
+use crate::net::metrics::Metrics;
+use crate::net::seq::SeqNum;
+
+use fragment::Reassembler;
+use packet::Packet;
+
+/// Room for the header of any packet, on top of its payload.
+const MAX_HEADER: usize = 16;
+
+/// The payload room of pooled buffers when nothing is fragmented.
+const DEFAULT_PAYLOAD: usize = 1024;
+
+/// Tuning for a [`Transport`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How long to wait for an acknowledgement before the first resend.
+    pub resend_timeout: Duration,
+    /// The longest wait between resends, as the timeout doubles each time.
+    pub max_resend_timeout: Duration,
+    /// How many times a reliable packet is resent before the connection is
+    /// considered dead.
+    pub max_resends: u32,
+    /// How long the connection can be quiet before a ping is sent, if at all.
+    pub keepalive_interval: Option<Duration>,
+    /// How long the other side can be silent before the connection times
+    /// out, if at all.
+    pub disconnect_timeout: Option<Duration>,
+    /// The largest reliable payload sent in a single datagram, if larger ones
+    /// should be split into fragments.
+    ///
+    /// Off by default, since the official clients don't understand fragments.
+    pub max_payload: Option<usize>,
+    /// The most fragments a single payload can be split into.
+    pub max_fragments: u16,
+    /// The largest payload that will be reassembled from fragments.
+    pub max_reassembly_len: usize,
+    /// How many payloads can be partially reassembled at once.
+    pub max_reassemblies: usize,
+    /// How long a partially reassembled payload is kept around.
+    pub reassembly_timeout: Duration,
+    /// How many buffers of sent datagrams are kept to encode new ones into.
+    pub spare_buffers: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            resend_timeout: Duration::from_millis(300),
+            max_resend_timeout: Duration::from_millis(1000),
+            max_resends: 10,
+            keepalive_interval: Some(Duration::from_millis(1500)),
+            disconnect_timeout: Some(Duration::from_secs(10)),
+            max_payload: None,
+            max_fragments: 256,
+            max_reassembly_len: 256 * 1024,
+            max_reassemblies: 8,
+            reassembly_timeout: Duration::from_secs(10),
+            spare_buffers: 32,
+        }
+    }
+}
+
+/// Something that happened on a [`Transport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The other side said hello.
+    Hello(Vec<u8>),
+    /// Data arrived.
+    Data {
+        payload: Vec<u8>,
+        reliable: bool,
+    },
+    /// The other side disconnected. The payload, if any, says why.
+    Disconnected(Vec<u8>),
+    /// A reliable packet ran out of resends without being acknowledged, or
+    /// the other side was silent for too long.
+    TimedOut,
+}
+
+/// A reliable payload needs more fragments than
+/// [`max_fragments`](Config::max_fragments) allows. Holds the length of the
+/// payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooLarge(pub usize);
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "payload of {} bytes needs too many fragments", self.0)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// The reliability layer of a single Hazel connection.
+pub struct Transport {
+    config: Config,
+    next_id: u16,
+    next_group: u16,
+    pending: HashMap<u16, Pending>,
+    received: ReceiveWindow,
+    reassembler: Reassembler,
+    last_sent: Option<Instant>,
+    // when the other side was last heard from, or when we first spoke
+    quiet_since: Option<Instant>,
+    rtt: Option<Rtt>,
+    metrics: Metrics,
+    timed_out: bool,
+    pool: BufferPool,
+    transmit: VecDeque<Vec<u8>>,
+    events: VecDeque<Event>,
+}
+
+struct Pending {
+    datagram: Vec<u8>,
+    sent_at: Instant,
+    resend_at: Instant,
+    resends: u32,
+}
+
+/// Smoothed round trip time, like TCP's.
+#[derive(Clone, Copy)]
+struct Rtt {
+    smoothed: Duration,
+    variance: Duration,
+}
+
+impl Rtt {
+    fn new(sample: Duration) -> Rtt {
+        Rtt {
+            smoothed: sample,
+            variance: sample / 2,
+        }
+    }
+
+    fn update(&mut self, sample: Duration) {
+        let diff = sample.abs_diff(self.smoothed);
+
+        self.variance = (self.variance * 3 + diff) / 4;
+        self.smoothed = (self.smoothed * 7 + sample) / 8;
+    }
+}
+
+impl Transport {
+    /// Create a new `Transport` with the default [`Config`].
+    pub fn new() -> Transport {
+        Transport::with_config(Config::default())
+    }
+
+    /// Create a new `Transport`.
+    pub fn with_config(config: Config) -> Transport {
+        Transport {
+            pool: BufferPool::new(
+                config.max_payload.unwrap_or(DEFAULT_PAYLOAD) + MAX_HEADER,
+                config.spare_buffers,
+            ),
+            config,
+            // hazel starts counting at 1, the hello gets id 1
+            next_id: 1,
+            next_group: 0,
+            pending: HashMap::new(),
+            received: ReceiveWindow::default(),
+            reassembler: Reassembler::new(),
+            last_sent: None,
+            quiet_since: None,
+            rtt: None,
+            metrics: Metrics::default(),
+            timed_out: false,
+            transmit: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// The config of the transport.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// How many reliable packets are still waiting for an acknowledgement.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether a reliable packet is still waiting for an acknowledgement.
+    pub fn is_pending(&self, id: u16) -> bool {
+        self.pending.contains_key(&id)
+    }
+
+    /// The buffers datagrams are encoded into.
+    pub fn pool(&self) -> &BufferPool {
+        &self.pool
+    }
+
+    /// What has gone over the transport so far.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    /// The smoothed round trip time, once something has been acknowledged.
+    ///
+    /// Only packets acknowledged without a resend are measured, since an
+    /// acknowledgement for a resent packet could be for any copy of it.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt.map(|rtt| rtt.smoothed)
+    }
+
+    /// How much the round trip time varies, once something has been
+    /// acknowledged.
+    pub fn jitter(&self) -> Option<Duration> {
+        self.rtt.map(|rtt| rtt.variance)
+    }
+
+    /// Sends a hello with the given payload.
+    pub fn send_hello(&mut self, payload: &[u8], now: Instant) -> u16 {
+        let id = self.next_id();
+
+        self.send_reliable_packet(Packet::Hello { id, payload: payload.to_vec() }, now);
+        id
+    }
+
+    /// Sends data that may be lost.
+    ///
+    /// Unreliable data is never fragmented, so keep it under the MTU.
+    pub fn send_unreliable(&mut self, payload: &[u8], now: Instant) {
+        self.send_packet(&Packet::Unreliable(payload.to_vec()), now);
+    }
+
+    /// Sends data that is resent until acknowledged.
+    ///
+    /// Payloads over [`max_payload`](Config::max_payload) are fragmented, if
+    /// it is set. Nothing is sent if the payload needs more than
+    /// [`max_fragments`](Config::max_fragments) fragments.
+    pub fn send_reliable(&mut self, payload: &[u8], now: Instant) -> Result<(), TooLarge> {
+        let max_payload = match self.config.max_payload {
+            Some(max_payload) if payload.len() > max_payload => max_payload,
+            _ => {
+                let id = self.next_id();
+
+                self.send_reliable_packet(Packet::Reliable { id, payload: payload.to_vec() }, now);
+                return Ok(());
+            }
+        };
+
+        let count = payload.len().div_ceil(max_payload);
+
+        if count > self.config.max_fragments as usize {
+            return Err(TooLarge(payload.len()));
+        }
+
+        let group = self.next_group;
+        self.next_group = group.wrapping_add(1);
+
+        for (index, chunk) in fragment::split(payload, max_payload).enumerate() {
+            let id = self.next_id();
+
+            self.send_reliable_packet(Packet::Fragment {
+                id,
+                group,
+                index: index as u16,
+                count: count as u16,
+                payload: chunk.to_vec(),
+            }, now);
+        }
+
+        Ok(())
+    }
+
+    /// Sends a ping, returning its id.
+    pub fn send_ping(&mut self, now: Instant) -> u16 {
+        let id = self.next_id();
+
+        self.send_reliable_packet(Packet::Ping { id }, now);
+        id
+    }
+
+    /// Sends a disconnect with the given reason, and stops resending.
+    pub fn send_disconnect(&mut self, payload: &[u8], now: Instant) {
+        self.clear_pending();
+        self.send_packet(&Packet::Disconnect(payload.to_vec()), now);
+    }
+
+    /// Handles a datagram from the other side.
+    pub fn handle_datagram(&mut self, datagram: &[u8], now: Instant) -> Result<(), decode::Error> {
+        let packet = match decode::parse::<Packet>(datagram) {
+            Ok(packet) => packet,
+            Err(err) => {
+                self.metrics.malformed += 1;
+                return Err(err);
+            }
+        };
+
+        self.quiet_since = Some(now);
+        self.metrics.packets_received += 1;
+        self.metrics.bytes_received += datagram.len() as u64;
+
+        // acknowledge everything reliable, even duplicates, since our last
+        // acknowledgement might have been the thing that got lost
+        let fresh = match packet.reliable_id() {
+            Some(id) => {
+                let fresh = self.received.insert(id);
+                let recent = self.received.recent(id);
+
+                self.send_packet(&Packet::Acknowledgement { id, recent }, now);
+                self.metrics.acks_sent += 1;
+                fresh
+            }
+            None => true,
+        };
+
+        if !fresh {
+            self.metrics.duplicates += 1;
+            return Ok(());
+        }
+
+        match packet {
+            Packet::Unreliable(payload) => {
+                self.events.push_back(Event::Data { payload, reliable: false });
+            }
+            Packet::Reliable { payload, .. } => {
+                self.events.push_back(Event::Data { payload, reliable: true });
+            }
+            Packet::Hello { payload, .. } => {
+                self.events.push_back(Event::Hello(payload));
+            }
+            Packet::Disconnect(payload) => {
+                self.clear_pending();
+                self.events.push_back(Event::Disconnected(payload));
+            }
+            Packet::Acknowledgement { id, recent } => {
+                self.metrics.acks_received += 1;
+
+                if let Some(pending) = self.pending.remove(&id) {
+                    if pending.resends == 0 {
+                        self.sample_rtt(now.saturating_duration_since(pending.sent_at));
+                    }
+
+                    self.pool.release(pending.datagram);
+                }
+
+                for i in 0..8 {
+                    if recent & (1 << i) != 0 {
+                        if let Some(pending) = self.pending.remove(&id.wrapping_sub(i + 1)) {
+                            self.pool.release(pending.datagram);
+                        }
+                    }
+                }
+            }
+            Packet::Fragment { group, index, count, payload, .. } => {
+                let payload = self.reassembler
+                    .insert(&self.config, group, index, count, payload, now)?;
+
+                if let Some(payload) = payload {
+                    self.events.push_back(Event::Data { payload, reliable: true });
+                }
+            }
+            Packet::Ping { .. } => (),
+        }
+
+        Ok(())
+    }
+
+    /// The next time [`handle_timeout`](Transport::handle_timeout) should be
+    /// called, if there is anything to wait for.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let resend = self.pending.values().map(|p| p.resend_at).min();
+        let keepalive = self.keepalive_at();
+        let silence = self.silence_at();
+        let expiry = self.reassembler.next_expiry();
+
+        [resend, keepalive, silence, expiry].iter().flatten().min().copied()
+    }
+
+    /// Resends unacknowledged packets, sends keepalives that are due and
+    /// drops stale fragments.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        if self.timed_out {
+            return;
+        }
+
+        self.reassembler.expire(now);
+
+        if self.silence_at().is_some_and(|at| at <= now) {
+            self.time_out();
+            return;
+        }
+
+        let mut resend = Vec::new();
+        let pool = &mut self.pool;
+
+        for pending in self.pending.values_mut() {
+            if pending.resend_at > now {
+                continue;
+            }
+
+            if pending.resends >= self.config.max_resends {
+                self.timed_out = true;
+                break;
+            }
+
+            pending.resends += 1;
+            pending.resend_at = now + backoff(&self.config, pending.resends);
+            resend.push(copy(pool, &pending.datagram));
+        }
+
+        if self.timed_out {
+            self.time_out();
+            return;
+        }
+
+        for datagram in resend {
+            self.metrics.resends += 1;
+            self.transmit(datagram, now);
+        }
+
+        if self.keepalive_at().is_some_and(|at| at <= now) {
+            self.send_ping(now);
+        }
+    }
+
+    /// Takes the next datagram to send.
+    pub fn poll_transmit(&mut self) -> Option<Vec<u8>> {
+        self.transmit.pop_front()
+    }
+
+    /// Gives back a datagram from [`poll_transmit`](Transport::poll_transmit)
+    /// once it is sent, so its buffer can be used again.
+    pub fn recycle(&mut self, datagram: Vec<u8>) {
+        self.pool.release(datagram);
+    }
+
+    /// Takes the next event.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    fn next_id(&mut self) -> u16 {
+        let id = self.next_id;
+        self.next_id = id.wrapping_add(1);
+        id
+    }
+
+    fn time_out(&mut self) {
+        self.timed_out = true;
+        self.clear_pending();
+        self.events.push_back(Event::TimedOut);
+    }
+
+    fn sample_rtt(&mut self, sample: Duration) {
+        match &mut self.rtt {
+            Some(rtt) => rtt.update(sample),
+            None => self.rtt = Some(Rtt::new(sample)),
+        }
+    }
+
+    fn silence_at(&self) -> Option<Instant> {
+        if self.timed_out {
+            return None;
+        }
+
+        match (self.config.disconnect_timeout, self.quiet_since) {
+            (Some(timeout), Some(quiet_since)) => Some(quiet_since + timeout),
+            _ => None,
+        }
+    }
+
+    fn keepalive_at(&self) -> Option<Instant> {
+        if self.timed_out {
+            return None;
+        }
+
+        match (self.config.keepalive_interval, self.last_sent) {
+            (Some(interval), Some(last_sent)) => Some(last_sent + interval),
+            _ => None,
+        }
+    }
+
+    fn clear_pending(&mut self) {
+        for (_, pending) in self.pending.drain() {
+            self.pool.release(pending.datagram);
+        }
+    }
+
+    fn send_reliable_packet(&mut self, packet: Packet, now: Instant) {
+        let id = packet.reliable_id().expect("packet is reliable");
+        let datagram = self.encode_packet(&packet);
+
+        self.pending.insert(id, Pending {
+            datagram: copy(&mut self.pool, &datagram),
+            sent_at: now,
+            resend_at: now + backoff(&self.config, 0),
+            resends: 0,
+        });
+        self.transmit(datagram, now);
+    }
+
+    fn send_packet(&mut self, packet: &Packet, now: Instant) {
+        let datagram = self.encode_packet(packet);
+        self.transmit(datagram, now);
+    }
+
+    fn encode_packet(&mut self, packet: &Packet) -> Vec<u8> {
+        let mut cursor = encode::CursorMut::from_pool(&mut self.pool);
+
+        // packets have no lengths or counts, so they can't fail to encode
+        cursor.encode(packet).expect("packet failed to encode");
+        cursor.into_inner()
+    }
+
+    fn transmit(&mut self, datagram: Vec<u8>, now: Instant) {
+        self.last_sent = Some(now);
+        self.quiet_since.get_or_insert(now);
+        self.metrics.packets_sent += 1;
+        self.metrics.bytes_sent += datagram.len() as u64;
+        self.transmit.push_back(datagram);
+    }
+}
+
+impl Default for Transport {
+    fn default() -> Transport {
+        Transport::new()
+    }
+}
+
+/// What a [`Connection`](crate::net::connection::Connection) sends its
+/// packets over: a plain [`Transport`], or a transport wrapped in something
+/// like DTLS.
+pub trait Link {
+    /// Why a datagram couldn't be handled or made.
+    type Error: From<decode::Error> + fmt::Debug + fmt::Display;
+
+    /// The transport the packets go through.
+    fn transport(&self) -> &Transport;
+
+    /// The transport the packets go through, mutably, to send on.
+    fn transport_mut(&mut self) -> &mut Transport;
+
+    /// Handles a datagram from the other side.
+    fn handle_datagram(&mut self, datagram: &[u8], now: Instant) -> Result<(), Self::Error>;
+
+    /// The next time [`handle_timeout`](Link::handle_timeout) should be
+    /// called.
+    fn next_timeout(&self) -> Option<Instant>;
+
+    /// Resends and sends keepalives that are due.
+    fn handle_timeout(&mut self, now: Instant);
+
+    /// Takes the next datagram to send.
+    fn poll_transmit(&mut self) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Gives back a datagram from [`poll_transmit`](Link::poll_transmit) once
+    /// it is sent, so its buffer can be used again.
+    fn recycle(&mut self, datagram: Vec<u8>);
+}
+
+impl Link for Transport {
+    type Error = decode::Error;
+
+    fn transport(&self) -> &Transport {
+        self
+    }
+
+    fn transport_mut(&mut self) -> &mut Transport {
+        self
+    }
+
+    fn handle_datagram(&mut self, datagram: &[u8], now: Instant) -> Result<(), decode::Error> {
+        Transport::handle_datagram(self, datagram, now)
+    }
+
+    fn next_timeout(&self) -> Option<Instant> {
+        Transport::next_timeout(self)
+    }
+
+    fn handle_timeout(&mut self, now: Instant) {
+        Transport::handle_timeout(self, now);
+    }
+
+    fn poll_transmit(&mut self) -> Result<Option<Vec<u8>>, decode::Error> {
+        Ok(Transport::poll_transmit(self))
+    }
+
+    fn recycle(&mut self, datagram: Vec<u8>) {
+        Transport::recycle(self, datagram);
+    }
+}
+
+/// Tracks which reliable ids have been received, to drop duplicates.
+#[derive(Default)]
+struct ReceiveWindow {
+    // the newest id received, if any
+    latest: Option<u16>,
+    // bit `n` is set if `latest - n` was received
+    mask: u64,
+}
+
+impl ReceiveWindow {
+    /// Marks an id as received, returning `false` if it already was.
+    ///
+    /// Ids too old to remember are treated as duplicates.
+    fn insert(&mut self, id: u16) -> bool {
+        let latest = match self.latest {
+            Some(latest) => latest,
+            None => {
+                self.latest = Some(id);
+                self.mask = 1;
+                return true;
+            }
+        };
+
+        let ahead = SeqNum(id).distance(SeqNum(latest));
+
+        if ahead > 0 {
+            self.mask = self.mask.checked_shl(ahead as u32).unwrap_or(0) | 1;
+            self.latest = Some(id);
+            true
+        } else {
+            let behind = -(ahead as i32) as u32;
+            let bit = 1u64.checked_shl(behind).unwrap_or(0);
+
+            let fresh = bit != 0 && self.mask & bit == 0;
+            self.mask |= bit;
+            fresh
+        }
+    }
+
+    fn contains(&self, id: u16) -> bool {
+        let latest = match self.latest {
+            Some(latest) => latest,
+            None => return false,
+        };
+
+        let behind = SeqNum(latest).distance(SeqNum(id));
+
+        behind >= 0 && self.mask & 1u64.checked_shl(behind as u32).unwrap_or(0) != 0
+    }
+
+    /// The recent packets byte to acknowledge `id` with.
+    fn recent(&self, id: u16) -> u8 {
+        (0..8).fold(0, |recent, i| {
+            if self.contains(id.wrapping_sub(i + 1)) {
+                recent | 1 << i
+            } else {
+                recent
+            }
+        })
+    }
+}
+
+fn backoff(config: &Config, resends: u32) -> Duration {
+    let timeout = config.resend_timeout
+        .checked_mul(1 << resends.min(16))
+        .unwrap_or(config.max_resend_timeout);
+
+    timeout.min(config.max_resend_timeout)
+}
+
+/// Copies a datagram into a buffer from the pool.
+fn copy(pool: &mut BufferPool, datagram: &[u8]) -> Vec<u8> {
+    let mut buffer = pool.acquire();
+    buffer.extend_from_slice(datagram);
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // no keepalives or silence timeouts unless a test asks for them
+    fn config() -> Config {
+        Config {
+            keepalive_interval: None,
+            disconnect_timeout: None,
+            ..Config::default()
+        }
+    }
+
+    fn packets(transport: &mut Transport) -> Vec<Packet> {
+        std::iter::from_fn(|| transport.poll_transmit())
+            .map(|datagram| decode::parse(&datagram).unwrap())
+            .collect()
+    }
+
+    fn datagram(packet: &Packet) -> Vec<u8> {
+        let mut cursor = encode::CursorMut::new();
+        cursor.encode(packet).unwrap();
+        cursor.into_inner()
+    }
+
+    fn events(transport: &mut Transport) -> Vec<Event> {
+        std::iter::from_fn(|| transport.poll_event()).collect()
+    }
+
+    #[test]
+    fn fragments_large_payloads() {
+        let now = Instant::now();
+        let mut sender = Transport::with_config(Config { max_payload: Some(4), ..config() });
+        let mut receiver = Transport::with_config(config());
+
+        sender.send_reliable(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], now).unwrap();
+
+        let mut fragments = packets(&mut sender);
+        assert_eq!(fragments.len(), 3);
+        assert_eq!(sender.pending(), 3);
+
+        // out of order, with the first fragment delivered twice
+        fragments.reverse();
+        fragments.push(fragments[2].clone());
+
+        for packet in &fragments {
+            receiver.handle_datagram(&datagram(packet), now).unwrap();
+        }
+
+        assert_eq!(events(&mut receiver), [Event::Data {
+            payload: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+            reliable: true,
+        }]);
+    }
+
+    #[test]
+    fn unfragmented_by_default() {
+        let mut transport = Transport::with_config(config());
+
+        transport.send_reliable(&[0; 4000], Instant::now()).unwrap();
+
+        assert!(matches!(packets(&mut transport)[..], [Packet::Reliable { .. }]));
+    }
+
+    #[test]
+    fn too_many_fragments() {
+        let mut transport = Transport::with_config(Config {
+            max_payload: Some(1),
+            max_fragments: 4,
+            ..config()
+        });
+
+        assert_eq!(transport.send_reliable(&[0; 5], Instant::now()), Err(TooLarge(5)));
+        assert!(packets(&mut transport).is_empty());
+        assert_eq!(transport.pending(), 0);
+
+        transport.send_reliable(&[0; 4], Instant::now()).unwrap();
+        assert_eq!(packets(&mut transport).len(), 4);
+    }
+
+    #[test]
+    fn resends_with_backoff() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut transport = Transport::with_config(config());
+
+        transport.send_reliable(&[1], start).unwrap();
+        assert_eq!(packets(&mut transport).len(), 1);
+        assert_eq!(transport.next_timeout(), Some(ms(300)));
+
+        transport.handle_timeout(ms(299));
+        assert!(packets(&mut transport).is_empty());
+
+        // the wait doubles, up to the max
+        for (at, next) in [(300, 900), (900, 1900), (1900, 2900)] {
+            transport.handle_timeout(ms(at));
+            assert_eq!(packets(&mut transport), [Packet::Reliable { id: 1, payload: vec![1] }]);
+            assert_eq!(transport.next_timeout(), Some(ms(next)));
+        }
+
+        assert_eq!(transport.metrics().resends, 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_resends() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut transport = Transport::with_config(Config { max_resends: 2, ..config() });
+
+        transport.send_reliable(&[1], start).unwrap();
+        transport.handle_timeout(ms(300));
+        transport.handle_timeout(ms(900));
+        assert_eq!(packets(&mut transport).len(), 3);
+        assert!(events(&mut transport).is_empty());
+
+        transport.handle_timeout(ms(1900));
+        assert!(packets(&mut transport).is_empty());
+        assert_eq!(events(&mut transport), [Event::TimedOut]);
+        assert_eq!(transport.pending(), 0);
+        assert_eq!(transport.next_timeout(), None);
+    }
+
+    #[test]
+    fn acknowledgements_clear_pending() {
+        let now = Instant::now();
+        let mut transport = Transport::with_config(config());
+
+        for _ in 0..4 {
+            transport.send_reliable(&[], now).unwrap();
+        }
+
+        // acknowledges 4, and through the recent byte, 3 and 1
+        let ack = Packet::Acknowledgement { id: 4, recent: 0b101 };
+        transport.handle_datagram(&datagram(&ack), now).unwrap();
+
+        assert_eq!(transport.pending(), 1);
+        assert!(transport.is_pending(2));
+    }
+
+    #[test]
+    fn duplicates_acknowledged_once_delivered() {
+        let now = Instant::now();
+        let mut transport = Transport::with_config(config());
+        let reliable = datagram(&Packet::Reliable { id: 5, payload: vec![7] });
+
+        transport.handle_datagram(&reliable, now).unwrap();
+        transport.handle_datagram(&reliable, now).unwrap();
+
+        assert_eq!(events(&mut transport), [Event::Data { payload: vec![7], reliable: true }]);
+        assert_eq!(packets(&mut transport), [
+            Packet::Acknowledgement { id: 5, recent: 0 },
+            Packet::Acknowledgement { id: 5, recent: 0 },
+        ]);
+        assert_eq!(transport.metrics().duplicates, 1);
+    }
+
+    #[test]
+    fn acknowledgement_recent_bits() {
+        let now = Instant::now();
+        let mut transport = Transport::with_config(config());
+
+        for id in [1, 2, 3, 5] {
+            let reliable = Packet::Reliable { id, payload: Vec::new() };
+            transport.handle_datagram(&datagram(&reliable), now).unwrap();
+        }
+
+        // 4 is missing, 3, 2 and 1 aren't
+        assert_eq!(
+            packets(&mut transport).last(),
+            Some(&Packet::Acknowledgement { id: 5, recent: 0b1110 }),
+        );
+    }
+
+    #[test]
+    fn receive_window() {
+        let mut window = ReceiveWindow::default();
+
+        assert!(window.insert(1));
+        assert!(!window.insert(1));
+        assert!(window.insert(3));
+        assert!(window.insert(2));
+        assert!(!window.insert(2));
+
+        // 63 behind is remembered, 64 behind is too old
+        assert!(window.insert(100));
+        assert!(window.insert(37));
+        assert!(!window.insert(37));
+        assert!(!window.insert(36));
+        assert!(window.contains(37));
+        assert!(!window.contains(38));
+    }
+
+    #[test]
+    fn receive_window_wraps() {
+        let mut window = ReceiveWindow::default();
+
+        assert!(window.insert(65534));
+        assert!(window.insert(0));
+        assert!(window.insert(65535));
+        assert!(!window.insert(65534));
+        assert!(!window.insert(0));
+        assert_eq!(window.recent(1), 0b111);
+    }
+
+    #[test]
+    fn rtt_smoothing() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut transport = Transport::with_config(config());
+        let ack = |id| datagram(&Packet::Acknowledgement { id, recent: 0 });
+
+        assert_eq!(transport.rtt(), None);
+
+        transport.send_reliable(&[], ms(0)).unwrap();
+        transport.handle_datagram(&ack(1), ms(100)).unwrap();
+        assert_eq!(transport.rtt(), Some(Duration::from_millis(100)));
+        assert_eq!(transport.jitter(), Some(Duration::from_millis(50)));
+
+        transport.send_reliable(&[], ms(1000)).unwrap();
+        transport.handle_datagram(&ack(2), ms(1180)).unwrap();
+        assert_eq!(transport.rtt(), Some(Duration::from_millis(110)));
+        assert_eq!(transport.jitter(), Some(Duration::from_micros(57500)));
+
+        // an acknowledgement for a resent packet could be for either copy
+        transport.send_reliable(&[], ms(2000)).unwrap();
+        transport.handle_timeout(ms(2300));
+        transport.handle_datagram(&ack(3), ms(2900)).unwrap();
+        assert_eq!(transport.rtt(), Some(Duration::from_millis(110)));
+    }
+}
@@ -0,0 +1,161 @@
+//! Hazel packets.
+//!
+//! Every UDP datagram Hazel sends is a single packet: a one byte kind,
+//! followed by a header depending on the kind, then the payload. Reliable
+//! packets carry a big-endian `u16` id that the other side acknowledges.
+
+use alloc::vec::Vec;
+
+use crate::net::binary::{decode, encode, endian::BigEndian};
+
+/// The kind byte of an unreliable packet.
+pub const UNRELIABLE: u8 = 0x00;
+/// The kind byte of a reliable packet.
+pub const RELIABLE: u8 = 0x01;
+/// The kind byte of a hello packet.
+pub const HELLO: u8 = 0x08;
+/// The kind byte of a disconnect packet.
+pub const DISCONNECT: u8 = 0x09;
+/// The kind byte of an acknowledgement packet.
+pub const ACKNOWLEDGEMENT: u8 = 0x0a;
+/// The kind byte of a fragment packet.
+pub const FRAGMENT: u8 = 0x0b;
+/// The kind byte of a ping packet.
+pub const PING: u8 = 0x0c;
+
+/// A single Hazel packet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Packet {
+    /// Data that may be lost or arrive out of order.
+    Unreliable(Vec<u8>),
+    /// Data that is resent until it is acknowledged.
+    Reliable {
+        id: u16,
+        payload: Vec<u8>,
+    },
+    /// The first packet a client sends, which is also reliable.
+    Hello {
+        id: u16,
+        payload: Vec<u8>,
+    },
+    /// The connection is closing. The payload, if any, says why.
+    Disconnect(Vec<u8>),
+    /// Acknowledges a reliable packet.
+    ///
+    /// Bit `n` of `recent` is set if packet `id - n - 1` was also received,
+    /// which covers for lost acknowledgements.
+    Acknowledgement {
+        id: u16,
+        recent: u8,
+    },
+    /// A reliable piece of a payload too large for a single datagram.
+    ///
+    /// Every fragment of a payload shares a `group`, and they are put back
+    /// together by `index` once all `count` of them have arrived.
+    Fragment {
+        id: u16,
+        group: u16,
+        index: u16,
+        count: u16,
+        payload: Vec<u8>,
+    },
+    /// A reliable keepalive with no payload.
+    Ping {
+        id: u16,
+    },
+}
+
+impl Packet {
+    /// The reliable id of the packet, if it has one that must be acknowledged.
+    pub fn reliable_id(&self) -> Option<u16> {
+        match self {
+            Packet::Reliable { id, .. }
+            | Packet::Hello { id, .. }
+            | Packet::Fragment { id, .. }
+            | Packet::Ping { id } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl decode::Decode for Packet {
+    fn decode<T>(cursor: &mut decode::Cursor<T>) -> Result<Self, decode::Error>
+    where T: AsRef<[u8]> {
+        let kind = cursor.decode::<u8>()?;
+
+        let packet = match kind {
+            UNRELIABLE => Packet::Unreliable(cursor.decode_rest().to_vec()),
+            RELIABLE => Packet::Reliable {
+                id: cursor.decode::<BigEndian<u16>>()?.0,
+                payload: cursor.decode_rest().to_vec(),
+            },
+            HELLO => Packet::Hello {
+                id: cursor.decode::<BigEndian<u16>>()?.0,
+                payload: cursor.decode_rest().to_vec(),
+            },
+            DISCONNECT => Packet::Disconnect(cursor.decode_rest().to_vec()),
+            ACKNOWLEDGEMENT => Packet::Acknowledgement {
+                id: cursor.decode::<BigEndian<u16>>()?.0,
+                // older clients don't send the recent packets byte
+                recent: if cursor.remaining() > 0 { cursor.decode()? } else { 0 },
+            },
+            FRAGMENT => Packet::Fragment {
+                id: cursor.decode::<BigEndian<u16>>()?.0,
+                group: cursor.decode::<BigEndian<u16>>()?.0,
+                index: cursor.decode::<BigEndian<u16>>()?.0,
+                count: cursor.decode::<BigEndian<u16>>()?.0,
+                payload: cursor.decode_rest().to_vec(),
+            },
+            PING => Packet::Ping {
+                id: cursor.decode::<BigEndian<u16>>()?.0,
+            },
+            kind => return Err(decode::Error::invalid_tag(kind)),
+        };
+
+        Ok(packet)
+    }
+}
+
+impl encode::Encode for Packet {
+    fn encode(&self, cursor: &mut encode::CursorMut) -> Result<(), encode::Error> {
+        match self {
+            Packet::Unreliable(payload) => {
+                cursor.encode(&UNRELIABLE)?;
+                cursor.write(payload);
+            }
+            Packet::Reliable { id, payload } => {
+                cursor.encode(&RELIABLE)?;
+                cursor.encode(&BigEndian(*id))?;
+                cursor.write(payload);
+            }
+            Packet::Hello { id, payload } => {
+                cursor.encode(&HELLO)?;
+                cursor.encode(&BigEndian(*id))?;
+                cursor.write(payload);
+            }
+            Packet::Disconnect(payload) => {
+                cursor.encode(&DISCONNECT)?;
+                cursor.write(payload);
+            }
+            Packet::Acknowledgement { id, recent } => {
+                cursor.encode(&ACKNOWLEDGEMENT)?;
+                cursor.encode(&BigEndian(*id))?;
+                cursor.encode(recent)?;
+            }
+            Packet::Fragment { id, group, index, count, payload } => {
+                cursor.encode(&FRAGMENT)?;
+                cursor.encode(&BigEndian(*id))?;
+                cursor.encode(&BigEndian(*group))?;
+                cursor.encode(&BigEndian(*index))?;
+                cursor.encode(&BigEndian(*count))?;
+                cursor.write(payload);
+            }
+            Packet::Ping { id } => {
+                cursor.encode(&PING)?;
+                cursor.encode(&BigEndian(*id))?;
+            }
+        }
+
+        Ok(())
+    }
+}